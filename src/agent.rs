@@ -23,9 +23,29 @@ pub struct AgentConfig {
     pub max_key_memories: usize,
     pub auto_summarize: bool,
 
+    // Model settings (overridden by the matching CLI flags)
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    // Containment (overridden by --allow-network)
+    #[serde(default)]
+    pub allow_network: Option<bool>,
+
+    /// Name shown for the assistant in the TUI (overridden by --display-name)
+    #[serde(default)]
+    pub display_name: Option<String>,
+
     // Statistics
     pub total_conversations: usize,
     pub total_messages: usize,
+    #[serde(default)]
+    pub total_files_changed: usize,
 }
 
 impl AgentConfig {
@@ -43,13 +63,43 @@ impl AgentConfig {
             max_routine_memories: 10000,
             max_key_memories: 1000,
             auto_summarize: false,
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            seed: None,
+            allow_network: None,
+            display_name: None,
             total_conversations: 0,
             total_messages: 0,
+            total_files_changed: 0,
+        }
+    }
+
+    /// Add a finished session to the lifetime statistics
+    pub fn record_session(&mut self, messages: usize, files_changed: usize) {
+        self.total_conversations += 1;
+        self.total_messages += messages;
+        self.total_files_changed += files_changed;
+    }
+
+    /// New config for `name` that inherits all of this agent's settings,
+    /// with fresh timestamps and statistics
+    pub fn derive(&self, name: &str) -> Self {
+        let fresh = Self::new(name);
+        Self {
+            name: fresh.name,
+            created_at: fresh.created_at,
+            last_active: fresh.last_active,
+            total_conversations: fresh.total_conversations,
+            total_messages: fresh.total_messages,
+            total_files_changed: fresh.total_files_changed,
+            ..self.clone()
         }
     }
 }
 
 /// Manager for agent lifecycle
+#[derive(Clone)]
 pub struct AgentManager {
     agents_dir: PathBuf,
 }
@@ -237,11 +287,13 @@ You are {{agent_name}}, a specialized AI assistant.
 **Context Variables Available:**
 - {{working_dir}} - Current working directory
 - {{project_name}} - Project name
+- {{project_overview}} - Project type and top-level files
 - {{git_branch}} - Git branch
 - {{git_status}} - Git status (clean/dirty)
+- {{git_summary}} - Branch and uncommitted change counts
 - {{model}} - LLM model name
 - {{date}}, {{time}}, {{datetime}} - Timestamps
-- {{username}}, {{hostname}}, {{os}} - System info
+- {{username}}, {{hostname}}, {{os}}, {{arch}}, {{shell}} - System info
 "#.to_string();
 
             std::fs::write(agent_dir.join("system_prompt.md"), prompt_template)?;
@@ -258,6 +310,35 @@ You are {{agent_name}}, a specialized AI assistant.
         Ok(config)
     }
 
+    /// Create a new agent from an existing one's config and system_prompt.md.
+    /// The clone starts with its own statistics and an empty memory.
+    pub fn clone_agent(&self, source: &str, name: &str) -> Result<AgentConfig> {
+        Self::validate_name(name)?;
+
+        if self.exists(name) {
+            return Err(anyhow!("Agent '{}' already exists", name));
+        }
+
+        let source_config = self
+            .load_agent(source)
+            .map_err(|_| anyhow!("Cannot clone: agent '{}' not found", source))?;
+        let config = source_config.derive(name);
+
+        let agent_dir = self.agents_dir.join(name);
+        std::fs::create_dir_all(agent_dir.join("memory"))?;
+
+        let source_prompt = self.agent_dir(source).join("system_prompt.md");
+        if source_prompt.exists() {
+            std::fs::copy(&source_prompt, agent_dir.join("system_prompt.md"))?;
+        }
+
+        self.save_agent(&config)?;
+
+        crate::terminal::print_success(&format!("Created agent '{}' from '{}'", name, source));
+
+        Ok(config)
+    }
+
     /// Load agent config
     pub fn load_agent(&self, name: &str) -> Result<AgentConfig> {
         let config_path = self.agents_dir.join(name).join("agent.json");
@@ -288,6 +369,13 @@ You are {{agent_name}}, a specialized AI assistant.
         Ok(())
     }
 
+    /// Add a finished session to an agent's lifetime statistics
+    pub fn record_session(&self, name: &str, messages: usize, files_changed: usize) -> Result<()> {
+        let mut config = self.load_agent(name)?;
+        config.record_session(messages, files_changed);
+        self.save_agent(&config)
+    }
+
     /// Get agent directory path
     pub fn agent_dir(&self, name: &str) -> PathBuf {
         self.agents_dir.join(name)
@@ -419,6 +507,46 @@ mod tests {
         assert!(!result.contains("Agent Identity")); // Should skip agent.json fields
     }
 
+    #[test]
+    fn test_derive_keeps_settings_resets_stats() {
+        let mut source = AgentConfig::new("rust-expert");
+        source.personality = Some("Pedantic".to_string());
+        source.memory_enabled = false;
+        source.max_key_memories = 42;
+        source.temperature = Some(0.2);
+        source.seed = Some(7);
+        source.total_conversations = 7;
+        source.total_messages = 120;
+
+        let clone = source.derive("rust-expert-strict");
+
+        assert_eq!(clone.name, "rust-expert-strict");
+        assert_eq!(clone.personality.as_deref(), Some("Pedantic"));
+        assert!(!clone.memory_enabled);
+        assert_eq!(clone.max_key_memories, 42);
+        assert_eq!(clone.temperature, Some(0.2));
+        assert_eq!(clone.seed, Some(7));
+        assert_eq!(clone.total_conversations, 0);
+        assert_eq!(clone.total_messages, 0);
+    }
+
+    #[test]
+    fn test_record_session() {
+        let mut config = AgentConfig::new("test");
+        config.record_session(4, 2);
+        config.record_session(1, 0);
+
+        assert_eq!(config.total_conversations, 2);
+        assert_eq!(config.total_messages, 5);
+        assert_eq!(config.total_files_changed, 2);
+
+        // Configs written before files were tracked still load
+        let mut json = serde_json::to_value(&config).unwrap();
+        json.as_object_mut().unwrap().remove("total_files_changed");
+        let loaded: AgentConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.total_files_changed, 0);
+    }
+
     #[test]
     fn test_build_system_prompt_without_file() {
         let mut config = AgentConfig::new("test");
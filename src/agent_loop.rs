@@ -1,21 +1,28 @@
-use crate::error::ToolError;
+use crate::audit::{AuditEntry, AuditLog};
+use crate::error::{AgentError, ToolError};
+use crate::git::Checkpoint;
 use crate::inspector::TrafficHandle;
 use crate::memory::types::RoutineMemoryChunk;
 use crate::permissions::GrantedPermissions;
 use crate::terminal;
+use crate::auto_continue;
+use crate::injection;
+use crate::plan;
+use crate::text_tool_calls::{self, StreamFilter};
 use crate::vecdb::VectorDB;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use crate::tools::{
-    ra_common, BashCommand, BashKill, BashList, BashOutput, BashStatus, EditFile, GlobFiles,
-    GrepSearch, ListDir, MathCalc, RaCodeActions, RaCompletion, RaDiagnostics, RaFindReferences,
-    RaFormat, RaGotoDefinition, RaHover, RaRename, RaSymbols, ReadFile, SearchKeyMemory,
-    SearchRoutineMemory, StoreKeyMemory, WebFetch, WebSearch, WriteFile,
+    clippy, context_dirs, file_limits, mask_secret_env, paths, ra_common, rustfmt, shell, AddDependency, BashArgs, BashCommand, BashKill, BashList, BashOutput, BashStatus, CargoCheck, CrateDocs, DiffFiles, EditFile, EditMarkdown, GitDiff, GitStatus,
+    GlobArgs, GlobFiles, GrepArgs, GrepSearch, JsonQuery, LineSink, ListDir, MathCalc, RaCodeActions, RaCompletion, RaDiagnostics, RaFindReferences,
+    RaFormat, RaGotoDefinition, RaHover, RaRename, RaSymbols, ReadFile, RemoveDependency, RunTests, ScratchRead, ScratchWrite, Scratchpad, SearchKeyMemory,
+    SearchRoutineMemory, ShellSession, StoreKeyMemory, SummarizeFile, VecdbSearch, WebFetch, WebSearch, WriteFile,
 };
 use crate::tui::TuiEvent;
 use anyhow::{anyhow, Result};
 use futures::StreamExt;
-use rig::completion::message::{AssistantContent, ToolCall, ToolResultContent};
+use lazy_static::lazy_static;
+use rig::completion::message::{AssistantContent, ToolCall, ToolFunction, ToolResultContent};
 use rig::completion::{CompletionModel, Message, ToolDefinition};
 use rig::message::{ToolResult, UserContent};
 use rig::one_or_many::OneOrMany;
@@ -23,9 +30,11 @@ use rig::streaming::StreamedAssistantContent;
 use rig::tool::Tool;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::Sender;
-use serde::Deserialize;
+use tokio::sync::Semaphore;
+use serde::{Deserialize, Serialize};
 
 /// Arguments for spawning a sub-agent
 #[derive(Debug, Deserialize)]
@@ -40,6 +49,108 @@ pub struct SpawnAgentArgs {
     pub preamble_append: Option<String>,
 }
 
+/// What a sub-agent did, returned to the parent as JSON (the spawn_agent
+/// result, or the error when the sub-agent failed, timed out or was stopped)
+#[derive(Debug, Serialize)]
+pub struct SubAgentResult {
+    pub success: bool,
+    /// The sub-agent's final response: its findings and what it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// Why the sub-agent didn't finish
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Files the sub-agent created or modified, including before a failure
+    pub files_changed: Vec<SubAgentFileChange>,
+    pub iterations: usize,
+    pub elapsed_secs: u64,
+}
+
+/// A file a sub-agent changed
+#[derive(Debug, Serialize)]
+pub struct SubAgentFileChange {
+    pub path: String,
+    /// "created", "modified" or "deleted"
+    pub operation: String,
+}
+
+impl SubAgentResult {
+    fn new<M: CompletionModel>(agent: &AgentLoop<M>, elapsed: Duration, outcome: Result<String, String>) -> Self {
+        let files_changed = agent
+            .get_file_changes_summary()
+            .into_iter()
+            .map(|change| SubAgentFileChange {
+                path: change.path.clone(),
+                operation: change.operation.to_string(),
+            })
+            .collect();
+        let (success, response, error) = match outcome {
+            Ok(response) => (true, Some(response), None),
+            Err(error) => (false, None, Some(error)),
+        };
+        Self {
+            success,
+            response,
+            error,
+            files_changed,
+            iterations: agent.iteration_count(),
+            elapsed_secs: elapsed.as_secs(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|e| format!("{{\"success\": false, \"error\": \"{}\"}}", e))
+    }
+}
+
+lazy_static! {
+    /// Cancellation tokens of the running sub-agents by agent ID, so the TUI
+    /// can stop one of them (Ctrl+K on its tab) and leave the others running
+    static ref SUBAGENT_TOKENS: std::sync::Mutex<HashMap<String, CancellationToken>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+/// A sub-agent's entry in `SUBAGENT_TOKENS`, removed when the sub-agent's
+/// run ends or is dropped along with an interrupted turn
+struct SubagentRegistration(String);
+
+impl SubagentRegistration {
+    fn new(agent_id: &str, token: CancellationToken) -> Self {
+        if let Ok(mut tokens) = SUBAGENT_TOKENS.lock() {
+            tokens.insert(agent_id.to_string(), token);
+        }
+        Self(agent_id.to_string())
+    }
+}
+
+impl Drop for SubagentRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut tokens) = SUBAGENT_TOKENS.lock() {
+            tokens.remove(&self.0);
+        }
+    }
+}
+
+/// Stop the running sub-agent with this ID and the sub-agents it spawned;
+/// false if no such sub-agent is running
+pub fn kill_subagent(agent_id: &str) -> bool {
+    let token = SUBAGENT_TOKENS.lock().ok().and_then(|tokens| tokens.get(agent_id).cloned());
+    match token {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Arguments for changing the working directory
+#[derive(Debug, Deserialize)]
+pub struct ChangeDirectoryArgs {
+    /// Directory to switch to (absolute, or relative to the current working directory)
+    pub path: String,
+}
+
 /// Default maximum iterations for main agents
 const DEFAULT_MAX_ITERATIONS: usize = 100;
 
@@ -49,13 +160,56 @@ const DEFAULT_SUBAGENT_MAX_ITERATIONS: usize = 100;
 /// Maximum agent nesting depth to prevent infinite recursion
 const MAX_DEPTH: usize = 3;
 
+/// Default cap on a single tool result appended to the chat history (in KB)
+pub const DEFAULT_MAX_TOOL_OUTPUT_KB: usize = 50;
+
+/// Tools that modify files: previewed instead of run while /dryrun is on, and
+/// eligible for per-file approval (those with a `file_path`)
+pub const FILE_WRITE_TOOLS: &[&str] = &["write_file", "edit_file", "edit_markdown_section", "add_dependency", "remove_dependency"];
+
+/// Read-only tools whose consecutive calls in one response run at the same time
+const CONCURRENT_TOOLS: &[&str] = &[
+    "read_file",
+    "list_dir",
+    "grep",
+    "glob",
+    "diff_files",
+    "git_status",
+    "git_diff",
+    "json_query",
+    "scratch_read",
+    "web_fetch",
+    "web_search",
+    "crate_docs",
+    "math_calc",
+];
+
+/// Lines of a running bash command shown in the TUI; the rest only go to the tool result
+const MAX_STREAMED_BASH_LINES: usize = 200;
+
+/// Upper bound for the derived max_tokens default
+const MAX_TOKENS_CEILING: u64 = 32768;
+
+/// Default max_tokens for a context window: half the window, leaving the other half
+/// for the prompt, capped at MAX_TOKENS_CEILING
+pub fn default_max_tokens(context_size: usize) -> u64 {
+    ((context_size / 2) as u64).clamp(256, MAX_TOKENS_CEILING)
+}
+
+/// Sampling options passed to the model; unset values use the model's defaults
+#[derive(Debug, Clone, Default)]
+pub struct SamplingParams {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub seed: Option<u64>,
+}
+
 /// Tracks a file modification
 #[derive(Debug, Clone)]
 pub struct FileChange {
     pub path: String,
     pub operation: FileOperation,
-    /// Timestamp when the change was recorded (for future use in displaying change history)
-    #[allow(dead_code)]
+    /// Timestamp when the change was recorded
     pub timestamp: Instant,
 }
 
@@ -86,6 +240,170 @@ impl TokenUsage {
     }
 }
 
+/// Estimated tokens the agent and its sub-agents may use over a session
+/// (--max-total-tokens), charged for every model request
+#[derive(Debug)]
+pub struct TokenBudget {
+    limit: usize,
+    used: std::sync::atomic::AtomicUsize,
+}
+
+impl TokenBudget {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            used: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// Record the estimated tokens of one request and its response
+    pub fn charge(&self, tokens: usize) {
+        self.used.fetch_add(tokens, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn used(&self) -> usize {
+        self.used.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.used() >= self.limit
+    }
+}
+
+/// Accumulated timing for one tool
+#[derive(Debug, Clone, Default)]
+pub struct ToolTiming {
+    pub calls: usize,
+    pub total_ms: u64,
+}
+
+/// Per-tool and LLM timing collected over an agent's lifetime
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionProfile {
+    pub tools: HashMap<String, ToolTiming>,
+    pub llm: ToolTiming,
+}
+
+impl ExecutionProfile {
+    /// Record one tool invocation
+    pub fn record_tool(&mut self, tool_name: &str, duration_ms: u64) {
+        let timing = self.tools.entry(tool_name.to_string()).or_default();
+        timing.calls += 1;
+        timing.total_ms += duration_ms;
+    }
+
+    /// Record one LLM request
+    pub fn record_llm(&mut self, duration_ms: u64) {
+        self.llm.calls += 1;
+        self.llm.total_ms += duration_ms;
+    }
+
+    /// Render the profile as a table, slowest tools first
+    pub fn format_table(&self) -> String {
+        let mut rows: Vec<(&str, &ToolTiming)> = self.tools
+            .iter()
+            .map(|(name, timing)| (name.as_str(), timing))
+            .collect();
+        rows.sort_by(|a, b| b.1.total_ms.cmp(&a.1.total_ms).then_with(|| a.0.cmp(b.0)));
+
+        let mut table = format!("{:<24} {:>6} {:>10} {:>8}\n", "Tool", "Calls", "Total ms", "Avg ms");
+        for (name, timing) in rows {
+            table.push_str(&Self::format_row(name, timing));
+        }
+        table.push_str(&Self::format_row("(LLM requests)", &self.llm));
+        table
+    }
+
+    fn format_row(name: &str, timing: &ToolTiming) -> String {
+        let avg = if timing.calls > 0 { timing.total_ms / timing.calls as u64 } else { 0 };
+        format!("{:<24} {:>6} {:>10} {:>8}\n", name, timing.calls, timing.total_ms, avg)
+    }
+}
+
+/// Successes and failures of one tool
+#[derive(Debug, Clone, Default)]
+pub struct ToolOutcomes {
+    pub successes: usize,
+    pub failures: usize,
+    /// The most recent error message
+    pub last_error: Option<String>,
+}
+
+impl ToolOutcomes {
+    /// Share of calls that failed, in percent
+    fn failure_rate(&self) -> f64 {
+        let calls = self.successes + self.failures;
+        if calls == 0 { 0.0 } else { self.failures as f64 * 100.0 / calls as f64 }
+    }
+}
+
+/// Per-tool success/failure counts collected over an agent's lifetime
+#[derive(Debug, Clone, Default)]
+pub struct ToolStats {
+    pub tools: HashMap<String, ToolOutcomes>,
+}
+
+impl ToolStats {
+    /// Record the outcome of one tool call (`error` is None on success)
+    pub fn record(&mut self, tool_name: &str, error: Option<&str>) {
+        let outcomes = self.tools.entry(tool_name.to_string()).or_default();
+        match error {
+            None => outcomes.successes += 1,
+            Some(error) => {
+                outcomes.failures += 1;
+                outcomes.last_error = Some(error.to_string());
+            }
+        }
+    }
+
+    /// Render the counts as a table, highest failure rate first, followed by
+    /// the last error of each tool that failed
+    pub fn format_table(&self) -> String {
+        if self.tools.is_empty() {
+            return "No tool calls yet.".to_string();
+        }
+
+        let mut rows: Vec<(&str, &ToolOutcomes)> = self.tools
+            .iter()
+            .map(|(name, outcomes)| (name.as_str(), outcomes))
+            .collect();
+        rows.sort_by(|a, b| {
+            b.1.failure_rate()
+                .total_cmp(&a.1.failure_rate())
+                .then_with(|| b.1.failures.cmp(&a.1.failures))
+                .then_with(|| a.0.cmp(b.0))
+        });
+
+        let mut table = format!("{:<24} {:>6} {:>6} {:>6} {:>7}\n", "Tool", "Calls", "OK", "Failed", "Fail %");
+        for (name, outcomes) in &rows {
+            table.push_str(&format!(
+                "{:<24} {:>6} {:>6} {:>6} {:>6.0}%\n",
+                name,
+                outcomes.successes + outcomes.failures,
+                outcomes.successes,
+                outcomes.failures,
+                outcomes.failure_rate()
+            ));
+        }
+
+        let errors: Vec<String> = rows
+            .iter()
+            .filter_map(|(name, outcomes)| {
+                let error = outcomes.last_error.as_deref()?;
+                Some(format!("  {}: {}", name, terminal::truncate_chars(&error.replace('\n', " "), 120)))
+            })
+            .collect();
+        if !errors.is_empty() {
+            table.push_str(&format!("\nLast errors:\n{}\n", errors.join("\n")));
+        }
+        table
+    }
+}
+
 /// Type of file operation
 #[derive(Debug, Clone, PartialEq)]
 pub enum FileOperation {
@@ -114,17 +432,31 @@ pub struct AgentLoop<M: CompletionModel> {
     traffic: TrafficHandle,
     confirm_dangerous: bool,
     streaming: bool,
+    /// Print streamed tokens to stderr instead of stdout (batch mode keeps stdout for the final response)
+    stream_to_stderr: bool,
     working_directory: String,
+    /// Directory the working directory may not leave (None = unrestricted)
+    root: Option<PathBuf>,
     /// Tracks file changes made during the session
     file_changes: HashMap<String, FileChange>,
     /// Tracks token usage
     token_usage: TokenUsage,
+    /// Tracks time spent per tool and in LLM requests
+    profile: ExecutionProfile,
+    /// Per-tool success/failure counts
+    tool_stats: ToolStats,
     /// Optional TUI event sender (None = use direct terminal printing)
     tui_tx: Option<Sender<TuiEvent>>,
     /// Tools that have been approved for all future uses
     approved_tools: HashSet<String>,
+    /// Files whose writes and edits have been approved for the rest of the session
+    approved_files: HashSet<PathBuf>,
     /// Context window size (num_ctx parameter for LLM)
     context_size: usize,
+    /// Maximum tokens the model may generate per request
+    max_tokens: u64,
+    /// Temperature / top_p / seed overrides
+    sampling: SamplingParams,
     /// Optional vector database for code context
     vecdb: Option<Arc<tokio::sync::Mutex<VectorDB>>>,
     /// Optional memory manager for long-term memory
@@ -145,6 +477,51 @@ pub struct AgentLoop<M: CompletionModel> {
     permissions: GrantedPermissions,
     /// Model name for memory tracking
     model_name: String,
+    /// Maximum size of a tool result kept in history, in KB (0 = unlimited)
+    max_tool_output_kb: usize,
+    /// Report permission denials to the model as tool results instead of aborting
+    continue_on_error: bool,
+    /// Optional on-disk audit log of tool calls (shared with sub-agents)
+    audit_log: Option<Arc<AuditLog>>,
+    /// Commit the working tree to a session branch after each turn (main agent only)
+    checkpoint: Option<Checkpoint>,
+    /// Interactive /dryrun: file-writing tools are previewed but never write
+    dry_run_writes: bool,
+    /// Limits in-flight model requests (shared with sub-agents)
+    request_limiter: Option<Arc<Semaphore>>,
+    /// Session-wide token budget (shared with sub-agents)
+    token_budget: Option<Arc<TokenBudget>>,
+    /// Ask the model to think and show its reasoning
+    show_reasoning: bool,
+    /// Keeps cwd and exported variables between bash calls (--persistent-shell)
+    shell_session: Option<Arc<tokio::sync::Mutex<ShellSession>>>,
+    /// Ask again when a final response promises an action it didn't take
+    auto_continue: bool,
+    /// Flag tool results that contain prompt-injection phrases (--scan-injection)
+    scan_injection: bool,
+    /// Run rustfmt on .rs files after write_file/edit_file (--format-on-write)
+    format_on_write: bool,
+    /// Run clippy on the package after .rs files are written or edited (--lint-on-write)
+    lint_on_write: bool,
+    /// Propose a plan for approval before acting on a request (--plan)
+    plan_mode: bool,
+    /// Whether a plan has been approved in this conversation
+    plan_approved: bool,
+    /// The current turn is a planning turn: no tools are offered
+    planning: bool,
+    /// Ask before a turn changes more files than this (--max-files-per-turn)
+    max_files_per_turn: Option<usize>,
+    /// Files changed in the current turn
+    turn_files: HashSet<PathBuf>,
+    /// Changing more files than the limit was approved for the current turn
+    turn_files_approved: bool,
+    /// Clippy diagnostics already reported to the model or present before its
+    /// first edit, so each is reported once
+    reported_lints: clippy::ReportedLints,
+    /// Wall-clock budget for one chat turn (--turn-timeout)
+    turn_timeout: Option<Duration>,
+    /// Key-value notes for scratch_read/scratch_write (shared with sub-agents)
+    scratchpad: Scratchpad,
 }
 
 impl<M: CompletionModel> AgentLoop<M> {
@@ -186,12 +563,19 @@ impl<M: CompletionModel> AgentLoop<M> {
             traffic,
             confirm_dangerous,
             streaming,
+            stream_to_stderr: false,
             working_directory,
+            root: None,
             file_changes: HashMap::new(),
             token_usage: TokenUsage::default(),
+            profile: ExecutionProfile::default(),
+            tool_stats: ToolStats::default(),
             tui_tx: None,
             approved_tools: HashSet::new(),
+            approved_files: HashSet::new(),
             context_size,
+            max_tokens: default_max_tokens(context_size),
+            sampling: SamplingParams::default(),
             vecdb,
             memory_manager,
             session_id,
@@ -202,6 +586,28 @@ impl<M: CompletionModel> AgentLoop<M> {
             cancel_token,
             permissions,
             model_name,
+            max_tool_output_kb: DEFAULT_MAX_TOOL_OUTPUT_KB,
+            continue_on_error: false,
+            audit_log: None,
+            checkpoint: None,
+            dry_run_writes: false,
+            request_limiter: None,
+            token_budget: None,
+            show_reasoning: false,
+            shell_session: None,
+            auto_continue: false,
+            scan_injection: false,
+            format_on_write: false,
+            lint_on_write: false,
+            plan_mode: false,
+            plan_approved: false,
+            planning: false,
+            max_files_per_turn: None,
+            turn_files: HashSet::new(),
+            turn_files_approved: false,
+            reported_lints: clippy::ReportedLints::default(),
+            turn_timeout: None,
+            scratchpad: Scratchpad::default(),
         }
     }
 
@@ -210,11 +616,263 @@ impl<M: CompletionModel> AgentLoop<M> {
         self.tui_tx = Some(tx);
     }
 
+    /// Send streamed tokens to stderr when there is no TUI
+    pub fn set_stream_to_stderr(&mut self, stream_to_stderr: bool) {
+        self.stream_to_stderr = stream_to_stderr;
+    }
+
+    /// Set the maximum number of tokens generated per request
+    pub fn set_max_tokens(&mut self, max_tokens: u64) {
+        self.max_tokens = max_tokens;
+    }
+
+    /// Set temperature / top_p / seed for completion requests
+    pub fn set_sampling(&mut self, sampling: SamplingParams) {
+        self.sampling = sampling;
+    }
+
+    /// Replace the system prompt; the history is kept
+    pub fn set_preamble(&mut self, preamble: String) {
+        self.preamble = preamble;
+    }
+
+    /// Restrict working directory changes to a root directory
+    pub fn set_root(&mut self, root: PathBuf) {
+        self.root = Some(root);
+    }
+
+    /// Get the current working directory
+    pub fn working_directory(&self) -> &str {
+        &self.working_directory
+    }
+
+    /// Change the working directory used to resolve relative paths.
+    /// The target must be an existing directory inside the root (if one is set).
+    /// The process directory is changed too, so the file tools follow along.
+    pub fn change_directory(&mut self, path: &str) -> Result<String, ToolError> {
+        let target = resolve_directory(Path::new(&self.working_directory), path, self.root.as_deref())?;
+        std::env::set_current_dir(&target)
+            .map_err(|e| ToolError::Other(format!("Failed to change directory to {}: {}", target.display(), e)))?;
+        self.working_directory = target.to_string_lossy().to_string();
+        if let Some(ref session) = self.shell_session
+            && let Ok(mut session) = session.try_lock() {
+                session.set_cwd(&target);
+            }
+        Ok(format!("Working directory is now {}", self.working_directory))
+    }
+
     /// Set the maximum iterations for this agent
     pub fn set_max_iterations(&mut self, max_iterations: usize) {
         self.max_iterations = max_iterations;
     }
 
+    /// Set the maximum size of a tool result kept in history, in KB (0 = unlimited)
+    pub fn set_max_tool_output_kb(&mut self, max_tool_output_kb: usize) {
+        self.max_tool_output_kb = max_tool_output_kb;
+    }
+
+    /// Keep going after a denied tool call, letting the model see the denial
+    pub fn set_continue_on_error(&mut self, continue_on_error: bool) {
+        self.continue_on_error = continue_on_error;
+    }
+
+    /// Record every tool call in an audit log
+    pub fn set_audit_log(&mut self, audit_log: Arc<AuditLog>) {
+        self.audit_log = Some(audit_log);
+    }
+
+    /// Ask reasoning models to think, and show the reasoning (dimmed, collapsed in the TUI)
+    pub fn set_show_reasoning(&mut self, show_reasoning: bool) {
+        self.show_reasoning = show_reasoning;
+    }
+
+    /// Run foreground bash calls in a session that keeps the directory and
+    /// exported variables of the previous call
+    pub fn set_persistent_shell(&mut self, enabled: bool) {
+        self.shell_session = enabled
+            .then(|| Arc::new(tokio::sync::Mutex::new(ShellSession::new(&self.working_directory))));
+    }
+
+    /// Send a "continue" prompt when the model stops right after announcing a step
+    pub fn set_auto_continue(&mut self, auto_continue: bool) {
+        self.auto_continue = auto_continue;
+    }
+
+    /// Scan every tool result for text that tries to instruct the model
+    pub fn set_scan_injection(&mut self, scan_injection: bool) {
+        self.scan_injection = scan_injection;
+    }
+
+    /// Format Rust files with rustfmt after each write or edit
+    pub fn set_format_on_write(&mut self, format_on_write: bool) {
+        self.format_on_write = format_on_write;
+    }
+
+    /// Run clippy after each write or edit of a Rust file and report new warnings
+    pub fn set_lint_on_write(&mut self, lint_on_write: bool) {
+        self.lint_on_write = lint_on_write;
+    }
+
+    /// Ask before one turn changes more than `max_files` files
+    pub fn set_max_files_per_turn(&mut self, max_files: usize) {
+        self.max_files_per_turn = Some(max_files);
+    }
+
+    /// Answer the first request with a plan and wait for it to be approved
+    pub fn set_plan_mode(&mut self, plan_mode: bool) {
+        self.plan_mode = plan_mode;
+    }
+
+    /// Share a parent's scratchpad, so sub-agents see each other's notes
+    pub fn set_scratchpad(&mut self, scratchpad: Scratchpad) {
+        self.scratchpad = scratchpad;
+    }
+
+    /// Stop a turn that runs longer than this, returning what it has so far
+    pub fn set_turn_timeout(&mut self, turn_timeout: Duration) {
+        self.turn_timeout = Some(turn_timeout);
+    }
+
+    /// Hold a permit from this semaphore for every model request
+    pub fn set_request_limiter(&mut self, limiter: Arc<Semaphore>) {
+        self.request_limiter = Some(limiter);
+    }
+
+    /// Stop making model requests once this budget is used up
+    pub fn set_token_budget(&mut self, budget: Arc<TokenBudget>) {
+        self.token_budget = Some(budget);
+    }
+
+    /// Preview file writes (diff and approval) without performing them
+    pub fn set_dry_run_writes(&mut self, enabled: bool) {
+        self.dry_run_writes = enabled;
+    }
+
+    pub fn dry_run_writes(&self) -> bool {
+        self.dry_run_writes
+    }
+
+    /// Approve tools for all further calls, as if "approve all" had been chosen
+    pub fn approve_tools(&mut self, tools: &[String]) {
+        self.approved_tools.extend(tools.iter().cloned());
+    }
+
+    /// Prompt again before these tools run
+    pub fn revoke_tools(&mut self, tools: &[String]) {
+        for tool in tools {
+            self.approved_tools.remove(tool);
+        }
+    }
+
+    /// Tools and files approved for all uses, to carry over to a recreated agent
+    pub fn approvals(&self) -> (HashSet<String>, HashSet<PathBuf>) {
+        (self.approved_tools.clone(), self.approved_files.clone())
+    }
+
+    /// Restore approvals taken from an earlier agent with `approvals`
+    pub fn set_approvals(&mut self, (tools, files): (HashSet<String>, HashSet<PathBuf>)) {
+        self.approved_tools = tools;
+        self.approved_files = files;
+    }
+
+    /// Forget every tool and per-file approval
+    pub fn revoke_all_approvals(&mut self) {
+        self.approved_tools.clear();
+        self.approved_files.clear();
+    }
+
+    /// Granted tools, tools and files approved for all uses, and dry-run state
+    pub fn permissions_summary(&self) -> String {
+        let mut tools: Vec<&str> = self.approved_tools.iter().map(|t| t.as_str()).collect();
+        tools.sort();
+        let mut files: Vec<String> = self.approved_files.iter().map(|p| p.display().to_string()).collect();
+        files.sort();
+
+        let mut text = format!("Granted:       {}", self.permissions.summary());
+        text.push_str(&format!(
+            "\nAuto-approved: {}",
+            if tools.is_empty() { "none (every call asks)".to_string() } else { tools.join(", ") }
+        ));
+        if !files.is_empty() {
+            text.push_str(&format!("\nApproved files:\n  {}", files.join("\n  ")));
+        }
+        text.push_str(&format!(
+            "\nDry run:       {}",
+            if self.dry_run_writes || self.permissions.is_dry_run() { "on" } else { "off" }
+        ));
+        text
+    }
+
+    /// Whether a call to this tool is simulated instead of executed
+    fn is_simulated(&self, tool_name: &str) -> bool {
+        self.permissions.is_dry_run() || (self.dry_run_writes && FILE_WRITE_TOOLS.contains(&tool_name))
+    }
+
+    /// Checkpoint the working tree after every turn that changed it
+    pub fn set_checkpoint(&mut self, checkpoint: Checkpoint) {
+        self.checkpoint = Some(checkpoint);
+    }
+
+    /// Commit a checkpoint for the turn that started at `turn_start`.
+    /// Failures are reported but never fail the turn.
+    fn checkpoint_turn(&self, user_input: &str, turn_start: Instant) {
+        let Some(ref checkpoint) = self.checkpoint else {
+            return;
+        };
+
+        let mut changed: Vec<&FileChange> = self
+            .file_changes
+            .values()
+            .filter(|change| change.timestamp >= turn_start)
+            .collect();
+        changed.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let request = user_input.lines().next().unwrap_or("").trim();
+        let mut subject: String = request.chars().take(60).collect();
+        if subject.chars().count() < request.chars().count() {
+            subject.push_str("...");
+        }
+        let mut message = format!("agent-t: {}\n", subject);
+        if !changed.is_empty() {
+            message.push_str("\nFiles changed:\n");
+            for change in &changed {
+                let path = Path::new(&change.path)
+                    .strip_prefix(&self.working_directory)
+                    .unwrap_or(Path::new(&change.path));
+                message.push_str(&format!("- {} ({})\n", path.display(), change.operation));
+            }
+        }
+
+        let agent_id = self.agent_id.clone();
+        let event = match checkpoint.commit(&message) {
+            Ok(Some(commit)) => TuiEvent::Info {
+                agent_id,
+                text: format!("Checkpoint {} on {}", commit, checkpoint.branch()),
+            },
+            Ok(None) => return,
+            Err(e) => TuiEvent::Warning {
+                agent_id,
+                text: format!("Checkpoint failed: {}", e),
+            },
+        };
+        if let Some(ref tx) = self.tui_tx {
+            let _ = tx.try_send(event);
+        } else if let TuiEvent::Info { text, .. } | TuiEvent::Warning { text, .. } = event {
+            eprintln!("{}", text);
+        }
+    }
+
+    /// Append a tool call to the audit log, if one is configured
+    fn audit_tool(&self, tool_name: &str, args: &Value, result: &str, duration_ms: u64, approved: bool) {
+        if let Some(ref audit_log) = self.audit_log {
+            let args = mask_secret_env(args);
+            let mut entry = AuditEntry::new(&self.agent_id, tool_name, &args, result, duration_ms);
+            entry.approved = approved;
+            entry.dry_run = self.is_simulated(tool_name);
+            audit_log.record(&entry);
+        }
+    }
+
     /// Set the agent ID (for sub-agents)
     pub fn set_agent_id(&mut self, agent_id: String) {
         self.agent_id = agent_id;
@@ -225,6 +883,26 @@ impl<M: CompletionModel> AgentLoop<M> {
         &self.token_usage
     }
 
+    /// Get per-tool and LLM timing
+    pub fn get_profile(&self) -> &ExecutionProfile {
+        &self.profile
+    }
+
+    /// Get per-tool success/failure counts
+    pub fn get_tool_stats(&self) -> &ToolStats {
+        &self.tool_stats
+    }
+
+    /// Continue the timing of an agent this one replaces
+    pub fn set_profile(&mut self, profile: ExecutionProfile) {
+        self.profile = profile;
+    }
+
+    /// Continue the tool call counts of an agent this one replaces
+    pub fn set_tool_stats(&mut self, tool_stats: ToolStats) {
+        self.tool_stats = tool_stats;
+    }
+
     /// Get the number of iterations used in the current chat session
     pub fn iteration_count(&self) -> usize {
         self.iteration_count
@@ -248,6 +926,8 @@ impl<M: CompletionModel> AgentLoop<M> {
 
             // Set the sub-agent's ID for event routing
             agent.set_agent_id(agent_id.clone());
+            let kill_token = agent.cancel_token.clone();
+            let registration = SubagentRegistration::new(&agent_id, kill_token.clone());
 
             // Notify TUI to create a tab for this sub-agent
             if let Some(ref tx) = self.tui_tx {
@@ -260,12 +940,30 @@ impl<M: CompletionModel> AgentLoop<M> {
             let timeout_duration = Duration::from_secs(args.timeout_secs.unwrap_or(300));
 
             // Execute with timeout (this blocks until sub-agent completes)
-            //eprintln!("DEBUG run_subagent: Starting sub-agent {} with instructions: {}",
-            //         agent_id, &args.instructions[..args.instructions.len().min(100)]);
+            tracing::debug!(
+                "Starting sub-agent {} with instructions: {}",
+                agent_id,
+                terminal::truncate_chars(&args.instructions, 100)
+            );
             let start_time = std::time::Instant::now();
-            let result = timeout(timeout_duration, agent.chat(&args.instructions)).await;
+            let result = tokio::select! {
+                result = timeout(timeout_duration, agent.chat(&args.instructions)) => Some(result),
+                _ = kill_token.cancelled() => None,
+            };
             let elapsed = start_time.elapsed();
-            //eprintln!("DEBUG run_subagent: Sub-agent {} completed in {:?}", agent_id, elapsed);
+            tracing::debug!("Sub-agent {} finished in {:?}", agent_id, elapsed);
+            drop(registration);
+
+            // Cancelled: killed from its tab, or interrupted along with its parent
+            let Some(result) = result else {
+                if let Some(ref tx) = self.tui_tx {
+                    let _ = tx.try_send(TuiEvent::TabKill {
+                        agent_id: agent_id.clone(),
+                    });
+                }
+                let error = "Sub-agent was stopped before finishing; its work so far may be incomplete";
+                return Err(anyhow!(SubAgentResult::new(&agent, elapsed, Err(error.to_string())).to_json()));
+            };
 
             // Notify TUI of completion or failure
             match result {
@@ -273,15 +971,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                     // Sub-agent succeeded
                     let file_count = agent.file_changes_count();
                     let iterations = agent.iteration_count();
-
-                    // Create completion summary
-                    let summary = format!(
-                        "Sub-agent completed successfully.\n\
-                         Result: {}\n\
-                         Files changed: {}\n\
-                         Iterations used: {}",
-                        response, file_count, iterations
-                    );
+                    let summary = SubAgentResult::new(&agent, elapsed, Ok(response.clone()));
 
                     // Display final response and completion info in the sub-agent's tab
                     if let Some(ref tx) = self.tui_tx {
@@ -303,7 +993,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                         });
                     }
 
-                    Ok(summary)
+                    Ok(summary.to_json())
                 }
                 Ok(Err(e)) => {
                     // Sub-agent failed
@@ -314,7 +1004,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                             error: error_msg.clone(),
                         });
                     }
-                    Err(anyhow!(error_msg))
+                    Err(anyhow!(SubAgentResult::new(&agent, elapsed, Err(error_msg)).to_json()))
                 }
                 Err(_) => {
                     // Timeout
@@ -328,12 +1018,31 @@ impl<M: CompletionModel> AgentLoop<M> {
                             error: error_msg.clone(),
                         });
                     }
-                    Err(anyhow!(error_msg))
+                    Err(anyhow!(SubAgentResult::new(&agent, elapsed, Err(error_msg)).to_json()))
                 }
             }
         })
     }
 
+    /// Push the new contents of an edited Rust file to rust-analyzer so later
+    /// ra_* calls don't answer from a stale copy
+    async fn notify_rust_analyzer(&self, path: &str) {
+        if !path.ends_with(".rs") || !ra_common::is_available().await {
+            return;
+        }
+        let Ok(client) = ra_common::get_client().await else {
+            return;
+        };
+
+        let absolute = std::path::Path::new(&self.working_directory).join(path);
+        let Ok(uri) = lsp_types::Url::from_file_path(&absolute) else {
+            return;
+        };
+        if let Ok(text) = tokio::fs::read_to_string(&absolute).await {
+            let _ = client.did_change(uri, text).await;
+        }
+    }
+
     /// Record a file change
     fn record_file_change(&mut self, path: &str, operation: FileOperation) {
         // Normalize path for consistent tracking
@@ -346,6 +1055,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                 .to_string()
         };
 
+        self.turn_files.insert(crate::tools::agent_ignore::normalize(Path::new(&normalized_path)));
         self.file_changes.insert(
             normalized_path.clone(),
             FileChange {
@@ -376,6 +1086,24 @@ impl<M: CompletionModel> AgentLoop<M> {
     /// Get all tool definitions for the agent
     async fn get_tool_definitions(&self) -> Vec<ToolDefinition> {
         let cwd_note = format!("Relative paths are resolved from: {}", self.working_directory);
+        let context_dirs = context_dirs::dirs();
+        let search_note = if context_dirs.is_empty() {
+            cwd_note.clone()
+        } else {
+            let dirs: Vec<String> = context_dirs.iter().map(|d| d.display().to_string()).collect();
+            format!(
+                "{}. Without an explicit path it also searches these read-only context directories: {}",
+                cwd_note,
+                dirs.join(", ")
+            )
+        };
+        let bash_note = match self.shell_session {
+            Some(ref session) => format!(
+                "Commands run in a persistent shell: the directory and exported variables carry over to the next call (a call with working_dir or background=true runs on its own). Current directory: {}",
+                session.lock().await.cwd().display()
+            ),
+            None => format!("Commands run in: {}", self.working_directory),
+        };
         let mut tools = vec![
             ToolDefinition {
                 name: "read_file".to_string(),
@@ -399,6 +1127,29 @@ impl<M: CompletionModel> AgentLoop<M> {
                     "required": ["file_path"]
                 }),
             },
+            ToolDefinition {
+                name: "summarize_file".to_string(),
+                description: format!(
+                    "Summarize a file too large to read whole: a separate model request returns its purpose, \
+                     top-level items with line numbers and key functions instead of the raw contents. \
+                     Use read_file with offset and limit afterwards for the parts you need. {}",
+                    cwd_note
+                ),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "file_path": {
+                            "type": "string",
+                            "description": "Path to the file (absolute or relative to working directory)"
+                        },
+                        "focus": {
+                            "type": "string",
+                            "description": "Optional aspect to pay particular attention to (e.g. 'error handling', 'the public API')"
+                        }
+                    },
+                    "required": ["file_path"]
+                }),
+            },
             ToolDefinition {
                 name: "write_file".to_string(),
                 description: format!("Write content to a file. Creates the file if it doesn't exist, or overwrites if it does. {}", cwd_note),
@@ -443,6 +1194,32 @@ impl<M: CompletionModel> AgentLoop<M> {
                     "required": ["file_path", "old_string", "new_string"]
                 }),
             },
+            ToolDefinition {
+                name: "edit_markdown_section".to_string(),
+                description: format!(
+                    "Replace the body of a Markdown section addressed by its heading path, e.g. \"## Installation > ### Linux\". \
+                     The body runs to the next heading of the same or higher level. Prefer this over edit_file for prose. {}",
+                    cwd_note
+                ),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "file_path": {
+                            "type": "string",
+                            "description": "Path to the Markdown file (absolute or relative to working directory)"
+                        },
+                        "heading_path": {
+                            "type": "string",
+                            "description": "Headings from outermost to target separated by '>'; '#' markers optional, case-insensitive"
+                        },
+                        "new_body": {
+                            "type": "string",
+                            "description": "New content for the section, without its heading line"
+                        }
+                    },
+                    "required": ["file_path", "heading_path", "new_body"]
+                }),
+            },
             ToolDefinition {
                 name: "list_dir".to_string(),
                 description: format!("List the contents of a directory. {}", cwd_note),
@@ -452,6 +1229,10 @@ impl<M: CompletionModel> AgentLoop<M> {
                         "path": {
                             "type": "string",
                             "description": "Path to the directory (absolute or relative to working directory). Use '.' for current directory."
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of entries to return (default: 500)"
                         }
                     },
                     "required": ["path"]
@@ -459,7 +1240,7 @@ impl<M: CompletionModel> AgentLoop<M> {
             },
             ToolDefinition {
                 name: "bash".to_string(),
-                description: format!("Execute a bash command and return the output. Can run in background for long-running tasks. Commands run in: {}", self.working_directory),
+                description: format!("Execute a shell command (run with {}) and return the output. Can run in background for long-running tasks. {}", shell::program(), bash_note),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -478,6 +1259,11 @@ impl<M: CompletionModel> AgentLoop<M> {
                         "background": {
                             "type": "boolean",
                             "description": "Execute in background and return immediately with process ID. Use bash_status/bash_output/bash_kill tools to manage."
+                        },
+                        "env": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                            "description": "Optional environment variables for this command only, e.g. {\"RUST_LOG\": \"debug\"}. Prefer this over prefixing the command with FOO=bar."
                         }
                     },
                     "required": ["command"]
@@ -485,7 +1271,7 @@ impl<M: CompletionModel> AgentLoop<M> {
             },
             ToolDefinition {
                 name: "grep".to_string(),
-                description: format!("Search for a pattern in files using ripgrep. {}", cwd_note),
+                description: format!("Search for a pattern in files using ripgrep. {}", search_note),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -511,7 +1297,7 @@ impl<M: CompletionModel> AgentLoop<M> {
             },
             ToolDefinition {
                 name: "glob".to_string(),
-                description: format!("Find files matching a glob pattern. {}", cwd_note),
+                description: format!("Find files matching a glob pattern. {}", search_note),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -528,35 +1314,91 @@ impl<M: CompletionModel> AgentLoop<M> {
                 }),
             },
             ToolDefinition {
-                name: "bash_status".to_string(),
-                description: "Check the status of a background bash process. Returns whether the process is running, completed, or failed.".to_string(),
+                name: "diff_files".to_string(),
+                description: format!("Compare two files, or a file against inline text, and return a unified diff. {}", cwd_note),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
-                        "process_id": {
+                        "old_path": {
                             "type": "string",
-                            "description": "The process ID returned by bash command with background=true"
+                            "description": "Path to the original file"
+                        },
+                        "new_path": {
+                            "type": "string",
+                            "description": "Path to the changed file"
+                        },
+                        "new_content": {
+                            "type": "string",
+                            "description": "Text to compare against old_path (instead of new_path)"
+                        },
+                        "context_lines": {
+                            "type": "integer",
+                            "description": "Lines of context around each change (default: 3)"
                         }
                     },
-                    "required": ["process_id"]
+                    "required": ["old_path"]
                 }),
             },
             ToolDefinition {
-                name: "bash_output".to_string(),
-                description: "Get the accumulated stdout and stderr output from a background bash process.".to_string(),
+                name: "git_status".to_string(),
+                description: "Show the git branch, staged, unstaged and untracked files, and the most recent commits. Use instead of running git status through bash.".to_string(),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
-                        "process_id": {
+                        "path": {
                             "type": "string",
-                            "description": "The process ID returned by bash command with background=true"
+                            "description": "Directory inside the repository (default: working directory)"
                         }
-                    },
-                    "required": ["process_id"]
+                    }
                 }),
             },
             ToolDefinition {
-                name: "bash_kill".to_string(),
+                name: "git_diff".to_string(),
+                description: "Show uncommitted changes as unified diffs: staged changes (index vs HEAD), unstaged changes (working tree vs index), or both. Untracked files are not included; see git_status.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "staged": {
+                            "type": "boolean",
+                            "description": "true for staged changes only, false for unstaged only (default: both)"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Limit the diff to this file or directory"
+                        }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "bash_status".to_string(),
+                description: "Check the status of a background bash process. Returns whether the process is running, completed, or failed.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "process_id": {
+                            "type": "string",
+                            "description": "The process ID returned by bash command with background=true"
+                        }
+                    },
+                    "required": ["process_id"]
+                }),
+            },
+            ToolDefinition {
+                name: "bash_output".to_string(),
+                description: "Get the accumulated stdout and stderr output from a background bash process.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "process_id": {
+                            "type": "string",
+                            "description": "The process ID returned by bash command with background=true"
+                        }
+                    },
+                    "required": ["process_id"]
+                }),
+            },
+            ToolDefinition {
+                name: "bash_kill".to_string(),
                 description: "Terminate a background bash process.".to_string(),
                 parameters: serde_json::json!({
                     "type": "object",
@@ -580,7 +1422,7 @@ impl<M: CompletionModel> AgentLoop<M> {
             },
             ToolDefinition {
                 name: "web_fetch".to_string(),
-                description: "Fetch content from a URL. Automatically converts HTML to readable text. Returns content with metadata (status, content type, final URL).".to_string(),
+                description: "Fetch content from a URL. HTML is converted to text: by default only the main content (mode 'readable'), or the whole page as plain text ('raw') or with headings and links ('markdown'). Returns content with metadata (status, content type, final URL).".to_string(),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -590,7 +1432,20 @@ impl<M: CompletionModel> AgentLoop<M> {
                         },
                         "size_limit_kb": {
                             "type": "integer",
-                            "description": "Optional size limit in KB (default: 100KB, max: 500KB)"
+                            "description": "Size of the returned text in KB (default: 100KB, max: 500KB). Longer pages are cut off at this size; use offset to read on."
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Character offset to start from, as given by an earlier call on the same page (default: 0)"
+                        },
+                        "no_cache": {
+                            "type": "boolean",
+                            "description": "Bypass the result cache and fetch a fresh copy (default: false)"
+                        },
+                        "mode": {
+                            "type": "string",
+                            "enum": ["readable", "raw", "markdown"],
+                            "description": "How HTML is converted: 'readable' keeps the main content (default), 'raw' the whole page as text, 'markdown' the whole page with headings and links"
                         }
                     },
                     "required": ["url"]
@@ -598,7 +1453,7 @@ impl<M: CompletionModel> AgentLoop<M> {
             },
             ToolDefinition {
                 name: "web_search".to_string(),
-                description: "Search the web using DuckDuckGo. Returns a list of search results with title, URL, and snippet for each result.".to_string(),
+                description: "Search the web. Returns a list of search results with title, URL, and snippet for each result.".to_string(),
                 parameters: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -609,11 +1464,37 @@ impl<M: CompletionModel> AgentLoop<M> {
                         "num_results": {
                             "type": "integer",
                             "description": "Number of results to return (default: 5, max: 10)"
+                        },
+                        "no_cache": {
+                            "type": "boolean",
+                            "description": "Bypass the result cache and run a fresh search (default: false)"
                         }
                     },
                     "required": ["query"]
                 }),
             },
+            ToolDefinition {
+                name: "crate_docs".to_string(),
+                description: "Read a Rust crate's API documentation from docs.rs: signatures and docs of an item (struct, trait, function, macro, ...), or the crate overview when no item is given. Use this instead of guessing an unfamiliar crate's API.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {
+                            "type": "string",
+                            "description": "Crate name as on crates.io, e.g. 'tokio' or 'serde_json'"
+                        },
+                        "item": {
+                            "type": "string",
+                            "description": "Item path inside the crate, e.g. 'sync::Mutex' or just 'Mutex' (default: crate overview)"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "Crate version, e.g. '1.40.0' (default: latest)"
+                        }
+                    },
+                    "required": ["crate_name"]
+                }),
+            },
             ToolDefinition {
                 name: "math_calc".to_string(),
                 description: "Evaluate mathematical expressions. Supports basic arithmetic (+, -, *, /), exponentiation (^), parentheses, and common mathematical functions (sin, cos, tan, sqrt, log, ln, abs, etc.). Use this tool for any mathematical calculations.".to_string(),
@@ -628,6 +1509,28 @@ impl<M: CompletionModel> AgentLoop<M> {
                     "required": ["expression"]
                 }),
             },
+            ToolDefinition {
+                name: "json_query".to_string(),
+                description: "Extract values from a JSON file or inline JSON with a path expression, instead of reading the whole document. Supports a jq subset (.a.b, .[0], .[-1], .[1:3], .[], .., '|', keys, length, ? to skip errors) and JSONPath ($.a[*].b, $..b, ['key']). Each match is printed as JSON.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Path expression, e.g. '.dependencies | keys', '.items[].name' or '$..id'"
+                        },
+                        "file_path": {
+                            "type": "string",
+                            "description": "JSON file to query"
+                        },
+                        "json": {
+                            "type": "string",
+                            "description": "Inline JSON to query (instead of file_path)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
         ];
 
         // Add memory tools if memory is enabled
@@ -686,6 +1589,10 @@ impl<M: CompletionModel> AgentLoop<M> {
                             "description": "Number of results to return (default: 5, max: 20)",
                             "minimum": 1,
                             "maximum": 20
+                        },
+                        "all_projects": {
+                            "type": "boolean",
+                            "description": "Also search conversations held in other projects (default: false)"
                         }
                     },
                     "required": ["query"]
@@ -720,6 +1627,34 @@ impl<M: CompletionModel> AgentLoop<M> {
                             "type": "string",
                             "enum": ["low", "medium", "high", "critical"],
                             "description": "Optional: Only return memories at or above this importance level"
+                        },
+                        "all_projects": {
+                            "type": "boolean",
+                            "description": "Also search memories from other projects (default: false, only this project and facts about the user)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            });
+        }
+
+        // Add semantic code search if the vecdb is enabled
+        if self.vecdb.is_some() {
+            tools.push(ToolDefinition {
+                name: "vecdb_search".to_string(),
+                description: "Semantic search over the indexed code base (--vecdb). Finds code by meaning rather than exact text (e.g. 'where are retries handled'); use grep for exact names. Returns file, line range, similarity and the code of each match.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "What to look for, described in words or as a code snippet"
+                        },
+                        "top_k": {
+                            "type": "integer",
+                            "description": "Number of results to return (default: 5, max: 20)",
+                            "minimum": 1,
+                            "maximum": 20
                         }
                     },
                     "required": ["query"]
@@ -733,6 +1668,8 @@ impl<M: CompletionModel> AgentLoop<M> {
                     "Spawn an independent sub-agent to work on a separate task. \
                      The sub-agent has its own context and can use all tools. \
                      Use this to delegate focused tasks that can be completed independently. \
+                     Returns JSON: success, response (the sub-agent's final answer), error, \
+                     files_changed ([{{path, operation}}]), iterations and elapsed_secs. \
                      Current depth: {}/{}",
                     self.depth, MAX_DEPTH
                 ),
@@ -760,6 +1697,130 @@ impl<M: CompletionModel> AgentLoop<M> {
                 }),
             });
 
+        tools.push(ToolDefinition {
+            name: "scratch_write".to_string(),
+            description: "Store text under a key in the scratchpad shared by the main agent and all its sub-agents. \
+                          Use it to hand results between agents, e.g. each sub-agent writes its part under its own key \
+                          and the parent reads them all with scratch_read.".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Key to store the value under (e.g. 'api_summary', 'review/src_main')"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "Text to store"
+                    },
+                    "append": {
+                        "type": "boolean",
+                        "description": "Append to the key's current value on a new line instead of replacing it (default: false)"
+                    }
+                },
+                "required": ["key", "value"]
+            }),
+        });
+        tools.push(ToolDefinition {
+            name: "scratch_read".to_string(),
+            description: "Read a value from the scratchpad shared by the main agent and all its sub-agents, \
+                          or list every key with a preview when no key is given.".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Key to read. Omit to list all keys"
+                    }
+                }
+            }),
+        });
+
+        // The process has a single current directory, so only the main agent may move it
+        if self.depth == 0 {
+            tools.push(ToolDefinition {
+                name: "change_directory".to_string(),
+                description: "Change the working directory used to resolve relative paths for all subsequent tool calls. Use this to work in a subdirectory or sibling repository instead of passing absolute paths everywhere.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Directory to switch to (absolute, or relative to the current working directory)"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            });
+        }
+
+        // Only add cargo tools in Cargo projects
+        if std::path::Path::new(&self.working_directory).join("Cargo.toml").exists() {
+            tools.push(ToolDefinition {
+                name: "run_tests".to_string(),
+                description: "Run `cargo test` and return a structured summary: passed/failed/ignored counts and, for each failure, the test name, file, line and assertion message. Compilation errors are reported as diagnostics. Prefer this over running cargo test through bash.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filter": {"type": "string", "description": "Only run tests whose name contains this string"},
+                        "package": {"type": "string", "description": "Package to test in a workspace (cargo -p)"},
+                        "working_dir": {"type": "string", "description": "Optional working directory (defaults to project root)"},
+                        "timeout_secs": {"type": "integer", "description": "Optional timeout in seconds (default: 600)"}
+                    },
+                    "required": []
+                }),
+            });
+            tools.push(ToolDefinition {
+                name: "cargo_check".to_string(),
+                description: "Run `cargo check` on the whole workspace and return compiler diagnostics as a list of file, line, level, code and message entries, errors first. Faster than a full build and does not need rust-analyzer. Use it after edits to verify the code compiles.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "package": {"type": "string", "description": "Package to check in a workspace (cargo -p)"},
+                        "all_targets": {"type": "boolean", "description": "Also check tests, examples and benches (default: false)"},
+                        "include_warnings": {"type": "boolean", "description": "Include warnings in the result (default: true)"},
+                        "working_dir": {"type": "string", "description": "Optional working directory (defaults to project root)"},
+                        "timeout_secs": {"type": "integer", "description": "Optional timeout in seconds (default: 300)"}
+                    },
+                    "required": []
+                }),
+            });
+            tools.push(ToolDefinition {
+                name: "add_dependency".to_string(),
+                description: "Add a dependency to Cargo.toml with `cargo add`, which picks a real version and checks feature names. Returns cargo's report and the manifest diff. Prefer this over editing Cargo.toml.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {"type": "string", "description": "Crate to add, e.g. 'serde'"},
+                        "version": {"type": "string", "description": "Version requirement, e.g. '1.0' (default: latest)"},
+                        "features": {"type": "array", "items": {"type": "string"}, "description": "Features to enable"},
+                        "no_default_features": {"type": "boolean", "description": "Disable the crate's default features (default: false)"},
+                        "optional": {"type": "boolean", "description": "Add as an optional dependency (default: false)"},
+                        "dev": {"type": "boolean", "description": "Add to [dev-dependencies] (default: false)"},
+                        "build": {"type": "boolean", "description": "Add to [build-dependencies] (default: false)"},
+                        "package": {"type": "string", "description": "Package to change in a workspace (cargo -p)"},
+                        "working_dir": {"type": "string", "description": "Optional working directory (defaults to project root)"}
+                    },
+                    "required": ["crate_name"]
+                }),
+            });
+            tools.push(ToolDefinition {
+                name: "remove_dependency".to_string(),
+                description: "Remove a dependency from Cargo.toml with `cargo remove`. Returns cargo's report and the manifest diff.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "crate_name": {"type": "string", "description": "Crate to remove"},
+                        "dev": {"type": "boolean", "description": "Remove from [dev-dependencies] (default: false)"},
+                        "build": {"type": "boolean", "description": "Remove from [build-dependencies] (default: false)"},
+                        "package": {"type": "string", "description": "Package to change in a workspace (cargo -p)"},
+                        "working_dir": {"type": "string", "description": "Optional working directory (defaults to project root)"}
+                    },
+                    "required": ["crate_name"]
+                }),
+            });
+        }
+
         // Only add rust-analyzer tools if rust-analyzer is available
         if ra_common::is_available().await {
             tools.extend(vec![
@@ -885,6 +1946,16 @@ impl<M: CompletionModel> AgentLoop<M> {
             ]);
         }
 
+        // Without network access the web tools would only return errors
+        if !crate::tools::network::allowed() {
+            tools.retain(|tool| !crate::tools::network::NETWORK_TOOLS.contains(&tool.name.as_str()));
+        }
+
+        // Review mode: don't offer tools that would be refused anyway
+        if self.permissions.is_review() {
+            tools.retain(|tool| self.permissions.is_granted(&tool.name));
+        }
+
         tools
     }
 
@@ -959,17 +2030,173 @@ impl<M: CompletionModel> AgentLoop<M> {
                     &new_content,
                 ))
             }
+            "edit_markdown_section" => {
+                let file_path = args.get("file_path")?.as_str()?;
+                let heading_path = args.get("heading_path")?.as_str()?;
+                let new_body = args.get("new_body")?.as_str()?;
+
+                let path = std::path::Path::new(&self.working_directory).join(file_path);
+                let old_content = fs::read_to_string(&path).await.ok()?;
+                let new_content = crate::tools::edit_markdown::replace_section(&old_content, heading_path, new_body).ok()?;
+
+                Some(crate::diff::UnifiedDiff::from_texts(
+                    file_path.to_string(),
+                    &old_content,
+                    &new_content,
+                ))
+            }
             _ => None,
         }
     }
 
     /// Request permission to execute a tool
     async fn request_permission(&mut self, tool_name: &str, args: &HashMap<String, String>, diff: Option<crate::diff::UnifiedDiff>) -> bool {
-        // Check if tool is already approved for all
+        if !self.needs_permission(tool_name, args) {
+            return true;
+        }
+
+        self.prompt_permission(tool_name, args, diff).await
+    }
+
+    /// Whether a call may run alongside its neighbours: a read-only tool that
+    /// is granted and runs without a prompt, outside dry-run
+    fn runs_concurrently(&self, tool_name: &str, args: &Value, batch_approved: bool) -> bool {
+        CONCURRENT_TOOLS.contains(&tool_name)
+            && self.permissions.is_granted(tool_name)
+            && !self.permissions.is_dry_run()
+            && (batch_approved
+                || self.tui_tx.is_none()
+                || self.permissions.should_skip_confirmations()
+                || !self.needs_permission(tool_name, &display_args(args)))
+    }
+
+    /// Run the leading calls of `calls` that may run concurrently, all at once.
+    /// Results come back in call order with each call's own duration; later
+    /// calls (writes, bash, ...) are left to run in order after them.
+    async fn execute_concurrently(&self, calls: &[ToolCall], batch_approved: bool) -> Vec<(Result<String, ToolError>, Duration)>
+    where
+        M: Clone,
+    {
+        let run: Vec<&ToolCall> = calls
+            .iter()
+            .take_while(|call| self.runs_concurrently(&call.function.name, &call.function.arguments, batch_approved))
+            .collect();
+        if run.len() > 1 {
+            tracing::debug!(agent_id = %self.agent_id, calls = run.len(), "Running read-only tool calls concurrently");
+        }
+        futures::future::join_all(run.into_iter().map(|call| async move {
+            let start = Instant::now();
+            let result = self.execute_tool(&call.function.name, call.function.arguments.clone()).await;
+            (result, start.elapsed())
+        }))
+        .await
+    }
+
+    /// Whether a call still has to be approved: neither the tool nor its file
+    /// has been approved for all uses
+    fn needs_permission(&self, tool_name: &str, args: &HashMap<String, String>) -> bool {
         if self.approved_tools.contains(tool_name) {
+            return false;
+        }
+        !self
+            .approval_path(tool_name, args)
+            .is_some_and(|path| self.approved_files.contains(&path))
+    }
+
+    /// Show the calls of a response that need approval together, when there
+    /// are several; fewer than two go straight to the per-call prompt
+    async fn prompt_batch_permission(&self, calls: Vec<(String, HashMap<String, String>)>) -> crate::tui::BatchDecision {
+        let Some(ref tx) = self.tui_tx else {
+            return crate::tui::BatchDecision::Review;
+        };
+        if calls.len() < 2 {
+            return crate::tui::BatchDecision::Review;
+        }
+
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        let event = crate::tui::TuiEvent::BatchPermissionRequest { calls, response_tx };
+        if tx.send(event).await.is_err() {
+            return crate::tui::BatchDecision::Review;
+        }
+        // A closed channel leaves it to the per-call prompts, which reject
+        response_rx.await.unwrap_or(crate::tui::BatchDecision::Review)
+    }
+
+    /// Whether a call would take the turn past --max-files-per-turn and
+    /// changing more files hasn't been approved yet
+    fn exceeds_file_limit(&self, tool_name: &str, args: &HashMap<String, String>) -> bool {
+        let Some(limit) = self.max_files_per_turn else {
+            return false;
+        };
+        if self.turn_files_approved || self.is_simulated(tool_name) {
+            return false;
+        }
+        self.approval_path(tool_name, args)
+            .is_some_and(|path| !self.turn_files.contains(&path) && self.turn_files.len() >= limit)
+    }
+
+    /// Ask whether the turn may change more files than --max-files-per-turn,
+    /// listing the files changed so far and those the response still targets.
+    /// Without the TUI there is nobody to ask, so the turn stops.
+    async fn prompt_file_limit(&self, tool_calls: &[ToolCall]) -> bool {
+        let Some(ref tx) = self.tui_tx else {
+            return false;
+        };
+        let Some(limit) = self.max_files_per_turn else {
+            return true;
+        };
+
+        let mut changed: Vec<String> = self.turn_files.iter().map(|path| path.display().to_string()).collect();
+        changed.sort();
+        let mut pending: Vec<String> = Vec::new();
+        for call in tool_calls {
+            let args = display_args(&call.function.arguments);
+            if let Some(path) = self.approval_path(&call.function.name, &args)
+                && !self.turn_files.contains(&path) {
+                    let path = path.display().to_string();
+                    if !pending.contains(&path) {
+                        pending.push(path);
+                    }
+                }
+        }
+
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        let event = crate::tui::TuiEvent::FileLimitRequest { limit, changed, pending, response_tx };
+        if tx.send(event).await.is_err() {
+            return false;
+        }
+        response_rx.await.unwrap_or(false)
+    }
+
+    /// Show a proposed plan for approval. Without the TUI it is approved as printed.
+    async fn prompt_plan_approval(&self, plan: &str) -> bool {
+        let Some(ref tx) = self.tui_tx else {
             return true;
+        };
+
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        let event = crate::tui::TuiEvent::PlanApproval {
+            plan: plan.to_string(),
+            response_tx,
+        };
+        if tx.send(event).await.is_err() {
+            return false;
         }
+        // A closed channel means the turn was interrupted
+        response_rx.await.unwrap_or(false)
+    }
+
+    /// Normalized absolute path a file-writing tool call targets, for per-file approval
+    fn approval_path(&self, tool_name: &str, args: &HashMap<String, String>) -> Option<PathBuf> {
+        if !FILE_WRITE_TOOLS.contains(&tool_name) {
+            return None;
+        }
+        let path = Path::new(&self.working_directory).join(args.get("file_path")?);
+        Some(crate::tools::agent_ignore::normalize(&path))
+    }
 
+    /// Show the permission modal, even for tools approved for all uses
+    async fn prompt_permission(&mut self, tool_name: &str, args: &HashMap<String, String>, diff: Option<crate::diff::UnifiedDiff>) -> bool {
         // If no TUI sender, auto-approve (fallback for non-TUI mode)
         let Some(ref tx) = self.tui_tx else {
             return true;
@@ -998,22 +2225,93 @@ impl<M: CompletionModel> AgentLoop<M> {
                 self.approved_tools.insert(tool_name.to_string());
                 true
             }
+            Ok(crate::tui::PermissionDecision::ApproveFile) => {
+                if let Some(path) = self.approval_path(tool_name, args) {
+                    self.approved_files.insert(path);
+                }
+                true
+            }
             Ok(crate::tui::PermissionDecision::Reject) => false,
             Err(_) => false, // Channel closed, default to reject
         }
     }
 
+    /// Ask before writing unusually large content. --yes and --no-confirm skip the question.
+    async fn confirm_large_write(
+        &mut self,
+        tool_name: &str,
+        args: &HashMap<String, String>,
+        diff: Option<crate::diff::UnifiedDiff>,
+        len: u64,
+    ) -> bool {
+        let msg = format!(
+            "Large write: {} bytes to {} (limit {} bytes)",
+            len,
+            args.get("file_path").map(String::as_str).unwrap_or("?"),
+            file_limits::max_file_size()
+        );
+
+        if self.permissions.should_skip_confirmations() || !self.confirm_dangerous {
+            return true;
+        }
+
+        if let Some(ref tx) = self.tui_tx {
+            terminal::emit_warning(tx, &self.agent_id, &msg);
+            return self.prompt_permission(tool_name, args, diff).await;
+        }
+
+        matches!(terminal::confirm(&msg), Ok(true))
+    }
+
+    /// Shows the output of a foreground bash command in the TUI while it runs
+    fn bash_line_sink(&self) -> Option<Box<LineSink>> {
+        let tx = self.tui_tx.clone()?;
+        let agent_id = self.agent_id.clone();
+        let shown = std::sync::atomic::AtomicUsize::new(0);
+        Some(Box::new(move |line: &str| {
+            match shown.fetch_add(1, std::sync::atomic::Ordering::Relaxed) {
+                n if n < MAX_STREAMED_BASH_LINES => terminal::emit_info(&tx, &agent_id, &format!("│ {}", line)),
+                n if n == MAX_STREAMED_BASH_LINES => {
+                    terminal::emit_info(&tx, &agent_id, "│ ... (further output is in the tool result)")
+                }
+                _ => {}
+            }
+        }))
+    }
+
     /// Execute a tool by name with the given arguments
-    async fn execute_tool(&self, name: &str, args: Value) -> Result<String, ToolError>
+    async fn execute_tool(&self, name: &str, mut args: Value) -> Result<String, ToolError>
     where
         M: Clone,
     {
+        // Relative paths are the working directory's, which /cd may have moved
+        paths::resolve_tool_paths(name, Path::new(&self.working_directory), &mut args)?;
+
         match name {
             "read_file" => {
                 let tool_args = serde_json::from_value(args)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
                 ReadFile.call(tool_args).await
             }
+            "summarize_file" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                // A plain request: no reasoning, and half the context window for the file
+                let mut model_options = self.model_options();
+                if let Some(options) = model_options.as_object_mut() {
+                    options.remove("think");
+                }
+                SummarizeFile {
+                    model: self.model.clone(),
+                    model_options,
+                    max_input_chars: self.context_size * 2,
+                    token_budget: self.token_budget.clone(),
+                    request_limiter: self.request_limiter.clone(),
+                    traffic: self.traffic.clone(),
+                }
+                .call(tool_args)
+                .await
+            }
             "write_file" => {
                 let tool_args = serde_json::from_value(args)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
@@ -1024,12 +2322,28 @@ impl<M: CompletionModel> AgentLoop<M> {
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
                 EditFile.call(tool_args).await
             }
+            "edit_markdown_section" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                EditMarkdown.call(tool_args).await
+            }
             "list_dir" => {
                 let tool_args = serde_json::from_value(args)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
                 ListDir.call(tool_args).await
             }
             "bash" => {
+                // The session shell runs plain foreground commands; explicit
+                // working_dir and background runs leave it untouched
+                if let Some(ref session) = self.shell_session
+                    && args.get("working_dir").is_none_or(|dir| dir.is_null())
+                    && !args.get("background").and_then(|b| b.as_bool()).unwrap_or(false) {
+                        let tool_args: BashArgs = serde_json::from_value(args)
+                            .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                        let env = tool_args.env.unwrap_or_default();
+                        let sink = self.bash_line_sink();
+                        return session.lock().await.run(&tool_args.command, &env, tool_args.timeout_secs, sink.as_deref()).await;
+                    }
                 // Inject default working directory if not specified
                 let mut args_with_cwd = args;
                 if let Some(obj) = args_with_cwd.as_object_mut()
@@ -1038,30 +2352,58 @@ impl<M: CompletionModel> AgentLoop<M> {
                     }
                 let tool_args = serde_json::from_value(args_with_cwd)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
-                BashCommand.call(tool_args).await
+                match self.bash_line_sink() {
+                    Some(sink) => BashCommand.call_streaming(tool_args, &*sink).await,
+                    None => BashCommand.call(tool_args).await,
+                }
             }
             "grep" => {
-                // Inject default path if not specified
+                // Inject default path if not specified; the default search also covers --context-dir roots
                 let mut args_with_path = args;
+                let mut default_path = false;
                 if let Some(obj) = args_with_path.as_object_mut()
                     && !obj.contains_key("path") {
                         obj.insert("path".to_string(), serde_json::Value::String(self.working_directory.clone()));
+                        default_path = true;
                     }
-                let tool_args = serde_json::from_value(args_with_path)
+                let mut tool_args: GrepArgs = serde_json::from_value(args_with_path)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                if default_path {
+                    tool_args.extra_roots = context_dirs::dirs();
+                }
                 GrepSearch.call(tool_args).await
             }
             "glob" => {
-                // Inject default base_dir if not specified
+                // Inject default base_dir if not specified; the default search also covers --context-dir roots
                 let mut args_with_base = args;
+                let mut default_base = false;
                 if let Some(obj) = args_with_base.as_object_mut()
                     && !obj.contains_key("base_dir") {
                         obj.insert("base_dir".to_string(), serde_json::Value::String(self.working_directory.clone()));
+                        default_base = true;
                     }
-                let tool_args = serde_json::from_value(args_with_base)
+                let mut tool_args: GlobArgs = serde_json::from_value(args_with_base)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                if default_base {
+                    tool_args.extra_roots = context_dirs::dirs();
+                }
                 GlobFiles.call(tool_args).await
             }
+            "diff_files" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                DiffFiles.call(tool_args).await
+            }
+            "git_status" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                GitStatus.call(tool_args).await
+            }
+            "git_diff" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                GitDiff.call(tool_args).await
+            }
             "bash_status" => {
                 let tool_args = serde_json::from_value(args)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
@@ -1090,12 +2432,79 @@ impl<M: CompletionModel> AgentLoop<M> {
             "web_search" => {
                 let tool_args = serde_json::from_value(args)
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
-                WebSearch.call(tool_args).await
+                WebSearch.call(tool_args).await
+            }
+            "crate_docs" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                CrateDocs.call(tool_args).await
+            }
+            "math_calc" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                MathCalc.call(tool_args).await
+            }
+            "json_query" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                JsonQuery.call(tool_args).await
+            }
+            "scratch_write" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                ScratchWrite {
+                    scratchpad: self.scratchpad.clone(),
+                }
+                .call(tool_args)
+                .await
+            }
+            "scratch_read" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                ScratchRead {
+                    scratchpad: self.scratchpad.clone(),
+                }
+                .call(tool_args)
+                .await
+            }
+            "run_tests" => {
+                // Inject default working directory if not specified
+                let mut args_with_cwd = args;
+                if let Some(obj) = args_with_cwd.as_object_mut()
+                    && !obj.contains_key("working_dir") {
+                        obj.insert("working_dir".to_string(), serde_json::Value::String(self.working_directory.clone()));
+                    }
+                let tool_args = serde_json::from_value(args_with_cwd)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                RunTests.call(tool_args).await
+            }
+            "cargo_check" => {
+                // Inject default working directory if not specified
+                let mut args_with_cwd = args;
+                if let Some(obj) = args_with_cwd.as_object_mut()
+                    && !obj.contains_key("working_dir") {
+                        obj.insert("working_dir".to_string(), serde_json::Value::String(self.working_directory.clone()));
+                    }
+                let tool_args = serde_json::from_value(args_with_cwd)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                CargoCheck.call(tool_args).await
             }
-            "math_calc" => {
-                let tool_args = serde_json::from_value(args)
-                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
-                MathCalc.call(tool_args).await
+            "add_dependency" | "remove_dependency" => {
+                // Inject default working directory if not specified
+                let mut args_with_cwd = args;
+                if let Some(obj) = args_with_cwd.as_object_mut()
+                    && !obj.contains_key("working_dir") {
+                        obj.insert("working_dir".to_string(), serde_json::Value::String(self.working_directory.clone()));
+                    }
+                if name == "add_dependency" {
+                    let tool_args = serde_json::from_value(args_with_cwd)
+                        .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                    AddDependency.call(tool_args).await
+                } else {
+                    let tool_args = serde_json::from_value(args_with_cwd)
+                        .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                    RemoveDependency.call(tool_args).await
+                }
             }
             "store_key_memory" => {
                 let tool_args = serde_json::from_value(args)
@@ -1103,6 +2512,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                 StoreKeyMemory {
                     memory_manager: self.memory_manager.clone(),
                     session_id: self.session_id.clone(),
+                    project: Some(crate::memory::project_scope(&self.working_directory)),
                 }
                 .call(tool_args)
                 .await
@@ -1112,6 +2522,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
                 SearchRoutineMemory {
                     memory_manager: self.memory_manager.clone(),
+                    project: Some(crate::memory::project_scope(&self.working_directory)),
                 }
                 .call(tool_args)
                 .await
@@ -1121,6 +2532,16 @@ impl<M: CompletionModel> AgentLoop<M> {
                     .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
                 SearchKeyMemory {
                     memory_manager: self.memory_manager.clone(),
+                    project: Some(crate::memory::project_scope(&self.working_directory)),
+                }
+                .call(tool_args)
+                .await
+            }
+            "vecdb_search" => {
+                let tool_args = serde_json::from_value(args)
+                    .map_err(|e| ToolError::invalid_arguments(e.to_string()))?;
+                VecdbSearch {
+                    vecdb: self.vecdb.clone(),
                 }
                 .call(tool_args)
                 .await
@@ -1158,7 +2579,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                     self.memory_manager.clone(),  // Share memory manager
                     self.session_id.clone(),  // Share session ID
                     self.depth + 1,  // Increment depth
-                    self.cancel_token.clone(),  // Share cancellation token
+                    self.cancel_token.child_token(),  // Cancelled with this agent, or on its own
                     self.permissions.clone(),  // Share permissions
                     self.model_name.clone(),  // Share model name
                 );
@@ -1167,6 +2588,33 @@ impl<M: CompletionModel> AgentLoop<M> {
                 if let Some(max_iter) = tool_args.max_iterations {
                     sub_agent.set_max_iterations(max_iter);
                 }
+                sub_agent.set_max_tool_output_kb(self.max_tool_output_kb);
+                sub_agent.set_max_tokens(self.max_tokens);
+                sub_agent.set_sampling(self.sampling.clone());
+                sub_agent.set_continue_on_error(self.continue_on_error);
+                if let Some(ref audit_log) = self.audit_log {
+                    sub_agent.set_audit_log(audit_log.clone());
+                }
+                if let Some(ref limiter) = self.request_limiter {
+                    sub_agent.set_request_limiter(limiter.clone());
+                }
+                if let Some(ref budget) = self.token_budget {
+                    sub_agent.set_token_budget(budget.clone());
+                }
+                sub_agent.set_show_reasoning(self.show_reasoning);
+                if let Some(ref root) = self.root {
+                    sub_agent.set_root(root.clone());
+                }
+                sub_agent.set_persistent_shell(self.shell_session.is_some());
+                sub_agent.set_auto_continue(self.auto_continue);
+                sub_agent.set_scan_injection(self.scan_injection);
+                sub_agent.set_format_on_write(self.format_on_write);
+                sub_agent.set_lint_on_write(self.lint_on_write);
+                sub_agent.set_dry_run_writes(self.dry_run_writes);
+                if let Some(max_files) = self.max_files_per_turn {
+                    sub_agent.set_max_files_per_turn(max_files);
+                }
+                sub_agent.set_scratchpad(self.scratchpad.clone());
 
                 // Pass TUI sender to sub-agent so it can send events
                 if let Some(ref tx) = self.tui_tx {
@@ -1231,6 +2679,26 @@ impl<M: CompletionModel> AgentLoop<M> {
         }
     }
 
+    /// Provider options sent with every completion request
+    fn model_options(&self) -> Value {
+        let mut options = serde_json::json!({
+            "num_ctx": self.context_size
+        });
+        if let Some(temperature) = self.sampling.temperature {
+            options["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(top_p) = self.sampling.top_p {
+            options["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(seed) = self.sampling.seed {
+            options["seed"] = serde_json::json!(seed);
+        }
+        if self.show_reasoning {
+            options["think"] = serde_json::json!(true);
+        }
+        options
+    }
+
     /// Serialize messages for logging
     fn serialize_messages(&self) -> Value {
         serde_json::json!(self.chat_history.iter().map(|m| {
@@ -1252,16 +2720,126 @@ impl<M: CompletionModel> AgentLoop<M> {
         }).collect::<Vec<_>>())
     }
 
-    /// Process a user message and run the agentic loop until completion
+    /// Process a user message and run the agentic loop until completion.
+    /// In plan mode, until a plan is approved, the request is answered with a
+    /// plan instead, and approving it starts the work.
     pub async fn chat(&mut self, user_input: &str) -> Result<String> {
-        //eprintln!("DEBUG chat(): agent_id={}, depth={}, chat_history_len={}, iteration_count={}, max_iterations={}",
-        //         self.agent_id, self.depth, self.chat_history.len(), self.iteration_count, self.max_iterations);
+        if !self.plan_mode || self.plan_approved {
+            return self.timed_turn(user_input).await;
+        }
+
+        self.planning = true;
+        let plan = self.timed_turn(&plan::plan_request(user_input)).await;
+        self.planning = false;
+        let plan = plan?;
+        if plan.trim().is_empty() {
+            return Err(anyhow!("The model returned an empty plan"));
+        }
+
+        // Waiting for the user is not part of either turn
+        if !self.prompt_plan_approval(&plan).await {
+            return Ok(plan::REJECTED_REPLY.to_string());
+        }
+        self.plan_approved = true;
+        self.timed_turn(plan::APPROVED_PROMPT).await
+    }
+
+    /// Run one turn under the turn timeout, if one is set. A turn that runs
+    /// over is cancelled (including its sub-agents) and the last reply it
+    /// produced is returned.
+    async fn timed_turn(&mut self, user_input: &str) -> Result<String> {
+        let Some(limit) = self.turn_timeout else {
+            return self.run_turn(user_input).await;
+        };
+
+        // Cancelling a child token stops this turn without touching the agent's own token
+        let agent_token = self.cancel_token.clone();
+        let turn_token = agent_token.child_token();
+        self.cancel_token = turn_token.clone();
+        let history_start = self.chat_history.len();
+
+        let result = tokio::select! {
+            result = self.run_turn(user_input) => Some(result),
+            _ = tokio::time::sleep(limit) => None,
+        };
+        turn_token.cancel();
+        self.cancel_token = agent_token;
+
+        match result {
+            Some(result) => result,
+            None => Ok(self.finish_timed_out_turn(history_start, limit)),
+        }
+    }
+
+    /// Close the history of a turn stopped by the turn timeout and build its partial result
+    fn finish_timed_out_turn(&mut self, history_start: usize, limit: Duration) -> String {
+        tracing::warn!(agent_id = %self.agent_id, limit_secs = limit.as_secs(), "Turn timed out");
+        self.emit_thinking(false);
+
+        // Tool calls that were cut off still need results for the next request
+        let pending: Vec<String> = match self.chat_history.last() {
+            Some(Message::Assistant { content, .. }) => content
+                .iter()
+                .filter_map(|c| match c {
+                    AssistantContent::ToolCall(call) => Some(call.id.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let results: Vec<UserContent> = pending
+            .into_iter()
+            .map(|id| {
+                UserContent::ToolResult(ToolResult {
+                    id: id.clone(),
+                    call_id: Some(id),
+                    content: OneOrMany::one(ToolResultContent::text("Not completed: the turn timed out")),
+                })
+            })
+            .collect();
+        if let Ok(content) = OneOrMany::many(results) {
+            self.chat_history.push(Message::User { content });
+        }
+
+        let partial = self.chat_history[history_start.min(self.chat_history.len())..]
+            .iter()
+            .rev()
+            .find_map(|message| match message {
+                Message::Assistant { content, .. } => content.iter().find_map(|c| match c {
+                    AssistantContent::Text(text) if !text.text.trim().is_empty() => Some(text.text.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            });
+        let note = format!("[Turn stopped after {}s (--turn-timeout); the work may be incomplete]", limit.as_secs());
+        if let Some(ref tx) = self.tui_tx {
+            terminal::emit_warning(tx, &self.agent_id, &note);
+        }
+        match partial {
+            Some(text) => format!("{}\n\n{}", text, note),
+            None => note,
+        }
+    }
+
+    /// One chat turn: the agentic loop until the model gives a final reply
+    async fn run_turn(&mut self, user_input: &str) -> Result<String> {
+        let turn_start = Instant::now();
+        self.turn_files.clear();
+        self.turn_files_approved = false;
+
+        tracing::debug!(
+            agent_id = %self.agent_id,
+            depth = self.depth,
+            history_len = self.chat_history.len(),
+            max_iterations = self.max_iterations,
+            "Starting turn"
+        );
 
         // Search vector database for relevant code context if available
         let mut enriched_input = user_input.to_string();
         if let Some(ref vecdb) = self.vecdb {
             let db = vecdb.lock().await;
-            match db.search(user_input, 3).await {
+            match db.search_relevant(user_input).await {
                 Ok(results) => {
                     if !results.is_empty() {
                         let mut context = String::from("\n\n[Relevant code context from vector database]:\n");
@@ -1327,7 +2905,7 @@ impl<M: CompletionModel> AgentLoop<M> {
         // Log user input
         self.traffic
             .log_request(
-                format!("User: {}", truncate_string(user_input, 50)),
+                format!("User: {}", terminal::truncate_chars(user_input, 50)),
                 serde_json::json!({
                     "user_input": user_input,
                     "history_length": self.chat_history.len(),
@@ -1337,6 +2915,7 @@ impl<M: CompletionModel> AgentLoop<M> {
             .await;
 
         let mut iterations = 0;
+        let mut continuations = 0;
 
 
         loop {
@@ -1347,14 +2926,21 @@ impl<M: CompletionModel> AgentLoop<M> {
 
             iterations += 1;
             if iterations > self.max_iterations {
-                return Err(anyhow!(
-                    "Maximum iterations ({}) exceeded. The agent may be stuck in a loop.",
-                    self.max_iterations
-                ));
+                return Err(AgentError::MaxIterations(self.max_iterations).into());
+            }
+            if let Some(ref budget) = self.token_budget
+                && budget.is_exhausted() {
+                    return Err(AgentError::TokenBudgetExhausted {
+                        used: budget.used(),
+                        limit: budget.limit(),
+                    }.into());
+                }
+            if let Some(ref tx) = self.tui_tx {
+                terminal::emit_iteration_update(tx, &self.agent_id, iterations, self.max_iterations, turn_start.elapsed());
             }
 
             // Log the request to LLM
-            let tool_defs = self.get_tool_definitions().await;
+            let tool_defs = if self.planning { Vec::new() } else { self.get_tool_definitions().await };
             self.traffic
                 .log_request(
                     format!("Completion request (iteration {})", iterations),
@@ -1367,12 +2953,25 @@ impl<M: CompletionModel> AgentLoop<M> {
                 )
                 .await;
 
+            // Wait for a request slot; held until the response is fully read
+            let request_permit = match self.request_limiter {
+                Some(ref limiter) => Some(
+                    limiter
+                        .clone()
+                        .acquire_owned()
+                        .await
+                        .map_err(|e| anyhow!("Request limiter closed: {}", e))?,
+                ),
+                None => None,
+            };
+
+            self.emit_thinking(true);
             let request_start = Instant::now();
 
             // Process the response - collect tool calls and text
             let mut tool_calls: Vec<ToolCall> = Vec::new();
             let mut text_response: Option<String> = None;
-            let response_choice: OneOrMany<AssistantContent>;
+            let mut response_choice: OneOrMany<AssistantContent>;
 		
 
             if self.streaming {
@@ -1382,18 +2981,25 @@ impl<M: CompletionModel> AgentLoop<M> {
                     .completion_request(&self.preamble)
                     .messages(self.chat_history.clone())
                     .tools(tool_defs.clone())
-                    .max_tokens(32768)
-                    .additional_params(serde_json::json!({
-                        "num_ctx": self.context_size
-                    }))
+                    .max_tokens(self.max_tokens)
+                    .additional_params(self.model_options())
                     .stream()
                     .await
                     .map_err(|e| anyhow!("Streaming request failed: {}", e))?;
 
                 let mut streamed_text = String::new();
+                // Keeps <tool_call> tags written as text out of the output
+                let mut tag_filter = StreamFilter::default();
+                // Reasoning was printed to the terminal and needs a line break before the answer
+                let mut reasoning_printed = false;
 
                 // Process stream items
+                let mut waiting = true;
                 while let Some(result) = stream.next().await {
+                    if waiting {
+                        self.emit_thinking(false);
+                        waiting = false;
+                    }
                     match result {
                         Ok(content) => match content {
                             StreamedAssistantContent::Text(text) => {
@@ -1407,12 +3013,14 @@ impl<M: CompletionModel> AgentLoop<M> {
                                     })
                                 ).await;
 
-                                // Emit to TUI or print to terminal
-                                if let Some(ref tx) = self.tui_tx {
-                                    terminal::emit_assistant_chunk(tx, &self.agent_id, &text.text);
-                                } else {
-                                    terminal::print_streaming_token(&text.text);
+                                if reasoning_printed {
+                                    terminal::end_streaming(self.stream_to_stderr);
+                                    reasoning_printed = false;
                                 }
+
+                                // Emit to TUI or print to terminal
+                                let visible = tag_filter.push(&text.text);
+                                self.emit_stream_text(&visible);
                                 streamed_text.push_str(&text.text);
                             }
                             StreamedAssistantContent::ToolCall(tool_call) => {
@@ -1438,14 +3046,14 @@ impl<M: CompletionModel> AgentLoop<M> {
                                 ).await;
                                 // Tool call deltas are accumulated automatically by rig
                             }
-                            StreamedAssistantContent::Reasoning(_) => {
-                                // Ignore reasoning content
+                            StreamedAssistantContent::Reasoning(reasoning) => {
+                                reasoning_printed |= self.emit_reasoning(&reasoning.reasoning.join("\n"));
                             }
                             StreamedAssistantContent::Final(_) => {
                                 // Final item contains usage info
                             }
-                            StreamedAssistantContent::ReasoningDelta { id, reasoning } => {
-                                // Ignore reasoning Delta
+                            StreamedAssistantContent::ReasoningDelta { reasoning, .. } => {
+                                reasoning_printed |= self.emit_reasoning(&reasoning);
                             },
                         },
                         Err(e) => {
@@ -1459,10 +3067,18 @@ impl<M: CompletionModel> AgentLoop<M> {
                     }
                 }
 
+                if waiting {
+                    self.emit_thinking(false);
+                }
+
                 // End streaming output
+                if reasoning_printed {
+                    terminal::end_streaming(self.stream_to_stderr);
+                }
+                self.emit_stream_text(&tag_filter.finish());
                 if !streamed_text.is_empty() {
                     if self.tui_tx.is_none() {
-                        terminal::end_streaming();
+                        terminal::end_streaming(self.stream_to_stderr);
                     }
                     text_response = Some(streamed_text);
                 }
@@ -1486,15 +3102,14 @@ impl<M: CompletionModel> AgentLoop<M> {
                     .completion_request(&self.preamble)
                     .messages(self.chat_history.clone())
                     .tools(tool_defs.clone())
-                    .max_tokens(32768)
-                    .additional_params(serde_json::json!({
-                        "num_ctx": self.context_size
-                    }))
+                    .max_tokens(self.max_tokens)
+                    .additional_params(self.model_options())
                     .send()
                     .await;
 
                 // Clear spinner before handling result
                 terminal::clear_spinner(&spinner);
+                self.emit_thinking(false);
 
                 let response = response.map_err(|e| anyhow!("Completion request failed: {}", e))?;
 
@@ -1509,8 +3124,10 @@ impl<M: CompletionModel> AgentLoop<M> {
                         AssistantContent::ToolCall(tool_call) => {
                             tool_calls.push(tool_call.clone());
                         }
-                        AssistantContent::Reasoning(_) => {
-                            // Reasoning content is internal model reasoning, we can ignore it
+                        AssistantContent::Reasoning(reasoning) => {
+                            if self.emit_reasoning(&reasoning.reasoning.join("\n")) {
+                                terminal::end_streaming(self.stream_to_stderr);
+                            }
                         }
                         AssistantContent::Image(image) => {
                             // Ignoring Images
@@ -1519,7 +3136,63 @@ impl<M: CompletionModel> AgentLoop<M> {
                 }
             }
 
+            drop(request_permit);
             let request_duration = request_start.elapsed().as_millis() as u64;
+            self.profile.record_llm(request_duration);
+            if let Some(ref budget) = self.token_budget {
+                let prompt_text = format!("{}\n{:?}", self.preamble, self.chat_history);
+                let completion_text = format!("{}{:?}", text_response.as_deref().unwrap_or(""), tool_calls);
+                budget.charge(TokenUsage::estimate_tokens(&prompt_text) + TokenUsage::estimate_tokens(&completion_text));
+            }
+
+            // Models without native function calling may write tool calls as text
+            let tool_names: Vec<&str> = tool_defs.iter().map(|t| t.name.as_str()).collect();
+            if tool_calls.is_empty()
+                && let Some(ref text) = text_response
+                && let Some(parsed) = text_tool_calls::parse(text, &tool_names)
+            {
+                self.traffic
+                    .log_system(
+                        "text_tool_calls",
+                        &format!("Parsed {} tool call(s) from response text", parsed.calls.len()),
+                        serde_json::json!({
+                            "calls": parsed.calls.iter().map(|c| {
+                                serde_json::json!({ "name": c.name, "arguments": c.arguments })
+                            }).collect::<Vec<_>>(),
+                        }),
+                    )
+                    .await;
+
+                tool_calls = parsed
+                    .calls
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, call)| {
+                        ToolCall::new(
+                            format!("text_call_{}_{}", iterations, idx),
+                            ToolFunction::new(call.name, call.arguments),
+                        )
+                    })
+                    .collect();
+
+                // History gets the calls as structured content so the results pair up
+                let mut content: Vec<AssistantContent> = Vec::new();
+                if !parsed.text.is_empty() {
+                    content.push(AssistantContent::text(parsed.text.clone()));
+                }
+                content.extend(tool_calls.iter().cloned().map(AssistantContent::ToolCall));
+                if let Ok(content) = OneOrMany::many(content) {
+                    response_choice = content;
+                }
+                text_response = (!parsed.text.is_empty()).then_some(parsed.text);
+            }
+
+            // Planning turns offer no tools; keep only the text of a response that calls one anyway
+            if self.planning && !tool_calls.is_empty() {
+                tool_calls.clear();
+                let text = text_response.get_or_insert_with(String::new);
+                response_choice = OneOrMany::one(AssistantContent::text(text.clone()));
+            }
 
             // Log the response
             let response_summary = if !tool_calls.is_empty() {
@@ -1532,7 +3205,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                         .join(", ")
                 )
             } else if let Some(ref text) = text_response {
-                format!("Text: {}", truncate_string(text, 50))
+                format!("Text: {}", terminal::truncate_chars(text, 50))
             } else {
                 "Empty response".to_string()
             };
@@ -1545,7 +3218,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                             serde_json::json!({
                                 "id": t.id,
                                 "name": t.function.name,
-                                "arguments": t.function.arguments
+                                "arguments": mask_secret_env(&t.function.arguments)
                             })
                         }).collect::<Vec<_>>(),
                         "text_response": text_response,
@@ -1570,38 +3243,49 @@ impl<M: CompletionModel> AgentLoop<M> {
                     content: response_choice.clone(),
                 });
 
+                // With several calls to approve, offer to approve them together
+                let batch_approved = if self.tui_tx.is_some() && !self.permissions.should_skip_confirmations() {
+                    let pending: Vec<(String, HashMap<String, String>)> = tool_calls
+                        .iter()
+                        .map(|call| (call.function.name.clone(), display_args(&call.function.arguments)))
+                        .filter(|(name, args)| self.permissions.is_granted(name) && self.needs_permission(name, args))
+                        .collect();
+                    match self.prompt_batch_permission(pending).await {
+                        crate::tui::BatchDecision::ApproveAll => true,
+                        crate::tui::BatchDecision::Review => false,
+                        crate::tui::BatchDecision::RejectAll => {
+                            for tool_call in &tool_calls {
+                                self.audit_tool(&tool_call.function.name, &tool_call.function.arguments, "Rejected by user", 0, false);
+                            }
+                            return Err(anyhow!("Operation cancelled by user. Please provide new instructions."));
+                        }
+                    }
+                } else {
+                    false
+                };
+
                 // Execute each tool and collect results
                 let mut tool_results: Vec<UserContent> = Vec::new();
+                // Results of read-only calls run ahead together, by call index
+                let mut concurrent_results: HashMap<usize, (Result<String, ToolError>, Duration)> = HashMap::new();
 
-                for tool_call in &tool_calls {
+                for (index, tool_call) in tool_calls.iter().enumerate() {
                     let tool_name = &tool_call.function.name;
                     // Arguments is already a serde_json::Value
                     let tool_args: Value = tool_call.function.arguments.clone();
 
                     // Emit/print tool execution info
-                    let mut args_map = HashMap::new();
-                    if let Some(obj) = tool_args.as_object() {
-                        for (key, value) in obj {
-                            let display_value = if let Some(s) = value.as_str() {
-                                if s.len() > 100 {
-                                    format!("{}...", &s[..100])
-                                } else {
-                                    s.to_string()
-                                }
-                            } else {
-                                value.to_string()
-                            };
-                            args_map.insert(key.clone(), display_value.clone());
-
+                    let args_map = display_args(&tool_args);
+                    if self.tui_tx.is_none()
+                        && let Some(obj) = tool_args.as_object() {
                             // For non-TUI mode, still print
-                            if self.tui_tx.is_none() {
-                                terminal::print_tool_arg(key, &display_value);
+                            for key in obj.keys() {
+                                terminal::print_tool_arg(key, &args_map[key]);
                             }
                         }
-                    }
 
                     // Generate diff for file operations
-                    let diff = if tool_name == "write_file" || tool_name == "edit_file" {
+                    let diff = if tool_name == "write_file" || tool_name == "edit_file" || tool_name == "edit_markdown_section" {
                         self.generate_diff_for_tool(tool_name, &tool_args).await
                     } else {
                         None
@@ -1610,20 +3294,66 @@ impl<M: CompletionModel> AgentLoop<M> {
                     // Check permissions first (for batch mode)
                     if !self.permissions.is_granted(tool_name) {
                         // Tool not granted in batch mode - fail immediately
-                        let error_msg = format!(
-                            "Permission denied: tool '{}' not granted. Use --grant {} or --grant-all",
-                            tool_name, tool_name
-                        );
+                        let error_msg = if self.permissions.is_review() {
+                            format!("Permission denied: tool '{}' is disabled in --review mode", tool_name)
+                        } else {
+                            format!(
+                                "Permission denied: tool '{}' not granted. Use --grant {} or --grant-all",
+                                tool_name, tool_name
+                            )
+                        };
                         if let Some(ref tx) = self.tui_tx {
                             terminal::emit_error(tx, &self.agent_id, &error_msg);
                         } else {
                             terminal::print_error(&error_msg);
                         }
-                        return Err(anyhow!(error_msg));
+                        let result = format!("Error: {}", error_msg);
+                        self.audit_tool(tool_name, &tool_args, &result, 0, false);
+                        if !self.continue_on_error {
+                            return Err(AgentError::PermissionDenied(error_msg).into());
+                        }
+
+                        // Let the model see the denial and choose another approach
+                        self.traffic.log_tool(tool_name, &mask_secret_env(&tool_args), &result, 0).await;
+                        tool_results.push(UserContent::ToolResult(ToolResult {
+                            id: tool_call.id.clone(),
+                            call_id: Some(tool_call.id.clone()),
+                            content: OneOrMany::one(ToolResultContent::text(result)),
+                        }));
+                        continue;
+                    }
+
+                    // Changing more files than --max-files-per-turn needs approval for the rest of the turn
+                    if self.exceeds_file_limit(tool_name, &args_map) {
+                        if !self.prompt_file_limit(&tool_calls[index..]).await {
+                            let limit = self.max_files_per_turn.unwrap_or_default();
+                            let error_msg = format!(
+                                "Stopped: this turn would change more than {} files (--max-files-per-turn)",
+                                limit
+                            );
+                            self.audit_tool(tool_name, &tool_args, &error_msg, 0, false);
+                            return Err(anyhow!("{}. Please provide new instructions.", error_msg));
+                        }
+                        self.turn_files_approved = true;
                     }
 
+                    // Writes over --max-file-size always need an explicit confirmation
+                    let large_write = if tool_name == "write_file" {
+                        tool_args
+                            .get("content")
+                            .and_then(|c| c.as_str())
+                            .map(|c| c.len() as u64)
+                            .filter(|len| file_limits::exceeds_limit(*len))
+                    } else {
+                        None
+                    };
+
                     // Request permission to execute the tool (for TUI mode)
-                    let has_permission = if self.tui_tx.is_some() && !self.permissions.should_skip_confirmations() {
+                    let has_permission = if let Some(len) = large_write {
+                        self.confirm_large_write(tool_name, &args_map, diff, len).await
+                    } else if batch_approved {
+                        true  // Approved with the rest of the batch
+                    } else if self.tui_tx.is_some() && !self.permissions.should_skip_confirmations() {
                         self.request_permission(tool_name, &args_map, diff).await
                     } else {
                         true  // Permission already granted via CLI
@@ -1641,6 +3371,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                     // If permission was explicitly rejected, stop the completion loop
                     if !has_permission {
                         // User rejected the permission - stop the agent loop and wait for new input
+                        self.audit_tool(tool_name, &tool_args, "Rejected by user", 0, false);
                         return Err(anyhow!("Operation cancelled by user. Please provide new instructions."));
                     }
 
@@ -1651,7 +3382,7 @@ impl<M: CompletionModel> AgentLoop<M> {
                                 let msg = format!(
                                     "Dangerous command detected ({}): {}",
                                     pattern,
-                                    truncate_string(command, 50)
+                                    terminal::truncate_chars(command, 50)
                                 );
                                 // TODO: Implement modal confirmation for TUI mode
                                 if self.tui_tx.is_some() {
@@ -1706,8 +3437,19 @@ impl<M: CompletionModel> AgentLoop<M> {
                             }
                     }
 
+                    // Warnings the package had before the agent touched it aren't reported
+                    if self.lint_on_write
+                        && (tool_name == "write_file" || tool_name == "edit_file")
+                        && !self.permissions.is_dry_run()
+                        && !self.is_simulated(tool_name)
+                        && let Some(path) = tool_args.get("file_path").and_then(|p| p.as_str())
+                        && path.ends_with(".rs") {
+                            let absolute = paths::absolute(Path::new(&self.working_directory), Path::new(path));
+                            clippy::baseline(&absolute, &mut self.reported_lints).await;
+                        }
+
                     // Execute the tool with timing and spinner
-                    let tool_start = Instant::now();
+                    let mut tool_start = Instant::now();
 
                     // Show spinner for potentially long-running tools (only in non-TUI mode)
                     let spinner = if self.tui_tx.is_none() {
@@ -1724,24 +3466,49 @@ impl<M: CompletionModel> AgentLoop<M> {
                             tool_name,
                             serde_json::to_string_pretty(&tool_args).unwrap_or_else(|_| "{}".to_string())
                         ))
+                    } else if self.is_simulated(tool_name) {
+                        Ok(dry_run_write_message(tool_name, &tool_args))
+                    } else if tool_name == "change_directory" {
+                        // Needs &mut self, so it can't go through execute_tool
+                        serde_json::from_value::<ChangeDirectoryArgs>(tool_args.clone())
+                            .map_err(|e| ToolError::invalid_arguments(e.to_string()))
+                            .and_then(|a| self.change_directory(&a.path))
                     } else {
-                        self.execute_tool(tool_name, tool_args.clone()).await
+                        // Consecutive read-only calls from here on run together
+                        if !concurrent_results.contains_key(&index)
+                            && self.runs_concurrently(tool_name, &tool_args, batch_approved) {
+                                let results = self.execute_concurrently(&tool_calls[index..], batch_approved).await;
+                                concurrent_results.extend(results.into_iter().enumerate().map(|(offset, r)| (index + offset, r)));
+                            }
+                        match concurrent_results.remove(&index) {
+                            Some((result, duration)) => {
+                                // Time the call itself, not its wait for the others
+                                tool_start = Instant::now().checked_sub(duration).unwrap_or(tool_start);
+                                result
+                            }
+                            None => self.execute_tool(tool_name, tool_args.clone()).await,
+                        }
                     };
                     let duration_ms = tool_start.elapsed().as_millis();
 
                     let result = match exec_result {
                         Ok(output) => {
+                            // Cap the result before it reaches the history to protect the context window
+                            let mut output = truncate_tool_output(output, self.max_tool_output_kb * 1024);
+                            self.tool_stats.record(tool_name, None);
                             let success_msg = format!("{} completed ({}ms, {} chars)", tool_name, duration_ms, output.len());
 
                             // Emit/print success
                             if let Some(ref tx) = self.tui_tx {
-                                terminal::emit_tool_success(tx, &self.agent_id, tool_name, &success_msg);
+                                terminal::emit_tool_success(tx, &self.agent_id, tool_name, &success_msg, &output);
                             } else if let Some(spinner) = spinner {
                                 terminal::finish_spinner_success(&spinner, &success_msg);
                             }
 
                             // Track file changes for write and edit operations
-                            if tool_name == "write_file" {
+                            if self.is_simulated(tool_name) {
+                                // Nothing was written
+                            } else if tool_name == "write_file" {
                                 if let Some(path) = tool_args.get("file_path").and_then(|p| p.as_str()) {
                                     // Determine if file was created or modified based on output
                                     let op = if output.contains("Created") {
@@ -1751,15 +3518,40 @@ impl<M: CompletionModel> AgentLoop<M> {
                                     };
                                     self.record_file_change(path, op);
                                 }
-                            } else if tool_name == "edit_file"
+                            } else if (tool_name == "edit_file" || tool_name == "edit_markdown_section")
                                 && let Some(path) = tool_args.get("file_path").and_then(|p| p.as_str()) {
                                     self.record_file_change(path, FileOperation::Modified);
                                 }
 
+                            if (tool_name == "write_file" || tool_name == "edit_file")
+                                && !self.is_simulated(tool_name)
+                                && let Some(path) = tool_args.get("file_path").and_then(|p| p.as_str()) {
+                                    let absolute = paths::absolute(Path::new(&self.working_directory), Path::new(path));
+                                    if self.format_on_write && path.ends_with(".rs") {
+                                        let note = rustfmt::format_file(&absolute).await;
+                                        output = format!("{}\n{}", output, note);
+                                    }
+                                    if self.lint_on_write && path.ends_with(".rs") {
+                                        let note = clippy::lint_file(&absolute, &mut self.reported_lints).await;
+                                        output = format!("{}\n{}", output, note);
+                                    }
+                                    self.notify_rust_analyzer(path).await;
+                                }
+
+                            let (output, injection) = injection::guard(tool_name, output, self.scan_injection);
+                            if let Some(phrase) = injection {
+                                let warning = format!("Possible prompt injection in {} result (\"{}\"); the model was told not to follow it", tool_name, phrase);
+                                if let Some(ref tx) = self.tui_tx {
+                                    terminal::emit_warning(tx, &self.agent_id, &warning);
+                                } else {
+                                    terminal::print_warning(&warning);
+                                }
+                            }
                             output
                         }
                         Err(e) => {
                             let error_msg = format!("{} failed: {}", tool_name, e);
+                            self.tool_stats.record(tool_name, Some(&e.to_string()));
 
                             // Emit/print error
                             if let Some(ref tx) = self.tui_tx {
@@ -1772,11 +3564,22 @@ impl<M: CompletionModel> AgentLoop<M> {
                         }
                     };
                     let tool_duration = tool_start.elapsed().as_millis() as u64;
+                    self.profile.record_tool(tool_name, tool_duration);
+
+                    if tool_name.starts_with("ra_")
+                        && let Some(notice) = ra_common::take_restart_notice() {
+                            if let Some(ref tx) = self.tui_tx {
+                                terminal::emit_warning(tx, &self.agent_id, &notice);
+                            } else {
+                                terminal::print_warning(&notice);
+                            }
+                        }
 
                     // Log tool execution
                     self.traffic
-                        .log_tool(tool_name, &tool_args, &result, tool_duration)
+                        .log_tool(tool_name, &mask_secret_env(&tool_args), &result, tool_duration)
                         .await;
+                    self.audit_tool(tool_name, &tool_args, &result, tool_duration, true);
 
                     // Create tool result
                     let tool_result = ToolResult {
@@ -1809,13 +3612,14 @@ impl<M: CompletionModel> AgentLoop<M> {
                 // Track token usage (estimated)
                 // Estimate prompt from preamble + history
                 let prompt_text = format!("{}\n{:?}", self.preamble, self.chat_history);
-                //eprintln!("DEBUG agent_loop.rs: Before add_estimated - prompt_tokens: {}, completion_tokens: {}",
-                //         self.token_usage.prompt_tokens, self.token_usage.completion_tokens);
-                //eprintln!("DEBUG agent_loop.rs: Prompt text length: {}, response text length: {}",
-                //         prompt_text.len(), text.len());
                 self.token_usage.add_estimated(&prompt_text, &text);
-                //eprintln!("DEBUG agent_loop.rs: After add_estimated - prompt_tokens: {}, completion_tokens: {}",
-                         //self.token_usage.prompt_tokens, self.token_usage.completion_tokens);
+                tracing::trace!(
+                    prompt_len = prompt_text.len(),
+                    response_len = text.len(),
+                    prompt_tokens = self.token_usage.prompt_tokens,
+                    completion_tokens = self.token_usage.completion_tokens,
+                    "Estimated token usage"
+                );
 
                 // Track iteration count
                 self.iteration_count = iterations;
@@ -1829,8 +3633,29 @@ impl<M: CompletionModel> AgentLoop<M> {
                 // Store assistant message in routine memory
                 self.store_in_routine_memory("assistant", &text, None).await;
 
-                //eprintln!("DEBUG chat(): Returning final text response (length: {}, iterations: {})",
-                //         text.len(), iterations);
+                if self.auto_continue
+                    && !self.planning
+                    && continuations < auto_continue::MAX_CONTINUATIONS
+                    && auto_continue::promises_action(&text) {
+                        continuations += 1;
+                        tracing::debug!(agent_id = %self.agent_id, continuations, "Response promised an action; continuing");
+                        if let Some(ref tx) = self.tui_tx {
+                            terminal::emit_info(tx, &self.agent_id, "Model stopped before acting; asking it to continue");
+                        }
+                        self.chat_history.push(Message::User {
+                            content: OneOrMany::one(UserContent::text(auto_continue::CONTINUE_PROMPT)),
+                        });
+                        continue;
+                    }
+
+                self.checkpoint_turn(user_input, turn_start);
+
+                tracing::debug!(
+                    agent_id = %self.agent_id,
+                    response_len = text.len(),
+                    iterations,
+                    "Turn finished"
+                );
                 return Ok(text);
             }
 
@@ -1839,9 +3664,142 @@ impl<M: CompletionModel> AgentLoop<M> {
         }
     }
 
+    /// Write a code block from a chat reply to disk through the normal
+    /// write_file permission flow (grant check, diff modal, sensitive paths)
+    pub async fn apply_code_block(&mut self, file_path: &str, content: &str) -> Result<String>
+    where
+        M: Clone,
+    {
+        let tool_name = "write_file";
+        let tool_args = serde_json::json!({
+            "file_path": file_path,
+            "content": content,
+        });
+
+        if !self.permissions.is_granted(tool_name) {
+            return Err(anyhow!("Permission denied: tool '{}' not granted", tool_name));
+        }
+
+        if self.confirm_dangerous
+            && let Some(pattern) = terminal::is_dangerous_path(file_path) {
+                return Err(anyhow!("Refusing to write to sensitive path ({}): {}", pattern, file_path));
+            }
+        crate::tools::agent_ignore::check(file_path)?;
+        context_dirs::check_writable(file_path)?;
+        crate::tools::write_root::check(file_path)?;
+
+        let mut args_map = HashMap::new();
+        args_map.insert("file_path".to_string(), file_path.to_string());
+        args_map.insert("content".to_string(), terminal::truncate_chars(content, 100));
+
+        let diff = self.generate_diff_for_tool(tool_name, &tool_args).await;
+        let has_permission = if self.tui_tx.is_some() && !self.permissions.should_skip_confirmations() {
+            self.request_permission(tool_name, &args_map, diff).await
+        } else {
+            true
+        };
+
+        if !has_permission {
+            return Err(anyhow!("Write to {} rejected", file_path));
+        }
+
+        if let Some(ref tx) = self.tui_tx {
+            terminal::emit_tool_start(tx, &self.agent_id, tool_name, args_map);
+        }
+
+        let existed = std::path::Path::new(&self.working_directory).join(file_path).exists();
+        let tool_start = Instant::now();
+        let exec_result = if self.permissions.is_dry_run() {
+            Ok(format!("[DRY RUN] Would write {} bytes to {}", content.len(), file_path))
+        } else if self.dry_run_writes {
+            Ok(dry_run_write_message(tool_name, &tool_args))
+        } else {
+            self.execute_tool(tool_name, tool_args.clone()).await
+        };
+        let duration_ms = tool_start.elapsed().as_millis() as u64;
+
+        let result = match exec_result {
+            Ok(output) => {
+                if let Some(ref tx) = self.tui_tx {
+                    terminal::emit_tool_success(tx, &self.agent_id, tool_name, &output, "");
+                }
+                if !self.is_simulated(tool_name) {
+                    let op = if existed { FileOperation::Modified } else { FileOperation::Created };
+                    self.record_file_change(file_path, op);
+                    self.notify_rust_analyzer(file_path).await;
+                }
+                Ok(output)
+            }
+            Err(e) => {
+                if let Some(ref tx) = self.tui_tx {
+                    terminal::emit_tool_error(tx, &self.agent_id, tool_name, &format!("{} failed: {}", tool_name, e));
+                }
+                Err(anyhow!("{} failed: {}", tool_name, e))
+            }
+        };
+
+        let logged = match &result {
+            Ok(output) => output.clone(),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.traffic.log_tool(tool_name, &tool_args, &logged, duration_ms).await;
+        self.audit_tool(tool_name, &tool_args, &logged, duration_ms, true);
+
+        result
+    }
+
+    /// Show streamed response text in the TUI or on the terminal
+    fn emit_stream_text(&self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(ref tx) = self.tui_tx {
+            terminal::emit_assistant_chunk(tx, &self.agent_id, text);
+        } else {
+            terminal::print_streaming_token(text, self.stream_to_stderr);
+        }
+    }
+
+    /// Toggle the TUI's thinking spinner (the terminal has its own spinner)
+    fn emit_thinking(&self, active: bool) {
+        if let Some(ref tx) = self.tui_tx {
+            terminal::emit_thinking(tx, &self.agent_id, active);
+        }
+    }
+
+    /// Show reasoning under --show-reasoning; true if it went to the terminal
+    fn emit_reasoning(&self, text: &str) -> bool {
+        if !self.show_reasoning || text.is_empty() {
+            return false;
+        }
+        if let Some(ref tx) = self.tui_tx {
+            terminal::emit_reasoning_chunk(tx, &self.agent_id, text);
+            false
+        } else {
+            terminal::print_reasoning_token(text, self.stream_to_stderr);
+            true
+        }
+    }
+
     /// Clear the conversation history
     pub fn clear_history(&mut self) {
         self.chat_history.clear();
+        self.scratchpad = Scratchpad::default();
+        self.plan_approved = false;
+    }
+
+    /// Start over in a new session: forget the conversation, plan approval, scratchpad, reported lints,
+    /// token counts, file changes and execution statistics
+    pub fn start_new_session(&mut self, session_id: String) {
+        self.chat_history.clear();
+        self.scratchpad = Scratchpad::default();
+        self.reported_lints = clippy::ReportedLints::default();
+        self.plan_approved = false;
+        self.token_usage = TokenUsage::default();
+        self.file_changes.clear();
+        self.profile = ExecutionProfile::default();
+        self.tool_stats = ToolStats::default();
+        self.session_id = Some(session_id);
     }
 
     /// Get the current conversation history length
@@ -1849,6 +3807,81 @@ impl<M: CompletionModel> AgentLoop<M> {
         self.chat_history.len()
     }
 
+    /// Number of user turns (typed prompts, not tool-result turns) in the history
+    pub fn user_turn_count(&self) -> usize {
+        self.chat_history.iter().filter(|m| is_user_prompt(m)).count()
+    }
+
+    /// Rewind the conversation so only the first `turns` user turns (and the
+    /// responses to them) remain. Returns the number of messages removed.
+    pub fn rewind_to_turn(&mut self, turns: usize) -> usize {
+        let cut = self.chat_history
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| is_user_prompt(m))
+            .nth(turns)
+            .map(|(idx, _)| idx)
+            .unwrap_or(self.chat_history.len());
+
+        let removed = self.chat_history.len() - cut;
+        self.chat_history.truncate(cut);
+        removed
+    }
+
+    /// Compact dump of chat_history for debugging: one entry per message with its
+    /// role and a preview of each content part, including tool calls and results
+    pub fn history_dump(&self, preview_chars: usize) -> String {
+        if self.chat_history.is_empty() {
+            return "Chat history is empty.".to_string();
+        }
+
+        let mut output = format!("{} message(s) in chat history:\n", self.chat_history.len());
+
+        for (idx, message) in self.chat_history.iter().enumerate() {
+            let (role, parts): (&str, Vec<String>) = match message {
+                Message::User { content } => {
+                    let role = if is_user_prompt(message) { "user" } else { "tool" };
+                    let parts = content.iter().map(|c| match c {
+                        UserContent::Text(text) => format!("text: {}", preview(&text.text, preview_chars)),
+                        UserContent::ToolResult(result) => {
+                            let text: Vec<&str> = result.content.iter().map(|r| match r {
+                                ToolResultContent::Text(t) => t.text.as_str(),
+                                ToolResultContent::Image(_) => "<image>",
+                            }).collect();
+                            format!("result [{}]: {}", result.id, preview(&text.join(" "), preview_chars))
+                        }
+                        UserContent::Image(_) => "<image>".to_string(),
+                        UserContent::Audio(_) => "<audio>".to_string(),
+                        UserContent::Video(_) => "<video>".to_string(),
+                        UserContent::Document(_) => "<document>".to_string(),
+                    }).collect();
+                    (role, parts)
+                }
+                Message::Assistant { content, .. } => {
+                    let parts = content.iter().map(|c| match c {
+                        AssistantContent::Text(text) => format!("text: {}", preview(&text.text, preview_chars)),
+                        AssistantContent::ToolCall(call) => format!(
+                            "call [{}]: {}({})",
+                            call.id,
+                            call.function.name,
+                            preview(&mask_secret_env(&call.function.arguments).to_string(), preview_chars)
+                        ),
+                        AssistantContent::Reasoning(_) => "<reasoning>".to_string(),
+                        AssistantContent::Image(_) => "<image>".to_string(),
+                    }).collect();
+                    ("assistant", parts)
+                }
+            };
+
+            output.push_str(&format!("\n#{} {}\n", idx, role));
+            for part in parts {
+                output.push_str(&format!("    {}\n", part));
+            }
+        }
+
+        output
+    }
+
     /// Store a message in routine memory (automatic conversation history)
     async fn store_in_routine_memory(&self, role: &str, content: &str, tool_name: Option<&str>) {
         // Only store if memory manager is available
@@ -1869,17 +3902,250 @@ impl<M: CompletionModel> AgentLoop<M> {
             let mut mm = memory_manager.lock().await;
             if let Err(e) = mm.store_routine_memory(chunk) {
                 // Log error but don't fail the conversation
-                eprintln!("Warning: Failed to store routine memory: {}", e);
+                tracing::warn!("Failed to store routine memory: {}", e);
             }
         }
     }
 }
 
-/// Truncate a string for display
-fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
+/// Tool arguments as display strings, long text values shortened
+fn display_args(args: &Value) -> HashMap<String, String> {
+    let args = mask_secret_env(args);
+    let Some(obj) = args.as_object() else {
+        return HashMap::new();
+    };
+    obj.iter()
+        .map(|(key, value)| {
+            let display_value = if let Some(s) = value.as_str() {
+                terminal::truncate_chars(s, 100)
+            } else {
+                value.to_string()
+            };
+            (key.clone(), display_value)
+        })
+        .collect()
+}
+
+/// Resolve a directory change request against the current directory, rejecting
+/// anything that is not an existing directory or that lies outside the root
+fn resolve_directory(current: &Path, path: &str, root: Option<&Path>) -> Result<PathBuf, ToolError> {
+    let target = current.join(path);
+    let target = target
+        .canonicalize()
+        .map_err(|_| ToolError::file_not_found(target.to_string_lossy().to_string()))?;
+    if !target.is_dir() {
+        return Err(ToolError::invalid_arguments(format!("{} is not a directory", target.display())));
+    }
+
+    if let Some(root) = root {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        if !target.starts_with(&root) {
+            return Err(ToolError::permission_denied(format!(
+                "{} is outside the allowed root {}",
+                target.display(),
+                root.display()
+            )));
+        }
+    }
+
+    Ok(target)
+}
+
+/// Whether a message is a user prompt rather than the user turn that carries
+/// tool results or an --auto-continue nudge
+fn is_user_prompt(message: &Message) -> bool {
+    match message {
+        Message::User { content } => content.iter().any(|c| match c {
+            UserContent::Text(text) => text.text != auto_continue::CONTINUE_PROMPT,
+            UserContent::ToolResult(_) => false,
+            _ => true,
+        }),
+        Message::Assistant { .. } => false,
+    }
+}
+
+/// Single-line, char-safe preview of a message part
+fn preview(text: &str, max_chars: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= max_chars {
+        flat
     } else {
-        format!("{}...", &s[..max_len])
+        format!("{}... ({} chars)", flat.chars().take(max_chars).collect::<String>(), text.chars().count())
+    }
+}
+
+/// Result returned to the model for a file write skipped by /dryrun
+fn dry_run_write_message(tool_name: &str, args: &Value) -> String {
+    let path = args.get("file_path").and_then(|p| p.as_str()).unwrap_or("<unknown>");
+    let crate_name = args.get("crate_name").and_then(|c| c.as_str()).unwrap_or("<unknown>");
+    match tool_name {
+        "add_dependency" | "remove_dependency" => {
+            let action = if tool_name == "add_dependency" { "add" } else { "remove" };
+            format!("[dry run] Would have run cargo {} {}; Cargo.toml was not changed", action, crate_name)
+        }
+        "write_file" => {
+            let bytes = args.get("content").and_then(|c| c.as_str()).map_or(0, str::len);
+            format!("[dry run] Would have written {} bytes to {}; the file was not changed", bytes, path)
+        }
+        _ => format!("[dry run] Would have applied {} to {}; the file was not changed", tool_name, path),
+    }
+}
+
+/// Truncate a tool result to at most `max_bytes` (on a char boundary), appending a
+/// marker so the model knows to page through the rest. `max_bytes == 0` disables the cap.
+fn truncate_tool_output(output: String, max_bytes: usize) -> String {
+    if max_bytes == 0 || output.len() <= max_bytes {
+        return output;
+    }
+
+    let mut end = max_bytes;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!(
+        "{}\n\n[output truncated, {} of {} bytes shown; use offset/limit]",
+        &output[..end],
+        end,
+        output.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_kill_subagent() {
+        let parent = CancellationToken::new();
+        let (first, second) = (parent.child_token(), parent.child_token());
+        let registration = SubagentRegistration::new("test-first", first.clone());
+        let _second = SubagentRegistration::new("test-second", second.clone());
+
+        // Killing one sub-agent leaves its parent and siblings running
+        assert!(kill_subagent("test-first"));
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled() && !parent.is_cancelled());
+
+        drop(registration);
+        assert!(!kill_subagent("test-first"));
+
+        // Interrupting the parent reaches every sub-agent
+        parent.cancel();
+        assert!(second.is_cancelled());
+    }
+
+    #[test]
+    fn test_subagent_result_json() {
+        let result = SubAgentResult {
+            success: false,
+            response: None,
+            error: Some("Sub-agent timed out after 300 seconds".to_string()),
+            files_changed: vec![SubAgentFileChange {
+                path: "src/lib.rs".to_string(),
+                operation: FileOperation::Modified.to_string(),
+            }],
+            iterations: 7,
+            elapsed_secs: 300,
+        };
+        let json: Value = serde_json::from_str(&result.to_json()).unwrap();
+        assert_eq!(json["success"], false);
+        assert!(json.get("response").is_none());
+        assert_eq!(json["files_changed"][0]["path"], "src/lib.rs");
+        assert_eq!(json["files_changed"][0]["operation"], "modified");
+        assert_eq!(json["iterations"], 7);
+    }
+
+    #[test]
+    fn test_is_user_prompt() {
+        let prompt = Message::User { content: OneOrMany::one(UserContent::text("hi")) };
+        let results = Message::User {
+            content: OneOrMany::one(UserContent::ToolResult(ToolResult {
+                id: "1".to_string(),
+                call_id: None,
+                content: OneOrMany::one(ToolResultContent::text("ok")),
+            })),
+        };
+        let nudge = Message::User { content: OneOrMany::one(UserContent::text(auto_continue::CONTINUE_PROMPT)) };
+        assert!(is_user_prompt(&prompt));
+        assert!(!is_user_prompt(&results));
+        assert!(!is_user_prompt(&nudge));
+    }
+
+    #[test]
+    fn test_preview_flattens_and_truncates() {
+        assert_eq!(preview("a\n  b", 10), "a b");
+        assert_eq!(preview("héllo wörld", 5), "héllo... (11 chars)");
+    }
+
+    #[test]
+    fn test_token_budget() {
+        let budget = TokenBudget::new(1000);
+        budget.charge(600);
+        assert!(!budget.is_exhausted());
+        budget.charge(400);
+        assert!(budget.is_exhausted());
+        assert_eq!(budget.used(), 1000);
+
+        let error: anyhow::Error = AgentError::TokenBudgetExhausted { used: budget.used(), limit: budget.limit() }.into();
+        assert!(error.to_string().starts_with("Token budget exhausted: 1000 of 1000 tokens"));
+    }
+
+    #[test]
+    fn test_default_max_tokens() {
+        assert_eq!(default_max_tokens(8192), 4096);
+        assert_eq!(default_max_tokens(131072), 32768);
+        assert_eq!(default_max_tokens(0), 256);
+    }
+
+    #[test]
+    fn test_resolve_directory() {
+        let dir = TempDir::new("cd");
+        let base = dir.path();
+        let sub = base.join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(base.join("file.txt"), "x").unwrap();
+
+        let resolved = resolve_directory(base, "sub", Some(base)).unwrap();
+        assert_eq!(resolved, sub.canonicalize().unwrap());
+        assert!(resolve_directory(&sub, "..", Some(base)).is_ok());
+        assert!(resolve_directory(base, "..", Some(base)).is_err());
+        assert!(resolve_directory(base, "..", None).is_ok());
+        assert!(resolve_directory(base, "file.txt", None).is_err());
+        assert!(resolve_directory(base, "missing", None).is_err());
+    }
+
+    #[test]
+    fn test_tool_stats_table() {
+        let mut stats = ToolStats::default();
+        stats.record("read_file", None);
+        stats.record("read_file", None);
+        stats.record("edit_file", None);
+        stats.record("edit_file", Some("old_string not found\nin src/main.rs"));
+
+        let table = stats.format_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].starts_with("edit_file") && lines[1].ends_with("50%"));
+        assert!(lines[2].starts_with("read_file") && lines[2].ends_with("0%"));
+        assert_eq!(lines[5], "  edit_file: old_string not found in src/main.rs");
+        assert_eq!(ToolStats::default().format_table(), "No tool calls yet.");
+    }
+
+    #[test]
+    fn test_truncate_tool_output() {
+        let short = "hello".to_string();
+        assert_eq!(truncate_tool_output(short.clone(), 10), short);
+        assert_eq!(truncate_tool_output(short.clone(), 0), short);
+
+        let long = "a".repeat(100);
+        let truncated = truncate_tool_output(long, 10);
+        assert!(truncated.starts_with(&"a".repeat(10)));
+        assert!(truncated.ends_with("[output truncated, 10 of 100 bytes shown; use offset/limit]"));
+
+        // Never split a multi-byte character
+        let multibyte = "é".repeat(10);
+        let truncated = truncate_tool_output(multibyte, 5);
+        assert!(truncated.contains("4 of 20 bytes shown"));
     }
 }
@@ -0,0 +1,132 @@
+//! Durable per-session audit log of tool calls
+//!
+//! Every tool call the agent makes (including denied ones) is appended as one
+//! JSON line to `~/.agent-t/agents/<name>/audit/<session>.jsonl` (named
+//! `batch-<time>` or `interactive-<time>` for runs without a session id), so
+//! an unattended run can be reviewed after the fact.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Maximum characters of a tool result kept in the audit log
+const MAX_RESULT_CHARS: usize = 2000;
+
+/// One audited tool call
+#[derive(Debug, Serialize)]
+pub struct AuditEntry<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub agent_id: &'a str,
+    pub tool: &'a str,
+    pub arguments: &'a serde_json::Value,
+    pub result: String,
+    pub duration_ms: u64,
+    /// False if the call was denied or rejected and never ran
+    pub approved: bool,
+    pub dry_run: bool,
+}
+
+impl<'a> AuditEntry<'a> {
+    pub fn new(
+        agent_id: &'a str,
+        tool: &'a str,
+        arguments: &'a serde_json::Value,
+        result: &str,
+        duration_ms: u64,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            agent_id,
+            tool,
+            arguments,
+            result: truncate_result(result),
+            duration_ms,
+            approved: true,
+            dry_run: false,
+        }
+    }
+}
+
+/// Append-only JSONL audit file for one session
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    /// Serializes writes from the main agent and its sub-agents
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    /// Audit log for a session, stored in the agent's directory
+    pub fn for_session(agent_dir: &Path, session_id: &str) -> Self {
+        Self {
+            path: agent_dir.join("audit").join(format!("{}.jsonl", session_id)),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Path of the audit file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry; failures are reported but never interrupt the agent
+    pub fn record(&self, entry: &AuditEntry) {
+        let Ok(line) = serde_json::to_string(entry) else {
+            return;
+        };
+        let _guard = self.lock.lock();
+
+        if let Some(dir) = self.path.parent()
+            && let Err(e) = std::fs::create_dir_all(dir)
+        {
+            tracing::warn!("Failed to create audit directory: {}", e);
+            return;
+        }
+
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            tracing::warn!("Failed to write audit log {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Keep the audit file readable when a tool returns a huge result
+fn truncate_result(result: &str) -> String {
+    if result.chars().count() <= MAX_RESULT_CHARS {
+        return result.to_string();
+    }
+    let kept: String = result.chars().take(MAX_RESULT_CHARS).collect();
+    format!("{}... [truncated, {} chars total]", kept, result.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_record_appends_jsonl() {
+        let dir = TempDir::new("audit");
+        let log = AuditLog::for_session(dir.path(), "session-1");
+        let args = serde_json::json!({ "command": "ls" });
+
+        log.record(&AuditEntry::new("main", "bash", &args, "ok", 12));
+        let mut denied = AuditEntry::new("main", "write_file", &args, &"x".repeat(3000), 0);
+        denied.approved = false;
+        log.record(&denied);
+
+        let contents = std::fs::read_to_string(log.path()).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["tool"], "bash");
+        assert_eq!(lines[0]["approved"], true);
+        assert_eq!(lines[1]["approved"], false);
+        assert!(lines[1]["result"].as_str().unwrap().ends_with("[truncated, 3000 chars total]"));
+    }
+}
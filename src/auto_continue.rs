@@ -0,0 +1,85 @@
+//! Nudging a model that stops mid-task (--auto-continue)
+//!
+//! Small models sometimes end a turn with "Let me now edit the file..." and
+//! never make the call. When a final response ends by promising an action,
+//! the agent adds a "continue" message and asks again, a few times at most.
+
+/// Follow-up requests per turn before the response is accepted as final
+pub const MAX_CONTINUATIONS: usize = 2;
+
+/// User message sent when the model stopped after promising an action
+pub const CONTINUE_PROMPT: &str = "You said what you would do next but did not call a tool. Continue with the task now, making the tool call. If the task is already complete, say so.";
+
+/// Words that can come before the promise ("Now, let me ...")
+const FILLERS: &[&str] = &["now", "next", "then", "first", "so", "ok", "okay", "great", "alright"];
+
+/// Openings of a sentence announcing the next step
+const PROMISES: &[&str] = &[
+    "let me",
+    "let's",
+    "i'll",
+    "i will",
+    "i'm going to",
+    "i am going to",
+    "i need to",
+    "i'm now going to",
+];
+
+/// Promising openings that hand the turn back to the user instead
+const HAND_BACKS: &[&str] = &["let me know", "i'll wait", "i will wait", "if you", "once you", "when you"];
+
+/// Whether a response ends by announcing a step it did not take
+pub fn promises_action(text: &str) -> bool {
+    let Some(last_line) = text.lines().rev().map(str::trim).find(|line| !line.is_empty()) else {
+        return false;
+    };
+    if last_line.ends_with('?') || last_line.starts_with("```") {
+        return false;
+    }
+
+    let sentence = strip_fillers(&last_sentence(last_line).to_lowercase());
+    PROMISES.iter().any(|promise| sentence.starts_with(promise))
+        && !HAND_BACKS.iter().any(|hand_back| sentence.contains(hand_back))
+}
+
+/// The last sentence of a line, without its closing punctuation
+fn last_sentence(line: &str) -> &str {
+    let line = line.trim_end_matches(['.', '!', ':', '…', ' ']);
+    line.rfind(". ")
+        .or_else(|| line.rfind("! "))
+        .map(|pos| &line[pos + 2..])
+        .unwrap_or(line)
+        .trim()
+}
+
+/// Drop leading "now", "ok, so" and the like
+fn strip_fillers(sentence: &str) -> String {
+    let mut rest = sentence.trim_start_matches(['*', '-', ' ']);
+    loop {
+        let word = rest.split([' ', ',']).next().unwrap_or("");
+        if word.is_empty() || !FILLERS.contains(&word) {
+            return rest.to_string();
+        }
+        rest = rest[word.len()..].trim_start_matches([',', ' ']);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promises_action() {
+        assert!(promises_action("Let me now edit the file..."));
+        assert!(promises_action("The test fails because of a missing import. Now, I'll fix it:"));
+        assert!(promises_action("Found the bug.\n\nOk, so let's update `parse_env`."));
+        assert!(promises_action("I'm going to run the tests next."));
+
+        assert!(!promises_action("Done. The tests pass."));
+        assert!(!promises_action("Let me know if you want anything else."));
+        assert!(!promises_action("Shall I apply the change?"));
+        assert!(!promises_action("I'll wait for your go-ahead."));
+        assert!(!promises_action("I fixed it; let me summarize:\n\n- one\n- two"));
+        assert!(!promises_action(""));
+    }
+}
@@ -0,0 +1,69 @@
+//! System clipboard access
+//!
+//! Tries the platform clipboard utilities first (pbcopy, wl-copy, xclip, xsel,
+//! clip.exe) and falls back to the OSC 52 terminal escape sequence, which most
+//! modern terminals (and tmux with `set-clipboard on`) forward to the host
+//! clipboard, including over SSH.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard utilities to try, in order of preference
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copy text to the system clipboard
+///
+/// Returns the name of the mechanism that accepted the text.
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        if run_clipboard_command(program, args, text).is_ok() {
+            return Ok(program);
+        }
+    }
+
+    copy_osc52(text)?;
+    Ok("OSC 52")
+}
+
+/// Pipe text into an external clipboard utility
+fn run_clipboard_command(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("{} exited with {}", program, status))
+    }
+}
+
+/// Ask the terminal to set the clipboard via the OSC 52 escape sequence
+fn copy_osc52(text: &str) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Build the OSC 52 escape sequence for the given text
+fn osc52_sequence(text: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    format!("\x1b]52;c;{}\x07", encoded)
+}
@@ -0,0 +1,238 @@
+//! Fenced code block extraction from assistant messages
+//!
+//! Models often answer with code in a markdown fence instead of calling
+//! `write_file`. This module finds those blocks and the filename hint that
+//! goes with them, so the TUI can offer to write them to disk.
+//!
+//! Recognised filename hints, in order of precedence:
+//! - In the info string: ```` ```rust src/main.rs ````, ```` ```rust:src/main.rs ````,
+//!   ```` ```python title="app.py" ```` (also `file=`, `filename=`, `path=`)
+//! - A comment on the first line of the block: `// src/main.rs`, `# file: app.py`
+//! - The line right before the fence: `` `src/main.rs`: ``, `**src/main.rs**`, `File: src/main.rs`
+
+/// A fenced code block found in a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Language from the fence info string, if any
+    pub language: Option<String>,
+    /// File path hinted for the block, if any
+    pub path: Option<String>,
+    /// Contents of the block (without the fences)
+    pub content: String,
+}
+
+/// Extract all terminated fenced code blocks from markdown text
+pub fn extract(text: &str) -> Vec<CodeBlock> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let Some(fence) = fence_marker(trimmed) else {
+            i += 1;
+            continue;
+        };
+
+        let info = trimmed[fence.len()..].trim();
+        let close = lines[i + 1..]
+            .iter()
+            .position(|line| {
+                let line = line.trim();
+                line.starts_with(fence) && line.chars().all(|c| fence.starts_with(c))
+            })
+            .map(|offset| i + 1 + offset);
+
+        // Unterminated fence (e.g. a message still being streamed) - stop here
+        let Some(close) = close else {
+            break;
+        };
+
+        let body = &lines[i + 1..close];
+        let (language, info_path) = parse_info_string(info);
+        let path = info_path
+            .or_else(|| body.first().and_then(|line| path_from_comment(line)))
+            .or_else(|| {
+                lines[..i]
+                    .iter()
+                    .rev()
+                    .find(|line| !line.trim().is_empty())
+                    .and_then(|line| path_from_preceding_line(line))
+            });
+
+        let mut content = body.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+
+        blocks.push(CodeBlock { language, path, content });
+        i = close + 1;
+    }
+
+    blocks
+}
+
+/// Return the fence marker (``` or ~~~, possibly longer) that opens a line
+pub(crate) fn fence_marker(line: &str) -> Option<&str> {
+    for fence_char in ['`', '~'] {
+        let len = line.chars().take_while(|&c| c == fence_char).count();
+        if len >= 3 {
+            return Some(&line[..len]);
+        }
+    }
+    None
+}
+
+/// Split a fence info string into a language and an optional path
+fn parse_info_string(info: &str) -> (Option<String>, Option<String>) {
+    let mut language = None;
+    let mut path = None;
+
+    for (idx, token) in info.split_whitespace().enumerate() {
+        if let Some((key, value)) = token.split_once('=') {
+            if matches!(key, "file" | "filename" | "path" | "title") {
+                let value = value.trim_matches(|c| c == '"' || c == '\'');
+                if looks_like_path(value) {
+                    path = Some(value.to_string());
+                }
+            }
+            continue;
+        }
+
+        if idx == 0 {
+            // "rust:src/main.rs" style
+            if let Some((lang, hinted)) = token.split_once(':')
+                && looks_like_path(hinted) {
+                    language = Some(lang.to_string());
+                    path = Some(hinted.to_string());
+                    continue;
+                }
+            if looks_like_path(token) && token.contains(['/', '.']) && path.is_none() {
+                path = Some(token.to_string());
+            } else {
+                language = Some(token.to_string());
+            }
+        } else if path.is_none() && looks_like_path(token) {
+            path = Some(token.to_string());
+        }
+    }
+
+    (language, path)
+}
+
+/// Recognise `// path`, `# file: path`, `-- path`, `/* path */` and `<!-- path -->`
+fn path_from_comment(line: &str) -> Option<String> {
+    let line = line.trim();
+    let rest = ["//", "#", "--", "/*", "<!--"]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))?;
+    let rest = rest.trim().trim_end_matches("*/").trim_end_matches("-->").trim();
+    let rest = strip_label(rest);
+
+    // Only accept the comment if it is nothing but a path
+    if rest.split_whitespace().count() == 1 && looks_like_path(rest) && rest.contains(['/', '.']) {
+        Some(rest.to_string())
+    } else {
+        None
+    }
+}
+
+/// Recognise `` `src/main.rs`: ``, `**src/main.rs**` or `File: src/main.rs`
+fn path_from_preceding_line(line: &str) -> Option<String> {
+    let cleaned = line
+        .trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_end_matches(':')
+        .trim_matches(|c| c == '*' || c == '`' || c == '_')
+        .trim_end_matches(':');
+    let cleaned = strip_label(cleaned).trim_matches(|c| c == '*' || c == '`' || c == '_');
+
+    if cleaned.split_whitespace().count() == 1 && looks_like_path(cleaned) && cleaned.contains(['/', '.']) {
+        Some(cleaned.to_string())
+    } else {
+        None
+    }
+}
+
+/// Strip a leading "file:", "filename:" or "path:" label (case-insensitive)
+fn strip_label(text: &str) -> &str {
+    for label in ["file:", "filename:", "path:"] {
+        if text.len() >= label.len()
+            && text.is_char_boundary(label.len())
+            && text[..label.len()].eq_ignore_ascii_case(label) {
+                return text[label.len()..].trim();
+            }
+    }
+    text
+}
+
+/// Heuristic check that a token is a relative or absolute file path with a file name
+fn looks_like_path(token: &str) -> bool {
+    if token.is_empty() || token.contains(char::is_whitespace) || token.contains("://") {
+        return false;
+    }
+
+    let file_name = token.rsplit('/').next().unwrap_or(token);
+    if file_name.is_empty() || file_name == "." || file_name == ".." {
+        return false;
+    }
+
+    // Require an extension or a well-known extensionless file name
+    match file_name.rsplit_once('.') {
+        Some((_, ext)) => {
+            !ext.is_empty() && ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => matches!(file_name, "Makefile" | "Dockerfile" | "Justfile" | "Rakefile" | "Gemfile"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_in_info_string() {
+        let text = "Here you go:\n```rust src/main.rs\nfn main() {}\n```\n";
+        let blocks = extract(text);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].path.as_deref(), Some("src/main.rs"));
+        assert_eq!(blocks[0].content, "fn main() {}\n");
+
+        let blocks = extract("```rust:src/lib.rs\npub fn f() {}\n```");
+        assert_eq!(blocks[0].path.as_deref(), Some("src/lib.rs"));
+
+        let blocks = extract("```python title=\"app.py\"\nprint(1)\n```");
+        assert_eq!(blocks[0].language.as_deref(), Some("python"));
+        assert_eq!(blocks[0].path.as_deref(), Some("app.py"));
+    }
+
+    #[test]
+    fn test_path_from_comment_and_preceding_line() {
+        let blocks = extract("```python\n# file: tools/run.py\nprint(1)\n```");
+        assert_eq!(blocks[0].path.as_deref(), Some("tools/run.py"));
+        // The hint comment stays in the content
+        assert!(blocks[0].content.starts_with("# file: tools/run.py"));
+
+        let blocks = extract("Update `src/config.rs`:\n\n```rust\nconst X: u8 = 1;\n```");
+        assert_eq!(blocks[0].path, None);
+
+        let blocks = extract("**src/config.rs**\n```rust\nconst X: u8 = 1;\n```");
+        assert_eq!(blocks[0].path.as_deref(), Some("src/config.rs"));
+    }
+
+    #[test]
+    fn test_no_hint_and_unterminated() {
+        let blocks = extract("```bash\ncargo build\n```\n```rust\nfn partial(");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].language.as_deref(), Some("bash"));
+        assert_eq!(blocks[0].path, None);
+    }
+
+    #[test]
+    fn test_comment_that_is_not_a_path() {
+        let blocks = extract("```rust\n// Add this to the file\nfn x() {}\n```");
+        assert_eq!(blocks[0].path, None);
+    }
+}
@@ -1,41 +1,273 @@
-/// Catppuccin Mocha color palette
-/// Inspired by https://catppuccin.com/palette
+/// Color themes for CLI output and the TUI
 ///
-/// This module provides color constants for both the `colored` crate (CLI output)
-/// and `ratatui` (TUI) using the Catppuccin Mocha theme.
-
-// Catppuccin Mocha RGB values
-pub const ROSEWATER: (u8, u8, u8) = (245, 224, 220);
-pub const FLAMINGO: (u8, u8, u8) = (242, 205, 205);
-pub const PINK: (u8, u8, u8) = (245, 194, 231);
-pub const MAUVE: (u8, u8, u8) = (203, 166, 247);
-pub const RED: (u8, u8, u8) = (243, 139, 168);
-pub const MAROON: (u8, u8, u8) = (235, 160, 172);
-pub const PEACH: (u8, u8, u8) = (250, 179, 135);
-pub const YELLOW: (u8, u8, u8) = (249, 226, 175);
-pub const GREEN: (u8, u8, u8) = (166, 227, 161);
-pub const TEAL: (u8, u8, u8) = (148, 226, 213);
-pub const SKY: (u8, u8, u8) = (137, 220, 235);
-pub const SAPPHIRE: (u8, u8, u8) = (116, 199, 236);
-pub const BLUE: (u8, u8, u8) = (137, 180, 250);
-pub const LAVENDER: (u8, u8, u8) = (180, 190, 254);
-
-// Text colors
-pub const TEXT: (u8, u8, u8) = (205, 214, 244);
-pub const SUBTEXT1: (u8, u8, u8) = (186, 194, 222);
-pub const SUBTEXT0: (u8, u8, u8) = (166, 173, 200);
-
-// Overlay colors
-pub const OVERLAY2: (u8, u8, u8) = (147, 153, 178);
-pub const OVERLAY1: (u8, u8, u8) = (127, 132, 156);
-pub const OVERLAY0: (u8, u8, u8) = (108, 112, 134);
-
-// Surface colors
-pub const SURFACE2: (u8, u8, u8) = (88, 91, 112);
-pub const SURFACE1: (u8, u8, u8) = (69, 71, 90);
-pub const SURFACE0: (u8, u8, u8) = (49, 50, 68);
-
-// Base colors
-pub const BASE: (u8, u8, u8) = (30, 30, 46);
-pub const MANTLE: (u8, u8, u8) = (24, 24, 37);
-pub const CRUST: (u8, u8, u8) = (17, 17, 27);
+/// The palette slots are named after Catppuccin's (https://catppuccin.com/palette),
+/// which the default theme, Catppuccin Mocha, comes from. Every preset fills the
+/// same slots, used by both the `colored` crate (CLI output) and `ratatui` (TUI).
+///
+/// `--theme` picks a preset or a JSON file; without it `~/.agent-t/theme.json`
+/// is used when it exists. A theme file starts from a preset and overrides
+/// single slots, as `[r, g, b]` or `"#rrggbb"`:
+///
+/// ```json
+/// { "preset": "high-contrast", "colors": { "sapphire": "#00d7ff", "red": [255, 64, 64] } }
+/// ```
+///
+/// `--no-color` or `NO_COLOR` (or `TERM=dumb`) turns colors off altogether:
+/// CLI output is printed as plain text and the TUI uses the terminal's own colors.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+/// Name of the theme file in `~/.agent-t/`
+pub const THEME_FILE: &str = "theme.json";
+
+/// Preset names accepted by `--theme`
+pub const THEME_NAMES: &[&str] = &["mocha", "latte", "high-contrast"];
+
+/// An RGB color
+pub type Rgb = (u8, u8, u8);
+
+/// The colors the UI draws with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Regular text
+    pub text: Rgb,
+    /// Dimmed text: hints, tool details, reasoning
+    pub overlay0: Rgb,
+    /// Raised backgrounds: tab bar, status bar
+    pub surface0: Rgb,
+    /// Background of the input and popups
+    pub base: Rgb,
+    /// Errors, denials
+    pub red: Rgb,
+    pub peach: Rgb,
+    /// Warnings, pending confirmation
+    pub yellow: Rgb,
+    /// Success, the user's messages
+    pub green: Rgb,
+    /// Tool calls
+    pub sapphire: Rgb,
+    /// The assistant's messages, links
+    pub blue: Rgb,
+    pub mauve: Rgb,
+}
+
+/// Catppuccin Mocha (dark, the default)
+pub const MOCHA: Theme = Theme {
+    text: (205, 214, 244),
+    overlay0: (108, 112, 134),
+    surface0: (49, 50, 68),
+    base: (30, 30, 46),
+    red: (243, 139, 168),
+    peach: (250, 179, 135),
+    yellow: (249, 226, 175),
+    green: (166, 227, 161),
+    sapphire: (116, 199, 236),
+    blue: (137, 180, 250),
+    mauve: (203, 166, 247),
+};
+
+/// Catppuccin Latte (light)
+pub const LATTE: Theme = Theme {
+    text: (76, 79, 105),
+    overlay0: (140, 143, 161),
+    surface0: (204, 208, 218),
+    base: (239, 241, 245),
+    red: (210, 15, 57),
+    peach: (254, 100, 11),
+    yellow: (223, 142, 29),
+    green: (64, 160, 43),
+    sapphire: (32, 159, 181),
+    blue: (30, 102, 245),
+    mauve: (136, 57, 239),
+};
+
+/// Saturated colors on black, with dimmed text kept well readable
+pub const HIGH_CONTRAST: Theme = Theme {
+    text: (255, 255, 255),
+    overlay0: (200, 200, 200),
+    surface0: (64, 64, 64),
+    base: (0, 0, 0),
+    red: (255, 85, 85),
+    peach: (255, 175, 0),
+    yellow: (255, 255, 0),
+    green: (0, 255, 0),
+    sapphire: (0, 255, 255),
+    blue: (95, 175, 255),
+    mauve: (255, 135, 255),
+};
+
+static THEME: RwLock<Theme> = RwLock::new(MOCHA);
+
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// The active theme
+pub fn theme() -> Theme {
+    THEME.read().map(|guard| *guard).unwrap_or(MOCHA)
+}
+
+/// Make `theme` the active theme
+pub fn set_theme(theme: Theme) {
+    if let Ok(mut guard) = THEME.write() {
+        *guard = theme;
+    }
+}
+
+/// Whether the environment asks for no colors: `NO_COLOR` set to anything, or a dumb terminal
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+        || std::env::var("TERM").is_ok_and(|term| term == "dumb")
+}
+
+/// Turn all colors and text styles off (or back on)
+pub fn set_no_color(no_color: bool) {
+    NO_COLOR.store(no_color, Ordering::Relaxed);
+    if no_color {
+        colored::control::set_override(false);
+    } else {
+        colored::control::unset_override();
+    }
+}
+
+/// Whether colors are off
+pub fn no_color() -> bool {
+    NO_COLOR.load(Ordering::Relaxed)
+}
+
+/// A theme color for ratatui (`colored` takes the tuple directly, via
+/// `custom_color`); the terminal's default color when colors are off
+pub fn tui((r, g, b): Rgb) -> ratatui::style::Color {
+    if no_color() {
+        ratatui::style::Color::Reset
+    } else {
+        ratatui::style::Color::Rgb(r, g, b)
+    }
+}
+
+/// A color in a theme file
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    Rgb([u8; 3]),
+    Hex(String),
+}
+
+impl ColorValue {
+    fn to_rgb(&self) -> Result<Rgb> {
+        match self {
+            ColorValue::Rgb([r, g, b]) => Ok((*r, *g, *b)),
+            ColorValue::Hex(hex) => {
+                let digits = hex.strip_prefix('#').unwrap_or(hex);
+                let channel = |i: usize| {
+                    digits.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok())
+                };
+                match (digits.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok((r, g, b)),
+                    _ => Err(anyhow!("invalid color '{}' (expected #rrggbb or [r, g, b])", hex)),
+                }
+            }
+        }
+    }
+}
+
+/// Contents of a theme file
+#[derive(Debug, Deserialize)]
+struct ThemeFile {
+    preset: Option<String>,
+    #[serde(default)]
+    colors: HashMap<String, ColorValue>,
+}
+
+impl Theme {
+    /// A preset by name
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name {
+            "mocha" => Some(MOCHA),
+            "latte" => Some(LATTE),
+            "high-contrast" => Some(HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    /// A theme from the JSON of a theme file
+    fn from_json(json: &str) -> Result<Theme> {
+        let file: ThemeFile = serde_json::from_str(json)?;
+        let mut theme = match file.preset.as_deref() {
+            None => MOCHA,
+            Some(name) => Theme::preset(name)
+                .ok_or_else(|| anyhow!("unknown preset '{}' (expected one of: {})", name, THEME_NAMES.join(", ")))?,
+        };
+        for (slot, value) in &file.colors {
+            let color = value.to_rgb()?;
+            let target = match slot.as_str() {
+                "text" => &mut theme.text,
+                "overlay0" => &mut theme.overlay0,
+                "surface0" => &mut theme.surface0,
+                "base" => &mut theme.base,
+                "red" => &mut theme.red,
+                "peach" => &mut theme.peach,
+                "yellow" => &mut theme.yellow,
+                "green" => &mut theme.green,
+                "sapphire" => &mut theme.sapphire,
+                "blue" => &mut theme.blue,
+                "mauve" => &mut theme.mauve,
+                other => return Err(anyhow!("unknown color '{}'", other)),
+            };
+            *target = color;
+        }
+        Ok(theme)
+    }
+
+    /// A preset name or the path of a theme file
+    pub fn load(name_or_path: &str) -> Result<Theme> {
+        if let Some(theme) = Theme::preset(name_or_path) {
+            return Ok(theme);
+        }
+        let path = Path::new(name_or_path);
+        if !path.exists() {
+            return Err(anyhow!(
+                "'{}' is neither a theme ({}) nor a theme file",
+                name_or_path,
+                THEME_NAMES.join(", ")
+            ));
+        }
+        Self::load_file(path)
+    }
+
+    fn load_file(path: &Path) -> Result<Theme> {
+        let json = std::fs::read_to_string(path)?;
+        Theme::from_json(&json).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// The user's `~/.agent-t/theme.json`, if it exists
+    pub fn load_default() -> Option<Result<Theme>> {
+        let path = theme_path()?;
+        path.exists().then(|| Self::load_file(&path))
+    }
+}
+
+fn theme_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".agent-t").join(THEME_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_json() {
+        let theme = Theme::from_json(r##"{"preset": "latte", "colors": {"red": "#ff0000", "text": [1, 2, 3]}}"##).unwrap();
+        assert_eq!(theme.red, (255, 0, 0));
+        assert_eq!(theme.text, (1, 2, 3));
+        assert_eq!(theme.blue, LATTE.blue);
+
+        assert_eq!(Theme::from_json("{}").unwrap(), MOCHA);
+        assert!(Theme::from_json(r#"{"preset": "solarized"}"#).is_err());
+        assert!(Theme::from_json(r##"{"colors": {"pink": "#ffffff"}}"##).is_err());
+        assert!(Theme::from_json(r##"{"colors": {"red": "#fff"}}"##).is_err());
+    }
+}
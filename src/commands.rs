@@ -5,6 +5,7 @@ use tokio::sync::mpsc::Sender;
 use crate::tui::TuiEvent;
 use crate::session::SessionManager;
 use crate::git::GitInfo;
+use crate::permissions;
 
 /// Result of executing a command
 #[derive(Debug, Clone)]
@@ -15,8 +16,38 @@ pub enum CommandResult {
     Exit,
     /// Clear chat history
     ClearHistory,
+    /// A new session was started (its ID); the agent starts over
+    NewSession(String),
     /// Show file changes summary
     ShowFileChanges,
+    /// Show per-tool timing
+    ShowProfile,
+    /// Show per-tool success/failure counts
+    ShowToolStats,
+    /// Show the estimated session cost
+    ShowCost,
+    /// Dump raw chat history for debugging
+    ShowHistory,
+    /// Rebuild the system prompt from its files, keeping the history
+    ReloadPrompt,
+    /// Show a symbol outline of a Rust file
+    ShowOutline(String),
+    /// Show rust-analyzer's errors and warnings for the workspace
+    ShowDiagnostics,
+    /// Rewind the conversation to the first N user turns
+    Rewind(usize),
+    /// Change the agent's working directory
+    ChangeDirectory(String),
+    /// Show the agent's lifetime statistics
+    ShowStats,
+    /// Turn interactive dry-run on or off (None reports the current state)
+    SetDryRun(Option<bool>),
+    /// Change which tools run without asking (None shows the permission state)
+    Permissions(Option<PermissionChange>),
+    /// Search long-term memory and show the matches
+    SearchMemory(String),
+    /// Save the inspector's captured traffic to this JSON file
+    ExportTraffic(String),
     /// Display informational message to user
     Info(String),
     /// Display warning message to user
@@ -25,6 +56,41 @@ pub enum CommandResult {
     Error(String),
 }
 
+/// A change to the tools the agent may use without asking
+#[derive(Debug, Clone, PartialEq)]
+pub enum PermissionChange {
+    /// Approve these tools for all further calls
+    Grant(Vec<String>),
+    /// Ask again before these tools run
+    Revoke(Vec<String>),
+    /// Forget every tool and file approval
+    RevokeAll,
+}
+
+/// A change to the agent tabs, carried out by the TUI
+#[derive(Debug, Clone, PartialEq)]
+pub enum TabAction {
+    /// List every tab with its status and runtime
+    List,
+    /// Switch to the Nth tab (numbered from 1, as with Ctrl+1-9)
+    Switch(usize),
+    /// Close the Nth tab once its sub-agent has finished
+    Close(usize),
+}
+
+/// Parse `/tabs` and `/tab ...` input. None when the input is any other command;
+/// an error holds the usage text.
+pub fn parse_tab_command(input: &str) -> Option<std::result::Result<TabAction, String>> {
+    let mut parts = input.trim().strip_prefix('/')?.split_whitespace();
+    let name = parts.next()?;
+    let args: Vec<&str> = parts.collect();
+    match name {
+        "tabs" => Some(Ok(TabAction::List)),
+        "tab" => Some(TabCommand::action(&args)),
+        _ => None,
+    }
+}
+
 /// Context provided to commands during execution
 pub struct CommandContext<'a> {
     pub session_manager: &'a mut SessionManager,
@@ -79,11 +145,29 @@ impl CommandRegistry {
         registry.register(Arc::new(HelpCommand));
         registry.register(Arc::new(ExitCommand));
         registry.register(Arc::new(ClearCommand));
+        registry.register(Arc::new(NewCommand));
         registry.register(Arc::new(SessionsCommand));
         registry.register(Arc::new(SaveCommand));
+        registry.register(Arc::new(NameCommand));
         registry.register(Arc::new(LoadCommand));
         registry.register(Arc::new(GitCommand));
         registry.register(Arc::new(ChangesCommand));
+        registry.register(Arc::new(ProfileCommand));
+        registry.register(Arc::new(ToolStatsCommand));
+        registry.register(Arc::new(HistoryCommand));
+        registry.register(Arc::new(ReloadPromptCommand));
+        registry.register(Arc::new(OutlineCommand));
+        registry.register(Arc::new(CheckCommand));
+        registry.register(Arc::new(InspectorCommand));
+        registry.register(Arc::new(RewindCommand));
+        registry.register(Arc::new(CdCommand));
+        registry.register(Arc::new(CostCommand));
+        registry.register(Arc::new(DryRunCommand));
+        registry.register(Arc::new(StatsCommand));
+        registry.register(Arc::new(PermissionsCommand));
+        registry.register(Arc::new(SearchCommand));
+        registry.register(Arc::new(TabsCommand));
+        registry.register(Arc::new(TabCommand));
 
         registry
     }
@@ -344,8 +428,8 @@ impl Command for SessionsCommand {
     }
 
     fn help(&self) -> String {
-        "List all saved sessions with their IDs, models, and message counts.\n\
-         Use /load <session_id> to resume a session.".to_string()
+        "List all saved sessions with their IDs, names, models, and message counts.\n\
+         Use /load <session_id|name|#tag> to resume a session.".to_string()
     }
 
     fn execute(&self, context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
@@ -357,24 +441,19 @@ impl Command for SessionsCommand {
 
         let mut output = String::from("Saved sessions:\n\n");
         for session in sessions {
-            let session_id_short = if session.id.len() > 8 {
-                &session.id[..8]
-            } else {
-                &session.id
-            };
-
             let created = session.created_at.format("%Y-%m-%d %H:%M:%S");
 
             output.push_str(&format!(
-                "  {} | {} | {} messages | Model: {}\n",
-                session_id_short,
+                "  {} | {} | {} | {} messages | Model: {}\n",
+                session.short_id(),
+                session.label(),
                 created,
                 session.message_count,
                 session.model
             ));
         }
 
-        output.push_str("\nUse /load <session_id> to resume a session.");
+        output.push_str("\nUse /load <session_id|name|#tag> to resume a session, /name to name this one.");
 
         Ok(CommandResult::Info(output))
     }
@@ -404,6 +483,62 @@ impl Command for SaveCommand {
     }
 }
 
+/// Name and tag the current session
+struct NameCommand;
+
+impl Command for NameCommand {
+    fn name(&self) -> &str {
+        "name"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["save-as"]
+    }
+
+    fn description(&self) -> &str {
+        "Name and tag the current session"
+    }
+
+    fn help(&self) -> String {
+        "Give the current session a name and tags, shown in /sessions and usable\n\
+         with /load. Words starting with # are tags; they replace earlier tags.\n\
+         The session is saved right away.\n\
+         Usage: /name <title> [#tag...]\n\
+         Without arguments, shows the current name and tags.".to_string()
+    }
+
+    fn execute(&self, context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        let Some(session) = context.session_manager.current_session_mut() else {
+            return Ok(CommandResult::Error("No active session".to_string()));
+        };
+
+        if !args.is_empty() {
+            let (tags, title): (Vec<&str>, Vec<&str>) = args.iter().partition(|arg| arg.starts_with('#'));
+            if !title.is_empty() {
+                session.name = Some(title.join(" "));
+            }
+            let tags: Vec<String> = tags
+                .iter()
+                .map(|tag| tag.trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            if !tags.is_empty() {
+                session.tags = tags;
+            }
+        }
+
+        let name = session.name.clone().unwrap_or_else(|| "(unnamed)".to_string());
+        let tags = session.tags.iter().map(|tag| format!(" #{}", tag)).collect::<String>();
+        let short_id = session.id[..8.min(session.id.len())].to_string();
+        if args.is_empty() {
+            return Ok(CommandResult::Info(format!("Session {}: {}{}", short_id, name, tags)));
+        }
+
+        context.session_manager.save_current_session()?;
+        Ok(CommandResult::Info(format!("Session {} saved as: {}{}", short_id, name, tags)))
+    }
+}
+
 /// Load a session
 struct LoadCommand;
 
@@ -413,44 +548,40 @@ impl Command for LoadCommand {
     }
 
     fn description(&self) -> &str {
-        "Load a saved session by ID"
+        "Load a saved session by ID, name or tag"
     }
 
     fn help(&self) -> String {
-        "Load a saved session by its ID.\n\
-         Usage: /load <session_id>\n\
-         You can find session IDs using the /sessions command.".to_string()
+        "Load a saved session by its ID (or the start of it), its name, part of\n\
+         its name, or one of its tags.\n\
+         Usage: /load <session_id|name|#tag>\n\
+         You can find sessions using the /sessions command.".to_string()
     }
 
     fn execute(&self, context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
         if args.is_empty() {
             return Ok(CommandResult::Error(
-                "Usage: /load <session_id>\nUse /sessions to see available sessions.".to_string()
+                "Usage: /load <session_id|name|#tag>\nUse /sessions to see available sessions.".to_string()
             ));
         }
 
-        let session_id = args[0];
-
-        // Try to find a matching session (allowing partial IDs)
-        let sessions = context.session_manager.list_sessions()?;
-        let matching_session = sessions
-            .iter()
-            .find(|s| s.id.starts_with(session_id));
+        let query = args.join(" ");
 
-        match matching_session {
-            Some(session) => {
-                let full_id = session.id.clone();
-                context.session_manager.load_session(&full_id)?;
+        // Try to find a matching session (allowing partial IDs and names)
+        match context.session_manager.find_session(&query) {
+            Ok(session) => {
+                context.session_manager.load_session(&session.id)?;
 
                 Ok(CommandResult::Info(format!(
-                    "Loaded session {} ({} messages)",
-                    &full_id[..8.min(full_id.len())],
+                    "Loaded session {} ({}, {} messages)",
+                    session.short_id(),
+                    session.label(),
                     session.message_count
                 )))
             }
-            None => Ok(CommandResult::Error(format!(
-                "Session '{}' not found. Use /sessions to see available sessions.",
-                session_id
+            Err(e) => Ok(CommandResult::Error(format!(
+                "{}. Use /sessions to see available sessions.",
+                e
             ))),
         }
     }
@@ -535,3 +666,513 @@ impl Command for ChangesCommand {
         Ok(CommandResult::ShowFileChanges)
     }
 }
+
+/// Show per-tool timing
+struct ProfileCommand;
+
+impl Command for ProfileCommand {
+    fn name(&self) -> &str {
+        "profile"
+    }
+
+    fn description(&self) -> &str {
+        "Show time spent per tool and in LLM requests"
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to display the profile from the agent
+        Ok(CommandResult::ShowProfile)
+    }
+}
+
+/// Show how often each tool succeeded and failed
+struct ToolStatsCommand;
+
+impl Command for ToolStatsCommand {
+    fn name(&self) -> &str {
+        "toolstats"
+    }
+
+    fn description(&self) -> &str {
+        "Show per-tool success and failure counts"
+    }
+
+    fn help(&self) -> String {
+        "Show how many calls of each tool succeeded and failed in this session,\n\
+         highest failure rate first, with each failing tool's last error.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to display the counts from the agent
+        Ok(CommandResult::ShowToolStats)
+    }
+}
+
+/// Dump the raw chat history sent to the model
+struct HistoryCommand;
+
+impl Command for HistoryCommand {
+    fn name(&self) -> &str {
+        "history"
+    }
+
+    fn description(&self) -> &str {
+        "Show raw chat history, including tool calls and results"
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to dump the agent's chat history
+        Ok(CommandResult::ShowHistory)
+    }
+}
+
+/// Re-read the system prompt without restarting
+struct ReloadPromptCommand;
+
+impl Command for ReloadPromptCommand {
+    fn name(&self) -> &str {
+        "reload-prompt"
+    }
+
+    fn description(&self) -> &str {
+        "Re-read the agent's system prompt files, keeping the conversation"
+    }
+
+    fn help(&self) -> String {
+        "Rebuild the system prompt from --system-prompt (or the built-in prompt),\n\
+         the agent's agent.json and system_prompt.md, and render its template\n\
+         variables again. --instructions and the previous session summary are kept.\n\
+         The conversation history is not changed.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to rebuild and swap the preamble
+        Ok(CommandResult::ReloadPrompt)
+    }
+}
+
+/// Show the item outline of a Rust file
+struct OutlineCommand;
+
+impl Command for OutlineCommand {
+    fn name(&self) -> &str {
+        "outline"
+    }
+
+    fn description(&self) -> &str {
+        "Show the modules, types, impls and functions in a Rust file"
+    }
+
+    fn help(&self) -> String {
+        "Show a hierarchical outline of a Rust file using rust-analyzer.\n\
+         Usage: /outline <file>".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        match args.first() {
+            // Signal to main loop to query rust-analyzer
+            Some(file) => Ok(CommandResult::ShowOutline(file.to_string())),
+            None => Ok(CommandResult::Error("Usage: /outline <file>".to_string())),
+        }
+    }
+}
+
+/// Show the workspace's rust-analyzer diagnostics
+struct CheckCommand;
+
+impl Command for CheckCommand {
+    fn name(&self) -> &str {
+        "check"
+    }
+
+    fn description(&self) -> &str {
+        "Show rust-analyzer errors and warnings without asking the model"
+    }
+
+    fn help(&self) -> String {
+        "List rust-analyzer's errors, then warnings, for the whole workspace,\n\
+         grouped by file. Nothing is sent to the model.\n\
+         Usage: /check".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to query rust-analyzer
+        Ok(CommandResult::ShowDiagnostics)
+    }
+}
+
+/// Traffic inspector actions
+struct InspectorCommand;
+
+impl Command for InspectorCommand {
+    fn name(&self) -> &str {
+        "inspector"
+    }
+
+    fn description(&self) -> &str {
+        "Save the traffic inspector's captured messages to a file"
+    }
+
+    fn help(&self) -> String {
+        "Write the traffic captured by --inspector (requests, responses, tool calls\n\
+         and system events) to a JSON file, e.g. to attach to a bug report.\n\
+         Relative paths are resolved against the working directory.\n\
+         Usage: /inspector export <path>".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        match args.as_slice() {
+            ["export", path] => Ok(CommandResult::ExportTraffic(path.to_string())),
+            _ => Ok(CommandResult::Error("Usage: /inspector export <path>".to_string())),
+        }
+    }
+}
+
+/// Rewind the conversation to an earlier user turn
+struct RewindCommand;
+
+impl Command for RewindCommand {
+    fn name(&self) -> &str {
+        "rewind"
+    }
+
+    fn description(&self) -> &str {
+        "Discard the conversation after the Nth user turn"
+    }
+
+    fn help(&self) -> String {
+        "Rewind the conversation, keeping only the first N user turns and the responses to them.\n\
+         Usage: /rewind <n>\n\
+         /rewind 0 discards everything, like /clear.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        match args.first().and_then(|n| n.parse::<usize>().ok()) {
+            // Signal to main loop to truncate the agent's history
+            Some(turns) => Ok(CommandResult::Rewind(turns)),
+            None => Ok(CommandResult::Error("Usage: /rewind <n>".to_string())),
+        }
+    }
+}
+
+/// Change the working directory
+struct CdCommand;
+
+impl Command for CdCommand {
+    fn name(&self) -> &str {
+        "cd"
+    }
+
+    fn description(&self) -> &str {
+        "Change the working directory for subsequent tool calls"
+    }
+
+    fn help(&self) -> String {
+        "Change the directory relative paths are resolved from.\n\
+         Usage: /cd <dir>\n\
+         With --root set, the directory must stay inside the root.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        if args.is_empty() {
+            return Ok(CommandResult::Error("Usage: /cd <dir>".to_string()));
+        }
+        // Signal to main loop to move the agent
+        Ok(CommandResult::ChangeDirectory(args.join(" ")))
+    }
+}
+
+/// Show the estimated cost of the session
+struct CostCommand;
+
+impl Command for CostCommand {
+    fn name(&self) -> &str {
+        "cost"
+    }
+
+    fn description(&self) -> &str {
+        "Show the estimated cost of this session"
+    }
+
+    fn help(&self) -> String {
+        "Estimate the session cost from token usage and ~/.agent-t/pricing.json.\n\
+         Models without a price are treated as local and cost nothing.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to price the agent's token usage
+        Ok(CommandResult::ShowCost)
+    }
+}
+
+/// Toggle previewing file writes without performing them
+struct DryRunCommand;
+
+impl Command for DryRunCommand {
+    fn name(&self) -> &str {
+        "dryrun"
+    }
+
+    fn description(&self) -> &str {
+        "Preview file writes without performing them"
+    }
+
+    fn help(&self) -> String {
+        "Turn dry-run mode on or off.\n\
+         Usage: /dryrun [on|off]\n\
+         While on, write_file, edit_file and edit_markdown_section still show their diff\n\
+         for approval, but nothing is written; the agent is told what would have changed.\n\
+         Other tools (including bash) run normally.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to update the agent
+        match args.first().map(|a| a.to_lowercase()).as_deref() {
+            None => Ok(CommandResult::SetDryRun(None)),
+            Some("on") => Ok(CommandResult::SetDryRun(Some(true))),
+            Some("off") => Ok(CommandResult::SetDryRun(Some(false))),
+            Some(_) => Ok(CommandResult::Error("Usage: /dryrun [on|off]".to_string())),
+        }
+    }
+
+    fn autocomplete(&self, _context: &CommandContext, args: Vec<&str>) -> Vec<String> {
+        let prefix = args.first().copied().unwrap_or("");
+        ["on", "off"]
+            .iter()
+            .filter(|option| option.starts_with(prefix))
+            .map(|option| option.to_string())
+            .collect()
+    }
+}
+
+/// Show what the agent has done over its lifetime
+struct StatsCommand;
+
+impl Command for StatsCommand {
+    fn name(&self) -> &str {
+        "stats"
+    }
+
+    fn description(&self) -> &str {
+        "Show the agent's lifetime statistics"
+    }
+
+    fn help(&self) -> String {
+        "Show conversations, messages and files changed over the agent's lifetime\n\
+         (including this session), plus memory counts when memory is enabled.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        // Signal to main loop to combine the saved totals with this session
+        Ok(CommandResult::ShowStats)
+    }
+}
+
+/// Save the current session and start a fresh one
+struct NewCommand;
+
+impl Command for NewCommand {
+    fn name(&self) -> &str {
+        "new"
+    }
+
+    fn description(&self) -> &str {
+        "Save this session and start a new one"
+    }
+
+    fn help(&self) -> String {
+        "Save the current session and start a new, empty one.\n\
+         The conversation, chat log and token counts are cleared; rust-analyzer,\n\
+         the vecdb and memory stay loaded. Use /load to return to the old session.".to_string()
+    }
+
+    fn execute(&self, context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        context.session_manager.save_current_session()?;
+        let session = context.session_manager.start_new_session(context.model, context.cwd);
+
+        // Signal to main loop to reset the agent
+        Ok(CommandResult::NewSession(session.id.clone()))
+    }
+}
+
+/// Show and change which tools run without a permission prompt
+struct PermissionsCommand;
+
+impl PermissionsCommand {
+    /// Tool names (or categories) from the arguments, checked against the known tools
+    fn tool_names(args: &[&str]) -> std::result::Result<Vec<String>, String> {
+        if args.is_empty() {
+            return Err("Name at least one tool or category (read-only, write, execute, rust-analyzer, all)".to_string());
+        }
+        let known = permissions::expand_tool_categories(vec!["all".to_string()]);
+        let tools = permissions::expand_tool_categories(args.iter().map(|a| a.to_string()).collect());
+        match tools.iter().find(|tool| !known.contains(tool)) {
+            Some(unknown) => Err(format!("Unknown tool or category: {}", unknown)),
+            None => Ok(tools),
+        }
+    }
+}
+
+impl Command for PermissionsCommand {
+    fn name(&self) -> &str {
+        "permissions"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["perms"]
+    }
+
+    fn description(&self) -> &str {
+        "Show or change which tools run without asking"
+    }
+
+    fn help(&self) -> String {
+        "Show the permission state: granted tools, tools and files approved for all\n\
+         uses, and whether dry run is on.\n\
+         Usage: /permissions [grant|revoke <tool|category>...]\n\
+         /permissions grant <tool>...   Approve tools for all further calls (like 'approve all' in the prompt)\n\
+         /permissions revoke <tool>...  Ask again before these tools run\n\
+         /permissions revoke all        Forget every tool and file approval\n\
+         Categories: read-only, write, execute, rust-analyzer, all.".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        let usage = "Usage: /permissions [grant|revoke <tool|category>...]";
+        let change = match args.first().map(|a| a.to_lowercase()).as_deref() {
+            None => None,
+            Some("grant") => Some(Self::tool_names(&args[1..]).map(PermissionChange::Grant)),
+            Some("revoke") if args[1..].iter().any(|a| a.eq_ignore_ascii_case("all")) => {
+                Some(Ok(PermissionChange::RevokeAll))
+            }
+            Some("revoke") => Some(Self::tool_names(&args[1..]).map(PermissionChange::Revoke)),
+            Some(_) => return Ok(CommandResult::Error(usage.to_string())),
+        };
+
+        // Signal to main loop to update the agent
+        match change {
+            None => Ok(CommandResult::Permissions(None)),
+            Some(Ok(change)) => Ok(CommandResult::Permissions(Some(change))),
+            Some(Err(e)) => Ok(CommandResult::Error(e)),
+        }
+    }
+
+    fn autocomplete(&self, _context: &CommandContext, args: Vec<&str>) -> Vec<String> {
+        let prefix = args.last().copied().unwrap_or("");
+        let options: Vec<String> = if args.len() <= 1 {
+            vec!["grant".to_string(), "revoke".to_string()]
+        } else {
+            ["read-only", "write", "execute", "rust-analyzer", "all"]
+                .iter()
+                .map(|c| c.to_string())
+                .chain(permissions::expand_tool_categories(vec!["all".to_string()]))
+                .collect()
+        };
+        options.into_iter().filter(|option| option.starts_with(prefix)).collect()
+    }
+}
+
+/// Search the agent's long-term memory
+struct SearchCommand;
+
+impl Command for SearchCommand {
+    fn name(&self) -> &str {
+        "search"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["remember"]
+    }
+
+    fn description(&self) -> &str {
+        "Search what the agent remembers"
+    }
+
+    fn help(&self) -> String {
+        "Run a semantic search over the agent's key and routine memories and show\n\
+         the best matches with their relevance, category, importance and time.\n\
+         Usage: /search <query>".to_string()
+    }
+
+    fn execute(&self, _context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        if args.is_empty() {
+            return Ok(CommandResult::Error("Usage: /search <query>".to_string()));
+        }
+        // Signal to main loop to query the memory manager
+        Ok(CommandResult::SearchMemory(args.join(" ")))
+    }
+}
+
+/// List the agent tabs
+struct TabsCommand;
+
+impl Command for TabsCommand {
+    fn name(&self) -> &str {
+        "tabs"
+    }
+
+    fn description(&self) -> &str {
+        "List the agent tabs with their status and runtime"
+    }
+
+    fn help(&self) -> String {
+        "List the main agent and every sub-agent tab with its number, name, status\n\
+         and how long it has been (or was) running. Use /tab <n> to switch to one.".to_string()
+    }
+
+    fn execute(&self, context: &mut CommandContext, _args: Vec<&str>) -> Result<CommandResult> {
+        let _ = context.tui_tx.try_send(TuiEvent::Tabs(TabAction::List));
+        Ok(CommandResult::Continue)
+    }
+}
+
+/// Switch to or close an agent tab
+struct TabCommand;
+
+impl TabCommand {
+    /// The tab action for the arguments of /tab
+    fn action(args: &[&str]) -> std::result::Result<TabAction, String> {
+        let usage = "Usage: /tab <n> | /tab close <n>";
+        let number = |arg: &str| arg.parse::<usize>().map_err(|_| usage.to_string());
+        match args {
+            [n] => number(n).map(TabAction::Switch),
+            ["close", n] => number(n).map(TabAction::Close),
+            _ => Err(usage.to_string()),
+        }
+    }
+}
+
+impl Command for TabCommand {
+    fn name(&self) -> &str {
+        "tab"
+    }
+
+    fn description(&self) -> &str {
+        "Switch to an agent tab, or close a finished one"
+    }
+
+    fn help(&self) -> String {
+        "Switch to or close an agent tab by its number in /tabs.\n\
+         Usage: /tab <n> | /tab close <n>\n\
+         /tab <n>        Show the Nth tab (like Ctrl+1-9, for any number of tabs)\n\
+         /tab close <n>  Close a completed, failed or stopped sub-agent's tab".to_string()
+    }
+
+    fn execute(&self, context: &mut CommandContext, args: Vec<&str>) -> Result<CommandResult> {
+        match Self::action(&args) {
+            Ok(action) => {
+                let _ = context.tui_tx.try_send(TuiEvent::Tabs(action));
+                Ok(CommandResult::Continue)
+            }
+            Err(usage) => Ok(CommandResult::Error(usage)),
+        }
+    }
+
+    fn autocomplete(&self, _context: &CommandContext, args: Vec<&str>) -> Vec<String> {
+        let prefix = args.first().copied().unwrap_or("");
+        if args.len() <= 1 && "close".starts_with(prefix) {
+            vec!["close".to_string()]
+        } else {
+            vec![]
+        }
+    }
+}
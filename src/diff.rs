@@ -95,6 +95,54 @@ impl UnifiedDiff {
             .iter()
             .any(|l| l.change_type != DiffChangeType::Context)
     }
+
+    /// Render as standard unified diff text with `context` lines around each hunk
+    pub fn to_unified_string(&self, old_label: &str, new_label: &str, context: usize) -> String {
+        let mut output = format!("--- {}\n+++ {}\n", old_label, new_label);
+
+        // Group changed lines (plus surrounding context) into hunks
+        let mut hunks: Vec<(usize, usize)> = Vec::new();
+        for (idx, line) in self.lines.iter().enumerate() {
+            if line.change_type == DiffChangeType::Context {
+                continue;
+            }
+            let start = idx.saturating_sub(context);
+            let end = (idx + context + 1).min(self.lines.len());
+            match hunks.last_mut() {
+                Some(last) if start <= last.1 => last.1 = end,
+                _ => hunks.push((start, end)),
+            }
+        }
+
+        for (start, end) in hunks {
+            let hunk = &self.lines[start..end];
+            let old_count = hunk.iter().filter(|l| l.change_type != DiffChangeType::Addition).count();
+            let new_count = hunk.iter().filter(|l| l.change_type != DiffChangeType::Deletion).count();
+            let old_start = hunk_start(hunk, &self.lines[..start], |l| l.old_line_num);
+            let new_start = hunk_start(hunk, &self.lines[..start], |l| l.new_line_num);
+
+            output.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start, new_count));
+            for line in hunk {
+                let prefix = match line.change_type {
+                    DiffChangeType::Context => ' ',
+                    DiffChangeType::Addition => '+',
+                    DiffChangeType::Deletion => '-',
+                };
+                output.push_str(&format!("{}{}\n", prefix, line.content));
+            }
+        }
+
+        output
+    }
+}
+
+/// First line number of a hunk on one side. A side with no lines in the hunk
+/// starts after the last line before it (0 at the top of the file), as in `diff -u`.
+fn hunk_start(hunk: &[DiffLine], before: &[DiffLine], side: impl Fn(&DiffLine) -> Option<usize>) -> usize {
+    hunk.iter()
+        .find_map(&side)
+        .or_else(|| before.iter().rev().find_map(&side))
+        .unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -122,6 +170,20 @@ mod tests {
         assert_eq!(diff.summary(), "+1, -0");
     }
 
+    #[test]
+    fn test_unified_string_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+
+        let diff = UnifiedDiff::from_texts("x".to_string(), old, new);
+        let text = diff.to_unified_string("old", "new", 1);
+
+        assert_eq!(
+            text,
+            "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -8,1 +8,2 @@\n h\n+i\n"
+        );
+    }
+
     #[test]
     fn test_deletion_only() {
         let old = "line 1\nline 2\n";
@@ -77,3 +77,17 @@ impl ToolError {
         Self::Network(msg.into())
     }
 }
+
+/// Errors that end an agent run early, kept distinct so batch mode can
+/// report why it stopped through its exit code
+#[derive(Debug, Error)]
+pub enum AgentError {
+    #[error("{0}")]
+    PermissionDenied(String),
+
+    #[error("Maximum iterations ({0}) exceeded. The agent may be stuck in a loop.")]
+    MaxIterations(usize),
+
+    #[error("Token budget exhausted: {used} of {limit} tokens used this session (--max-total-tokens)")]
+    TokenBudgetExhausted { used: usize, limit: usize },
+}
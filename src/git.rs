@@ -1,4 +1,5 @@
-use std::path::Path;
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Git repository information
@@ -62,39 +63,9 @@ impl GitInfo {
             });
 
         // Get status counts
-        let (staged_count, unstaged_count, untracked_count) = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(path)
-            .output()
-            .map(|o| {
-                if o.status.success() {
-                    let output = String::from_utf8_lossy(&o.stdout);
-                    let mut staged = 0;
-                    let mut unstaged = 0;
-                    let mut untracked = 0;
-
-                    for line in output.lines() {
-                        if line.len() >= 2 {
-                            let status = &line[..2];
-                            if status.starts_with('?') {
-                                untracked += 1;
-                            } else {
-                                // First char is staged status, second is unstaged
-                                if !status.starts_with(' ') && !status.starts_with('?') {
-                                    staged += 1;
-                                }
-                                if status.chars().nth(1) != Some(' ') {
-                                    unstaged += 1;
-                                }
-                            }
-                        }
-                    }
-                    (staged, unstaged, untracked)
-                } else {
-                    (0, 0, 0)
-                }
-            })
-            .unwrap_or((0, 0, 0));
+        let status = GitStatus::read(path).unwrap_or_default();
+        let (staged_count, unstaged_count, untracked_count) =
+            (status.staged.len(), status.unstaged.len(), status.untracked.len());
 
         let is_dirty = staged_count > 0 || unstaged_count > 0 || untracked_count > 0;
 
@@ -149,3 +120,263 @@ impl GitInfo {
         )
     }
 }
+
+/// Porcelain status of a repository (paths relative to the repository root)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GitStatus {
+    /// (status letter, path) of changes in the index
+    pub staged: Vec<(char, String)>,
+    /// (status letter, path) of changes in the working tree
+    pub unstaged: Vec<(char, String)>,
+    pub untracked: Vec<String>,
+}
+
+impl GitStatus {
+    /// Run `git status` for the repository containing `path`
+    pub fn read(path: &Path) -> Result<Self> {
+        let output = run_git(path, &["status", "--porcelain", "-z"], None)?;
+        Ok(Self::parse(&output))
+    }
+
+    /// Parse `git status --porcelain -z` output
+    pub fn parse(output: &str) -> Self {
+        let mut status = Self::default();
+        let mut entries = output.split('\0').filter(|entry| !entry.is_empty());
+
+        while let Some(entry) = entries.next() {
+            let mut codes = entry.chars();
+            let (Some(index), Some(worktree)) = (codes.next(), codes.next()) else {
+                continue;
+            };
+            let path = entry.get(3..).unwrap_or("").to_string();
+
+            // Renames and copies are followed by the original path
+            if matches!(index, 'R' | 'C') || matches!(worktree, 'R' | 'C') {
+                entries.next();
+            }
+
+            if index == '?' {
+                status.untracked.push(path);
+                continue;
+            }
+            if index != ' ' {
+                status.staged.push((index, path.clone()));
+            }
+            if worktree != ' ' {
+                status.unstaged.push((worktree, path));
+            }
+        }
+
+        status
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty() && self.unstaged.is_empty() && self.untracked.is_empty()
+    }
+}
+
+/// Root of the work tree containing `path`
+pub fn toplevel(path: &Path) -> Result<PathBuf> {
+    run_git(path, &["rev-parse", "--show-toplevel"], None).map(PathBuf::from)
+}
+
+/// Unified diff of the index against HEAD (`staged`) or of the working tree against the index,
+/// optionally limited to one path
+pub fn diff(path: &Path, staged: bool, pathspec: Option<&str>) -> Result<String> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        args.push("--cached");
+    }
+    if let Some(pathspec) = pathspec {
+        args.extend(["--", pathspec]);
+    }
+    run_git(path, &args, None)
+}
+
+/// The last `count` commits as "<short hash> <subject>" lines
+pub fn recent_commits(path: &Path, count: usize) -> Result<Vec<String>> {
+    let count = format!("-{}", count);
+    let output = run_git(path, &["log", &count, "--format=%h %s"], None)?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+/// Identity used for checkpoint commits
+const CHECKPOINT_AUTHOR_NAME: &str = "user.name=agent-t";
+const CHECKPOINT_AUTHOR_EMAIL: &str = "user.email=agent-t@localhost";
+
+/// Per-session checkpoints of the agent's work.
+///
+/// Each checkpoint snapshots the whole working tree into a commit on
+/// `agent-t/<session>`, built through a temporary index so the checked-out
+/// branch, the real index and the working tree are left untouched.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    repo: PathBuf,
+    branch: String,
+}
+
+impl Checkpoint {
+    /// Checkpoints for a session, or None if `path` is not inside a git repository
+    pub fn new(path: &str, session_id: &str) -> Option<Self> {
+        Some(Self {
+            repo: toplevel(Path::new(path)).ok()?,
+            branch: branch_name(session_id),
+        })
+    }
+
+    /// Checkpoints of the same repository for another session
+    pub fn for_session(&self, session_id: &str) -> Self {
+        Self {
+            repo: self.repo.clone(),
+            branch: branch_name(session_id),
+        }
+    }
+
+    /// Branch the checkpoints are committed to
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    /// Commit the current working tree to the checkpoint branch.
+    /// Returns the short commit hash, or None if nothing changed since the last checkpoint.
+    pub fn commit(&self, message: &str) -> Result<Option<String>> {
+        let branch_ref = format!("refs/heads/{}", self.branch);
+        let parent = self
+            .git(&["rev-parse", "--verify", "-q", &branch_ref], None)
+            .or_else(|_| self.git(&["rev-parse", "--verify", "-q", "HEAD"], None))
+            .ok();
+
+        // Start from a copy of the real index so unchanged files keep their stat cache
+        let index = std::env::temp_dir().join(format!("agent-t-checkpoint-{}.index", std::process::id()));
+        let real_index = self.git(&["rev-parse", "--git-path", "index"], None)?;
+        let real_index = self.repo.join(real_index);
+        if real_index.exists() {
+            std::fs::copy(&real_index, &index)?;
+            // git re-checks entries modified in the same second as the index was
+            // written; a fresh mtime on the copy would hide those changes
+            let modified = std::fs::metadata(&real_index)?.modified()?;
+            std::fs::File::options().write(true).open(&index)?.set_modified(modified)?;
+        } else {
+            let _ = std::fs::remove_file(&index);
+        }
+
+        let tree = self
+            .git(&["add", "-A"], Some(&index))
+            .and_then(|_| self.git(&["write-tree"], Some(&index)));
+        let _ = std::fs::remove_file(&index);
+        let tree = tree?;
+
+        if let Some(ref parent) = parent
+            && self.git(&["rev-parse", &format!("{}^{{tree}}", parent)], None)? == tree
+        {
+            return Ok(None);
+        }
+
+        // Checkpoints are the agent's work, and this also works without a configured user identity
+        let mut args = vec![
+            "-c", CHECKPOINT_AUTHOR_NAME, "-c", CHECKPOINT_AUTHOR_EMAIL,
+            "commit-tree", tree.as_str(), "-m", message,
+        ];
+        if let Some(ref parent) = parent {
+            args.extend(["-p", parent.as_str()]);
+        }
+        let commit = self.git(&args, None)?;
+        self.git(&["update-ref", "-m", "agent-t checkpoint", &branch_ref, &commit], None)?;
+
+        Ok(Some(commit.chars().take(7).collect()))
+    }
+
+    fn git(&self, args: &[&str], index: Option<&Path>) -> Result<String> {
+        run_git(&self.repo, args, index)
+    }
+}
+
+/// Run a git command, returning its trimmed stdout
+fn run_git(dir: &Path, args: &[&str], index: Option<&Path>) -> Result<String> {
+    let mut command = Command::new("git");
+    command.args(args).current_dir(dir);
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // Only trailing separators: leading spaces are columns in `status --porcelain`
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end_matches(['\n', '\0']).to_string())
+}
+
+/// Checkpoint branch for a session; characters git rejects in ref names become '-'
+fn branch_name(session_id: &str) -> String {
+    let session: String = session_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    format!("agent-t/{}", session.trim_matches('-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_checkpoint_leaves_worktree_alone() {
+        let temp = TempDir::new("checkpoint");
+        let dir = temp.path();
+        let identity = ["-c", "user.name=test", "-c", "user.email=test@localhost"];
+        run_git(dir, &["init", "-q"], None).unwrap();
+        std::fs::write(dir.join("a.txt"), "1").unwrap();
+        run_git(dir, &["add", "."], None).unwrap();
+        run_git(dir, &[&identity[..], &["commit", "-qm", "init"]].concat(), None).unwrap();
+
+        let checkpoint = Checkpoint::new(dir.to_str().unwrap(), "s1").unwrap();
+        assert_eq!(checkpoint.commit("nothing").unwrap(), None);
+
+        std::fs::write(dir.join("a.txt"), "2").unwrap();
+        std::fs::write(dir.join("b.txt"), "new").unwrap();
+        assert!(checkpoint.commit("agent-t: turn").unwrap().is_some());
+        assert_eq!(checkpoint.commit("unchanged").unwrap(), None);
+        assert_eq!(checkpoint.for_session("s2").branch(), "agent-t/s2");
+
+        let files = run_git(dir, &["show", "--name-only", "--format=%s", "agent-t/s1"], None).unwrap();
+        assert_eq!(files, "agent-t: turn\n\na.txt\nb.txt");
+        // The user's branch and index are untouched
+        let status = run_git(dir, &["status", "--porcelain"], None).unwrap();
+        assert_eq!(status, " M a.txt\n?? b.txt");
+    }
+
+    #[test]
+    fn test_parse_porcelain_status() {
+        let output = "M  src/main.rs\0 M README.md\0MM src/lib.rs\0R  new.rs\0old.rs\0?? notes.txt\0";
+        let status = GitStatus::parse(output);
+
+        assert_eq!(
+            status.staged,
+            vec![('M', "src/main.rs".to_string()), ('M', "src/lib.rs".to_string()), ('R', "new.rs".to_string())]
+        );
+        assert_eq!(status.unstaged, vec![('M', "README.md".to_string()), ('M', "src/lib.rs".to_string())]);
+        assert_eq!(status.untracked, vec!["notes.txt".to_string()]);
+        assert!(GitStatus::parse("").is_clean());
+
+        // An unstaged first entry keeps its leading space
+        let status = GitStatus::parse(" M README.md\0A  src/new.rs\0");
+        assert_eq!(status.unstaged, vec![('M', "README.md".to_string())]);
+        assert_eq!(status.staged, vec![('A', "src/new.rs".to_string())]);
+    }
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(
+            branch_name("3f0c9a4e-6b1d-4c55-9a7e-0d2b8f1e7a13"),
+            "agent-t/3f0c9a4e-6b1d-4c55-9a7e-0d2b8f1e7a13"
+        );
+        assert_eq!(branch_name("batch-20261014-101500"), "agent-t/batch-20261014-101500");
+        assert_eq!(branch_name("a..b:c~"), "agent-t/a--b-c");
+    }
+}
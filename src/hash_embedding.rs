@@ -0,0 +1,93 @@
+//! Offline fallback embeddings (--embedding-fallback)
+//!
+//! The vecdb and memory need an embedding model: Ollama's for code, a
+//! fastembed download for memory. When that model can't be loaded, text is
+//! embedded here instead by feature hashing: words and their character
+//! trigrams are hashed into a fixed number of buckets. This only captures
+//! shared vocabulary, but needs no model, network or GPU.
+
+/// Name recorded for indexes built with these embeddings
+pub const NAME: &str = "hashing";
+
+/// Weight of a character trigram relative to a whole word
+const TRIGRAM_WEIGHT: f32 = 0.5;
+
+/// Embed `text` into a unit vector with `dimension` components
+pub fn embed(text: &str, dimension: usize) -> Vec<f32> {
+    let mut vector = vec![0.0f32; dimension];
+    if dimension == 0 {
+        return vector;
+    }
+
+    for word in words(text) {
+        add_feature(&mut vector, &word, 1.0);
+        let padded: Vec<char> = format!("_{}_", word).chars().collect();
+        for trigram in padded.windows(3) {
+            add_feature(&mut vector, &trigram.iter().collect::<String>(), TRIGRAM_WEIGHT);
+        }
+    }
+
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+/// Lowercase words, with snake_case and camelCase identifiers split apart
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for token in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut word = String::new();
+        let mut prev_lower = false;
+        for c in token.chars() {
+            if c.is_uppercase() && prev_lower && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            word.extend(c.to_lowercase());
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Add a feature to its bucket, with a hash-derived sign so collisions cancel out
+fn add_feature(vector: &mut [f32], feature: &str, weight: f32) {
+    let hash = fnv1a(feature.as_bytes());
+    let bucket = (hash % vector.len() as u64) as usize;
+    let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    vector[bucket] += sign * weight;
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words("parseEnv(state_file) -> HTTPServer"), ["parse", "env", "state", "file", "httpserver"]);
+    }
+
+    #[test]
+    fn test_embed_similarity() {
+        let query = embed("retry the request", 384);
+        let related = embed("fn retry_request(client: &Client)", 384);
+        let unrelated = embed("render the status bar spinner", 384);
+
+        assert!((cosine(&query, &query) - 1.0).abs() < 1e-5);
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated));
+        assert_eq!(embed("", 8), vec![0.0; 8]);
+    }
+}
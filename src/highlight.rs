@@ -0,0 +1,185 @@
+//! Syntax highlighting of code blocks in the TUI
+//!
+//! Uses the tree-sitter grammars that the code chunker already links in, with
+//! the highlight queries they ship. Capture names (`keyword`, `string`,
+//! `function.method`, ...) are mapped onto theme colors the way editor themes
+//! do; code in a language without a grammar is left plain.
+
+use crate::colors::{self, Rgb, Theme};
+use lazy_static::lazy_static;
+use ratatui::style::Style;
+use ratatui::text::Span;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language, Parser, Query, QueryCursor};
+
+/// A grammar and its compiled highlight query
+struct Grammar {
+    language: Language,
+    query: Query,
+}
+
+lazy_static! {
+    /// Compiled grammars by canonical name; compiling a query takes milliseconds
+    /// and code blocks are rendered on every frame
+    static ref GRAMMARS: Mutex<HashMap<&'static str, Option<Arc<Grammar>>>> = Mutex::new(HashMap::new());
+}
+
+/// Canonical name, grammar and highlight queries for a code block's language.
+/// The queries are tried in order: TypeScript and C++ extend the JavaScript and
+/// C queries, which are left out if they don't compile against the grammar.
+fn grammar_source(language: &str) -> Option<(&'static str, Language, Vec<String>)> {
+    let language = language.to_lowercase();
+    let (name, grammar, queries): (_, Language, Vec<String>) = match language.as_str() {
+        "rust" | "rs" => ("rust", tree_sitter_rust::LANGUAGE.into(), vec![tree_sitter_rust::HIGHLIGHTS_QUERY.into()]),
+        "python" | "py" => ("python", tree_sitter_python::LANGUAGE.into(), vec![tree_sitter_python::HIGHLIGHTS_QUERY.into()]),
+        "javascript" | "js" | "jsx" | "mjs" | "cjs" => (
+            "javascript",
+            tree_sitter_javascript::LANGUAGE.into(),
+            vec![format!("{}\n{}", tree_sitter_javascript::HIGHLIGHT_QUERY, tree_sitter_javascript::JSX_HIGHLIGHT_QUERY)],
+        ),
+        "typescript" | "ts" | "tsx" => {
+            let grammar = if language == "tsx" {
+                tree_sitter_typescript::LANGUAGE_TSX
+            } else {
+                tree_sitter_typescript::LANGUAGE_TYPESCRIPT
+            };
+            (
+                if language == "tsx" { "tsx" } else { "typescript" },
+                grammar.into(),
+                vec![
+                    format!("{}\n{}", tree_sitter_javascript::HIGHLIGHT_QUERY, tree_sitter_typescript::HIGHLIGHTS_QUERY),
+                    tree_sitter_typescript::HIGHLIGHTS_QUERY.into(),
+                ],
+            )
+        }
+        "go" | "golang" => ("go", tree_sitter_go::LANGUAGE.into(), vec![tree_sitter_go::HIGHLIGHTS_QUERY.into()]),
+        "java" => ("java", tree_sitter_java::LANGUAGE.into(), vec![tree_sitter_java::HIGHLIGHTS_QUERY.into()]),
+        "c" | "h" => ("c", tree_sitter_c::LANGUAGE.into(), vec![tree_sitter_c::HIGHLIGHT_QUERY.into()]),
+        "cpp" | "c++" | "cc" | "cxx" | "hpp" => (
+            "cpp",
+            tree_sitter_cpp::LANGUAGE.into(),
+            vec![
+                format!("{}\n{}", tree_sitter_c::HIGHLIGHT_QUERY, tree_sitter_cpp::HIGHLIGHT_QUERY),
+                tree_sitter_cpp::HIGHLIGHT_QUERY.into(),
+            ],
+        ),
+        _ => return None,
+    };
+    Some((name, grammar, queries))
+}
+
+/// The compiled grammar for a code block's language
+fn grammar(language: &str) -> Option<Arc<Grammar>> {
+    let (name, language, queries) = grammar_source(language)?;
+    let mut grammars = GRAMMARS.lock().ok()?;
+    grammars
+        .entry(name)
+        .or_insert_with(|| {
+            let query = queries.iter().find_map(|source| Query::new(&language, source).ok())?;
+            Some(Arc::new(Grammar { language, query }))
+        })
+        .clone()
+}
+
+/// Color of a highlight capture; None keeps the text color
+fn capture_color(capture: &str, theme: &Theme) -> Option<Rgb> {
+    match capture {
+        "comment" | "comment.documentation" => Some(theme.overlay0),
+        "keyword" => Some(theme.mauve),
+        "string" | "string.special" => Some(theme.green),
+        "escape" | "string.escape" | "number" | "constant" | "constant.builtin" => Some(theme.peach),
+        "type" | "type.builtin" | "attribute" | "constructor" => Some(theme.yellow),
+        "function" | "function.method" | "function.builtin" | "function.macro" | "function.special" => {
+            Some(theme.blue)
+        }
+        "property" | "label" => Some(theme.sapphire),
+        "variable.builtin" | "variable.parameter" => Some(theme.red),
+        _ => None,
+    }
+}
+
+/// The lines of `code` as colored spans on top of `base`, or None when the
+/// language has no grammar
+pub fn highlight(language: &str, code: &str, base: Style) -> Option<Vec<Vec<Span<'static>>>> {
+    let grammar = grammar(language)?;
+    let mut parser = Parser::new();
+    parser.set_language(&grammar.language).ok()?;
+    let tree = parser.parse(code, None)?;
+
+    // Paint byte by byte: captures come in document order, so a node inside
+    // another one (an escape in a string) overrides it, while for a node
+    // captured twice the first pattern wins, as in editors
+    let theme = colors::theme();
+    let names = grammar.query.capture_names();
+    let mut paint: Vec<Option<Rgb>> = vec![None; code.len()];
+    let mut painted = HashSet::new();
+    let mut cursor = QueryCursor::new();
+    let mut captures = cursor.captures(&grammar.query, tree.root_node(), code.as_bytes());
+    while let Some((found, index)) = captures.next() {
+        let capture = found.captures[*index];
+        let range = capture.node.byte_range();
+        if !painted.insert(range.clone()) {
+            continue;
+        }
+        let color = capture_color(names[capture.index as usize], &theme);
+        paint[range].iter_mut().for_each(|byte| *byte = color);
+    }
+
+    let mut lines = vec![Vec::new()];
+    let mut run = String::new();
+    let mut run_color = None;
+    for (i, c) in code.char_indices() {
+        if c == '\n' || paint[i] != run_color {
+            if !run.is_empty() {
+                let style = run_color.map_or(base, |color| base.fg(colors::tui(color)));
+                lines.last_mut()?.push(Span::styled(std::mem::take(&mut run), style));
+            }
+            run_color = paint[i];
+        }
+        if c == '\n' {
+            lines.push(Vec::new());
+        } else {
+            run.push(c);
+        }
+    }
+    if !run.is_empty() {
+        let style = run_color.map_or(base, |color| base.fg(colors::tui(color)));
+        lines.last_mut()?.push(Span::styled(run, style));
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_rust() {
+        let theme = colors::theme();
+        let lines = highlight("rs", "// add\nfn add(a: i32) -> i32 {\n    a + 1\n}", Style::default()).unwrap();
+        assert_eq!(lines.len(), 4);
+
+        let color_of = |line: usize, text: &str| {
+            lines[line].iter().find(|span| span.content == text).map(|span| span.style.fg)
+        };
+        assert_eq!(color_of(0, "// add"), Some(Some(colors::tui(theme.overlay0))));
+        assert_eq!(color_of(1, "fn"), Some(Some(colors::tui(theme.mauve))));
+        assert_eq!(color_of(1, "add"), Some(Some(colors::tui(theme.blue))));
+        assert_eq!(color_of(1, "i32"), Some(Some(colors::tui(theme.yellow))));
+        assert_eq!(color_of(2, "1"), Some(Some(colors::tui(theme.peach))));
+
+        let text: String = lines[2].iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(text, "    a + 1");
+
+        assert!(highlight("brainfuck", "+++", Style::default()).is_none());
+    }
+
+    #[test]
+    fn test_grammars_compile() {
+        for language in ["rust", "python", "javascript", "typescript", "tsx", "go", "java", "c", "cpp"] {
+            assert!(grammar(language).is_some(), "{}", language);
+        }
+    }
+}
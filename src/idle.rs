@@ -0,0 +1,45 @@
+//! Going idle in interactive mode (--idle-timeout)
+//!
+//! When the TUI has seen no input and no agent activity for the timeout, the
+//! session is saved and, with --idle-unload, the model is unloaded from
+//! Ollama so a long-lived session doesn't hold GPU memory while nobody is
+//! using it. The next request loads it again.
+
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+/// Ollama server used when --ollama-url isn't given
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// How long the unload request may take
+const UNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Endpoint that loads and unloads models
+fn generate_url(base_url: &str) -> String {
+    format!("{}/api/generate", base_url.trim_end_matches('/'))
+}
+
+/// Ask Ollama to drop `model` from memory now (a request with keep_alive 0)
+pub async fn unload_model(base_url: &str, model: &str) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(generate_url(base_url))
+        .json(&serde_json::json!({ "model": model, "keep_alive": 0 }))
+        .timeout(UNLOAD_TIMEOUT)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Ollama returned {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_url() {
+        assert_eq!(generate_url(DEFAULT_OLLAMA_URL), "http://localhost:11434/api/generate");
+        assert_eq!(generate_url("http://gpu-box:11434/"), "http://gpu-box:11434/api/generate");
+    }
+}
@@ -1,9 +1,11 @@
 use axum::{
     extract::{
         ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
-        State,
+        Query, Request, State,
     },
-    response::{Html, IntoResponse},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
     routing::get,
     Router,
 };
@@ -27,6 +29,17 @@ pub enum Direction {
     System,   // System messages (info, errors)
 }
 
+impl Direction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Request => "request",
+            Direction::Response => "response",
+            Direction::Tool => "tool",
+            Direction::System => "system",
+        }
+    }
+}
+
 /// A single traffic message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrafficMessage {
@@ -65,6 +78,43 @@ impl TrafficMessage {
     }
 }
 
+/// Query parameters narrowing the traffic from /api/history, /api/export and /ws,
+/// e.g. `?direction=tool&q=vecdb_search`
+#[derive(Debug, Default, Deserialize)]
+pub struct TrafficFilter {
+    /// Comma-separated directions to keep (request, response, tool, system); empty keeps all
+    pub direction: Option<String>,
+    /// Case-insensitive text to look for in the type, summary or content
+    pub q: Option<String>,
+}
+
+impl TrafficFilter {
+    /// Whether a message passes the filter
+    pub fn matches(&self, message: &TrafficMessage) -> bool {
+        if let Some(directions) = self.direction.as_deref().filter(|d| !d.trim().is_empty())
+            && !directions.split(',').any(|d| d.trim().eq_ignore_ascii_case(message.direction.as_str()))
+        {
+            return false;
+        }
+        match self.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            Some(query) => {
+                let query = query.to_lowercase();
+                message.message_type.to_lowercase().contains(&query)
+                    || message.summary.to_lowercase().contains(&query)
+                    || message.content.to_string().to_lowercase().contains(&query)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Captured traffic saved for later analysis (/inspector export, /api/export)
+#[derive(Debug, Serialize)]
+pub struct TrafficExport {
+    pub exported_at: DateTime<Utc>,
+    pub messages: Vec<TrafficMessage>,
+}
+
 /// Shared state for the traffic inspector
 pub struct InspectorState {
     tx: broadcast::Sender<TrafficMessage>,
@@ -105,9 +155,17 @@ impl InspectorState {
         let _ = self.tx.send(message);
     }
 
-    /// Get the message history
-    pub async fn get_history(&self) -> Vec<TrafficMessage> {
-        self.history.read().await.clone()
+    /// The messages in the history that pass `filter`
+    pub async fn filtered_history(&self, filter: &TrafficFilter) -> Vec<TrafficMessage> {
+        self.history.read().await.iter().filter(|msg| filter.matches(msg)).cloned().collect()
+    }
+
+    /// The message history (or the part passing `filter`), ready to be saved
+    pub async fn export(&self, filter: &TrafficFilter) -> TrafficExport {
+        TrafficExport {
+            exported_at: Utc::now(),
+            messages: self.filtered_history(filter).await,
+        }
     }
 
     /// Subscribe to message broadcasts
@@ -135,6 +193,12 @@ impl TrafficHandle {
         self.state.is_some()
     }
 
+    /// The captured traffic as pretty-printed JSON (None when the inspector is off)
+    pub async fn export_json(&self) -> Option<serde_json::Result<String>> {
+        let state = self.state.as_ref()?;
+        Some(serde_json::to_string_pretty(&state.export(&TrafficFilter::default()).await))
+    }
+
     /// Log a request being sent to the LLM
     pub async fn log_request(&self, summary: impl Into<String>, content: serde_json::Value) {
         if let Some(state) = &self.state {
@@ -195,47 +259,100 @@ impl TrafficHandle {
     }
 }
 
-/// Start the traffic inspector web server
-pub async fn start_server(state: Arc<InspectorState>, port: u16) -> anyhow::Result<()> {
-    let app = Router::new()
+/// Default address the inspector listens on; only this machine can connect
+pub const DEFAULT_BIND: &str = "127.0.0.1";
+
+/// Start the traffic inspector web server on `bind:port`. With a token, every
+/// request must carry it as `Authorization: Bearer <token>` or `?token=<token>`.
+pub async fn start_server(state: Arc<InspectorState>, bind: &str, port: u16, token: Option<String>) -> anyhow::Result<()> {
+    let mut app = Router::new()
         .route("/", get(index_handler))
         .route("/ws", get(ws_handler))
         .route("/api/history", get(history_handler))
+        .route("/api/export", get(export_handler))
         .layer(CorsLayer::permissive())
         .with_state(state);
+    if let Some(ref token) = token {
+        app = app.layer(middleware::from_fn_with_state(Arc::new(token.clone()), require_token));
+    }
 
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
-    println!("Traffic inspector available at http://localhost:{}", port);
+    let listener = tokio::net::TcpListener::bind((bind, port)).await?;
+    let host = if bind == "0.0.0.0" || bind == "::" { "localhost" } else { bind };
+    let query = token.as_ref().map(|t| format!("/?token={}", t)).unwrap_or_default();
+    println!("Traffic inspector available at http://{}:{}{}", host, port, query);
 
     axum::serve(listener, app).await?;
     Ok(())
 }
 
+/// Reject requests that don't carry the inspector token
+async fn require_token(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+    let auth_header = request.headers().get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    if is_authorized(&token, auth_header, request.uri().query()) {
+        next.run(request).await
+    } else {
+        (StatusCode::UNAUTHORIZED, "Inspector token required: open the URL printed at startup").into_response()
+    }
+}
+
+/// Whether a bearer header or `token` query parameter matches the expected token
+fn is_authorized(expected: &str, auth_header: Option<&str>, query: Option<&str>) -> bool {
+    let from_header = auth_header.and_then(|value| value.strip_prefix("Bearer "));
+    let from_query = query.and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    });
+    from_header.is_some_and(|token| constant_time_eq(token, expected))
+        || from_query.is_some_and(|token| constant_time_eq(&token, expected))
+}
+
+/// Compare without returning early, so response times don't leak the token
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 /// Serve the HTML page
 async fn index_handler() -> impl IntoResponse {
     Html(include_str!("inspector.html"))
 }
 
 /// Get message history
-async fn history_handler(State(state): State<Arc<InspectorState>>) -> impl IntoResponse {
-    let history = state.get_history().await;
-    axum::Json(history)
+async fn history_handler(
+    State(state): State<Arc<InspectorState>>,
+    Query(filter): Query<TrafficFilter>,
+) -> impl IntoResponse {
+    axum::Json(state.filtered_history(&filter).await)
+}
+
+/// Download the message history as a JSON file
+async fn export_handler(
+    State(state): State<Arc<InspectorState>>,
+    Query(filter): Query<TrafficFilter>,
+) -> impl IntoResponse {
+    let export = state.export(&filter).await;
+    let disposition = format!(
+        "attachment; filename=\"agent-t-traffic-{}.json\"",
+        export.exported_at.format("%Y%m%d-%H%M%S")
+    );
+    ([(header::CONTENT_DISPOSITION, disposition)], axum::Json(export))
 }
 
 /// Handle WebSocket connections
 async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<InspectorState>>,
+    Query(filter): Query<TrafficFilter>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, filter))
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<InspectorState>) {
+async fn handle_socket(socket: WebSocket, state: Arc<InspectorState>, filter: TrafficFilter) {
     let (mut sender, mut receiver) = socket.split();
     let mut rx = state.subscribe();
 
     // Send history first
-    let history = state.get_history().await;
+    let history = state.filtered_history(&filter).await;
     for msg in history {
         if let Ok(json) = serde_json::to_string(&msg)
             && sender.send(WsMessage::Text(json.into())).await.is_err() {
@@ -246,7 +363,8 @@ async fn handle_socket(socket: WebSocket, state: Arc<InspectorState>) {
     // Spawn task to send broadcast messages to this client
     let send_task = tokio::spawn(async move {
         while let Ok(msg) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&msg)
+            if filter.matches(&msg)
+                && let Ok(json) = serde_json::to_string(&msg)
                 && sender.send(WsMessage::Text(json.into())).await.is_err() {
                     break;
                 }
@@ -262,3 +380,37 @@ async fn handle_socket(socket: WebSocket, state: Arc<InspectorState>) {
 
     send_task.abort();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized("s3cret", Some("Bearer s3cret"), None));
+        assert!(is_authorized("s3cret", None, Some("view=all&token=s3cret")));
+        assert!(is_authorized("a b", None, Some("token=a%20b")));
+
+        assert!(!is_authorized("s3cret", None, None));
+        assert!(!is_authorized("s3cret", Some("Bearer s3cre"), Some("token=wrong")));
+        assert!(!is_authorized("s3cret", Some("s3cret"), None));
+    }
+
+    #[test]
+    fn test_traffic_filter() {
+        let tool = TrafficMessage::new(1, Direction::Tool, "tool_execution", "Tool: vecdb_search",
+            serde_json::json!({ "arguments": { "query": "Retry logic" } }));
+        let request = TrafficMessage::new(2, Direction::Request, "completion_request", "3 messages", serde_json::json!({}));
+
+        let filter = |direction: Option<&str>, q: Option<&str>| TrafficFilter {
+            direction: direction.map(str::to_string),
+            q: q.map(str::to_string),
+        };
+        assert!(filter(None, None).matches(&request));
+        assert!(filter(Some("tool, system"), None).matches(&tool));
+        assert!(!filter(Some("tool,system"), None).matches(&request));
+        assert!(filter(Some(""), Some("VECDB_search")).matches(&tool));
+        assert!(filter(None, Some("retry logic")).matches(&tool));
+        assert!(!filter(None, Some("retry logic")).matches(&request));
+    }
+}
@@ -9,19 +9,33 @@ use tokio_util::sync::CancellationToken;
 
 mod agent;
 mod agent_loop;
+mod audit;
+mod auto_continue;
+mod clipboard;
+mod codeblock;
 mod colors;
 mod commands;
 mod diff;
 mod error;
 mod git;
+mod hash_embedding;
+mod highlight;
+mod idle;
+mod injection;
 mod inspector;
+mod markdown;
 mod memory;
 mod permissions;
+mod plan;
+mod pricing;
 mod process_manager;
 mod rust_analyzer;
 mod session;
 mod template;
 mod terminal;
+#[cfg(test)]
+mod test_util;
+mod text_tool_calls;
 mod tools;
 mod tree_sitter_chunker;
 mod tui;
@@ -46,6 +60,10 @@ struct Args {
     #[arg(long)]
     list_agents: bool,
 
+    /// Create the --agent by copying an existing agent's personality, system prompt and memory settings
+    #[arg(long, value_name = "EXISTING", visible_alias = "agent-from-template", requires = "agent")]
+    clone: Option<String>,
+
     /// Enable the traffic inspector web interface
     #[arg(long, short = 'i')]
     inspector: bool,
@@ -54,6 +72,14 @@ struct Args {
     #[arg(long, default_value = "8080")]
     inspector_port: u16,
 
+    /// Address the traffic inspector listens on; use 0.0.0.0 to allow other machines
+    #[arg(long, value_name = "ADDR", default_value = inspector::DEFAULT_BIND)]
+    inspector_bind: String,
+
+    /// Require this token (Bearer header or ?token=) to view the traffic inspector
+    #[arg(long, value_name = "TOKEN")]
+    inspector_token: Option<String>,
+
     /// Ollama model to use
     #[arg(long, short = 'm', default_value = "qwen3-coder")]
     model: String,
@@ -66,6 +92,18 @@ struct Args {
     #[arg(long)]
     session: Option<String>,
 
+    /// Save the session every SECS seconds as well as after each turn (0 = only after turns and on exit)
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    autosave_interval: u64,
+
+    /// In the TUI, after MINS minutes without input or agent activity, save the session and show an idle indicator
+    #[arg(long, value_name = "MINS", value_parser = clap::value_parser!(u64).range(1..))]
+    idle_timeout: Option<u64>,
+
+    /// When going idle (--idle-timeout), also unload the model from Ollama to free GPU memory
+    #[arg(long, requires = "idle_timeout")]
+    idle_unload: bool,
+
     /// Disable dangerous command confirmations
     #[arg(long)]
     no_confirm: bool,
@@ -74,14 +112,30 @@ struct Args {
     #[arg(long, short = 'u')]
     ollama_url: Option<String>,
 
-    /// Enable streaming output
-    #[arg(long, short = 's')]
+    /// Enable streaming output (in batch mode tokens are streamed to stderr)
+    #[arg(long, short = 's', visible_alias = "stream")]
     streaming: bool,
 
     /// Context window size (num_ctx) for the LLM (default: 8192)
     #[arg(long, short = 'c', default_value = "8192")]
     context_size: usize,
 
+    /// Maximum tokens generated per request (default: half of --context-size, at most 32768)
+    #[arg(long)]
+    max_tokens: Option<u64>,
+
+    /// Sampling temperature (model default if unset)
+    #[arg(long)]
+    temperature: Option<f64>,
+
+    /// Nucleus sampling probability mass (model default if unset)
+    #[arg(long)]
+    top_p: Option<f64>,
+
+    /// Random seed for reproducible runs
+    #[arg(long)]
+    seed: Option<u64>,
+
     /// Special instructions to append to system prompt (inline text or path to file starting with @)
     #[arg(long, short = 'I')]
     instructions: Option<String>,
@@ -98,6 +152,30 @@ struct Args {
     #[arg(long, default_value = "nomic-embed-text")]
     vecdb_embedding_model: String,
 
+    /// Number of vecdb chunks added to each message (0 = none)
+    #[arg(long, value_name = "N", default_value_t = vecdb::DEFAULT_TOP_K)]
+    vecdb_top_k: usize,
+
+    /// Leave out vecdb chunks whose cosine similarity to the message is below this
+    #[arg(long, value_name = "SCORE", default_value_t = vecdb::DEFAULT_MIN_SIMILARITY)]
+    vecdb_min_similarity: f32,
+
+    /// Largest vecdb chunk in bytes; bigger items are split between statements (takes effect on --reindex)
+    #[arg(long, value_name = "BYTES", default_value_t = tree_sitter_chunker::DEFAULT_CHUNK_SIZE)]
+    vecdb_chunk_size: usize,
+
+    /// Lines before each vecdb chunk repeated at its start (takes effect on --reindex)
+    #[arg(long, value_name = "LINES", default_value_t = tree_sitter_chunker::DEFAULT_CHUNK_OVERLAP)]
+    vecdb_chunk_overlap: usize,
+
+    /// Only index these languages in the vecdb, e.g. rust,python (names or extensions; takes effect on --reindex)
+    #[arg(long, value_name = "LANGS", value_delimiter = ',')]
+    vecdb_languages: Vec<String>,
+
+    /// Use offline hashing embeddings for the vecdb and memory when their embedding model is unavailable (lower quality)
+    #[arg(long)]
+    embedding_fallback: bool,
+
     /// Force reindex of code files (rebuilds vector database)
     #[arg(long)]
     reindex: bool,
@@ -123,6 +201,22 @@ struct Args {
     #[arg(long)]
     prompt_file: Option<String>,
 
+    /// Interactive mode: submit this prompt as soon as the TUI starts, then keep the session open
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["prompt", "prompt_file"])]
+    init_prompt: Option<String>,
+
+    /// Color theme (mocha, latte, high-contrast) or a theme JSON file [default: ~/.agent-t/theme.json, else mocha]
+    #[arg(long, value_name = "NAME|FILE")]
+    theme: Option<String>,
+
+    /// Print plain text without colors or styles, and use the terminal's colors in the TUI (also set by NO_COLOR)
+    #[arg(long)]
+    no_color: bool,
+
+    /// Name shown for the assistant's messages in the TUI (default: the agent's display_name, else its name)
+    #[arg(long, value_name = "NAME")]
+    display_name: Option<String>,
+
     /// Grant tool permissions (comma-separated: read_file,bash,write_file)
     #[arg(short = 'g', long, value_delimiter = ',')]
     grant: Vec<String>,
@@ -139,6 +233,10 @@ struct Args {
     #[arg(long)]
     max_iterations: Option<usize>,
 
+    /// Stop a single agent turn after this many seconds and return what it has so far
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    turn_timeout: Option<u64>,
+
     /// Batch mode timeout in seconds (default: 300)
     #[arg(long, default_value = "300")]
     batch_timeout: u64,
@@ -147,14 +245,129 @@ struct Args {
     #[arg(long)]
     dry_run: bool,
 
+    /// Review mode: only read-only tools are available (overrides --grant-all and --yes)
+    #[arg(long)]
+    review: bool,
+
+    /// More diagnostics on stderr: -v info, -vv debug, -vvv trace (default: warnings only)
+    #[arg(short = 'v', long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
     /// Quiet mode: only output final response (for batch mode)
     #[arg(short = 'q', long)]
     quiet: bool,
+
+    /// Print per-tool timing at the end of a batch run
+    #[arg(long)]
+    profile: bool,
+
+    /// Maximum size of a single tool result kept in context, in KB (0 = unlimited)
+    #[arg(long, default_value_t = agent_loop::DEFAULT_MAX_TOOL_OUTPUT_KB)]
+    max_tool_output_kb: usize,
+
+    /// Shell used by the bash tool and `!` commands (default: bash or sh; PowerShell or cmd on Windows)
+    #[arg(long, value_name = "PROGRAM")]
+    shell: Option<String>,
+
+    /// How long web_fetch/web_search results stay cached, in seconds (0 = no cache)
+    #[arg(long, default_value_t = tools::web_cache::DEFAULT_TTL_SECS)]
+    web_cache_ttl: u64,
+
+    /// Allow web_fetch, web_search and crate_docs (default: the agent's allow_network setting, else true); --allow-network=false blocks them
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    allow_network: Option<bool>,
+
+    /// Search engine for web_search (brave reads BRAVE_API_KEY; google reads GOOGLE_API_KEY and GOOGLE_CSE_ID)
+    #[arg(long, value_name = "BACKEND", default_value = "duckduckgo", value_parser = tools::web_search::BACKEND_NAMES)]
+    search_backend: String,
+
+    /// SearXNG instance for --search-backend searxng (JSON output must be enabled)
+    #[arg(long, value_name = "URL")]
+    searxng_url: Option<String>,
+
+    /// Largest file read_file reads whole, and write_file writes without asking, in bytes (0 = unlimited)
+    #[arg(long, value_name = "BYTES", default_value_t = tools::file_limits::DEFAULT_MAX_FILE_SIZE)]
+    max_file_size: u64,
+
+    /// Ask reasoning models (deepseek-r1, qwen3) to think and show the reasoning, collapsed in the TUI
+    #[arg(long)]
+    show_reasoning: bool,
+
+    /// Keep the directory and exported variables of a bash call for the next one
+    #[arg(long)]
+    persistent_shell: bool,
+
+    /// When the model ends a turn announcing a step it didn't take, ask it to continue (up to 2 times)
+    #[arg(long)]
+    auto_continue: bool,
+
+    /// Scan every tool result for prompt-injection phrases and flag them to the model and to you
+    #[arg(long)]
+    scan_injection: bool,
+
+    /// Run rustfmt on every .rs file the agent writes or edits, noting the result in the tool output
+    #[arg(long)]
+    format_on_write: bool,
+
+    /// Run cargo clippy on the package after the agent writes or edits a .rs file, passing new warnings back in the tool output
+    #[arg(long)]
+    lint_on_write: bool,
+
+    /// Ask before one turn changes more than N files, listing them for approval (stops the turn in batch mode)
+    #[arg(long, value_name = "N")]
+    max_files_per_turn: Option<usize>,
+
+    /// Answer the first request with a numbered plan (no tools) and act on it only once approved
+    #[arg(long)]
+    plan: bool,
+
+    /// Limit how many model requests the agent and its sub-agents have in flight at once (default: unlimited)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_concurrent_requests: Option<u32>,
+
+    /// Stop once the agent and its sub-agents have used this many (estimated) tokens in total this run
+    #[arg(long, value_name = "TOKENS", value_parser = clap::value_parser!(u64).range(1..))]
+    max_total_tokens: Option<u64>,
+
+    /// Batch mode: feed denied tool calls back to the model instead of aborting the run
+    #[arg(long)]
+    continue_on_error: bool,
+
+    /// Commit the working tree to an agent-t/<session> branch after each turn that changed it
+    #[arg(long)]
+    checkpoint: bool,
+
+    /// Keep the working directory inside this directory (limits change_directory and /cd)
+    #[arg(long, value_name = "DIR")]
+    root: Option<std::path::PathBuf>,
+
+    /// Extra read-only directory searched by grep/glob and indexed by the vecdb (repeatable)
+    #[arg(long = "context-dir", value_name = "DIR")]
+    context_dirs: Vec<std::path::PathBuf>,
+
+    /// Refuse file writes that resolve outside this directory, following `..` and symlinks (default: the current directory)
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = ".")]
+    write_root: Option<std::path::PathBuf>,
 }
 
 // System prompt loaded from external file at compile time
 const SYSTEM_PROMPT: &str = include_str!("../prompts/system.txt");
 
+/// Read input until an interrupt arrives, queueing everything else in order.
+/// Pending forever once the channel is closed.
+async fn wait_for_interrupt(input_rx: &mut tokio::sync::mpsc::Receiver<String>, queued: &mut std::collections::VecDeque<String>) {
+    while let Some(input) = input_rx.recv().await {
+        if input == "\x1b[INTERRUPT]" {
+            return;
+        }
+        queued.push_back(input);
+    }
+    std::future::pending().await
+}
+
+/// How often the TUI's diagnostics panel is refreshed from rust-analyzer
+const DIAGNOSTICS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 
 /// Load special instructions from either inline text or a file path
 /// If the input starts with '@', treat it as a file path, otherwise treat as inline text
@@ -169,6 +382,31 @@ fn load_instructions(instructions: &str) -> Result<String> {
     }
 }
 
+/// Rebuild the system prompt for /reload-prompt from --system-prompt (or the
+/// built-in prompt), the agent's agent.json and system_prompt.md, rendered for
+/// `cwd`. `suffix` is what was appended at startup (--instructions and the
+/// previous session's summary), which is kept as it was.
+fn reload_system_prompt(
+    custom_prompt: Option<&str>,
+    agent_manager: &agent::AgentManager,
+    agent_name: &str,
+    cwd: &str,
+    model: &str,
+    suffix: &str,
+) -> Result<String> {
+    let base_prompt = match custom_prompt {
+        Some(custom_prompt) => load_instructions(custom_prompt)?,
+        None => SYSTEM_PROMPT.to_string(),
+    };
+    let agent_config = agent_manager.load_agent(agent_name)?;
+    let agent_file_prompt = agent::load_agent_system_prompt(agent_manager, agent_name)?;
+    let prompt_with_agent = agent::build_system_prompt(&agent_config, &base_prompt, agent_file_prompt.as_deref());
+
+    let mut rendered = TemplateContext::new(cwd, model, agent_name).render(&prompt_with_agent);
+    rendered.push_str(suffix);
+    Ok(rendered)
+}
+
 /// Build GrantedPermissions from CLI arguments
 fn build_permissions(args: &Args) -> permissions::GrantedPermissions {
     let grant_all = args.grant_all || args.yes;
@@ -177,12 +415,77 @@ fn build_permissions(args: &Args) -> permissions::GrantedPermissions {
     // Expand tool categories (e.g., "read-only" -> ["read_file", "grep", ...])
     granted_tools = permissions::expand_tool_categories(granted_tools);
 
-    permissions::GrantedPermissions::new(
+    let permissions = permissions::GrantedPermissions::new(
         granted_tools,
         grant_all,
         args.yes,
         args.dry_run,
-    )
+    );
+
+    if args.review {
+        permissions.with_review_mode()
+    } else {
+        permissions
+    }
+}
+
+/// Resolve max_tokens from --max-tokens / agent config, falling back to a context-based default
+fn max_tokens(args: &Args) -> u64 {
+    args.max_tokens
+        .unwrap_or_else(|| agent_loop::default_max_tokens(args.context_size))
+}
+
+/// Sampling options from --temperature / --top-p / --seed (or agent config)
+fn sampling(args: &Args) -> agent_loop::SamplingParams {
+    agent_loop::SamplingParams {
+        temperature: args.temperature,
+        top_p: args.top_p,
+        seed: args.seed,
+    }
+}
+
+/// Log level for the number of -v flags
+fn log_level(verbose: u8) -> tracing_subscriber::filter::LevelFilter {
+    use tracing_subscriber::filter::LevelFilter;
+    match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Location of the log file used while the TUI owns the terminal
+fn log_file_path() -> Option<std::path::PathBuf> {
+    Some(dirs::home_dir()?.join(".agent-t").join("agent-t.log"))
+}
+
+/// Send tracing output to stderr in batch mode. The TUI would be garbled by
+/// it, so interactive sessions append to ~/.agent-t/agent-t.log instead.
+fn init_logging(args: &Args) {
+    let builder = tracing_subscriber::fmt().with_max_level(log_level(args.verbose));
+    let interactive = args.prompt.is_none() && args.prompt_file.is_none();
+    let log_file = log_file_path().filter(|_| interactive).and_then(|path| {
+        std::fs::create_dir_all(path.parent()?).ok()?;
+        std::fs::OpenOptions::new().create(true).append(true).open(path).ok()
+    });
+
+    match log_file {
+        Some(file) => builder.with_ansi(false).with_writer(std::sync::Mutex::new(file)).init(),
+        None => builder.with_writer(std::io::stderr).init(),
+    }
+}
+
+/// Semaphore shared by all agents of this run, from --max-concurrent-requests
+fn request_limiter(args: &Args) -> Option<Arc<tokio::sync::Semaphore>> {
+    args.max_concurrent_requests
+        .map(|permits| Arc::new(tokio::sync::Semaphore::new(permits as usize)))
+}
+
+/// Token budget shared by all agents of this run, from --max-total-tokens
+fn token_budget(args: &Args) -> Option<Arc<agent_loop::TokenBudget>> {
+    args.max_total_tokens
+        .map(|limit| Arc::new(agent_loop::TokenBudget::new(limit as usize)))
 }
 
 /// Get the initial prompt for batch mode (from --prompt or --prompt-file)
@@ -198,26 +501,79 @@ fn get_initial_prompt(args: &Args) -> Result<Option<String>> {
     }
 }
 
-/// Run agent in batch mode (non-interactive)
-async fn run_batch_mode<M: rig::completion::CompletionModel>(
+/// Batch mode exit codes (0 = success)
+const EXIT_MODEL_ERROR: i32 = 1;
+const EXIT_PERMISSION_DENIED: i32 = 2;
+const EXIT_TIMEOUT: i32 = 3;
+const EXIT_MAX_ITERATIONS: i32 = 4;
+const EXIT_TOKEN_BUDGET: i32 = 5;
+
+/// Pick the batch exit code for an error returned by the agent loop
+fn batch_exit_code(error: &anyhow::Error) -> i32 {
+    match error.downcast_ref::<error::AgentError>() {
+        Some(error::AgentError::PermissionDenied(_)) => EXIT_PERMISSION_DENIED,
+        Some(error::AgentError::MaxIterations(_)) => EXIT_MAX_ITERATIONS,
+        Some(error::AgentError::TokenBudgetExhausted { .. }) => EXIT_TOKEN_BUDGET,
+        None => EXIT_MODEL_ERROR,
+    }
+}
+
+/// Turn on --checkpoint for an agent, warning if its directory isn't a git repository
+fn enable_checkpoints<M: CompletionModel>(agent: &mut AgentLoop<M>, session_id: &str) -> Option<git::Checkpoint> {
+    match git::Checkpoint::new(agent.working_directory(), session_id) {
+        Some(checkpoint) => {
+            agent.set_checkpoint(checkpoint.clone());
+            Some(checkpoint)
+        }
+        None => {
+            eprintln!("Warning: --checkpoint ignored: the working directory is not a git repository");
+            None
+        }
+    }
+}
+
+/// What a batch run needs besides the model and the command line
+struct BatchRun<'a> {
     prompt: String,
-    model: M,
     system_prompt: String,
     permissions: permissions::GrantedPermissions,
-    args: &Args,
     cwd: String,
     vecdb: Option<Arc<tokio::sync::Mutex<vecdb::VectorDB>>>,
     memory_manager: Option<Arc<tokio::sync::Mutex<memory::MemoryManager>>>,
     traffic: TrafficHandle,
-) -> Result<()> {
+    price: Option<pricing::ModelPrice>,
+    audit_log: Arc<audit::AuditLog>,
+    agent_manager: &'a agent::AgentManager,
+    agent_name: &'a str,
+}
+
+/// Run agent in batch mode (non-interactive)
+async fn run_batch_mode<M: rig::completion::CompletionModel>(run: BatchRun<'_>, model: M, args: &Args) -> Result<()> {
     use tokio::time::{timeout, Duration};
 
+    let BatchRun {
+        prompt,
+        system_prompt,
+        permissions,
+        cwd,
+        vecdb,
+        memory_manager,
+        traffic,
+        price,
+        audit_log,
+        agent_manager,
+        agent_name,
+    } = run;
+
     if !args.quiet {
         eprintln!("Running in batch mode...");
         eprintln!("Permissions: {}", permissions.summary());
         if permissions.is_dry_run() {
             eprintln!("DRY RUN MODE: No tools will actually execute");
         }
+        if permissions.is_review() {
+            eprintln!("REVIEW MODE: read-only tools only; file writes and commands are blocked");
+        }
         eprintln!();
     }
 
@@ -233,7 +589,7 @@ async fn run_batch_mode<M: rig::completion::CompletionModel>(
         system_prompt,
         traffic,
         !args.no_confirm,
-        false,  // No streaming in batch mode
+        args.streaming,
         cwd,
         args.context_size,
         vecdb,
@@ -249,11 +605,55 @@ async fn run_batch_mode<M: rig::completion::CompletionModel>(
     if let Some(max_iter) = args.max_iterations {
         agent.set_max_iterations(max_iter);
     }
+    agent.set_max_tool_output_kb(args.max_tool_output_kb);
+    agent.set_max_tokens(max_tokens(args));
+    agent.set_sampling(sampling(args));
+    agent.set_show_reasoning(args.show_reasoning);
+    agent.set_persistent_shell(args.persistent_shell);
+    agent.set_auto_continue(args.auto_continue);
+    agent.set_scan_injection(args.scan_injection);
+    agent.set_format_on_write(args.format_on_write);
+    agent.set_lint_on_write(args.lint_on_write);
+    agent.set_plan_mode(args.plan);
+    if let Some(max_files) = args.max_files_per_turn {
+        agent.set_max_files_per_turn(max_files);
+    }
+    if let Some(secs) = args.turn_timeout {
+        agent.set_turn_timeout(Duration::from_secs(secs));
+    }
+    if let Some(limiter) = request_limiter(args) {
+        agent.set_request_limiter(limiter);
+    }
+    if let Some(budget) = token_budget(args) {
+        agent.set_token_budget(budget);
+    }
+    if let Some(ref root) = args.root {
+        agent.set_root(root.clone());
+    }
+    // Streamed tokens go to stderr so stdout only carries the final response
+    agent.set_stream_to_stderr(true);
+    agent.set_continue_on_error(args.continue_on_error);
+    if !args.quiet {
+        eprintln!("Audit log: {}", audit_log.path().display());
+    }
+    agent.set_audit_log(audit_log);
+    if args.checkpoint {
+        enable_checkpoints(&mut agent, &format!("batch-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+    }
 
     // Run with timeout
     let timeout_duration = Duration::from_secs(args.batch_timeout);
     let result = timeout(timeout_duration, agent.chat(&prompt)).await;
 
+    if let Err(e) = agent_manager.record_session(agent_name, 1, agent.file_changes_count()) {
+        eprintln!("Warning: Failed to update agent statistics: {}", e);
+    }
+
+    if args.profile {
+        eprintln!("\n=== Profile ===");
+        eprint!("{}", agent.get_profile().format_table());
+    }
+
     match result {
         Ok(Ok(response)) => {
             // Success - print the final response
@@ -268,6 +668,9 @@ async fn run_batch_mode<M: rig::completion::CompletionModel>(
                 eprintln!("Iterations: {}", agent.iteration_count());
                 let usage = agent.get_token_usage();
                 eprintln!("Token usage: {} prompt, {} completion", usage.prompt_tokens, usage.completion_tokens);
+                if price.is_some() {
+                    eprintln!("Estimated cost: {}", pricing::format_cost(price, usage.prompt_tokens, usage.completion_tokens));
+                }
             }
             // Flush memory before exit
             if let Some(ref mm) = memory_manager_cleanup {
@@ -285,7 +688,7 @@ async fn run_batch_mode<M: rig::completion::CompletionModel>(
                 let manager = mm.lock().await;
                 let _ = manager.flush();
             }
-            std::process::exit(1);
+            std::process::exit(batch_exit_code(&e));
         }
         Err(_) => {
             if !args.quiet {
@@ -296,18 +699,42 @@ async fn run_batch_mode<M: rig::completion::CompletionModel>(
                 let manager = mm.lock().await;
                 let _ = manager.flush();
             }
-            std::process::exit(3);
+            std::process::exit(EXIT_TIMEOUT);
         }
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
 
-    tracing_subscriber::fmt()
-        .with_max_level(tracing_subscriber::filter::LevelFilter::WARN)
-        .init();
+    init_logging(&args);
+
+    // Pick the theme before anything is printed
+    let theme = match args.theme {
+        Some(ref theme) => Some(colors::Theme::load(theme)?),
+        None => colors::Theme::load_default().transpose().unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring {}: {}", colors::THEME_FILE, e);
+            None
+        }),
+    };
+    if let Some(theme) = theme {
+        colors::set_theme(theme);
+    }
+    if args.no_color || colors::no_color_requested() {
+        colors::set_no_color(true);
+    }
+
+    tools::web_cache::set_ttl(args.web_cache_ttl);
+    tools::web_search::set_backend(
+        tools::web_search::SearchBackend::from_config(&args.search_backend, args.searxng_url.as_deref())
+            .map_err(|e| anyhow::anyhow!(e))?,
+    );
+    if let Some(ref shell) = args.shell {
+        tools::shell::set(shell);
+    }
+    tools::file_limits::set_max_file_size(args.max_file_size);
+    terminal::load_dangerous_patterns();
 
     // Handle --list-agents
     if args.list_agents {
@@ -355,7 +782,12 @@ async fn main() -> Result<()> {
     // Validate and load/create agent
     agent::AgentManager::validate_name(&agent_name)?;
 
-    let agent_config = if agent_manager.exists(&agent_name) {
+    let agent_config = if let Some(ref source) = args.clone {
+        if agent_manager.exists(&agent_name) {
+            return Err(anyhow::anyhow!("Agent '{}' already exists; --clone needs a new agent name", agent_name));
+        }
+        agent_manager.clone_agent(source, &agent_name)?
+    } else if agent_manager.exists(&agent_name) {
         // Load existing agent
         let config = agent_manager.load_agent(&agent_name)?;
         terminal::print_success(&format!("Loaded agent '{}'", agent_name));
@@ -380,6 +812,18 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Agent config supplies defaults for model settings not given on the command line
+    args.max_tokens = args.max_tokens.or(agent_config.max_tokens);
+    args.temperature = args.temperature.or(agent_config.temperature);
+    args.top_p = args.top_p.or(agent_config.top_p);
+    args.seed = args.seed.or(agent_config.seed);
+
+    let allow_network = args.allow_network.or(agent_config.allow_network).unwrap_or(true);
+    tools::network::set_allowed(allow_network);
+    if !allow_network {
+        terminal::print_info("Network access disabled: web_fetch, web_search and crate_docs are unavailable");
+    }
+
     // Update last active
     agent_manager.update_last_active(&agent_name)?;
 
@@ -390,7 +834,8 @@ async fn main() -> Result<()> {
         terminal::print_info("Initializing long-term memory...");
         let mut manager = memory::MemoryManager::new(
             &agent_name,
-            &args.memory_embedding_model
+            &args.memory_embedding_model,
+            args.embedding_fallback,
         )?;
         match manager.load_or_initialize().await {
             Ok(_) => {
@@ -402,7 +847,10 @@ async fn main() -> Result<()> {
                 ));
 
                 // Get the last session summary for continuity
-                let last_summary = manager.get_last_session_summary();
+                let project = std::env::current_dir()
+                    .map(|dir| memory::project_scope(&dir.to_string_lossy()))
+                    .unwrap_or_default();
+                let last_summary = manager.get_last_session_summary(&project);
                 if let Some(ref summary) = last_summary {
                     terminal::print_info(&format!(
                         "Found previous session summary from {}",
@@ -427,15 +875,15 @@ async fn main() -> Result<()> {
         tokio::spawn(async move {
             match tokio::signal::ctrl_c().await {
                 Ok(()) => {
-                    eprintln!("\n[INFO] Ctrl-C received, flushing memory...");
+                    tracing::info!("Ctrl-C received, flushing memory");
                     let mm = memory_clone.lock().await;
                     if let Err(e) = mm.flush() {
-                        eprintln!("[ERROR] Failed to flush memory on Ctrl-C: {}", e);
+                        tracing::error!("Failed to flush memory on Ctrl-C: {}", e);
                     }
                     std::process::exit(0);
                 }
                 Err(err) => {
-                    eprintln!("[WARN] Unable to listen for Ctrl-C signal: {}", err);
+                    tracing::warn!("Unable to listen for Ctrl-C signal: {}", err);
                 }
             }
         });
@@ -453,8 +901,17 @@ async fn main() -> Result<()> {
     // Start inspector web server if enabled
     if let Some(state) = inspector_state {
         let port = args.inspector_port;
+        let bind = args.inspector_bind.clone();
+        let token = args.inspector_token.clone().filter(|t| !t.is_empty());
+        let is_loopback = bind.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback()) || bind == "localhost";
+        if !is_loopback && token.is_none() {
+            terminal::print_warning(&format!(
+                "Inspector is reachable from other machines on {} without --inspector-token; anyone there can read the model traffic",
+                bind
+            ));
+        }
         tokio::spawn(async move {
-            if let Err(e) = inspector::start_server(state, port).await {
+            if let Err(e) = inspector::start_server(state, &bind, port, token).await {
                 terminal::print_error(&format!("Inspector server error: {}", e));
             }
         });
@@ -468,6 +925,24 @@ async fn main() -> Result<()> {
         .map(|p| p.display().to_string())
         .unwrap_or_else(|_| "unknown".to_string());
 
+    // Load .agentignore before any tool or the indexer can touch the tree
+    if let Some(count) = tools::agent_ignore::load(std::path::Path::new(&cwd)) {
+        terminal::print_info(&format!("Loaded {} with {} rule(s)", tools::agent_ignore::IGNORE_FILE, count));
+    }
+
+    // Register extra read-only roots for grep, glob and the vecdb
+    for missing in tools::context_dirs::set(&args.context_dirs) {
+        terminal::print_warning(&format!("Ignoring --context-dir {}: not a directory", missing.display()));
+    }
+
+    // Confine file writes before any tool can run
+    if let Some(ref root) = args.write_root {
+        match tools::write_root::set(root) {
+            Ok(root) => terminal::print_info(&format!("File writes are limited to {}", root.display())),
+            Err(e) => return Err(anyhow::anyhow!("Invalid --write-root {}: {}", root.display(), e)),
+        }
+    }
+
     // Detect and initialize rust-analyzer if this is a Rust project
     let is_rust_project = std::path::Path::new(&cwd).join("Cargo.toml").exists();
     if is_rust_project {
@@ -488,10 +963,23 @@ async fn main() -> Result<()> {
         terminal::print_info("Initializing vector database...");
         match vecdb::VectorDB::new(args.ollama_url.as_deref(), &args.vecdb_embedding_model) {
             Ok(mut db) => {
+                db.set_search_limits(args.vecdb_top_k, args.vecdb_min_similarity);
+                db.set_chunk_options(tree_sitter_chunker::ChunkOptions {
+                    max_size: args.vecdb_chunk_size,
+                    overlap_lines: args.vecdb_chunk_overlap,
+                });
+                db.set_languages(&args.vecdb_languages);
+                db.set_embedding_fallback(args.embedding_fallback);
+
                 // Check if we need to index or reindex
-                if args.reindex || !db.index_exists() {
+                if let Err(e) = db.check_embedder().await {
+                    terminal::print_error(&format!("Failed to initialize vector database: {}", e));
+                    None
+                } else if args.reindex || !db.index_exists() {
                     terminal::print_info("Indexing code files... This may take a few minutes.");
-                    match db.index_directory(&cwd).await {
+                    let mut roots = vec![cwd.clone()];
+                    roots.extend(tools::context_dirs::dirs().iter().map(|d| d.display().to_string()));
+                    match db.index_directories(&roots).await {
                         Ok(num_chunks) => {
                             terminal::print_success(&format!("Indexed {} code chunks", num_chunks));
                             Some(Arc::new(tokio::sync::Mutex::new(db)))
@@ -565,6 +1053,22 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Save the session periodically so a crash or kill doesn't lose it
+    if args.autosave_interval > 0 {
+        let session_manager_autosave = Arc::clone(&session_manager);
+        let period = std::time::Duration::from_secs(args.autosave_interval);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.tick().await; // The first tick completes immediately
+            loop {
+                ticker.tick().await;
+                if let Err(e) = session_manager_autosave.lock().await.save_current_session() {
+                    tracing::warn!("Failed to auto-save session: {}", e);
+                }
+            }
+        });
+    }
+
     // Create Ollama client
     let ollama_client = if let Some(ref url) = args.ollama_url {
         terminal::print_info(&format!("Using Ollama at: {}", url));
@@ -586,6 +1090,9 @@ async fn main() -> Result<()> {
     //let completion_model_type = open
     let model: ollama::CompletionModel<reqwest::Client> = ollama_client.completion_model(&args.model);
 
+    // Price per 1k tokens for cost estimates (None = local model)
+    let model_price = pricing::PricingTable::load().price_for(&args.model);
+
     // Create channels for TUI <-> Agent communication
     let (tui_tx, tui_rx) = tokio::sync::mpsc::channel::<tui::TuiEvent>(100);
     let (input_tx, mut input_rx) = tokio::sync::mpsc::channel::<String>(100);
@@ -635,6 +1142,7 @@ async fn main() -> Result<()> {
     // Render system prompt with template variables
     let template_ctx = TemplateContext::new(&cwd, &args.model, &agent_name);
     let mut rendered_prompt = template_ctx.render(&prompt_with_agent);
+    let rendered_len = rendered_prompt.len();
 
     // Append special instructions if provided
     if let Some(ref instructions_input) = args.instructions {
@@ -668,34 +1176,46 @@ async fn main() -> Result<()> {
         rendered_prompt.push_str("\n\nUse this context to continue where you left off. You can search for more details using search_routine_memory or search_key_memory tools.");
     }
 
+    // Kept when /reload-prompt rebuilds the rest
+    let prompt_suffix = rendered_prompt[rendered_len..].to_string();
+
     // Check for batch mode
     if let Some(prompt) = get_initial_prompt(&args)? {
         // BATCH MODE - run non-interactively and exit
         let permissions = build_permissions(&args);
-        return run_batch_mode(
+        let audit_log = Arc::new(audit::AuditLog::for_session(
+            &agent_manager.agent_dir(&agent_name),
+            &format!("batch-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S")),
+        ));
+        let run = BatchRun {
             prompt,
-            model,
-            rendered_prompt,
+            system_prompt: rendered_prompt,
             permissions,
-            &args,
             cwd,
             vecdb,
-            _memory_manager,
-            traffic_handle,
-        )
-        .await;
+            memory_manager: _memory_manager,
+            traffic: traffic_handle,
+            price: model_price,
+            audit_log,
+            agent_manager: &agent_manager,
+            agent_name: &agent_name,
+        };
+        return run_batch_mode(run, model, &args).await;
     }
 
     // INTERACTIVE MODE (TUI)
     // For interactive mode, allow all tools (permissions handled via TUI prompts)
-    let permissions = permissions::GrantedPermissions::allow_all();
+    let mut permissions = permissions::GrantedPermissions::allow_all();
+    if args.review {
+        permissions = permissions.with_review_mode();
+    }
 
     // Create cancellation token for interrupt handling
     let cancel_token = CancellationToken::new();
 
     // Create the agentic loop with traffic handle and confirmation setting
     // Clone rendered_prompt before moving it so we can recreate the agent later
-    let rendered_prompt_agent = rendered_prompt.clone();
+    let mut rendered_prompt_agent = rendered_prompt.clone();
 
     // Get session ID from session manager
     let session_id = {
@@ -720,8 +1240,57 @@ async fn main() -> Result<()> {
         args.model.clone(),  // Model name
     );
 
+    // Record tool calls for this session under the agent's directory; without
+    // a session the file is named for this run, so separate runs stay apart
+    let audit_log = Arc::new(audit::AuditLog::for_session(
+        &agent_manager.agent_dir(&agent_name),
+        &session_id
+            .clone()
+            .unwrap_or_else(|| format!("interactive-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"))),
+    ));
+    agent.set_audit_log(audit_log.clone());
+    agent.set_show_reasoning(args.show_reasoning);
+    agent.set_persistent_shell(args.persistent_shell);
+    agent.set_auto_continue(args.auto_continue);
+    agent.set_scan_injection(args.scan_injection);
+    agent.set_format_on_write(args.format_on_write);
+    agent.set_lint_on_write(args.lint_on_write);
+    agent.set_plan_mode(args.plan);
+    if let Some(max_files) = args.max_files_per_turn {
+        agent.set_max_files_per_turn(max_files);
+    }
+    if let Some(secs) = args.turn_timeout {
+        agent.set_turn_timeout(std::time::Duration::from_secs(secs));
+    }
+
+    // One limiter for the whole session, so it survives agent recreation
+    let session_limiter = request_limiter(&args);
+    if let Some(ref limiter) = session_limiter {
+        agent.set_request_limiter(limiter.clone());
+    }
+    let session_budget = token_budget(&args);
+    if let Some(ref budget) = session_budget {
+        agent.set_token_budget(budget.clone());
+    }
+
+    // Checkpoints go to a branch named after the session
+    let checkpoint = match (args.checkpoint, session_id.as_deref()) {
+        (true, Some(id)) => enable_checkpoints(&mut agent, id),
+        (true, None) => {
+            eprintln!("Warning: --checkpoint ignored: there is no session to name the checkpoint branch after");
+            None
+        }
+        (false, _) => None,
+    };
+
     // Set TUI event sender on agent
     agent.set_tui_sender(tui_tx.clone());
+    agent.set_max_tool_output_kb(args.max_tool_output_kb);
+    agent.set_max_tokens(max_tokens(&args));
+    agent.set_sampling(sampling(&args));
+    if let Some(ref root) = args.root {
+        agent.set_root(root.clone());
+    }
 
     // Get session info for TUI
     let session_id = {
@@ -753,6 +1322,13 @@ async fn main() -> Result<()> {
         model: args.model.clone(),
     });
 
+    if permissions.is_review() {
+        let _ = tui_tx.try_send(tui::TuiEvent::Warning {
+            agent_id: "main".to_string(),
+            text: "REVIEW MODE: only read-only tools are available. write_file, edit_file, bash and other mutating tools are blocked.".to_string(),
+        });
+    }
+
     // Send session list to TUI for autocomplete
     {
         let sm = session_manager.lock().await;
@@ -771,7 +1347,6 @@ async fn main() -> Result<()> {
     let command_registry = CommandRegistry::new();
     let session_manager_clone = Arc::clone(&session_manager);
     let model_clone = args.model.clone();
-    let cwd_clone = cwd.clone();
 
     // Spawn agent task to handle user inputs
     let mut cancel_token_agent = cancel_token.clone();
@@ -783,11 +1358,50 @@ async fn main() -> Result<()> {
     let context_size_agent = args.context_size;
     let vecdb_agent = vecdb.clone();
     let memory_manager_agent = _memory_manager.clone();
-    let session_id_agent = session_id.clone();
+    let idle_unload_url = args
+        .idle_unload
+        .then(|| args.ollama_url.clone().unwrap_or_else(|| idle::DEFAULT_OLLAMA_URL.to_string()));
+    let mut session_id_agent = session_id.clone();
     let permissions_agent = permissions.clone();
+    let max_tool_output_kb_agent = args.max_tool_output_kb;
+    let max_tokens_agent = max_tokens(&args);
+    let sampling_agent = sampling(&args);
+    let root_agent = args.root.clone();
+    let mut audit_log_agent = audit_log.clone();
+    let request_limiter_agent = session_limiter.clone();
+    let token_budget_agent = session_budget.clone();
+    let show_reasoning_agent = args.show_reasoning;
+    let persistent_shell_agent = args.persistent_shell;
+    let auto_continue_agent = args.auto_continue;
+    let scan_injection_agent = args.scan_injection;
+    let format_on_write_agent = args.format_on_write;
+    let lint_on_write_agent = args.lint_on_write;
+    let plan_agent = args.plan;
+    let max_files_per_turn_agent = args.max_files_per_turn;
+    let turn_timeout_agent = args.turn_timeout;
+    let mut checkpoint_agent = checkpoint.clone();
+    let agent_manager_agent = agent_manager.clone();
+    let agent_name_agent = agent_name.clone();
+    let custom_prompt_agent = args.system_prompt.clone();
+    let tui_tx_diagnostics = tui_tx.clone();
 
     let agent_task = tokio::spawn(async move {
-        while let Some(user_input) = input_rx.recv().await {
+        // Session totals for the agent's lifetime statistics; file changes of
+        // agents replaced after an interrupt are carried over
+        let mut session_messages = 0;
+        let mut earlier_file_changes = 0;
+        // Input that arrived while a turn was running, handled once it has ended
+        let mut queued_inputs = std::collections::VecDeque::new();
+
+        loop {
+            let user_input = match queued_inputs.pop_front() {
+                Some(input) => input,
+                None => match input_rx.recv().await {
+                    Some(input) => input,
+                    None => break,
+                },
+            };
+
             // Check for interrupt signal
             if user_input == "\x1b[INTERRUPT]" {
                 // Trigger cancellation
@@ -796,6 +1410,11 @@ async fn main() -> Result<()> {
                 let _ = tui_tx.try_send(tui::TuiEvent::Interrupt);
 
                 // Recreate the agent with a new cancellation token
+                let dry_run_writes = agent.dry_run_writes();
+                let approvals = agent.approvals();
+                let profile = agent.get_profile().clone();
+                let tool_stats = agent.get_tool_stats().clone();
+                earlier_file_changes += agent.file_changes_count();
                 let new_cancel_token = CancellationToken::new();
                 let new_model = ollama_client_agent.completion_model(&model_name_agent);
                 agent = AgentLoop::new(
@@ -804,7 +1423,7 @@ async fn main() -> Result<()> {
                     traffic_handle_agent.clone(),
                     !no_confirm_agent,
                     streaming_agent,
-                    cwd_clone.clone(),
+                    agent.working_directory().to_string(),  // Keep any /cd or change_directory
                     context_size_agent,
                     vecdb_agent.clone(),
                     memory_manager_agent.clone(),
@@ -815,10 +1434,92 @@ async fn main() -> Result<()> {
                     model_name_agent.clone(),  // Model name
                 );
                 agent.set_tui_sender(tui_tx.clone());
+                agent.set_max_tool_output_kb(max_tool_output_kb_agent);
+                agent.set_max_tokens(max_tokens_agent);
+                agent.set_sampling(sampling_agent.clone());
+                if let Some(ref root) = root_agent {
+                    agent.set_root(root.clone());
+                }
+                agent.set_audit_log(audit_log_agent.clone());
+                if let Some(ref limiter) = request_limiter_agent {
+                    agent.set_request_limiter(limiter.clone());
+                }
+                if let Some(ref budget) = token_budget_agent {
+                    agent.set_token_budget(budget.clone());
+                }
+                agent.set_show_reasoning(show_reasoning_agent);
+                agent.set_persistent_shell(persistent_shell_agent);
+                agent.set_auto_continue(auto_continue_agent);
+                agent.set_scan_injection(scan_injection_agent);
+                agent.set_format_on_write(format_on_write_agent);
+                agent.set_lint_on_write(lint_on_write_agent);
+                agent.set_plan_mode(plan_agent);
+                if let Some(max_files) = max_files_per_turn_agent {
+                    agent.set_max_files_per_turn(max_files);
+                }
+                if let Some(secs) = turn_timeout_agent {
+                    agent.set_turn_timeout(std::time::Duration::from_secs(secs));
+                }
+                if let Some(ref checkpoint) = checkpoint_agent {
+                    agent.set_checkpoint(checkpoint.clone());
+                }
+                agent.set_dry_run_writes(dry_run_writes);
+                agent.set_approvals(approvals);
+                agent.set_profile(profile);
+                agent.set_tool_stats(tool_stats);
                 cancel_token_agent = new_cancel_token;
                 continue;
             }
 
+            // Nothing has happened for --idle-timeout: persist everything and free the model
+            if user_input == "\x1b[IDLE]" {
+                let mut done = Vec::new();
+                if let Err(e) = session_manager_clone.lock().await.save_current_session() {
+                    tracing::warn!("Failed to save session when going idle: {}", e);
+                } else {
+                    done.push("session saved");
+                }
+                if let Some(ref mm) = memory_manager_agent
+                    && let Err(e) = mm.lock().await.flush() {
+                        tracing::warn!("Failed to flush memory when going idle: {}", e);
+                    }
+                if let Some(ref url) = idle_unload_url {
+                    match idle::unload_model(url, &model_name_agent).await {
+                        Ok(()) => done.push("model unloaded"),
+                        Err(e) => tracing::warn!("Failed to unload {} when going idle: {}", model_name_agent, e),
+                    }
+                }
+                let _ = tui_tx.try_send(tui::TuiEvent::Idle(done.join(", ")));
+                continue;
+            }
+
+            // Check for a request to apply code blocks from an assistant message
+            if let Some(message) = user_input.strip_prefix("\x1b[APPLY]") {
+                let blocks: Vec<_> = codeblock::extract(message)
+                    .into_iter()
+                    .filter_map(|block| block.path.clone().map(|path| (path, block)))
+                    .collect();
+
+                let mut applied = 0;
+                for (path, block) in &blocks {
+                    match agent.apply_code_block(path, &block.content).await {
+                        Ok(_) => applied += 1,
+                        Err(e) => {
+                            let _ = tui_tx.try_send(tui::TuiEvent::Warning {
+                                agent_id: "main".to_string(),
+                                text: e.to_string(),
+                            });
+                        }
+                    }
+                }
+
+                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                    agent_id: "main".to_string(),
+                    text: format!("Applied {} of {} code block(s)", applied, blocks.len()),
+                });
+                continue;
+            }
+
             // Skip empty input
             if user_input.trim().is_empty() {
                 continue;
@@ -837,13 +1538,11 @@ async fn main() -> Result<()> {
                 }
 
                 // Execute shell command directly
-                use tokio::process::Command;
                 use std::process::Stdio;
                 use tokio::time::{timeout, Duration};
 
-                let mut cmd = Command::new("bash");
-                cmd.arg("-c").arg(shell_command);
-                cmd.current_dir(&cwd_clone);
+                let mut cmd = tools::shell::command(shell_command);
+                cmd.current_dir(agent.working_directory());
                 cmd.stdout(Stdio::piped());
                 cmd.stderr(Stdio::piped());
 
@@ -912,10 +1611,11 @@ async fn main() -> Result<()> {
             if CommandRegistry::is_command(&user_input) {
                 // Execute command
                 let mut sm = session_manager_clone.lock().await;
+                let cwd_now = agent.working_directory().to_string();
                 let mut context = CommandContext {
                     session_manager: &mut sm,
                     tui_tx: &tui_tx,
-                    cwd: &cwd_clone,
+                    cwd: &cwd_now,
                     model: &model_clone,
                 };
 
@@ -931,6 +1631,38 @@ async fn main() -> Result<()> {
                                 agent.clear_history();
                                 let _ = tui_tx.try_send(tui::TuiEvent::Clear);
                             }
+                            CommandResult::NewSession(new_session_id) => {
+                                earlier_file_changes += agent.file_changes_count();
+                                agent.start_new_session(new_session_id.clone());
+                                session_id_agent = new_session_id.clone();
+                                // The audit log and checkpoint branch are named after the session
+                                audit_log_agent = Arc::new(audit::AuditLog::for_session(
+                                    &agent_manager_agent.agent_dir(&agent_name_agent),
+                                    &new_session_id,
+                                ));
+                                agent.set_audit_log(audit_log_agent.clone());
+                                if let Some(ref mut checkpoint) = checkpoint_agent {
+                                    *checkpoint = checkpoint.for_session(&new_session_id);
+                                    agent.set_checkpoint(checkpoint.clone());
+                                }
+                                let _ = tui_tx.try_send(tui::TuiEvent::Clear);
+                                let _ = tui_tx.try_send(tui::TuiEvent::TokenUsage {
+                                    agent_id: "main".to_string(),
+                                    prompt: 0,
+                                    completion: 0,
+                                });
+                                let _ = tui_tx.try_send(tui::TuiEvent::SessionUpdate {
+                                    id: new_session_id.clone(),
+                                    model: model_clone.clone(),
+                                });
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text: format!(
+                                        "Started new session {}",
+                                        &new_session_id[..8.min(new_session_id.len())]
+                                    ),
+                                });
+                            }
                             CommandResult::ShowFileChanges => {
                                 let changes = agent.get_file_changes_summary();
                                 let msg = if changes.is_empty() {
@@ -952,6 +1684,237 @@ async fn main() -> Result<()> {
                                     text: msg,
                                 });
                             }
+                            CommandResult::ShowCost => {
+                                let usage = agent.get_token_usage();
+                                let text = match model_price {
+                                    Some(price) => format!(
+                                        "Estimated cost this session: {}\n\n\
+                                         Prompt:     {} tokens at ${}/1k\n\
+                                         Completion: {} tokens at ${}/1k\n\n\
+                                         Token counts are estimates (about 4 characters per token).",
+                                        pricing::format_cost(Some(price), usage.prompt_tokens, usage.completion_tokens),
+                                        usage.prompt_tokens,
+                                        price.prompt_per_1k,
+                                        usage.completion_tokens,
+                                        price.completion_per_1k
+                                    ),
+                                    None => format!(
+                                        "{} runs locally: $0.00. Add it to ~/.agent-t/{} to estimate costs for a paid model.",
+                                        model_clone,
+                                        pricing::PRICING_FILE
+                                    ),
+                                };
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text,
+                                });
+                            }
+                            CommandResult::ShowProfile => {
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text: format!("Time spent this session:\n\n{}", agent.get_profile().format_table()),
+                                });
+                            }
+                            CommandResult::ShowToolStats => {
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text: format!("Tool calls this session:\n\n{}", agent.get_tool_stats().format_table()),
+                                });
+                            }
+                            CommandResult::Rewind(turns) => {
+                                let available = agent.user_turn_count();
+                                if turns >= available {
+                                    let _ = tui_tx.try_send(tui::TuiEvent::Warning {
+                                        agent_id: "main".to_string(),
+                                        text: format!("Nothing to rewind: the conversation has {} user turn(s)", available),
+                                    });
+                                } else {
+                                    let removed = agent.rewind_to_turn(turns);
+                                    let _ = tui_tx.try_send(tui::TuiEvent::Rewind {
+                                        agent_id: "main".to_string(),
+                                        user_turns: turns,
+                                    });
+                                    let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                        agent_id: "main".to_string(),
+                                        text: format!("Rewound to user turn {} ({} message(s) removed)", turns, removed),
+                                    });
+                                }
+                            }
+                            CommandResult::ChangeDirectory(path) => {
+                                let event = match agent.change_directory(&path) {
+                                    Ok(msg) => tui::TuiEvent::Info {
+                                        agent_id: "main".to_string(),
+                                        text: msg,
+                                    },
+                                    Err(e) => tui::TuiEvent::Error {
+                                        agent_id: "main".to_string(),
+                                        text: format!("Failed to change directory: {}", e),
+                                    },
+                                };
+                                let _ = tui_tx.try_send(event);
+                            }
+                            CommandResult::ShowStats => {
+                                let files_changed = earlier_file_changes + agent.file_changes_count();
+                                let text = match agent_manager_agent.load_agent(&agent_name_agent) {
+                                    Ok(config) => {
+                                        let mut text = format!(
+                                            "Agent '{}' (created {})\n\n\
+                                             Conversations: {}\n\
+                                             Messages:      {}\n\
+                                             Files changed: {}",
+                                            config.name,
+                                            config.created_at.format("%Y-%m-%d"),
+                                            config.total_conversations + 1,
+                                            config.total_messages + session_messages,
+                                            config.total_files_changed + files_changed,
+                                        );
+                                        if let Some(ref mm) = memory_manager_agent {
+                                            let stats = mm.lock().await.stats();
+                                            text.push_str(&format!(
+                                                "\nMemories:      {} routine, {} key",
+                                                stats.routine_count, stats.key_count
+                                            ));
+                                        }
+                                        text.push_str(&format!(
+                                            "\n\nIncludes this session: {} message(s), {} file(s) changed.",
+                                            session_messages, files_changed
+                                        ));
+                                        text
+                                    }
+                                    Err(e) => format!("Failed to load agent statistics: {}", e),
+                                };
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text,
+                                });
+                            }
+                            CommandResult::SetDryRun(enabled) => {
+                                if let Some(enabled) = enabled {
+                                    agent.set_dry_run_writes(enabled);
+                                }
+                                let text = if agent.dry_run_writes() {
+                                    "Dry run is on: file writes are previewed for approval but not performed"
+                                } else {
+                                    "Dry run is off: approved file writes are performed"
+                                };
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text: text.to_string(),
+                                });
+                            }
+                            CommandResult::Permissions(change) => {
+                                match change {
+                                    Some(commands::PermissionChange::Grant(tools)) => agent.approve_tools(&tools),
+                                    Some(commands::PermissionChange::Revoke(tools)) => agent.revoke_tools(&tools),
+                                    Some(commands::PermissionChange::RevokeAll) => agent.revoke_all_approvals(),
+                                    None => {}
+                                }
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text: agent.permissions_summary(),
+                                });
+                            }
+                            CommandResult::SearchMemory(query) => {
+                                let event = match memory_manager_agent {
+                                    Some(ref mm) => match mm.lock().await.search_report(&query, 10) {
+                                        Ok(text) => tui::TuiEvent::Info {
+                                            agent_id: "main".to_string(),
+                                            text,
+                                        },
+                                        Err(e) => tui::TuiEvent::Error {
+                                            agent_id: "main".to_string(),
+                                            text: format!("Memory search failed: {}", e),
+                                        },
+                                    },
+                                    None => tui::TuiEvent::Error {
+                                        agent_id: "main".to_string(),
+                                        text: "Memory is not enabled (start with --memory)".to_string(),
+                                    },
+                                };
+                                let _ = tui_tx.try_send(event);
+                            }
+                            CommandResult::ShowDiagnostics => {
+                                let event = match tools::diagnostics_report().await {
+                                    Ok(report) => tui::TuiEvent::Info {
+                                        agent_id: "main".to_string(),
+                                        text: report,
+                                    },
+                                    Err(e) => tui::TuiEvent::Error {
+                                        agent_id: "main".to_string(),
+                                        text: format!("Failed to get diagnostics: {}", e),
+                                    },
+                                };
+                                let _ = tui_tx.try_send(event);
+                            }
+                            CommandResult::ExportTraffic(path) => {
+                                let path = std::path::Path::new(agent.working_directory()).join(path);
+                                let event = match traffic_handle_agent.export_json().await {
+                                    None => tui::TuiEvent::Error {
+                                        agent_id: "main".to_string(),
+                                        text: "The traffic inspector is not running (start with --inspector)".to_string(),
+                                    },
+                                    Some(json) => match json.map_err(anyhow::Error::from)
+                                        .and_then(|json| std::fs::write(&path, json).map_err(anyhow::Error::from))
+                                    {
+                                        Ok(()) => tui::TuiEvent::Info {
+                                            agent_id: "main".to_string(),
+                                            text: format!("Saved inspector traffic to {}", path.display()),
+                                        },
+                                        Err(e) => tui::TuiEvent::Error {
+                                            agent_id: "main".to_string(),
+                                            text: format!("Failed to export inspector traffic: {}", e),
+                                        },
+                                    },
+                                };
+                                let _ = tui_tx.try_send(event);
+                            }
+                            CommandResult::ShowOutline(file) => {
+                                let event = match tools::document_outline(&file).await {
+                                    Ok(outline) => tui::TuiEvent::Info {
+                                        agent_id: "main".to_string(),
+                                        text: outline,
+                                    },
+                                    Err(e) => tui::TuiEvent::Error {
+                                        agent_id: "main".to_string(),
+                                        text: format!("Failed to build outline: {}", e),
+                                    },
+                                };
+                                let _ = tui_tx.try_send(event);
+                            }
+                            CommandResult::ReloadPrompt => {
+                                let event = match reload_system_prompt(
+                                    custom_prompt_agent.as_deref(),
+                                    &agent_manager_agent,
+                                    &agent_name_agent,
+                                    agent.working_directory(),
+                                    &model_name_agent,
+                                    &prompt_suffix,
+                                ) {
+                                    Ok(prompt) => {
+                                        let text = format!(
+                                            "Reloaded the system prompt ({} characters); the conversation is kept",
+                                            prompt.chars().count()
+                                        );
+                                        agent.set_preamble(prompt.clone());
+                                        rendered_prompt_agent = prompt;
+                                        tui::TuiEvent::Info {
+                                            agent_id: "main".to_string(),
+                                            text,
+                                        }
+                                    }
+                                    Err(e) => tui::TuiEvent::Error {
+                                        agent_id: "main".to_string(),
+                                        text: format!("Failed to reload the system prompt: {}", e),
+                                    },
+                                };
+                                let _ = tui_tx.try_send(event);
+                            }
+                            CommandResult::ShowHistory => {
+                                let _ = tui_tx.try_send(tui::TuiEvent::Info {
+                                    agent_id: "main".to_string(),
+                                    text: agent.history_dump(120),
+                                });
+                            }
                             CommandResult::Info(msg) => {
                                 let _ = tui_tx.try_send(tui::TuiEvent::Info {
                                     agent_id: "main".to_string(),
@@ -992,8 +1955,31 @@ async fn main() -> Result<()> {
             });
 
             // Run the agentic loop
-            match agent.chat(&user_input).await {
+            session_messages += 1;
+            // Keep reading input while the turn runs, so Escape stops it midway
+            // (sub-agents included) instead of waiting for it to finish
+            let turn = tokio::select! {
+                result = agent.chat(&user_input) => Some(result),
+                _ = wait_for_interrupt(&mut input_rx, &mut queued_inputs) => None,
+            };
+            let Some(turn) = turn else {
+                queued_inputs.push_front("\x1b[INTERRUPT]".to_string());
+                continue;
+            };
+            match turn {
                 Ok(response) => {
+                    // Record the turn and save, so it survives a crash
+                    {
+                        let mut sm = session_manager_clone.lock().await;
+                        if let Some(session) = sm.current_session_mut() {
+                            session.add_user_message(&user_input);
+                            session.add_assistant_message(&response);
+                        }
+                        if let Err(e) = sm.save_current_session() {
+                            tracing::warn!("Failed to save session after turn: {}", e);
+                        }
+                    }
+
                     // Always send the final complete message to finalize streaming
                     // The TUI will replace any streaming message with the final one
                     let _ = tui_tx.try_send(tui::TuiEvent::AssistantMessage {
@@ -1003,14 +1989,16 @@ async fn main() -> Result<()> {
 
                     // Update token usage
                     let usage = agent.get_token_usage();
-                    //eprintln!("DEBUG main.rs: Agent token usage - prompt: {}, completion: {}",
-                    //         usage.prompt_tokens, usage.completion_tokens);
+                    tracing::debug!(
+                        prompt = usage.prompt_tokens,
+                        completion = usage.completion_tokens,
+                        "Sending token usage to the TUI"
+                    );
                     let _ = tui_tx.try_send(tui::TuiEvent::TokenUsage {
                         agent_id: "main".to_string(),
                         prompt: usage.prompt_tokens,
                         completion: usage.completion_tokens,
                     });
-                    eprintln!("DEBUG main.rs: TokenUsage event sent");
                 }
                 Err(e) => {
                     let _ = tui_tx.try_send(tui::TuiEvent::Error {
@@ -1020,20 +2008,59 @@ async fn main() -> Result<()> {
                 }
             }
         }
+
+        (session_messages, earlier_file_changes + agent.file_changes_count())
     });
 
+    // Queue --init-prompt as the first input; the agent task echoes it to the TUI
+    if let Some(ref init_prompt) = args.init_prompt
+        && !init_prompt.trim().is_empty()
+    {
+        let _ = input_tx.try_send(init_prompt.clone());
+    }
+
+    // Feed the diagnostics panel (Ctrl+G) with rust-analyzer's latest report
+    if tools::ra_common::is_available().await {
+        let diagnostics_tx = tui_tx_diagnostics;
+        tokio::spawn(async move {
+            let mut last = None;
+            let mut interval = tokio::time::interval(DIAGNOSTICS_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                let report = tools::panel_report().await;
+                if report == last {
+                    continue;
+                }
+                if diagnostics_tx.send(tui::TuiEvent::Diagnostics(report.clone())).await.is_err() {
+                    break;
+                }
+                last = report;
+            }
+        });
+    }
+
     // Run TUI (this blocks until user quits)
+    let display_name = args.display_name.clone()
+        .or(agent_config.display_name.clone())
+        .unwrap_or_else(|| agent_name.clone());
     let tui_result = tui::run(
         session_id,
         args.model.clone(),
-        agent_name.clone(),
+        display_name,
         cwd.clone(),
+        model_price,
+        args.idle_timeout.map(|mins| std::time::Duration::from_secs(mins * 60)),
         tui_rx,
         input_tx,
     ).await;
 
-    // Wait for agent task to complete
-    let _ = agent_task.await;
+    // Wait for agent task to complete, then add the session to the agent's statistics
+    if let Ok((messages, files_changed)) = agent_task.await
+        && messages > 0
+        && let Err(e) = agent_manager.record_session(&agent_name, messages, files_changed)
+    {
+        tracing::warn!("Failed to update agent statistics: {}", e);
+    }
 
     // Handle any TUI errors
     if let Err(e) = tui_result {
@@ -1044,7 +2071,7 @@ async fn main() -> Result<()> {
     if let Some(ref memory_manager) = _memory_manager {
         let mm = memory_manager.lock().await;
         if let Err(e) = mm.flush() {
-            eprintln!("[WARN] Failed to flush memory on exit: {}", e);
+            tracing::warn!("Failed to flush memory on exit: {}", e);
         }
     }
 
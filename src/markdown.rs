@@ -0,0 +1,343 @@
+//! Markdown rendering of assistant messages for the TUI
+//!
+//! A line-based subset, enough for what models write: fenced code blocks
+//! (boxed, never wrapped, and syntax highlighted when the language is known),
+//! ATX headings, bullet and numbered lists, block quotes, rules, and inline
+//! `code`, **bold**, *italic* and [links](url).
+//! Anything else is shown as the text it is. Prose is word-wrapped; a message
+//! that is still streaming renders fine, an open fence just has no bottom yet.
+
+use crate::codeblock;
+use crate::colors;
+use crate::highlight;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+/// Indent of every rendered line, under the assistant's name
+const INDENT: &str = "  ";
+
+/// Render `text` as lines no wider than `width` (code blocks excepted)
+pub fn render(text: &str, width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    // Open fence marker, language, and the block's lines so far
+    let mut code: Option<(String, String, Vec<String>)> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some((fence, language, body)) = code.as_mut() {
+            if closes_fence(trimmed, fence) {
+                lines.extend(code_block(language, body, true));
+                code = None;
+            } else {
+                body.push(line.replace('\t', "    "));
+            }
+            continue;
+        }
+
+        if let Some(fence) = codeblock::fence_marker(trimmed) {
+            let language = trimmed[fence.len()..].split_whitespace().next().unwrap_or("");
+            // Drop a `:path` suffix (```rust:src/main.rs)
+            let language = language.split(':').next().unwrap_or("").to_string();
+            code = Some((fence.to_string(), language, Vec::new()));
+            continue;
+        }
+
+        lines.extend(block_line(line, width));
+    }
+
+    if let Some((_, language, body)) = code {
+        lines.extend(code_block(&language, &body, false));
+    }
+    lines
+}
+
+/// Whether `line` closes a fence opened with `fence`
+fn closes_fence(line: &str, fence: &str) -> bool {
+    let line = line.trim_end();
+    let fence_char = fence.chars().next().unwrap_or('`');
+    line.len() >= fence.len() && line.chars().all(|c| c == fence_char)
+}
+
+/// A fenced code block in a box, its language in the top border
+fn code_block(language: &str, body: &[String], closed: bool) -> Vec<Line<'static>> {
+    let border = Style::default().fg(colors::tui(colors::theme().overlay0));
+    let code = Style::default().fg(colors::tui(colors::theme().text));
+
+    let top = if language.is_empty() { "╭─".to_string() } else { format!("╭─ {}", language) };
+    let mut lines = vec![Line::from(Span::styled(format!("{}{}", INDENT, top), border))];
+    let highlighted = (!body.is_empty())
+        .then(|| highlight::highlight(language, &body.join("\n"), code))
+        .flatten()
+        .unwrap_or_else(|| body.iter().map(|line| vec![Span::styled(line.clone(), code)]).collect());
+    for spans in highlighted {
+        let mut line = vec![Span::styled(format!("{}│ ", INDENT), border)];
+        line.extend(spans);
+        lines.push(Line::from(line));
+    }
+    if closed {
+        lines.push(Line::from(Span::styled(format!("{}╰─", INDENT), border)));
+    }
+    lines
+}
+
+/// One line outside code blocks: heading, list item, quote, rule or prose
+fn block_line(line: &str, width: usize) -> Vec<Line<'static>> {
+    let theme = colors::theme();
+    let text = Style::default().fg(colors::tui(theme.text));
+    let trimmed = line.trim_start();
+
+    if trimmed.is_empty() {
+        return vec![Line::from("")];
+    }
+
+    if is_rule(trimmed) {
+        let rule = "─".repeat(width.saturating_sub(INDENT.len()).min(40));
+        return vec![Line::from(Span::styled(
+            format!("{}{}", INDENT, rule),
+            Style::default().fg(colors::tui(theme.overlay0)),
+        ))];
+    }
+
+    if let Some((level, heading)) = heading(trimmed) {
+        let mut style = Style::default().add_modifier(Modifier::BOLD);
+        style = match level {
+            1 => style.fg(colors::tui(theme.mauve)).add_modifier(Modifier::UNDERLINED),
+            2 => style.fg(colors::tui(theme.mauve)),
+            _ => style.fg(colors::tui(theme.blue)),
+        };
+        return wrap(INDENT.to_string(), INDENT.to_string(), inline(heading, style), width);
+    }
+
+    if let Some(quote) = trimmed.strip_prefix('>') {
+        let style = Style::default().fg(colors::tui(theme.overlay0)).add_modifier(Modifier::ITALIC);
+        let prefix = format!("{}▎ ", INDENT);
+        return wrap(prefix.clone(), prefix, inline(quote.trim_start(), style), width);
+    }
+
+    let depth = (line.len() - trimmed.len()) / 2;
+    if let Some((marker, item)) = list_item(trimmed, depth) {
+        let first = format!("{}{}{}", INDENT, "  ".repeat(depth), marker);
+        let rest = " ".repeat(first.chars().count());
+        return wrap(first, rest, inline(item, text), width);
+    }
+
+    wrap(INDENT.to_string(), INDENT.to_string(), inline(trimmed, text), width)
+}
+
+/// `---`, `***` or `___`, possibly spaced out
+fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && ['-', '*', '_'].iter().any(|&rule| chars.iter().all(|&c| c == rule))
+}
+
+/// Level and text of an ATX heading (`## Title`)
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    (rest.is_empty() || rest.starts_with(' ')).then(|| (level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Marker shown for a list item and the item's text
+fn list_item(line: &str, depth: usize) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            let marker = if depth.is_multiple_of(2) { "• " } else { "◦ " };
+            return Some((marker.to_string(), item));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    if (1..=9).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return Some((format!("{}. ", &line[..digits]), &rest[2..]));
+    }
+    None
+}
+
+/// Spans of a line's inline markup, on top of `base`
+fn inline(text: &str, base: Style) -> Vec<Span<'static>> {
+    let theme = colors::theme();
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let styled = match c {
+            '`' => rest[1..].find('`').map(|end| {
+                let code = Style::default().fg(colors::tui(theme.peach));
+                (vec![Span::styled(rest[1..1 + end].to_string(), code)], end + 2)
+            }),
+            '*' | '_' if rest.len() > 1 && rest[1..].starts_with(c) => {
+                let marker = &rest[..2];
+                rest[2..].find(marker).filter(|&end| end > 0).map(|end| {
+                    (inline(&rest[2..2 + end], base.add_modifier(Modifier::BOLD)), end + 4)
+                })
+            }
+            '*' | '_' if opens_emphasis(&plain, rest) => rest[1..]
+                .find(c)
+                .filter(|&end| end > 0 && (c == '*' || closes_emphasis(&rest[1 + end + 1..])))
+                .map(|end| (inline(&rest[1..1 + end], base.add_modifier(Modifier::ITALIC)), end + 2)),
+            '[' => link(rest).map(|(label, url, len)| {
+                let mut link = inline(label, base.fg(colors::tui(theme.blue)).add_modifier(Modifier::UNDERLINED));
+                if url != label {
+                    link.push(Span::styled(format!(" ({})", url), Style::default().fg(colors::tui(theme.overlay0))));
+                }
+                (link, len)
+            }),
+            _ => None,
+        };
+
+        match styled {
+            Some((styled, len)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.extend(styled);
+                rest = &rest[len..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+/// Emphasis opens on `*`/`_` followed by a non-space, and for `_` not inside a word (snake_case)
+fn opens_emphasis(before: &str, rest: &str) -> bool {
+    let next_is_text = rest[1..].chars().next().is_some_and(|c| !c.is_whitespace());
+    let inside_word = before.chars().last().is_some_and(char::is_alphanumeric);
+    next_is_text && !(rest.starts_with('_') && inside_word)
+}
+
+/// `_` emphasis only closes at the end of a word
+fn closes_emphasis(after: &str) -> bool {
+    !after.chars().next().is_some_and(char::is_alphanumeric)
+}
+
+/// Label, URL and length of a `[label](url)` link at the start of `text`
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let url_end = text[label_end + 2..].find(')')? + label_end + 2;
+    let label = &text[1..label_end];
+    let url = &text[label_end + 2..url_end];
+    (!label.is_empty() && !label.contains('[') && !url.contains(' ')).then_some((label, url, url_end + 1))
+}
+
+/// A word and the styled pieces it is made of
+struct Word {
+    pieces: Vec<(String, Style)>,
+    space_before: bool,
+    width: usize,
+}
+
+/// Split spans into words; text touching across spans (`**bold**,`) stays one word
+fn words(spans: &[Span<'static>]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut space = false;
+    let mut in_word = false;
+    for span in spans {
+        for c in span.content.chars() {
+            if c.is_whitespace() {
+                space = true;
+                in_word = false;
+                continue;
+            }
+            if !in_word {
+                words.push(Word { pieces: Vec::new(), space_before: space, width: 0 });
+                space = false;
+                in_word = true;
+            }
+            let Some(word) = words.last_mut() else {
+                continue;
+            };
+            match word.pieces.last_mut() {
+                Some((text, style)) if *style == span.style => text.push(c),
+                _ => word.pieces.push((c.to_string(), span.style)),
+            }
+            word.width += 1;
+        }
+    }
+    words
+}
+
+/// Word-wrap spans to `width`, starting the first line with `first` and the
+/// others with `rest`
+fn wrap(first: String, rest: String, spans: Vec<Span<'static>>, width: usize) -> Vec<Line<'static>> {
+    let border = Style::default().fg(colors::tui(colors::theme().overlay0));
+    let mut lines = Vec::new();
+    let mut used = first.chars().count();
+    let mut current = vec![Span::styled(first, border)];
+    let mut empty = true;
+
+    for word in words(&spans) {
+        if !empty && used + 1 + word.width > width {
+            lines.push(Line::from(std::mem::take(&mut current)));
+            used = rest.chars().count();
+            current.push(Span::styled(rest.clone(), border));
+            empty = true;
+        }
+        if !empty && word.space_before {
+            current.push(Span::styled(" ", word.pieces[0].1));
+            used += 1;
+        }
+        used += word.width;
+        current.extend(word.pieces.into_iter().map(|(text, style)| Span::styled(text, style)));
+        empty = false;
+    }
+    lines.push(Line::from(current));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect()).collect()
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let text = "# Plan\n\n- read `main.rs`\n  - then **fix** it\n1. test\n> note\n---\n```rust\nfn main() {}\n```\nDone.";
+        assert_eq!(plain(&render(text, 80)), [
+            "  Plan",
+            "",
+            "  • read main.rs",
+            "    ◦ then fix it",
+            "  1. test",
+            "  ▎ note",
+            &format!("  {}", "─".repeat(40)),
+            "  ╭─ rust",
+            "  │ fn main() {}",
+            "  ╰─",
+            "  Done.",
+        ]);
+
+        // A block still being streamed has no bottom border yet
+        assert_eq!(plain(&render("```\nlet x = 1;", 80)), ["  ╭─", "  │ let x = 1;"]);
+    }
+
+    #[test]
+    fn test_inline() {
+        let spans = inline("a **bold** and *it* or snake_case_name, see [docs](https://x.rs)", Style::default());
+        let text: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, ["a ", "bold", " and ", "it", " or snake_case_name, see ", "docs", " (https://x.rs)"]);
+        assert!(spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert!(spans[3].style.add_modifier.contains(Modifier::ITALIC));
+        assert_eq!(inline("2 * 3 * 4", Style::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let lines = render("- one two three four five, six", 16);
+        assert_eq!(plain(&lines), ["  • one two", "    three four", "    five, six"]);
+    }
+}
@@ -3,7 +3,9 @@ use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use ruvector_core::{DistanceMetric, SearchQuery, VectorDB as RuVectorDB, VectorEntry};
 use ruvector_core::types::{DbOptions, HnswConfig};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::hash_embedding;
 
 use super::types::{ImportanceLevel, KeyMemoryChunk, MemoryCategory, RoutineMemoryChunk};
 
@@ -20,14 +22,17 @@ pub struct MemoryManager {
     key_db: Option<RuVectorDB>,
     key_chunks: Vec<KeyMemoryChunk>,
 
-    // Local embedding model
-    embedding_model: TextEmbedding,
+    // Local embedding model (None = offline hashing fallback)
+    embedding_model: Option<TextEmbedding>,
+    /// Name of the embeddings in use, recorded with the stored vectors
+    embedder_name: String,
     dimension: usize,
 }
 
 impl MemoryManager {
-    /// Create a new memory manager for an agent
-    pub fn new(agent_name: &str, embedding_model_name: &str) -> Result<Self> {
+    /// Create a new memory manager for an agent. With `fallback`, a model
+    /// that can't be loaded is replaced by offline hashing embeddings.
+    pub fn new(agent_name: &str, embedding_model_name: &str, fallback: bool) -> Result<Self> {
         let memory_dir = dirs::home_dir()
             .ok_or_else(|| anyhow!("Cannot determine home directory"))?
             .join(".agent-t")
@@ -38,16 +43,16 @@ impl MemoryManager {
         std::fs::create_dir_all(&memory_dir)?;
 
         // Initialize local embedding model
-        let model = match embedding_model_name {
-            "BAAI/bge-small-en-v1.5" => EmbeddingModel::BGESmallENV15,
-            "BAAI/bge-base-en-v1.5" => EmbeddingModel::BGEBaseENV15,
-            "sentence-transformers/all-MiniLM-L6-v2" => EmbeddingModel::AllMiniLML6V2,
+        let (model, model_name) = match embedding_model_name {
+            "BAAI/bge-small-en-v1.5" => (EmbeddingModel::BGESmallENV15, embedding_model_name),
+            "BAAI/bge-base-en-v1.5" => (EmbeddingModel::BGEBaseENV15, embedding_model_name),
+            "sentence-transformers/all-MiniLM-L6-v2" => (EmbeddingModel::AllMiniLML6V2, embedding_model_name),
             _ => {
-                eprintln!(
-                    "Warning: Unknown model '{}', defaulting to BAAI/bge-small-en-v1.5",
+                tracing::warn!(
+                    "Unknown model '{}', defaulting to BAAI/bge-small-en-v1.5",
                     embedding_model_name
                 );
-                EmbeddingModel::BGESmallENV15
+                (EmbeddingModel::BGESmallENV15, "BAAI/bge-small-en-v1.5")
             }
         };
 
@@ -70,7 +75,29 @@ impl MemoryManager {
             .with_cache_dir(cache_dir)
             .with_show_download_progress(true);
 
-        let embedding_model = TextEmbedding::try_new(init_options)?;
+        let embedding_model = match TextEmbedding::try_new(init_options) {
+            Ok(model) => Some(model),
+            Err(e) if fallback => {
+                tracing::warn!(
+                    "Could not load embedding model '{}' ({}); using offline hashing embeddings, which match on shared words only",
+                    embedding_model_name,
+                    e
+                );
+                None
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to load embedding model '{}': {} (it is downloaded to ~/.agent-t/fastembed_cache on first use; check network access or pass --embedding-fallback)",
+                    embedding_model_name,
+                    e
+                ));
+            }
+        };
+
+        let embedder_name = match embedding_model {
+            Some(_) => model_name.to_string(),
+            None => hash_embedding::NAME.to_string(),
+        };
 
         Ok(Self {
             agent_name: agent_name.to_string(),
@@ -80,6 +107,7 @@ impl MemoryManager {
             key_db: None,
             key_chunks: Vec::new(),
             embedding_model,
+            embedder_name,
             dimension,
         })
     }
@@ -156,13 +184,86 @@ impl MemoryManager {
             self.key_chunks = Vec::new();
         }
 
+        // Vectors from one embedding model can't be searched with another, so
+        // memories stored with different (or unrecorded) embeddings are re-embedded
+        let embedder_path = self.memory_dir.join("embedder");
+        let built_with = std::fs::read_to_string(&embedder_path).ok();
+        if built_with.as_deref().map(str::trim) != Some(self.embedder_name.as_str()) {
+            if !self.routine_chunks.is_empty() || !self.key_chunks.is_empty() {
+                tracing::warn!(
+                    "Memories were stored with '{}' embeddings; re-embedding {} of them with '{}'",
+                    built_with.as_deref().map(str::trim).unwrap_or("unrecorded"),
+                    self.routine_chunks.len() + self.key_chunks.len(),
+                    self.embedder_name
+                );
+                self.rebuild_vectors()?;
+            }
+            std::fs::write(&embedder_path, &self.embedder_name)?;
+        }
+
         Ok(())
     }
 
+    /// Replace both vector databases with fresh ones holding the stored
+    /// memories embedded with the current embeddings
+    fn rebuild_vectors(&mut self) -> Result<()> {
+        let routine_texts: Vec<String> = self.routine_chunks.iter().map(|c| c.content.clone()).collect();
+        let key_texts: Vec<String> = self.key_chunks.iter().map(|c| c.content.clone()).collect();
+        let routine_vectors = self.embed_texts(&routine_texts)?;
+        let key_vectors = self.embed_texts(&key_texts)?;
+
+        // Close the old databases before removing their files
+        self.routine_db = None;
+        self.key_db = None;
+        self.routine_db = Some(self.fresh_db("routine.db", 10000, routine_vectors)?);
+        self.key_db = Some(self.fresh_db("key.db", 1000, key_vectors)?);
+        Ok(())
+    }
+
+    /// An empty vector database in `file_name` (replacing any existing one),
+    /// filled with `vectors` under their positions as ids
+    fn fresh_db(&self, file_name: &str, max_elements: usize, vectors: Vec<Vec<f32>>) -> Result<RuVectorDB> {
+        let path = self.memory_dir.join(file_name);
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        } else if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+
+        let options = DbOptions {
+            dimensions: self.dimension,
+            distance_metric: DistanceMetric::Cosine,
+            storage_path: path.to_string_lossy().to_string(),
+            hnsw_config: Some(HnswConfig {
+                m: 16,
+                ef_construction: 200,
+                ef_search: 100,
+                max_elements,
+            }),
+            quantization: None,
+        };
+        let db = RuVectorDB::new(options)?;
+        if !vectors.is_empty() {
+            let entries = vectors
+                .into_iter()
+                .enumerate()
+                .map(|(idx, vector)| VectorEntry {
+                    id: Some(idx.to_string()),
+                    vector,
+                    metadata: None,
+                })
+                .collect();
+            db.insert_batch(entries)?;
+        }
+        Ok(db)
+    }
+
     /// Generate embeddings locally
     fn embed_texts(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        let embeddings = self.embedding_model.embed(texts.to_vec(), None)?;
-        Ok(embeddings)
+        match self.embedding_model {
+            Some(ref mut model) => Ok(model.embed(texts.to_vec(), None)?),
+            None => Ok(texts.iter().map(|text| hash_embedding::embed(text, self.dimension)).collect()),
+        }
     }
 
     /// Store a routine memory (automatic, from chat)
@@ -219,11 +320,12 @@ impl MemoryManager {
         Ok(())
     }
 
-    /// Search routine memories
+    /// Search routine memories, only from `project` when given
     pub fn search_routine(
         &mut self,
         query: &str,
         top_k: usize,
+        project: Option<&str>,
     ) -> Result<Vec<(RoutineMemoryChunk, f32)>> {
         // Generate query embedding first (requires mutable borrow)
         let query_embedding = self.embed_texts(&[query.to_string()])?;
@@ -234,9 +336,10 @@ impl MemoryManager {
             .as_ref()
             .ok_or_else(|| anyhow!("Routine memory DB not initialized"))?;
 
+        // Get more results for filtering
         let search_query = SearchQuery {
             vector: query_embedding[0].clone(),
-            k: top_k,
+            k: if project.is_some() { top_k * 3 } else { top_k },
             filter: None,
             ef_search: None,
         };
@@ -247,20 +350,32 @@ impl MemoryManager {
         for result in results {
             if let Ok(idx) = result.id.parse::<usize>()
                 && idx < self.routine_chunks.len() {
-                    memories.push((self.routine_chunks[idx].clone(), result.score));
+                    let chunk = &self.routine_chunks[idx];
+                    if let Some(project) = project
+                        && !chunk.in_project(project) {
+                            continue;
+                        }
+
+                    memories.push((chunk.clone(), result.score));
+
+                    if memories.len() >= top_k {
+                        break;
+                    }
                 }
         }
 
         Ok(memories)
     }
 
-    /// Search key memories with optional filtering
+    /// Search key memories with optional filtering; with `project`, only
+    /// memories that apply there (its own and those not tied to a project)
     pub fn search_key(
         &mut self,
         query: &str,
         top_k: usize,
         categories: Option<Vec<MemoryCategory>>,
         min_importance: Option<ImportanceLevel>,
+        project: Option<&str>,
     ) -> Result<Vec<(KeyMemoryChunk, f32)>> {
         // Generate query embedding first (requires mutable borrow)
         let query_embedding = self.embed_texts(&[query.to_string()])?;
@@ -299,6 +414,12 @@ impl MemoryManager {
                             continue;
                         }
 
+                    // Apply project filter
+                    if let Some(project) = project
+                        && !chunk.in_project(project) {
+                            continue;
+                        }
+
                     memories.push((chunk.clone(), result.score));
 
                     if memories.len() >= top_k {
@@ -310,6 +431,49 @@ impl MemoryManager {
         Ok(memories)
     }
 
+    /// Key and routine memories matching a query, ranked together by
+    /// relevance and formatted for display (/search)
+    pub fn search_report(&mut self, query: &str, top_k: usize) -> Result<String> {
+        let key = self.search_key(query, top_k, None, None, None)?;
+        let routine = self.search_routine(query, top_k, None)?;
+
+        let mut entries: Vec<(f32, String, &str)> = key
+            .iter()
+            .map(|(chunk, score)| {
+                let heading = format!(
+                    "key · {} · {} importance · {}",
+                    chunk.category,
+                    chunk.importance,
+                    chunk.timestamp.format("%Y-%m-%d %H:%M")
+                );
+                (1.0 - *score, heading, chunk.content.as_str())
+            })
+            .chain(routine.iter().map(|(chunk, score)| {
+                let heading = format!(
+                    "routine · {} · {} · {}",
+                    chunk.role,
+                    chunk.timestamp.format("%Y-%m-%d %H:%M"),
+                    chunk.working_directory
+                );
+                (1.0 - *score, heading, chunk.content.as_str())
+            }))
+            .collect();
+        // Searches return cosine distances; rank and show similarity, most similar first
+        entries.sort_by(|a, b| b.0.total_cmp(&a.0));
+        entries.truncate(top_k);
+
+        if entries.is_empty() {
+            return Ok(format!("No memories found for '{}'", query));
+        }
+
+        let mut report = format!("Memories matching '{}':\n", query);
+        for (idx, (score, heading, content)) in entries.iter().enumerate() {
+            let content = crate::terminal::truncate_chars(&content.replace('\n', " "), 300);
+            report.push_str(&format!("\n{}. [{:.2}] {}\n   {}\n", idx + 1, score, heading, content));
+        }
+        Ok(report)
+    }
+
     /// Save routine chunks metadata
     fn save_routine_chunks(&self) -> Result<()> {
         let path = self.memory_dir.join("routine_chunks.json");
@@ -335,12 +499,12 @@ impl MemoryManager {
         }
     }
 
-    /// Get the most recent session summary
-    pub fn get_last_session_summary(&self) -> Option<KeyMemoryChunk> {
+    /// Get the most recent session summary for a project
+    pub fn get_last_session_summary(&self, project: &str) -> Option<KeyMemoryChunk> {
         // Find the most recent SessionSummary
         self.key_chunks
             .iter()
-            .filter(|chunk| chunk.category == MemoryCategory::SessionSummary)
+            .filter(|chunk| chunk.category == MemoryCategory::SessionSummary && chunk.in_project(project))
             .max_by_key(|chunk| chunk.timestamp)
             .cloned()
     }
@@ -349,12 +513,12 @@ impl MemoryManager {
     pub fn flush(&self) -> Result<()> {
         // Save routine chunks
         if let Err(e) = self.save_routine_chunks() {
-            eprintln!("[WARN] Failed to flush routine memory: {}", e);
+            tracing::warn!("Failed to flush routine memory: {}", e);
         }
 
         // Save key chunks
         if let Err(e) = self.save_key_chunks() {
-            eprintln!("[WARN] Failed to flush key memory: {}", e);
+            tracing::warn!("Failed to flush key memory: {}", e);
         }
 
         // Note: VectorDB uses memory-mapped files that auto-persist,
@@ -363,6 +527,14 @@ impl MemoryManager {
     }
 }
 
+/// Project key memories are filed under: the root of the git work tree
+/// containing `dir`, or `dir` itself outside a repository
+pub fn project_scope(dir: &str) -> String {
+    crate::git::toplevel(Path::new(dir))
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_else(|_| dir.to_string())
+}
+
 // Manual Debug implementation since TextEmbedding and VectorDB don't implement Debug
 impl std::fmt::Debug for MemoryManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -371,6 +543,7 @@ impl std::fmt::Debug for MemoryManager {
             .field("memory_dir", &self.memory_dir)
             .field("routine_chunks", &self.routine_chunks.len())
             .field("key_chunks", &self.key_chunks.len())
+            .field("embedder_name", &self.embedder_name)
             .field("dimension", &self.dimension)
             .finish()
     }
@@ -381,9 +554,9 @@ impl Drop for MemoryManager {
     fn drop(&mut self) {
         // Flush memory to disk on drop
         if let Err(e) = self.flush() {
-            eprintln!("[ERROR] Failed to flush memory on shutdown: {}", e);
+            tracing::error!("Failed to flush memory on shutdown: {}", e);
         } else {
-            eprintln!("[INFO] Memory flushed successfully ({} routine, {} key memories)",
+            tracing::info!("Memory flushed successfully ({} routine, {} key memories)",
                 self.routine_chunks.len(), self.key_chunks.len());
         }
     }
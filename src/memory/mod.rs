@@ -1,7 +1,7 @@
 pub mod manager;
 pub mod types;
 
-pub use manager::MemoryManager;
+pub use manager::{project_scope, MemoryManager};
 pub use types::{
     ImportanceLevel, KeyMemoryChunk, MemoryCategory,
 };
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// A chunk of routine memory (conversation history)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +26,9 @@ pub struct KeyMemoryChunk {
     pub importance: ImportanceLevel,
     pub tags: Vec<String>,
     pub related_files: Vec<String>,
+    /// Project the memory belongs to (see `project_scope`); None = all projects
+    #[serde(default)]
+    pub project: Option<String>,
 }
 
 /// Categories for key memories
@@ -39,6 +43,14 @@ pub enum MemoryCategory {
     SessionSummary,   // "Currently working on X, next steps are Y" - for session continuity
 }
 
+impl MemoryCategory {
+    /// Whether memories in this category are about the user rather than a
+    /// project, and so apply everywhere
+    pub fn is_personal(&self) -> bool {
+        matches!(self, MemoryCategory::UserPreference | MemoryCategory::PersonalInfo)
+    }
+}
+
 impl std::fmt::Display for MemoryCategory {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -74,6 +86,11 @@ impl std::fmt::Display for ImportanceLevel {
 }
 
 impl RoutineMemoryChunk {
+    /// Whether this message was exchanged inside `project`
+    pub fn in_project(&self, project: &str) -> bool {
+        Path::new(&self.working_directory).starts_with(project)
+    }
+
     /// Extract context tags from content
     pub fn extract_tags(content: &str, tool_name: Option<&str>) -> Vec<String> {
         let mut tags = Vec::new();
@@ -112,11 +129,13 @@ impl KeyMemoryChunk {
         tags: Vec<String>,
         related_files: Vec<String>,
         session_id: Option<String>,
+        project: Option<String>,
     ) -> Self {
         Self {
             memory_id: uuid::Uuid::new_v4().to_string(),
             timestamp: Utc::now(),
             session_id,
+            project: if category.is_personal() { None } else { project },
             category,
             content,
             importance,
@@ -124,4 +143,38 @@ impl KeyMemoryChunk {
             related_files,
         }
     }
+
+    /// Whether this memory applies in `project`
+    pub fn in_project(&self, project: &str) -> bool {
+        self.project.as_deref().is_none_or(|p| p == project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_memory(category: MemoryCategory, project: &str) -> KeyMemoryChunk {
+        KeyMemoryChunk::new(
+            "fact".to_string(),
+            category,
+            ImportanceLevel::Medium,
+            Vec::new(),
+            Vec::new(),
+            None,
+            Some(project.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_key_memory_project_scope() {
+        let fact = key_memory(MemoryCategory::ProjectFact, "/src/app");
+        assert!(fact.in_project("/src/app"));
+        assert!(!fact.in_project("/src/other"));
+
+        // Facts about the user follow them into every project
+        let preference = key_memory(MemoryCategory::UserPreference, "/src/app");
+        assert_eq!(preference.project, None);
+        assert!(preference.in_project("/src/other"));
+    }
 }
@@ -11,6 +11,8 @@ pub struct GrantedPermissions {
     skip_confirmations: bool,
     /// If true, only simulate tool execution (dry-run mode)
     dry_run: bool,
+    /// If true, only REVIEW_TOOLS are allowed, regardless of other grants
+    review: bool,
 }
 
 impl GrantedPermissions {
@@ -29,6 +31,7 @@ impl GrantedPermissions {
             all_granted,
             skip_confirmations,
             dry_run,
+            review: false,
         }
     }
 
@@ -39,11 +42,22 @@ impl GrantedPermissions {
             all_granted: true,
             skip_confirmations: false,
             dry_run: false,
+            review: false,
         }
     }
 
+    /// Restrict to read-only review tools. Overrides --grant, --grant-all and --yes.
+    pub fn with_review_mode(mut self) -> Self {
+        self.review = true;
+        self
+    }
+
     /// Check if a tool is granted permission
     pub fn is_granted(&self, tool_name: &str) -> bool {
+        if self.review {
+            return REVIEW_TOOLS.contains(&tool_name.to_lowercase().as_str());
+        }
+
         if self.all_granted {
             return true;
         }
@@ -65,9 +79,16 @@ impl GrantedPermissions {
         self.dry_run
     }
 
+    /// Check if we're in read-only review mode
+    pub fn is_review(&self) -> bool {
+        self.review
+    }
+
     /// Get a summary of granted permissions for display
     pub fn summary(&self) -> String {
-        if self.all_granted {
+        if self.review {
+            "Review mode (read-only tools only)".to_string()
+        } else if self.all_granted {
             "All tools granted".to_string()
         } else if self.tools.is_empty() {
             "No tools granted".to_string()
@@ -82,27 +103,41 @@ impl GrantedPermissions {
 /// Define tool categories for convenience
 pub const READ_ONLY_TOOLS: &[&str] = &[
     "read_file",
+    "summarize_file",
     "list_dir",
     "grep",
     "glob",
+    "diff_files",
+    "git_status",
+    "git_diff",
+    "json_query",
+    "scratch_read",
+    "change_directory",
     "bash_status",
     "bash_output",
     "bash_list",
     "web_fetch",
     "web_search",
+    "crate_docs",
     "search_routine_memory",
     "search_key_memory",
+    "vecdb_search",
 ];
 
 pub const WRITE_TOOLS: &[&str] = &[
     "write_file",
     "edit_file",
+    "edit_markdown_section",
     "store_key_memory",
+    "add_dependency",
+    "remove_dependency",
 ];
 
 pub const EXECUTE_TOOLS: &[&str] = &[
     "bash",
     "bash_kill",
+    "run_tests",
+    "cargo_check",
 ];
 
 pub const RUST_ANALYZER_TOOLS: &[&str] = &[
@@ -117,6 +152,35 @@ pub const RUST_ANALYZER_TOOLS: &[&str] = &[
     "ra_format",
 ];
 
+/// Tools allowed in --review mode: nothing here can modify files or run commands
+pub const REVIEW_TOOLS: &[&str] = &[
+    "read_file",
+    "summarize_file",
+    "list_dir",
+    "grep",
+    "glob",
+    "diff_files",
+    "git_status",
+    "git_diff",
+    "json_query",
+    "scratch_read",
+    "change_directory",
+    "web_fetch",
+    "web_search",
+    "crate_docs",
+    "search_routine_memory",
+    "search_key_memory",
+    "vecdb_search",
+    "math_calc",
+    "ra_diagnostics",
+    "ra_goto_definition",
+    "ra_find_references",
+    "ra_hover",
+    "ra_symbols",
+    "ra_completion",
+    "ra_code_actions",
+];
+
 /// Expand tool categories to individual tool names
 pub fn expand_tool_categories(grants: Vec<String>) -> Vec<String> {
     let mut expanded = Vec::new();
@@ -144,6 +208,7 @@ pub fn expand_tool_categories(grants: Vec<String>) -> Vec<String> {
                 expanded.extend(RUST_ANALYZER_TOOLS.iter().map(|s| s.to_string()));
                 expanded.push("spawn_agent".to_string());
                 expanded.push("math_calc".to_string());
+                expanded.push("scratch_write".to_string());
             }
             _ => {
                 // Treat as individual tool name
@@ -182,6 +247,18 @@ mod tests {
         assert!(perms.is_granted("bash"));
     }
 
+    #[test]
+    fn test_review_mode_overrides_grant_all() {
+        let perms = GrantedPermissions::new(vec!["bash".to_string()], true, true, false).with_review_mode();
+
+        assert!(perms.is_granted("read_file"));
+        assert!(perms.is_granted("ra_hover"));
+        assert!(!perms.is_granted("bash"));
+        assert!(!perms.is_granted("write_file"));
+        assert!(!perms.is_granted("edit_file"));
+        assert!(!perms.is_granted("ra_rename"));
+    }
+
     #[test]
     fn test_expand_categories() {
         let expanded = expand_tool_categories(vec!["read-only".to_string()]);
@@ -0,0 +1,51 @@
+//! Planning before acting (--plan)
+//!
+//! The first request of a conversation is answered with a numbered plan and
+//! no tools. The plan is shown for approval; once approved the agent carries
+//! it out, and until then each new message asks for a revised plan.
+
+/// Added to the user's request for the planning turn
+const PLAN_REQUEST: &str = "[Plan mode] Do not use any tools or change anything yet. Reply only with a numbered plan \
+for the request above: one line per step, naming the files you expect to read or change and how you will check the result. \
+If the request is ambiguous, state the assumptions the plan makes.";
+
+/// User message that starts carrying out an approved plan
+pub const APPROVED_PROMPT: &str = "The plan is approved. Carry it out step by step now, using the tools. \
+If a step turns out to be wrong, adjust it and say why.";
+
+/// Reply when the plan was rejected
+pub const REJECTED_REPLY: &str = "Plan rejected; nothing was changed. Describe what to change and a revised plan will be proposed.";
+
+/// The user's request with the instructions for a planning turn
+pub fn plan_request(user_input: &str) -> String {
+    format!("{}\n\n{}", user_input, PLAN_REQUEST)
+}
+
+/// Number of numbered steps ("1." or "1)") in a plan
+pub fn step_count(plan: &str) -> usize {
+    plan.lines()
+        .map(|line| line.trim_start().trim_start_matches(['*', '#', ' ']))
+        .filter(|line| {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            digits > 0 && matches!(line[digits..].chars().next(), Some('.') | Some(')'))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_count() {
+        let plan = "Here is the plan:\n\n1. Read src/main.rs\n2) Add the flag\n   - wire it up\n**3.** Run the tests\n10. Done\n";
+        assert_eq!(step_count(plan), 4);
+        assert_eq!(step_count("No steps here, version 2.0 only"), 0);
+    }
+
+    #[test]
+    fn test_plan_request() {
+        let request = plan_request("Add a --plan flag");
+        assert!(request.starts_with("Add a --plan flag\n\n[Plan mode]"));
+    }
+}
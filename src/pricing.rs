@@ -0,0 +1,106 @@
+//! Token pricing for cost estimates
+//!
+//! Prices are read from `~/.agent-t/pricing.json`, a map from model name to
+//! per-1k-token prices:
+//!
+//! ```json
+//! { "gpt-4o": { "prompt_per_1k": 0.0025, "completion_per_1k": 0.01 } }
+//! ```
+//!
+//! A key also matches model names it is a prefix of (`gpt-4o` covers
+//! `gpt-4o-2024-08-06`), the longest key winning. Models without a price are
+//! treated as local and cost nothing.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Name of the pricing file in `~/.agent-t/`
+pub const PRICING_FILE: &str = "pricing.json";
+
+/// Price of a model in dollars per 1000 tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub prompt_per_1k: f64,
+    pub completion_per_1k: f64,
+}
+
+impl ModelPrice {
+    /// Cost in dollars for the given token counts
+    pub fn cost(&self, prompt_tokens: usize, completion_tokens: usize) -> f64 {
+        (prompt_tokens as f64 / 1000.0) * self.prompt_per_1k
+            + (completion_tokens as f64 / 1000.0) * self.completion_per_1k
+    }
+}
+
+/// Per-model prices
+#[derive(Debug, Default)]
+pub struct PricingTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl PricingTable {
+    /// Load the pricing file; a missing or invalid file gives an empty table
+    pub fn load() -> Self {
+        let Some(path) = pricing_path() else {
+            return Self::default();
+        };
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match serde_json::from_str(&json) {
+            Ok(prices) => Self { prices },
+            Err(e) => {
+                eprintln!("Warning: Ignoring {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Price for a model, by exact name or longest matching prefix
+    pub fn price_for(&self, model: &str) -> Option<ModelPrice> {
+        if let Some(price) = self.prices.get(model) {
+            return Some(*price);
+        }
+        self.prices
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, price)| *price)
+    }
+}
+
+/// Short cost label for the status bar: "local" for unpriced models
+pub fn format_cost(price: Option<ModelPrice>, prompt_tokens: usize, completion_tokens: usize) -> String {
+    match price {
+        Some(price) => format!("${:.4}", price.cost(prompt_tokens, completion_tokens)),
+        None => "local".to_string(),
+    }
+}
+
+/// Location of the pricing file
+pub fn pricing_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".agent-t").join(PRICING_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_lookup_and_cost() {
+        let mut prices = HashMap::new();
+        prices.insert("gpt-4o".to_string(), ModelPrice { prompt_per_1k: 0.0025, completion_per_1k: 0.01 });
+        prices.insert("gpt-4o-mini".to_string(), ModelPrice { prompt_per_1k: 0.00015, completion_per_1k: 0.0006 });
+        let table = PricingTable { prices };
+
+        assert_eq!(table.price_for("gpt-4o-2024-08-06").unwrap().prompt_per_1k, 0.0025);
+        assert_eq!(table.price_for("gpt-4o-mini-2024-07-18").unwrap().prompt_per_1k, 0.00015);
+        assert!(table.price_for("llama3.1:8b").is_none());
+
+        let price = table.price_for("gpt-4o").unwrap();
+        assert!((price.cost(2000, 1000) - 0.015).abs() < 1e-9);
+        assert_eq!(format_cost(Some(price), 2000, 1000), "$0.0150");
+        assert_eq!(format_cost(None, 2000, 1000), "local");
+    }
+}
@@ -1,8 +1,9 @@
+use crate::tools::shell;
 use std::collections::HashMap;
 use std::process::Stdio;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
+use tokio::process::Child;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 
@@ -55,19 +56,20 @@ impl ProcessManager {
         &self,
         command: String,
         working_dir: Option<String>,
+        env: HashMap<String, String>,
     ) -> Result<String, String> {
         // Generate unique ID
         let id = uuid::Uuid::new_v4().to_string();
 
         // Create the command
-        let mut cmd = Command::new("bash");
-        cmd.arg("-c").arg(&command);
+        let mut cmd = shell::command(&command);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
         if let Some(ref dir) = working_dir {
             cmd.current_dir(dir);
         }
+        cmd.envs(&env);
 
         // Spawn the process
         let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn process: {}", e))?;
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command};
@@ -79,6 +79,10 @@ pub struct RustAnalyzerClient {
     initialized: Arc<RwLock<bool>>,
     /// Received diagnostics
     diagnostics: Arc<RwLock<HashMap<Url, Vec<Diagnostic>>>>,
+    /// Cleared when the server's stdout closes (process exited or crashed)
+    alive: Arc<AtomicBool>,
+    /// Versions of documents currently open on the server
+    open_documents: Arc<RwLock<HashMap<Url, i32>>>,
 }
 
 impl RustAnalyzerClient {
@@ -108,6 +112,8 @@ impl RustAnalyzerClient {
             workspace_root,
             initialized: Arc::new(RwLock::new(false)),
             diagnostics: Arc::new(RwLock::new(HashMap::new())),
+            alive: Arc::new(AtomicBool::new(true)),
+            open_documents: Arc::new(RwLock::new(HashMap::new())),
         };
 
         // Spawn reader task
@@ -124,97 +130,128 @@ impl RustAnalyzerClient {
         let pending = Arc::clone(&self.pending);
         let initialized = Arc::clone(&self.initialized);
         let diagnostics = Arc::clone(&self.diagnostics);
+        let alive = Arc::clone(&self.alive);
 
         tokio::spawn(async move {
-            let mut reader = BufReader::new(stdout);
-            let mut headers = Vec::new();
-            let mut content = Vec::new();
+            Self::read_messages(stdout, &pending, &initialized, &diagnostics).await;
 
+            // The server is gone: fail in-flight requests now instead of letting them time out
+            alive.store(false, Ordering::SeqCst);
+            for (_, pending_req) in pending.write().await.drain() {
+                let _ = pending_req.tx.send(Err(anyhow!("rust-analyzer exited")));
+            }
+        });
+    }
+
+    /// Read and dispatch messages until stdout reaches EOF or a read fails
+    async fn read_messages(
+        stdout: ChildStdout,
+        pending: &RwLock<HashMap<i32, PendingRequest>>,
+        initialized: &RwLock<bool>,
+        diagnostics: &RwLock<HashMap<Url, Vec<Diagnostic>>>,
+    ) {
+        let mut reader = BufReader::new(stdout);
+        let mut headers = Vec::new();
+        let mut content = Vec::new();
+
+        loop {
+            // Read headers
+            headers.clear();
             loop {
-                // Read headers
-                headers.clear();
-                loop {
-                    let mut line = String::new();
-                    match reader.read_line(&mut line).await {
-                        Ok(0) => return, // EOF
-                        Ok(_) => {
-                            if line == "\r\n" || line == "\n" {
-                                break; // End of headers
-                            }
-                            headers.push(line);
-                        }
-                        Err(e) => {
-                            eprintln!("Error reading header: {}", e);
-                            return;
+                let mut line = String::new();
+                match reader.read_line(&mut line).await {
+                    Ok(0) => return, // EOF
+                    Ok(_) => {
+                        if line == "\r\n" || line == "\n" {
+                            break; // End of headers
                         }
+                        headers.push(line);
                     }
-                }
-
-                // Parse Content-Length
-                let mut content_length = 0;
-                for header in &headers {
-                    if let Some(len_str) = header.strip_prefix("Content-Length: ") {
-                        content_length = len_str.trim().parse().unwrap_or(0);
+                    Err(e) => {
+                        tracing::warn!("Error reading rust-analyzer header: {}", e);
+                        return;
                     }
                 }
+            }
 
-                if content_length == 0 {
-                    continue;
+            // Parse Content-Length
+            let mut content_length = 0;
+            for header in &headers {
+                if let Some(len_str) = header.strip_prefix("Content-Length: ") {
+                    content_length = len_str.trim().parse().unwrap_or(0);
                 }
+            }
 
-                // Read content
-                content.clear();
-                content.resize(content_length, 0);
-                if let Err(e) = tokio::io::AsyncReadExt::read_exact(&mut reader, &mut content).await {
-                    eprintln!("Error reading content: {}", e);
-                    return;
-                }
+            if content_length == 0 {
+                continue;
+            }
 
-                // Parse message
-                let msg_str = String::from_utf8_lossy(&content);
-                match serde_json::from_str::<Message>(&msg_str) {
-                    Ok(Message::Response(response)) => {
-                        // Handle response
-                        let mut pending_map = pending.write().await;
-                        if let Some(pending_req) = pending_map.remove(&response.id) {
-                            let result = if let Some(error) = response.error {
-                                Err(anyhow!("LSP error: {}", error.message))
-                            } else {
-                                Ok(response.result.unwrap_or(Value::Null))
-                            };
-                            let _ = pending_req.tx.send(result);
-                        }
+            // Read content
+            content.clear();
+            content.resize(content_length, 0);
+            if let Err(e) = tokio::io::AsyncReadExt::read_exact(&mut reader, &mut content).await {
+                tracing::warn!("Error reading rust-analyzer content: {}", e);
+                return;
+            }
+
+            // Parse message
+            let msg_str = String::from_utf8_lossy(&content);
+            match serde_json::from_str::<Message>(&msg_str) {
+                Ok(Message::Response(response)) => {
+                    // Handle response
+                    let mut pending_map = pending.write().await;
+                    if let Some(pending_req) = pending_map.remove(&response.id) {
+                        let result = if let Some(error) = response.error {
+                            Err(anyhow!("LSP error: {}", error.message))
+                        } else {
+                            Ok(response.result.unwrap_or(Value::Null))
+                        };
+                        let _ = pending_req.tx.send(result);
                     }
-                    Ok(Message::Notification(notification)) => {
-                        // Handle notification
-                        match notification.method.as_str() {
-                            "initialized" => {
-                                *initialized.write().await = true;
-                            }
-                            "textDocument/publishDiagnostics" => {
-                                if let Some(params) = notification.params
-                                    && let Ok(diag_params) = serde_json::from_value::<PublishDiagnosticsParams>(params) {
-                                        diagnostics.write().await.insert(diag_params.uri, diag_params.diagnostics);
-                                    }
-                            }
-                            _ => {
-                                // Ignore other notifications
-                            }
+                }
+                Ok(Message::Notification(notification)) => {
+                    // Handle notification
+                    match notification.method.as_str() {
+                        "initialized" => {
+                            *initialized.write().await = true;
+                        }
+                        "textDocument/publishDiagnostics" => {
+                            if let Some(params) = notification.params
+                                && let Ok(diag_params) = serde_json::from_value::<PublishDiagnosticsParams>(params) {
+                                    diagnostics.write().await.insert(diag_params.uri, diag_params.diagnostics);
+                                }
+                        }
+                        _ => {
+                            // Ignore other notifications
                         }
-                    }
-                    Ok(Message::Request(_)) => {
-                        // We don't handle server requests for now
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to parse message: {} - {}", e, msg_str);
                     }
                 }
+                Ok(Message::Request(_)) => {
+                    // We don't handle server requests for now
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to parse rust-analyzer message: {} - {}", e, msg_str);
+                }
             }
-        });
+        }
+    }
+
+    /// Whether the rust-analyzer process is still running
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Workspace root the server was started for
+    pub fn workspace_root(&self) -> &PathBuf {
+        &self.workspace_root
     }
 
     /// Send a request and wait for response
     async fn send_request(&self, method: &str, params: Value) -> Result<Value> {
+        if !self.is_alive() {
+            return Err(anyhow!("rust-analyzer is not running"));
+        }
+
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
         let (tx, rx) = tokio::sync::oneshot::channel();
 
@@ -239,7 +276,12 @@ impl RustAnalyzerClient {
         {
             let mut stdin = self.stdin.lock().await;
             if let Some(ref mut stdin) = *stdin {
-                stdin.write_all(message.as_bytes()).await?;
+                if let Err(e) = stdin.write_all(message.as_bytes()).await {
+                    // Broken pipe: the process has exited
+                    self.alive.store(false, Ordering::SeqCst);
+                    self.pending.write().await.remove(&id);
+                    return Err(anyhow!("rust-analyzer is not running: {}", e));
+                }
                 stdin.flush().await?;
             } else {
                 return Err(anyhow!("stdin not available"));
@@ -389,6 +431,8 @@ impl RustAnalyzerClient {
 
     /// Open a document
     pub async fn did_open(&self, uri: Url, language_id: String, version: i32, text: String) -> Result<()> {
+        self.open_documents.write().await.insert(uri.clone(), version);
+
         let params = DidOpenTextDocumentParams {
             text_document: TextDocumentItem {
                 uri,
@@ -403,6 +447,8 @@ impl RustAnalyzerClient {
 
     /// Close a document
     pub async fn did_close(&self, uri: Url) -> Result<()> {
+        self.open_documents.write().await.remove(&uri);
+
         let params = DidCloseTextDocumentParams {
             text_document: TextDocumentIdentifier { uri },
         };
@@ -410,6 +456,42 @@ impl RustAnalyzerClient {
         self.send_notification("textDocument/didClose", serde_json::to_value(params)?).await
     }
 
+    /// Tell the server a file's contents changed on disk.
+    /// Open documents get a full-text `didChange`; closed ones a watched-file
+    /// change so the server re-reads them from disk.
+    pub async fn did_change(&self, uri: Url, text: String) -> Result<()> {
+        let version = {
+            let mut open = self.open_documents.write().await;
+            open.get_mut(&uri).map(|version| {
+                *version += 1;
+                *version
+            })
+        };
+
+        match version {
+            Some(version) => {
+                let params = DidChangeTextDocumentParams {
+                    text_document: VersionedTextDocumentIdentifier { uri, version },
+                    content_changes: vec![TextDocumentContentChangeEvent {
+                        range: None,
+                        range_length: None,
+                        text,
+                    }],
+                };
+                self.send_notification("textDocument/didChange", serde_json::to_value(params)?).await
+            }
+            None => {
+                let params = DidChangeWatchedFilesParams {
+                    changes: vec![FileEvent {
+                        uri,
+                        typ: FileChangeType::CHANGED,
+                    }],
+                };
+                self.send_notification("workspace/didChangeWatchedFiles", serde_json::to_value(params)?).await
+            }
+        }
+    }
+
     /// Go to definition
     pub async fn goto_definition(&self, uri: Url, position: Position) -> Result<Option<Vec<Location>>> {
         let params = GotoDefinitionParams {
@@ -18,6 +18,9 @@ pub struct SavedMessage {
 pub struct Session {
     pub id: String,
     pub name: Option<String>,
+    /// Tags set with /name, for finding the session later
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub model: String,
@@ -32,6 +35,7 @@ impl Session {
         Self {
             id: Uuid::new_v4().to_string(),
             name: None,
+            tags: Vec::new(),
             created_at: now,
             updated_at: now,
             model: model.to_string(),
@@ -168,6 +172,7 @@ impl SessionManager {
                         sessions.push(SessionSummary {
                             id: session.id,
                             name: session.name,
+                            tags: session.tags,
                             created_at: session.created_at,
                             updated_at: session.updated_at,
                             message_count: session.messages.len(),
@@ -182,6 +187,21 @@ impl SessionManager {
         Ok(sessions)
     }
 
+    /// Find a saved session by ID prefix, name or `#tag`
+    pub fn find_session(&self, name_or_id: &str) -> Result<SessionSummary> {
+        let sessions = self.list_sessions()?;
+        match match_sessions(&sessions, name_or_id).as_slice() {
+            [] => Err(anyhow!("Session '{}' not found", name_or_id)),
+            [session] => Ok((*session).clone()),
+            matches => Err(anyhow!(
+                "'{}' matches {} sessions: {}",
+                name_or_id,
+                matches.len(),
+                matches.iter().map(|s| format!("{} ({})", s.short_id(), s.label())).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
     /// Delete a session by ID
     pub fn delete_session(&mut self, session_id: &str) -> Result<()> {
         let session_path = self.sessions_dir.join(format!("{}.json", session_id));
@@ -216,28 +236,101 @@ impl SessionManager {
     }
 }
 
+/// Sessions a /load argument refers to, most specific match first: ID
+/// prefixes, then names equal to it, then names containing it. `#tag`
+/// matches sessions with that tag.
+fn match_sessions<'a>(sessions: &'a [SessionSummary], query: &str) -> Vec<&'a SessionSummary> {
+    let query = query.trim();
+    if let Some(tag) = query.strip_prefix('#') {
+        return sessions.iter().filter(|s| s.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))).collect();
+    }
+
+    let by_id: Vec<_> = sessions.iter().filter(|s| s.id.starts_with(query)).collect();
+    if !by_id.is_empty() {
+        return by_id;
+    }
+
+    let lower = query.to_lowercase();
+    let name = |s: &SessionSummary| s.name.as_deref().unwrap_or("").to_lowercase();
+    let exact: Vec<_> = sessions.iter().filter(|s| name(s) == lower).collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    sessions.iter().filter(|s| !lower.is_empty() && name(s).contains(&lower)).collect()
+}
+
 /// Summary of a session for listing
 #[derive(Debug, Clone)]
 pub struct SessionSummary {
     pub id: String,
     pub name: Option<String>,
+    pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub message_count: usize,
     pub model: String,
 }
 
+impl SessionSummary {
+    /// First 8 characters of the ID, as shown in listings
+    pub fn short_id(&self) -> &str {
+        &self.id[..8.min(self.id.len())]
+    }
+
+    /// Name and tags, e.g. "auth refactor #auth #api"
+    pub fn label(&self) -> String {
+        let mut label = self.name.clone().unwrap_or_else(|| "(unnamed)".to_string());
+        for tag in &self.tags {
+            label.push_str(&format!(" #{}", tag));
+        }
+        label
+    }
+}
+
 impl std::fmt::Display for SessionSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = self.name.as_deref().unwrap_or("(unnamed)");
         let date = self.updated_at.format("%Y-%m-%d %H:%M");
         write!(
             f,
             "{} - {} ({} messages) [{}]",
-            &self.id[..8],
-            name,
+            self.short_id(),
+            self.label(),
             self.message_count,
             date
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(id: &str, name: Option<&str>, tags: &[&str]) -> SessionSummary {
+        SessionSummary {
+            id: id.to_string(),
+            name: name.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            message_count: 0,
+            model: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_match_sessions() {
+        let sessions = vec![
+            summary("3f2a9c1e-0000", Some("Auth refactor"), &["auth"]),
+            summary("7b41d0aa-0000", Some("Auth refactor, part 2"), &["auth", "api"]),
+            summary("a0c3e511-0000", None, &[]),
+        ];
+        let ids = |query: &str| match_sessions(&sessions, query).iter().map(|s| s.short_id().to_string()).collect::<Vec<_>>();
+
+        assert_eq!(ids("7b4"), ["7b41d0aa"]);
+        assert_eq!(ids("auth refactor"), ["3f2a9c1e"]);
+        assert_eq!(ids("part 2"), ["7b41d0aa"]);
+        assert_eq!(ids("refactor").len(), 2);
+        assert_eq!(ids("#API"), ["7b41d0aa"]);
+        assert!(ids("payments").is_empty());
+    }
+}
@@ -4,6 +4,30 @@ use std::env;
 use std::path::Path;
 
 use crate::git::GitInfo;
+use crate::tools::{agent_ignore, shell};
+
+/// Maximum number of top-level entries listed in {{project_overview}}
+const MAX_OVERVIEW_ENTRIES: usize = 40;
+
+/// Marker files identifying a project type, most specific first
+const PROJECT_MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust (Cargo)"),
+    ("package.json", "JavaScript/TypeScript (npm)"),
+    ("deno.json", "Deno"),
+    ("pyproject.toml", "Python (pyproject)"),
+    ("setup.py", "Python (setuptools)"),
+    ("requirements.txt", "Python (pip)"),
+    ("go.mod", "Go (modules)"),
+    ("pom.xml", "Java (Maven)"),
+    ("build.gradle", "JVM (Gradle)"),
+    ("build.gradle.kts", "JVM (Gradle)"),
+    ("Gemfile", "Ruby (Bundler)"),
+    ("composer.json", "PHP (Composer)"),
+    ("mix.exs", "Elixir (Mix)"),
+    ("CMakeLists.txt", "C/C++ (CMake)"),
+    ("meson.build", "C/C++ (Meson)"),
+    ("Makefile", "Make"),
+];
 
 /// Context for template variable replacement
 #[derive(Debug, Clone)]
@@ -38,6 +62,8 @@ impl TemplateContext {
         );
 
         variables.insert("os".to_string(), std::env::consts::OS.to_string());
+        variables.insert("arch".to_string(), std::env::consts::ARCH.to_string());
+        variables.insert("shell".to_string(), detect_shell());
         variables.insert("platform".to_string(),
             format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)
         );
@@ -54,12 +80,20 @@ impl TemplateContext {
 
         // Git information
         let git_info = GitInfo::detect(working_dir);
+        variables.insert("git_summary".to_string(), git_info.summary());
+        let git_status = if !git_info.is_repo {
+            "not a git repository"
+        } else if git_info.is_dirty {
+            "dirty"
+        } else {
+            "clean"
+        };
+        variables.insert("git_status".to_string(), git_status.to_string());
         variables.insert("git_branch".to_string(),
             git_info.branch.unwrap_or_default()
         );
-        variables.insert("git_status".to_string(),
-            if git_info.is_dirty { "dirty".to_string() } else { "clean".to_string() }
-        );
+
+        variables.insert("project_overview".to_string(), project_overview(Path::new(working_dir)));
 
         // Agent information
         variables.insert("model".to_string(), model.to_string());
@@ -97,9 +131,64 @@ impl TemplateContext {
     }
 }
 
+/// Short project snapshot: detected project types and the top-level entries
+/// (hidden and `.agentignore`d entries are left out)
+fn project_overview(dir: &Path) -> String {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') || agent_ignore::is_ignored(&entry.path()) {
+                continue;
+            }
+            if entry.path().is_dir() {
+                dirs.push(format!("{}/", name));
+            } else {
+                files.push(name);
+            }
+        }
+    }
+    dirs.sort();
+    files.sort();
+
+    let mut kinds: Vec<&str> = PROJECT_MARKERS
+        .iter()
+        .filter(|(marker, _)| files.iter().any(|f| f == marker))
+        .map(|(_, kind)| *kind)
+        .collect();
+    kinds.dedup();
+    if files.iter().any(|f| f.ends_with(".sln") || f.ends_with(".csproj")) {
+        kinds.push(".NET");
+    }
+    let kind = if kinds.is_empty() { "unknown".to_string() } else { kinds.join(", ") };
+
+    let entries: Vec<String> = dirs.into_iter().chain(files).collect();
+    let mut listing = entries.iter().take(MAX_OVERVIEW_ENTRIES).cloned().collect::<Vec<_>>().join(", ");
+    if entries.len() > MAX_OVERVIEW_ENTRIES {
+        listing.push_str(&format!(", ... ({} more)", entries.len() - MAX_OVERVIEW_ENTRIES));
+    }
+    if listing.is_empty() {
+        listing = "(empty)".to_string();
+    }
+
+    format!("Project type: {}\nTop-level entries: {}", kind, listing)
+}
+
+/// Name of the shell the agent's commands run with (--shell or the detected one)
+fn detect_shell() -> String {
+    let program = shell::program();
+    Path::new(&program)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(str::to_string)
+        .unwrap_or(program)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::TempDir;
 
     #[test]
     fn test_template_rendering() {
@@ -152,4 +241,49 @@ mod tests {
 
         assert!(!ctx.get("date").unwrap().is_empty());
     }
+
+    #[test]
+    fn test_system_variables_exist() {
+        let ctx = TemplateContext::new("/test", "test-model", "test-agent");
+
+        assert_eq!(ctx.get("os"), Some(std::env::consts::OS));
+        assert_eq!(ctx.get("arch"), Some(std::env::consts::ARCH));
+        assert!(!ctx.get("shell").unwrap().is_empty());
+        assert!(!ctx.get("shell").unwrap().contains('/'));
+        assert!(!ctx.get("username").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_project_overview() {
+        let temp = TempDir::new("overview");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.join("package.json"), "").unwrap();
+        for i in 0..MAX_OVERVIEW_ENTRIES {
+            std::fs::write(dir.join(format!("file{:02}.txt", i)), "").unwrap();
+        }
+
+        let overview = project_overview(dir);
+        let mut lines = overview.lines();
+        assert_eq!(lines.next(), Some("Project type: Rust (Cargo), JavaScript/TypeScript (npm)"));
+        let listing = lines.next().unwrap();
+        assert!(listing.starts_with("Top-level entries: src/, Cargo.toml, file00.txt"));
+        assert!(listing.ends_with("... (3 more)"));
+        assert!(!listing.contains(".git"));
+    }
+
+    #[test]
+    fn test_git_variables_outside_repo() {
+        let temp = TempDir::new("template");
+        let dir = temp.path();
+        let ctx = TemplateContext::new(dir.to_str().unwrap(), "test-model", "test-agent");
+
+        if !GitInfo::detect(dir.to_str().unwrap()).is_repo {
+            assert_eq!(ctx.get("git_branch"), Some(""));
+            assert_eq!(ctx.get("git_status"), Some("not a git repository"));
+            assert_eq!(ctx.get("git_summary"), Some("Not a git repository"));
+        }
+    }
 }
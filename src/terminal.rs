@@ -4,6 +4,10 @@ use tokio::sync::mpsc::Sender;
 use crate::tui::TuiEvent;
 use crate::colors;
 use std::collections::HashMap;
+use std::sync::RwLock;
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
 
 /// Dangerous command patterns that should trigger confirmation
 const DANGEROUS_PATTERNS: &[&str] = &[
@@ -55,23 +59,105 @@ const DANGEROUS_PATHS: &[&str] = &[
     "/sys/",
 ];
 
-/// Check if a command is potentially dangerous
-pub fn is_dangerous_command(command: &str) -> Option<&'static str> {
-    let cmd_lower = command.to_lowercase();
+/// Name of the file in `~/.agent-t/` that extends or relaxes the patterns above
+pub const DANGEROUS_FILE: &str = "dangerous.json";
+
+/// User changes to the built-in patterns, read from `~/.agent-t/dangerous.json`:
+///
+/// ```json
+/// { "commands": ["terraform\\s+destroy", "kubectl\\s+delete"], "allow": ["rmdir"] }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DangerousConfig {
+    /// Extra command patterns (regexes, case-insensitive)
+    pub commands: Vec<String>,
+    /// Extra file path patterns (regexes)
+    pub paths: Vec<String>,
+    /// Built-in command or path patterns to stop flagging
+    pub allow: Vec<String>,
+}
+
+/// Built-in patterns merged with the user's config
+struct DangerousPatterns {
+    commands: Vec<&'static str>,
+    paths: Vec<&'static str>,
+    extra_commands: Vec<Regex>,
+    extra_paths: Vec<Regex>,
+}
+
+impl DangerousPatterns {
+    fn from_config(config: &DangerousConfig) -> Result<Self, regex::Error> {
+        let allowed = |pattern: &&str| !config.allow.iter().any(|a| a.eq_ignore_ascii_case(pattern));
+        Ok(Self {
+            commands: DANGEROUS_PATTERNS.iter().copied().filter(allowed).collect(),
+            paths: DANGEROUS_PATHS.iter().copied().filter(allowed).collect(),
+            extra_commands: config.commands.iter()
+                .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+                .collect::<Result<_, _>>()?,
+            extra_paths: config.paths.iter().map(|p| Regex::new(p)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn command_match(&self, command: &str) -> Option<String> {
+        let cmd_lower = command.to_lowercase();
+        self.commands.iter()
+            .find(|pattern| cmd_lower.contains(&pattern.to_lowercase()))
+            .map(|pattern| pattern.to_string())
+            .or_else(|| self.extra_commands.iter().find(|re| re.is_match(command)).map(|re| re.as_str().to_string()))
+    }
+
+    fn path_match(&self, path: &str) -> Option<String> {
+        self.paths.iter()
+            .find(|dangerous_path| path.contains(*dangerous_path))
+            .map(|dangerous_path| dangerous_path.to_string())
+            .or_else(|| self.extra_paths.iter().find(|re| re.is_match(path)).map(|re| re.as_str().to_string()))
+    }
+}
+
+lazy_static! {
+    static ref PATTERNS: RwLock<DangerousPatterns> = RwLock::new(
+        DangerousPatterns::from_config(&DangerousConfig::default()).expect("no user regexes")
+    );
+}
+
+/// Merge `~/.agent-t/dangerous.json` into the built-in patterns. A missing
+/// file keeps the defaults; an invalid one is reported and ignored.
+pub fn load_dangerous_patterns() {
+    let Some(path) = dirs::home_dir().map(|home| home.join(".agent-t").join(DANGEROUS_FILE)) else {
+        return;
+    };
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let patterns = serde_json::from_str::<DangerousConfig>(&json)
+        .map_err(|e| e.to_string())
+        .and_then(|config| DangerousPatterns::from_config(&config).map_err(|e| e.to_string()));
+    match patterns {
+        Ok(patterns) => {
+            if let Ok(mut guard) = PATTERNS.write() {
+                *guard = patterns;
+            }
+        }
+        Err(e) => eprintln!("Warning: Ignoring {}: {}", path.display(), e),
+    }
+}
 
-    DANGEROUS_PATTERNS.iter().find(|&pattern| cmd_lower.contains(&pattern.to_lowercase())).map(|v| v as _)
+/// Check if a command is potentially dangerous, returning the matched pattern
+pub fn is_dangerous_command(command: &str) -> Option<String> {
+    PATTERNS.read().ok()?.command_match(command)
 }
 
-/// Check if a file path is potentially dangerous
-pub fn is_dangerous_path(path: &str) -> Option<&'static str> {
-    DANGEROUS_PATHS.iter().find(|&dangerous_path| path.contains(dangerous_path)).map(|v| v as _)
+/// Check if a file path is potentially dangerous, returning the matched pattern
+pub fn is_dangerous_path(path: &str) -> Option<String> {
+    PATTERNS.read().ok()?.path_match(path)
 }
 
 /// Prompt the user for confirmation
 pub fn confirm(message: &str) -> io::Result<bool> {
-    print!("{} {} ", "⚠️".truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2),
-           message.truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2));
-    print!("{}", "[y/N] ".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2));
+    print!("{} {} ", "⚠️".custom_color(colors::theme().yellow),
+           message.custom_color(colors::theme().yellow));
+    print!("{}", "[y/N] ".custom_color(colors::theme().overlay0));
     io::stdout().flush()?;
 
     let mut input = String::new();
@@ -82,13 +168,13 @@ pub fn confirm(message: &str) -> io::Result<bool> {
 
 /// Print a user prompt
 pub fn print_user_prompt() {
-    print!("{} ", "You:".truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2).bold());
+    print!("{} ", "You:".custom_color(colors::theme().green).bold());
     io::stdout().flush().ok();
 }
 
 /// Print the assistant name
 pub fn print_assistant_prompt() {
-    print!("\n{} ", "agent-t:".truecolor(colors::BLUE.0, colors::BLUE.1, colors::BLUE.2).bold());
+    print!("\n{} ", "agent-t:".custom_color(colors::theme().blue).bold());
     io::stdout().flush().ok();
 }
 
@@ -100,19 +186,24 @@ pub fn print_assistant_response(response: &str) {
 /// Print a tool execution header
 pub fn print_tool_header(tool_name: &str) {
     println!("\n{} {}",
-             "[Tool:".truecolor(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2),
-             tool_name.truecolor(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2).bold());
+             "[Tool:".custom_color(colors::theme().mauve),
+             tool_name.custom_color(colors::theme().mauve).bold());
+}
+
+/// Shorten text to at most `max_chars` characters for display, adding "..."
+/// when cut. Counts chars rather than bytes, so multibyte text can't panic.
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
 }
 
 /// Print tool arguments
 pub fn print_tool_arg(key: &str, value: &str) {
-    let display_value = if value.len() > 100 {
-        format!("{}...", &value[..100])
-    } else {
-        value.to_string()
-    };
+    let display_value = truncate_chars(value, 100);
     println!("  {}: {}",
-             key.truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
+             key.custom_color(colors::theme().overlay0),
              display_value);
 }
 
@@ -120,38 +211,38 @@ pub fn print_tool_arg(key: &str, value: &str) {
 /// Print an info message
 pub fn print_info(message: &str) {
     println!("{} {}",
-             "ℹ️".truecolor(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2),
-             message.truecolor(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2));
+             "ℹ️".custom_color(colors::theme().sapphire),
+             message.custom_color(colors::theme().sapphire));
 }
 
 /// Print a success message
 pub fn print_success(message: &str) {
     println!("{} {}",
-             "✓".truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2),
-             message.truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2));
+             "✓".custom_color(colors::theme().green),
+             message.custom_color(colors::theme().green));
 }
 
 /// Print a warning message
 pub fn print_warning(message: &str) {
     println!("{} {}",
-             "⚠️".truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2),
-             message.truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2));
+             "⚠️".custom_color(colors::theme().yellow),
+             message.custom_color(colors::theme().yellow));
 }
 
 /// Print an error message
 pub fn print_error(message: &str) {
     eprintln!("{} {}",
-              "✗".truecolor(colors::RED.0, colors::RED.1, colors::RED.2),
-              message.truecolor(colors::RED.0, colors::RED.1, colors::RED.2));
+              "✗".custom_color(colors::theme().red),
+              message.custom_color(colors::theme().red));
 }
 
 /// Print the inspector URL
 pub fn print_inspector_url(port: u16) {
     println!(
         "\n{} Traffic Inspector: {}",
-        "🔍".truecolor(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2),
+        "🔍".custom_color(colors::theme().mauve),
         format!("http://localhost:{}", port)
-            .truecolor(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2)
+            .custom_color(colors::theme().mauve)
             .underline()
     );
 }
@@ -160,8 +251,8 @@ pub fn print_inspector_url(port: u16) {
 pub fn print_working_dir(path: &str) {
     println!(
         "{} {}",
-        "Working directory:".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
-        path.truecolor(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)
+        "Working directory:".custom_color(colors::theme().overlay0),
+        path.custom_color(colors::theme().text)
     );
 }
 
@@ -170,9 +261,9 @@ pub fn print_git_info(git_info: &crate::git::GitInfo) {
     if git_info.is_repo {
         let branch = git_info.branch.as_deref().unwrap_or("unknown");
         let status_color = if git_info.is_dirty {
-            branch.truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2)
+            branch.custom_color(colors::theme().yellow)
         } else {
-            branch.truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2)
+            branch.custom_color(colors::theme().green)
         };
 
         let status_indicator = if git_info.is_dirty {
@@ -180,14 +271,14 @@ pub fn print_git_info(git_info: &crate::git::GitInfo) {
                 " ({} staged, {} modified, {} untracked)",
                 git_info.staged_count, git_info.unstaged_count, git_info.untracked_count
             )
-            .truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2)
+            .custom_color(colors::theme().yellow)
         } else {
-            " (clean)".truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2)
+            " (clean)".custom_color(colors::theme().green)
         };
 
         println!(
             "{} {}{}",
-            "Git:".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
+            "Git:".custom_color(colors::theme().overlay0),
             status_color,
             status_indicator
         );
@@ -200,7 +291,7 @@ pub fn print_history_count(count: usize) {
     println!(
         "{}\n",
         format!("[History: {} messages]", count)
-            .truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2)
+            .custom_color(colors::theme().overlay0)
     );
 }
 
@@ -208,21 +299,64 @@ pub fn print_history_count(count: usize) {
 pub fn print_session_info(session_id: &str, message_count: usize) {
     println!(
         "{} Session: {} ({} messages)",
-        "📁".truecolor(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2),
-        &session_id[..8].truecolor(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2),
+        "📁".custom_color(colors::theme().sapphire),
+        &session_id[..8].custom_color(colors::theme().sapphire),
         message_count
     );
 }
 
 /// Format a streaming token for display
-pub fn print_streaming_token(token: &str) {
-    print!("{}", token);
-    io::stdout().flush().ok();
+pub fn print_streaming_token(token: &str, to_stderr: bool) {
+    if to_stderr {
+        eprint!("{}", token);
+        io::stderr().flush().ok();
+    } else {
+        print!("{}", token);
+        io::stdout().flush().ok();
+    }
+}
+
+/// Print model reasoning, dimmed, as it streams
+pub fn print_reasoning_token(token: &str, to_stderr: bool) {
+    let dimmed = token.custom_color(colors::theme().overlay0).italic();
+    if to_stderr {
+        eprint!("{}", dimmed);
+        io::stderr().flush().ok();
+    } else {
+        print!("{}", dimmed);
+        io::stdout().flush().ok();
+    }
 }
 
 /// End streaming output
-pub fn end_streaming() {
-    println!("\n");
+pub fn end_streaming(to_stderr: bool) {
+    if to_stderr {
+        eprintln!("\n");
+    } else {
+        println!("\n");
+    }
+}
+
+/// An indicatif template, without its color specs when colors are off
+fn progress_template(template: &str) -> String {
+    if colors::no_color() {
+        strip_template_colors(template)
+    } else {
+        template.to_string()
+    }
+}
+
+/// Drop the `.color` / `.fg/bg` part of each `{key:width.style}` placeholder
+fn strip_template_colors(template: &str) -> String {
+    lazy_static! {
+        static ref STYLE: Regex = Regex::new(r"\{(\w+):(\d*)\.[\w/.]+\}").unwrap();
+    }
+    STYLE
+        .replace_all(template, |caps: &regex::Captures| match &caps[2] {
+            "" => format!("{{{}}}", &caps[1]),
+            width => format!("{{{}:{}}}", &caps[1], width),
+        })
+        .to_string()
 }
 
 /// Create a spinner for LLM thinking
@@ -231,7 +365,7 @@ pub fn create_thinking_spinner() -> indicatif::ProgressBar {
     spinner.set_style(
         indicatif::ProgressStyle::default_spinner()
             .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
-            .template("{spinner:.blue} {msg}")
+            .template(&progress_template("{spinner:.blue} {msg}"))
             .unwrap(),
     );
     spinner.set_message("Thinking...");
@@ -245,7 +379,7 @@ pub fn create_tool_spinner(tool_name: &str) -> indicatif::ProgressBar {
     spinner.set_style(
         indicatif::ProgressStyle::default_spinner()
             .tick_chars("⣾⣽⣻⢿⡿⣟⣯⣷")
-            .template("{spinner:.magenta} {msg}")
+            .template(&progress_template("{spinner:.magenta} {msg}"))
             .unwrap(),
     );
     spinner.set_message(format!("Running {}...", tool_name));
@@ -256,16 +390,16 @@ pub fn create_tool_spinner(tool_name: &str) -> indicatif::ProgressBar {
 /// Finish a spinner with success
 pub fn finish_spinner_success(spinner: &indicatif::ProgressBar, message: &str) {
     let formatted = format!("{} {}",
-                            "✓".truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2),
-                            message.truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2));
+                            "✓".custom_color(colors::theme().green),
+                            message.custom_color(colors::theme().green));
     spinner.finish_with_message(formatted);
 }
 
 /// Finish a spinner with error
 pub fn finish_spinner_error(spinner: &indicatif::ProgressBar, message: &str) {
     let formatted = format!("{} {}",
-                            "✗".truecolor(colors::RED.0, colors::RED.1, colors::RED.2),
-                            message.truecolor(colors::RED.0, colors::RED.1, colors::RED.2));
+                            "✗".custom_color(colors::theme().red),
+                            message.custom_color(colors::theme().red));
     spinner.finish_with_message(formatted);
 }
 
@@ -279,7 +413,7 @@ pub fn create_indexing_progress(total: u64) -> indicatif::ProgressBar {
     let pb = indicatif::ProgressBar::new(total);
     pb.set_style(
         indicatif::ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .template(&progress_template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}"))
             .unwrap()
             .progress_chars("█▓▒░  "),
     );
@@ -292,7 +426,7 @@ pub fn create_embedding_progress(total: u64) -> indicatif::ProgressBar {
     let pb = indicatif::ProgressBar::new(total);
     pb.set_style(
         indicatif::ProgressStyle::default_bar()
-            .template("{spinner:.yellow} [{elapsed_precise}] [{bar:40.yellow/blue}] {pos}/{len} {msg}")
+            .template(&progress_template("{spinner:.yellow} [{elapsed_precise}] [{bar:40.yellow/blue}] {pos}/{len} {msg}"))
             .unwrap()
             .progress_chars("█▓▒░  "),
     );
@@ -304,27 +438,27 @@ pub fn create_embedding_progress(total: u64) -> indicatif::ProgressBar {
 pub fn print_token_usage(usage: &crate::agent_loop::TokenUsage) {
     println!(
         "\n{} Token Usage (estimated):",
-        "📊".truecolor(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2)
+        "📊".custom_color(colors::theme().sapphire)
     );
     println!(
         "  {} {} prompt tokens",
-        "→".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
-        usage.prompt_tokens.to_string().truecolor(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)
+        "→".custom_color(colors::theme().overlay0),
+        usage.prompt_tokens.to_string().custom_color(colors::theme().text)
     );
     println!(
         "  {} {} completion tokens",
-        "←".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
-        usage.completion_tokens.to_string().truecolor(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)
+        "←".custom_color(colors::theme().overlay0),
+        usage.completion_tokens.to_string().custom_color(colors::theme().text)
     );
     println!(
         "  {} {} total tokens",
-        "∑".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
-        usage.total_tokens.to_string().truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2)
+        "∑".custom_color(colors::theme().overlay0),
+        usage.total_tokens.to_string().custom_color(colors::theme().yellow)
     );
     println!(
         "  {} {} requests",
-        "#".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
-        usage.request_count.to_string().truecolor(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)
+        "#".custom_color(colors::theme().overlay0),
+        usage.request_count.to_string().custom_color(colors::theme().text)
     );
     println!();
 }
@@ -333,30 +467,30 @@ pub fn print_token_usage(usage: &crate::agent_loop::TokenUsage) {
 pub fn print_file_changes_summary(changes: &[&crate::agent_loop::FileChange]) {
     if changes.is_empty() {
         println!("{} No files modified this session.",
-                 "📄".truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2));
+                 "📄".custom_color(colors::theme().overlay0));
         return;
     }
 
     println!(
         "\n{} {} file(s) modified this session:",
-        "📄".truecolor(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2),
+        "📄".custom_color(colors::theme().sapphire),
         changes.len()
     );
 
     for change in changes {
         let symbol = match change.operation {
             crate::agent_loop::FileOperation::Created =>
-                "+".truecolor(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2),
+                "+".custom_color(colors::theme().green),
             crate::agent_loop::FileOperation::Modified =>
-                "~".truecolor(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2),
+                "~".custom_color(colors::theme().yellow),
             crate::agent_loop::FileOperation::Deleted =>
-                "-".truecolor(colors::RED.0, colors::RED.1, colors::RED.2),
+                "-".custom_color(colors::theme().red),
         };
         println!(
             "  {} {} ({})",
             symbol,
-            change.path.truecolor(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2),
-            change.operation.to_string().truecolor(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2)
+            change.path.custom_color(colors::theme().text),
+            change.operation.to_string().custom_color(colors::theme().overlay0)
         );
     }
     println!();
@@ -374,6 +508,14 @@ pub fn emit_assistant_chunk(tx: &Sender<TuiEvent>, agent_id: &str, chunk: &str)
     });
 }
 
+/// Emit a chunk of model reasoning
+pub fn emit_reasoning_chunk(tx: &Sender<TuiEvent>, agent_id: &str, chunk: &str) {
+    let _ = tx.try_send(TuiEvent::ReasoningChunk {
+        agent_id: agent_id.to_string(),
+        chunk: chunk.to_string(),
+    });
+}
+
 /// Emit a complete assistant message
 pub fn emit_assistant_message(tx: &Sender<TuiEvent>, agent_id: &str, message: &str) {
     let _ = tx.try_send(TuiEvent::AssistantMessage {
@@ -391,12 +533,13 @@ pub fn emit_tool_start(tx: &Sender<TuiEvent>, agent_id: &str, tool_name: &str, a
     });
 }
 
-/// Emit a tool success event
-pub fn emit_tool_success(tx: &Sender<TuiEvent>, agent_id: &str, tool_name: &str, result: &str) {
+/// Emit a tool success event: a summary line and the tool's output (may be empty)
+pub fn emit_tool_success(tx: &Sender<TuiEvent>, agent_id: &str, tool_name: &str, result: &str, output: &str) {
     let _ = tx.try_send(TuiEvent::ToolSuccess {
         agent_id: agent_id.to_string(),
         name: tool_name.to_string(),
         result: result.to_string(),
+        output: output.to_string(),
     });
 }
 
@@ -442,6 +585,24 @@ pub fn emit_token_usage(tx: &Sender<TuiEvent>, agent_id: &str, prompt: usize, co
     });
 }
 
+/// Emit whether the agent is waiting for the model to start responding
+pub fn emit_thinking(tx: &Sender<TuiEvent>, agent_id: &str, active: bool) {
+    let _ = tx.try_send(TuiEvent::Thinking {
+        agent_id: agent_id.to_string(),
+        active,
+    });
+}
+
+/// Emit the agent loop's progress through the current turn
+pub fn emit_iteration_update(tx: &Sender<TuiEvent>, agent_id: &str, iteration: usize, max_iterations: usize, elapsed: std::time::Duration) {
+    let _ = tx.try_send(TuiEvent::IterationUpdate {
+        agent_id: agent_id.to_string(),
+        iteration,
+        max_iterations,
+        elapsed_ms: elapsed.as_millis() as u64,
+    });
+}
+
 /// Emit session update
 pub fn emit_session_update(tx: &Sender<TuiEvent>, id: &str, model: &str) {
     let _ = tx.try_send(TuiEvent::SessionUpdate {
@@ -449,3 +610,43 @@ pub fn emit_session_update(tx: &Sender<TuiEvent>, id: &str, model: &str) {
         model: model.to_string(),
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_template_colors() {
+        assert_eq!(strip_template_colors("{spinner:.blue} {msg}"), "{spinner} {msg}");
+        assert_eq!(
+            strip_template_colors("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}"),
+            "{spinner} [{elapsed_precise}] [{bar:40}] {pos}/{len} {msg}"
+        );
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate_chars("héllo wörld", 5), "héllo...");
+        assert_eq!(truncate_chars("📁📁📁", 2), "📁📁...");
+        assert_eq!(truncate_chars("", 0), "");
+    }
+
+    #[test]
+    fn test_dangerous_patterns_config() {
+        let config: DangerousConfig = serde_json::from_str(
+            r#"{ "commands": ["terraform\\s+destroy"], "paths": ["\\.env$"], "allow": ["rmdir"] }"#
+        ).unwrap();
+        let patterns = DangerousPatterns::from_config(&config).unwrap();
+
+        assert_eq!(patterns.command_match("Terraform  destroy -auto-approve").as_deref(), Some(r"terraform\s+destroy"));
+        assert_eq!(patterns.command_match("sudo rm -rf build").as_deref(), Some("rm -rf"));
+        assert_eq!(patterns.command_match("rmdir empty"), None);
+        assert_eq!(patterns.path_match("app/.env").as_deref(), Some(r"\.env$"));
+        assert_eq!(patterns.path_match("/etc/shadow").as_deref(), Some("/etc/shadow"));
+
+        let invalid = DangerousConfig { commands: vec!["(".to_string()], ..Default::default() };
+        assert!(DangerousPatterns::from_config(&invalid).is_err());
+    }
+}
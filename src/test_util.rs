@@ -0,0 +1,39 @@
+//! Helpers shared by the unit tests
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Numbers the directories of one test process
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// An empty directory under the system temp dir, removed when dropped
+/// (also when the test panics)
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create a directory whose name starts with `agent-t-<label>`
+    pub fn new(label: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "agent-t-{}-{}-{}",
+            label,
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Left behind by an earlier process that had the same pid
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,230 @@
+//! Tool calls written as text
+//!
+//! Small local models without native function calling often write the call
+//! into their reply instead: Hermes/Qwen-style `<tool_call>{...}</tool_call>`
+//! tags, or a fenced JSON block naming a tool. When a response has no
+//! structured tool calls, these are parsed out and run as real calls.
+//! [`StreamFilter`] keeps the tags out of streamed output, even when a tag is
+//! split across chunks.
+
+use crate::codeblock;
+use serde_json::{Map, Value};
+
+const OPEN_TAG: &str = "<tool_call>";
+const CLOSE_TAG: &str = "</tool_call>";
+
+/// A tool call found in response text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextToolCall {
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// Tool calls parsed out of a response, and what is left of the text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedText {
+    pub calls: Vec<TextToolCall>,
+    /// The response with the tool calls removed (trimmed, may be empty)
+    pub text: String,
+}
+
+/// Find tool calls in response text.
+///
+/// `<tool_call>` tags are taken as they are; fenced JSON blocks only count
+/// when they name one of `tools`, so examples in prose aren't run.
+pub fn parse(text: &str, tools: &[&str]) -> Option<ParsedText> {
+    let parsed = parse_tags(text).or_else(|| parse_fences(text, tools))?;
+    (!parsed.calls.is_empty()).then_some(parsed)
+}
+
+/// `<tool_call>...</tool_call>` spans; an unclosed tag runs to the end
+fn parse_tags(text: &str) -> Option<ParsedText> {
+    let mut calls = Vec::new();
+    let mut remaining = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(OPEN_TAG) {
+        remaining.push_str(&rest[..start]);
+        let body_start = start + OPEN_TAG.len();
+        let (body, after) = match rest[body_start..].find(CLOSE_TAG) {
+            Some(end) => (&rest[body_start..body_start + end], body_start + end + CLOSE_TAG.len()),
+            None => (&rest[body_start..], rest.len()),
+        };
+        calls.extend(calls_from_json(strip_fence(body.trim())));
+        rest = &rest[after..];
+    }
+    remaining.push_str(rest);
+
+    (!calls.is_empty()).then(|| ParsedText { calls, text: remaining.trim().to_string() })
+}
+
+/// Fenced blocks whose JSON names a known tool
+fn parse_fences(text: &str, tools: &[&str]) -> Option<ParsedText> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut calls = Vec::new();
+    let mut kept: Vec<&str> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let close = codeblock::fence_marker(trimmed).and_then(|fence| {
+            lines[i + 1..]
+                .iter()
+                .position(|line| line.trim() == fence)
+                .map(|offset| i + 1 + offset)
+        });
+        let Some(close) = close else {
+            kept.push(lines[i]);
+            i += 1;
+            continue;
+        };
+
+        let body = lines[i + 1..close].join("\n");
+        let found: Vec<TextToolCall> = calls_from_json(&body)
+            .into_iter()
+            .filter(|call| tools.contains(&call.name.as_str()))
+            .collect();
+        if found.is_empty() {
+            kept.extend(&lines[i..=close]);
+        } else {
+            calls.extend(found);
+        }
+        i = close + 1;
+    }
+
+    (!calls.is_empty()).then(|| ParsedText { calls, text: kept.join("\n").trim().to_string() })
+}
+
+/// Remove a surrounding ``` fence from a tag body, if there is one
+fn strip_fence(body: &str) -> &str {
+    let Some(fence) = codeblock::fence_marker(body) else {
+        return body;
+    };
+    let inner = body.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+    inner.trim_end().strip_suffix(fence).unwrap_or(inner).trim()
+}
+
+/// Calls described by a JSON object or array of objects
+fn calls_from_json(body: &str) -> Vec<TextToolCall> {
+    match serde_json::from_str::<Value>(body) {
+        Ok(Value::Array(items)) => items.iter().filter_map(call_from_value).collect(),
+        Ok(value) => call_from_value(&value).into_iter().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// `{"name": ..., "arguments": {...}}`, also accepting `parameters`/`input`,
+/// arguments encoded as a string, and an OpenAI-style `function` wrapper
+fn call_from_value(value: &Value) -> Option<TextToolCall> {
+    let object = value.as_object()?;
+    if let Some(function) = object.get("function").filter(|f| f.is_object()) {
+        return call_from_value(function);
+    }
+
+    let name = object.get("name")?.as_str()?.trim().to_string();
+    if name.is_empty() {
+        return None;
+    }
+    let arguments = ["arguments", "parameters", "input"]
+        .iter()
+        .find_map(|key| object.get(*key))
+        .cloned()
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    let arguments = match arguments {
+        Value::String(encoded) => serde_json::from_str(&encoded).ok()?,
+        other => other,
+    };
+
+    Some(TextToolCall { name, arguments })
+}
+
+/// Hides `<tool_call>` spans from streamed text
+#[derive(Debug, Default)]
+pub struct StreamFilter {
+    /// Text held back because it may be the start of a tag
+    pending: String,
+    inside_tag: bool,
+}
+
+impl StreamFilter {
+    /// Feed a streamed chunk, returning the part that can be shown now
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.pending.push_str(chunk);
+        let mut shown = String::new();
+
+        loop {
+            let tag = if self.inside_tag { CLOSE_TAG } else { OPEN_TAG };
+            if let Some(pos) = self.pending.find(tag) {
+                if !self.inside_tag {
+                    shown.push_str(&self.pending[..pos]);
+                }
+                self.pending.drain(..pos + tag.len());
+                self.inside_tag = !self.inside_tag;
+                continue;
+            }
+
+            // Keep a possible partial tag at the end for the next chunk
+            let cut = self.pending.len() - partial_tag_len(&self.pending, tag);
+            if !self.inside_tag {
+                shown.push_str(&self.pending[..cut]);
+            }
+            self.pending.drain(..cut);
+            return shown;
+        }
+    }
+
+    /// Whatever is still held back once the stream has ended
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        if self.inside_tag { String::new() } else { rest }
+    }
+}
+
+/// Length of the longest proper prefix of `tag` that `text` ends with
+fn partial_tag_len(text: &str, tag: &str) -> usize {
+    (1..tag.len()).rev().find(|&len| text.ends_with(&tag[..len])).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_tags() {
+        let text = "Let me look.\n<tool_call>\n{\"name\": \"read_file\", \"arguments\": {\"file_path\": \"src/main.rs\"}}\n</tool_call>";
+        let parsed = parse(text, &[]).unwrap();
+        assert_eq!(parsed.calls, vec![TextToolCall {
+            name: "read_file".to_string(),
+            arguments: json!({ "file_path": "src/main.rs" }),
+        }]);
+        assert_eq!(parsed.text, "Let me look.");
+
+        // String-encoded arguments, a function wrapper and an unclosed tag
+        let text = "<tool_call>{\"function\": {\"name\": \"bash\", \"arguments\": \"{\\\"command\\\": \\\"ls\\\"}\"}}";
+        let parsed = parse(text, &[]).unwrap();
+        assert_eq!(parsed.calls[0].name, "bash");
+        assert_eq!(parsed.calls[0].arguments, json!({ "command": "ls" }));
+        assert_eq!(parsed.text, "");
+    }
+
+    #[test]
+    fn test_parse_fences_needs_known_tool() {
+        let text = "Running it:\n```json\n{\"name\": \"bash\", \"parameters\": {\"command\": \"cargo test\"}}\n```\nDone.";
+        let parsed = parse(text, &["bash", "read_file"]).unwrap();
+        assert_eq!(parsed.calls[0].arguments, json!({ "command": "cargo test" }));
+        assert_eq!(parsed.text, "Running it:\nDone.");
+
+        let example = "A package.json looks like:\n```json\n{\"name\": \"my-app\", \"version\": \"1.0.0\"}\n```";
+        assert!(parse(example, &["bash"]).is_none());
+        assert!(parse("No tools here.", &["bash"]).is_none());
+    }
+
+    #[test]
+    fn test_stream_filter_split_tags() {
+        let mut filter = StreamFilter::default();
+        let chunks = ["Checking.", " <tool", "_call>{\"name\":", " \"ls\"}</tool_", "call> after", " <b>"];
+        let shown: String = chunks.iter().map(|chunk| filter.push(chunk)).collect::<String>() + &filter.finish();
+        assert_eq!(shown, "Checking.  after <b>");
+    }
+}
@@ -0,0 +1,207 @@
+//! `.agentignore` support
+//!
+//! A `.agentignore` file in the project root uses gitignore syntax to mark
+//! paths the agent must never see or touch. The rules are loaded once at
+//! startup and checked by the file tools, grep, glob and the vecdb indexer.
+
+use crate::error::ToolError;
+use glob::{MatchOptions, Pattern};
+use lazy_static::lazy_static;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+/// Name of the ignore file looked up in the project root
+pub const IGNORE_FILE: &str = ".agentignore";
+
+lazy_static! {
+    static ref AGENT_IGNORE: RwLock<Option<AgentIgnore>> = RwLock::new(None);
+}
+
+/// A single parsed ignore rule
+#[derive(Debug)]
+struct Rule {
+    pattern: Pattern,
+    /// Pattern contained a slash, so it matches the path relative to the root
+    anchored: bool,
+    /// Pattern ended with a slash, so it only matches directories
+    dir_only: bool,
+    /// Pattern started with `!` and re-includes a path
+    negated: bool,
+}
+
+/// Parsed `.agentignore` rules for a project root
+#[derive(Debug)]
+pub struct AgentIgnore {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl AgentIgnore {
+    /// Parse rules from the contents of an ignore file
+    pub fn parse(root: &Path, contents: &str) -> Self {
+        let rules = contents.lines().filter_map(parse_rule).collect();
+        Self {
+            root: normalize(root),
+            rules,
+        }
+    }
+
+    /// Whether the path (absolute, or relative to the root) is ignored.
+    /// A path is ignored if it or any of its parent directories matches.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = if path.is_absolute() {
+            normalize(path)
+        } else {
+            normalize(&self.root.join(path))
+        };
+
+        // Paths outside the project are not governed by .agentignore
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        for i in 0..components.len() {
+            let prefix = components[..=i].join("/");
+            let prefix_is_dir = i + 1 < components.len() || is_dir;
+            if self.matches(&prefix, &components[i], prefix_is_dir) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Apply the rules to one path; the last matching rule wins
+    fn matches(&self, relative: &str, name: &str, is_dir: bool) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let candidate = if rule.anchored { relative } else { name };
+            if rule.pattern.matches_with(candidate, options) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Parse one gitignore-style line
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let anchored = line.contains('/');
+    let line = line.trim_start_matches('/');
+    if line.is_empty() {
+        return None;
+    }
+
+    Some(Rule {
+        pattern: Pattern::new(line).ok()?,
+        anchored,
+        dir_only,
+        negated,
+    })
+}
+
+/// Lexically resolve `.` and `..` without touching the filesystem
+/// (the target of write_file may not exist yet)
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            other => result.push(other.as_os_str()),
+        }
+    }
+    result
+}
+
+/// Load `.agentignore` from the project root, replacing any previous rules.
+/// Returns the number of rules loaded, or None if there is no ignore file.
+pub fn load(root: &Path) -> Option<usize> {
+    let contents = std::fs::read_to_string(root.join(IGNORE_FILE)).ok()?;
+    let ignore = AgentIgnore::parse(root, &contents);
+    let count = ignore.rules.len();
+    if let Ok(mut guard) = AGENT_IGNORE.write() {
+        *guard = Some(ignore);
+    }
+    Some(count)
+}
+
+/// Whether a path is excluded by the loaded `.agentignore`
+pub fn is_ignored(path: &Path) -> bool {
+    let Ok(guard) = AGENT_IGNORE.read() else {
+        return false;
+    };
+    match guard.as_ref() {
+        Some(ignore) => ignore.is_ignored(path, path.is_dir()),
+        None => false,
+    }
+}
+
+/// Reject access to a path excluded by `.agentignore`
+pub fn check(path: &str) -> Result<(), ToolError> {
+    if is_ignored(Path::new(path)) {
+        return Err(ToolError::permission_denied(format!(
+            "{} is excluded by {} and cannot be accessed",
+            path, IGNORE_FILE
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_semantics() {
+        let root = Path::new("/project");
+        let ignore = AgentIgnore::parse(
+            root,
+            "# secrets\n.env\nsecrets/\n/data/*.csv\nvendor/**\n!vendor/keep.rs\n*.pem\n",
+        );
+
+        assert!(ignore.is_ignored(Path::new("/project/.env"), false));
+        assert!(ignore.is_ignored(Path::new("/project/sub/.env"), false));
+        assert!(ignore.is_ignored(Path::new("/project/secrets/key.txt"), false));
+        assert!(ignore.is_ignored(Path::new("secrets"), true));
+        assert!(!ignore.is_ignored(Path::new("secrets"), false));
+        assert!(ignore.is_ignored(Path::new("/project/data/big.csv"), false));
+        assert!(!ignore.is_ignored(Path::new("/project/other/data/big.csv"), false));
+        assert!(ignore.is_ignored(Path::new("/project/vendor/lib.rs"), false));
+        assert!(!ignore.is_ignored(Path::new("/project/vendor/keep.rs"), false));
+        assert!(ignore.is_ignored(Path::new("/project/certs/server.pem"), false));
+        assert!(ignore.is_ignored(Path::new("/project/src/../.env"), false));
+        assert!(!ignore.is_ignored(Path::new("/project/src/main.rs"), false));
+        assert!(!ignore.is_ignored(Path::new("/elsewhere/.env"), false));
+    }
+}
@@ -1,12 +1,28 @@
 use crate::error::ToolError;
+use crate::tools::shell;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::process::Stdio;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 
+/// Variables the shell sets itself, which are not carried between calls
+const SHELL_MANAGED_VARS: &[&str] = &["SHLVL", "_", "PWD", "OLDPWD"];
+
+/// Parts of environment variable names that mark the value as a credential
+const SECRET_NAME_PARTS: &[&str] = &["TOKEN", "KEY", "SECRET", "PASSWORD"];
+
+/// Shown in place of a masked environment value
+const MASKED_VALUE: &str = "********";
+
+/// Receives each stdout/stderr line of a foreground command as it is printed
+pub type LineSink = dyn Fn(&str) + Send + Sync;
+
 /// Arguments for the BashCommand tool
 #[derive(Debug, Deserialize)]
 pub struct BashArgs {
@@ -18,6 +34,8 @@ pub struct BashArgs {
     pub timeout_secs: Option<u64>,
     /// Execute in background (default: false)
     pub background: Option<bool>,
+    /// Extra environment variables for this command only
+    pub env: Option<HashMap<String, String>>,
 }
 
 /// Tool to execute bash commands
@@ -52,6 +70,11 @@ impl Tool for BashCommand {
                     "background": {
                         "type": "boolean",
                         "description": "Execute in background and return immediately with process ID. Use bash_status/bash_output tools to check progress."
+                    },
+                    "env": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Optional environment variables for this command only, e.g. {\"RUST_LOG\": \"debug\"}. Prefer this over prefixing the command with FOO=bar."
                     }
                 },
                 "required": ["command"]
@@ -60,11 +83,38 @@ impl Tool for BashCommand {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.run(args, None).await
+    }
+}
+
+/// Tool arguments with the values of `env` entries whose names look like
+/// credentials masked, for the audit log, the TUI and the inspector
+pub fn mask_secret_env(args: &serde_json::Value) -> serde_json::Value {
+    let mut masked = args.clone();
+    if let Some(env) = masked.get_mut("env").and_then(|env| env.as_object_mut()) {
+        for (name, value) in env.iter_mut() {
+            let upper = name.to_ascii_uppercase();
+            if SECRET_NAME_PARTS.iter().any(|part| upper.contains(part)) {
+                *value = json!(MASKED_VALUE);
+            }
+        }
+    }
+    masked
+}
+
+impl BashCommand {
+    /// Like `call`, but shows foreground output through `on_line` while the
+    /// command runs; the result still has all of it
+    pub async fn call_streaming(&self, args: BashArgs, on_line: &LineSink) -> Result<String, ToolError> {
+        self.run(args, Some(on_line)).await
+    }
+
+    async fn run(&self, args: BashArgs, on_line: Option<&LineSink>) -> Result<String, ToolError> {
         // Check if background execution is requested
         if args.background.unwrap_or(false) {
             // Use process manager for background execution
             let process_id = crate::process_manager::PROCESS_MANAGER
-                .spawn_background(args.command.clone(), args.working_dir.clone())
+                .spawn_background(args.command.clone(), args.working_dir.clone(), args.env.clone().unwrap_or_default())
                 .await
                 .map_err(ToolError::Other)?;
 
@@ -77,46 +127,351 @@ impl Tool for BashCommand {
         // Foreground execution (original behavior)
         let timeout_duration = Duration::from_secs(args.timeout_secs.unwrap_or(600));
 
-        let mut cmd = Command::new("bash");
-        cmd.arg("-c").arg(&args.command);
+        let mut cmd = shell::command(&args.command);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
         if let Some(ref dir) = args.working_dir {
             cmd.current_dir(dir);
         }
+        if let Some(ref env) = args.env {
+            cmd.envs(env);
+        }
 
-        let output = timeout(timeout_duration, cmd.output())
-            .await
-            .map_err(|_| ToolError::CommandTimeout)?
-            .map_err(ToolError::Io)?;
+        let output = run_command(&mut cmd, timeout_duration, on_line).await?;
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(format_output(&output))
+    }
+}
+
+/// Shell state carried between `bash` calls (--persistent-shell): the
+/// directory the last command ended in and the variables it exported.
+/// Only POSIX shells report their state; others just keep the directory.
+#[derive(Debug)]
+pub struct ShellSession {
+    cwd: PathBuf,
+    /// Full environment after the last command (empty until the first one)
+    env: HashMap<String, String>,
+    /// Private directory the state is dumped to, created on first use and
+    /// removed with the session
+    state_dir: Option<PathBuf>,
+}
 
-        let mut result = String::new();
+impl ShellSession {
+    pub fn new(cwd: impl Into<PathBuf>) -> Self {
+        Self { cwd: cwd.into(), env: HashMap::new(), state_dir: None }
+    }
 
-        if !stdout.is_empty() {
-            result.push_str(&stdout);
+    /// Directory the next command starts in
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    /// Start the next command somewhere else (e.g. after /cd)
+    pub fn set_cwd(&mut self, cwd: impl Into<PathBuf>) {
+        self.cwd = cwd.into();
+    }
+
+    /// Run a foreground command where the previous one left off, then record
+    /// where it ended up and what it exported. `env` applies to this command
+    /// only and is not carried over unless the command changes it.
+    pub async fn run(
+        &mut self,
+        command: &str,
+        env: &HashMap<String, String>,
+        timeout_secs: Option<u64>,
+        on_line: Option<&LineSink>,
+    ) -> Result<String, ToolError> {
+        let program = shell::program();
+        let posix = shell::is_posix(&program);
+
+        let state_file = if posix { self.state_file().map_err(ToolError::Io)? } else { PathBuf::new() };
+        let script = if posix { with_state_capture(command, &state_file) } else { command.to_string() };
+
+        let mut cmd = shell::command(&script);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+        cmd.current_dir(&self.cwd);
+        if !self.env.is_empty() {
+            cmd.env_clear().envs(&self.env);
         }
+        cmd.envs(env);
+
+        let duration = Duration::from_secs(timeout_secs.unwrap_or(600));
+        let output = run_command(&mut cmd, duration, on_line).await;
 
-        if !stderr.is_empty() {
-            if !result.is_empty() {
-                result.push_str("\n--- stderr ---\n");
+        if posix {
+            if let Ok(state) = std::fs::read_to_string(&state_file) {
+                self.update_from_state(&state, env);
             }
-            result.push_str(&stderr);
+            let _ = std::fs::remove_file(&state_file);
         }
 
-        if result.is_empty() {
-            result = "(no output)".to_string();
+        Ok(format_output(&output?))
+    }
+
+    /// An empty file only this user can read for the exit trap to write to.
+    /// The dump holds the whole environment, secrets included, so it lives in
+    /// a directory with an unguessable name that no one else may enter.
+    fn state_file(&mut self) -> std::io::Result<PathBuf> {
+        let dir = match self.state_dir {
+            Some(ref dir) => dir.clone(),
+            None => {
+                let dir = std::env::temp_dir().join(format!("agent-t-shell-{}", uuid::Uuid::new_v4()));
+                let mut builder = std::fs::DirBuilder::new();
+                #[cfg(unix)]
+                std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+                // Fails rather than reusing a directory someone else created
+                builder.create(&dir)?;
+                self.state_dir = Some(dir.clone());
+                dir
+            }
+        };
+
+        let path = dir.join("state");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&path)?;
+        Ok(path)
+    }
+
+    /// Apply the `pwd` line and `env -0` dump written by the exit trap
+    fn update_from_state(&mut self, state: &str, one_off: &HashMap<String, String>) {
+        let (cwd, dump) = state.split_once('\n').unwrap_or((state, ""));
+        let cwd = Path::new(cwd.trim_end_matches('\r'));
+        if cwd.is_dir() {
+            self.cwd = cwd.to_path_buf();
+        }
+        let mut env = parse_env(dump);
+        if env.is_empty() {
+            return;
         }
+        // Put back what the one-off variables replaced, unless the command changed them
+        for (name, value) in one_off {
+            if env.get(name) != Some(value) {
+                continue;
+            }
+            match self.previous_value(name) {
+                Some(previous) => env.insert(name.clone(), previous),
+                None => env.remove(name),
+            };
+        }
+        self.env = env;
+    }
 
-        // Add exit code info if non-zero
-        if !output.status.success() {
-            let exit_code = output.status.code().unwrap_or(-1);
-            result.push_str(&format!("\n[Exit code: {}]", exit_code));
+    /// Value a variable had before the last command
+    fn previous_value(&self, name: &str) -> Option<String> {
+        if self.env.is_empty() {
+            std::env::var(name).ok()
+        } else {
+            self.env.get(name).cloned()
         }
+    }
+}
+
+impl Drop for ShellSession {
+    fn drop(&mut self) {
+        if let Some(ref dir) = self.state_dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Wrap a command so that, however it exits, the shell writes its final
+/// directory and environment to `state_file`. The exit status is unchanged.
+fn with_state_capture(command: &str, state_file: &Path) -> String {
+    let quoted = state_file.to_string_lossy().replace('\'', "'\\''");
+    format!(
+        "__agent_t_state='{}'\ntrap '{{ pwd; env -0; }} > \"$__agent_t_state\" 2>/dev/null' EXIT\n{}",
+        quoted, command
+    )
+}
+
+/// Parse `env -0` output. Entries are NUL-separated, so multi-line values
+/// and exported bash functions (`BASH_FUNC_name%%=() { ... }`) stay whole.
+fn parse_env(dump: &str) -> HashMap<String, String> {
+    let mut env: HashMap<String, String> = dump
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+    for name in SHELL_MANAGED_VARS {
+        env.remove(*name);
+    }
+    env
+}
+
+/// Run a command with piped output to completion, passing lines to
+/// `on_line` as they arrive when given
+async fn run_command(cmd: &mut Command, duration: Duration, on_line: Option<&LineSink>) -> Result<Output, ToolError> {
+    let Some(on_line) = on_line else {
+        return timeout(duration, cmd.output())
+            .await
+            .map_err(|_| ToolError::CommandTimeout)?
+            .map_err(ToolError::Io);
+    };
+
+    // Dropping the child on timeout kills it
+    let mut child = cmd.kill_on_drop(true).spawn().map_err(ToolError::Io)?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let run = async {
+        let (stdout, stderr, status) = tokio::join!(
+            read_lines(stdout, on_line),
+            read_lines(stderr, on_line),
+            child.wait()
+        );
+        Ok::<_, std::io::Error>(Output { status: status?, stdout: stdout?, stderr: stderr? })
+    };
+    timeout(duration, run)
+        .await
+        .map_err(|_| ToolError::CommandTimeout)?
+        .map_err(ToolError::Io)
+}
+
+/// Everything read from a pipe, handing each line to `on_line` on the way
+async fn read_lines(pipe: Option<impl AsyncRead + Unpin>, on_line: &LineSink) -> std::io::Result<Vec<u8>> {
+    let mut collected = Vec::new();
+    let Some(pipe) = pipe else {
+        return Ok(collected);
+    };
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        on_line(String::from_utf8_lossy(&line).trim_end());
+        collected.extend_from_slice(&line);
+        line.clear();
+    }
+    Ok(collected)
+}
+
+/// Combine stdout, stderr and a non-zero exit code into the tool result
+fn format_output(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let mut result = String::new();
+
+    if !stdout.is_empty() {
+        result.push_str(&stdout);
+    }
+
+    if !stderr.is_empty() {
+        if !result.is_empty() {
+            result.push_str("\n--- stderr ---\n");
+        }
+        result.push_str(&stderr);
+    }
+
+    if result.is_empty() {
+        result = "(no output)".to_string();
+    }
+
+    // Add exit code info if non-zero
+    if !output.status.success() {
+        let exit_code = output.status.code().unwrap_or(-1);
+        result.push_str(&format!("\n[Exit code: {}]", exit_code));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_mask_secret_env() {
+        let args = json!({
+            "command": "make deploy",
+            "env": {"GITHUB_TOKEN": "ghp_abc", "api_key": "k", "DB_PASSWORD": "p", "RUST_LOG": "debug"}
+        });
+        let masked = mask_secret_env(&args);
+        assert_eq!(masked["env"]["GITHUB_TOKEN"], MASKED_VALUE);
+        assert_eq!(masked["env"]["api_key"], MASKED_VALUE);
+        assert_eq!(masked["env"]["DB_PASSWORD"], MASKED_VALUE);
+        assert_eq!(masked["env"]["RUST_LOG"], "debug");
+        assert_eq!(masked["command"], "make deploy");
+        assert_eq!(mask_secret_env(&json!({"command": "ls"})), json!({"command": "ls"}));
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let env = parse_env("HOME=/root\0GREETING=two\nlines\0BASH_FUNC_greet%%=() {  echo hi\n}\0SHLVL=2\0EMPTY=\0");
+        assert_eq!(env.get("HOME").map(String::as_str), Some("/root"));
+        assert_eq!(env.get("GREETING").map(String::as_str), Some("two\nlines"));
+        assert_eq!(env.get("BASH_FUNC_greet%%").map(String::as_str), Some("() {  echo hi\n}"));
+        assert_eq!(env.get("EMPTY").map(String::as_str), Some(""));
+        assert!(!env.contains_key("SHLVL"));
+    }
+
+    #[test]
+    fn test_session_keeps_cwd_and_exports() {
+        if !shell::is_posix(&shell::program()) {
+            return;
+        }
+        let temp = TempDir::new("shell-session");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut session = ShellSession::new(dir);
+
+        runtime.block_on(async {
+            let one_off = HashMap::from([("AGENT_T_ONE_OFF".to_string(), "once".to_string())]);
+            let first = session
+                .run("cd sub && export AGENT_T_TEST=kept; echo $AGENT_T_ONE_OFF; exit 3", &one_off, None, None)
+                .await
+                .unwrap();
+            assert!(first.starts_with("once\n"));
+            assert!(first.ends_with("[Exit code: 3]"));
+            assert_eq!(session.cwd(), dir.join("sub").canonicalize().unwrap());
+
+            let second = session
+                .run("echo \"$AGENT_T_TEST in $(basename \"$PWD\")${AGENT_T_ONE_OFF:-}\"", &HashMap::new(), None, None)
+                .await
+                .unwrap();
+            // Only stdout's line: a BASH_ENV script from the user's setup may add stderr
+            assert_eq!(second.lines().next(), Some("kept in sub"));
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_state_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let mut session = ShellSession::new(std::env::temp_dir());
+        let state_file = session.state_file().unwrap();
+        let dir = state_file.parent().unwrap().to_path_buf();
+        assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+        assert_eq!(std::fs::metadata(&state_file).unwrap().permissions().mode() & 0o777, 0o600);
+
+        drop(session);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_streamed_lines_match_output() {
+        if !shell::is_posix(&shell::program()) {
+            return;
+        }
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let lines = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = lines.clone();
+        let sink = move |line: &str| seen.lock().unwrap().push(line.to_string());
+
+        let mut cmd = shell::command("echo one; echo two >&2; printf three");
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let output = runtime
+            .block_on(run_command(&mut cmd, Duration::from_secs(10), Some(&sink)))
+            .unwrap();
 
-        Ok(result)
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "one\nthree");
+        let lines = lines.lock().unwrap();
+        assert!(["one", "three", "two"].iter().all(|line| lines.iter().any(|l| l == line)));
     }
 }
@@ -0,0 +1,174 @@
+use crate::error::ToolError;
+use crate::tools::cargo_common::{self, CargoDiagnostic};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Arguments for the CargoCheck tool
+#[derive(Debug, Deserialize)]
+pub struct CargoCheckArgs {
+    /// Package to check in a workspace (cargo -p)
+    pub package: Option<String>,
+    /// Also check tests, examples and benches (--all-targets)
+    pub all_targets: Option<bool>,
+    /// Include warnings in the result (default: true)
+    pub include_warnings: Option<bool>,
+    /// Optional working directory (the crate or workspace root)
+    pub working_dir: Option<String>,
+    /// Optional timeout in seconds (default: 300)
+    pub timeout_secs: Option<u64>,
+}
+
+/// Tool to run cargo check and report parsed diagnostics
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct CargoCheck;
+
+impl Tool for CargoCheck {
+    const NAME: &'static str = "cargo_check";
+    type Error = ToolError;
+    type Args = CargoCheckArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Run `cargo check` on the whole workspace and return compiler diagnostics as a list of file, line, level, code and message entries, errors first. Faster than a full build and does not need rust-analyzer.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "package": {
+                        "type": "string",
+                        "description": "Package to check in a workspace (cargo -p)"
+                    },
+                    "all_targets": {
+                        "type": "boolean",
+                        "description": "Also check tests, examples and benches (default: false)"
+                    },
+                    "include_warnings": {
+                        "type": "boolean",
+                        "description": "Include warnings in the result (default: true)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Optional working directory (defaults to project root)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Optional timeout in seconds (default: 300)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut cargo_args = vec!["check".to_string(), "--message-format=json".to_string()];
+        if args.all_targets.unwrap_or(false) {
+            cargo_args.push("--all-targets".to_string());
+        }
+        if let Some(ref package) = args.package {
+            cargo_args.push("-p".to_string());
+            cargo_args.push(package.clone());
+        }
+
+        let output = cargo_common::run_cargo(
+            &cargo_args,
+            args.working_dir.as_deref(),
+            args.timeout_secs.unwrap_or(300),
+        )
+        .await?;
+
+        let include_warnings = args.include_warnings.unwrap_or(true);
+        let diagnostics = collect_diagnostics(&output.stdout, include_warnings);
+
+        if diagnostics.is_empty() {
+            if output.success {
+                return Ok("cargo check passed with no diagnostics.".to_string());
+            }
+            // cargo itself failed (bad manifest, unknown package, ...)
+            return Err(ToolError::command_failed(output.stderr.trim().to_string()));
+        }
+
+        Ok(format_diagnostics(&diagnostics, output.success))
+    }
+}
+
+/// Parse, filter, de-duplicate and sort diagnostics (errors first, then by location)
+fn collect_diagnostics(stdout: &str, include_warnings: bool) -> Vec<CargoDiagnostic> {
+    let mut diagnostics: Vec<CargoDiagnostic> = Vec::new();
+
+    for diag in stdout.lines().filter_map(cargo_common::parse_compiler_message) {
+        // Skip the trailing "aborting due to N previous errors" / "N warnings emitted" summaries
+        if diag.file.is_none() && (diag.message.starts_with("aborting due to") || diag.message.ends_with("emitted")) {
+            continue;
+        }
+        if !include_warnings && !diag.is_error() {
+            continue;
+        }
+        // The same diagnostic is reported once per target (lib, bin, test)
+        if !diagnostics.contains(&diag) {
+            diagnostics.push(diag);
+        }
+    }
+
+    diagnostics.sort_by(|a, b| {
+        b.is_error()
+            .cmp(&a.is_error())
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+
+    diagnostics
+}
+
+/// Render diagnostics for the model
+fn format_diagnostics(diagnostics: &[CargoDiagnostic], success: bool) -> String {
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+
+    let mut result = format!(
+        "cargo check {}: {} error(s), {} warning(s)\n\n",
+        if success { "passed" } else { "failed" },
+        errors,
+        warnings
+    );
+
+    for diag in diagnostics {
+        let code = diag.code.as_ref().map(|c| format!("[{}]", c)).unwrap_or_default();
+        result.push_str(&format!(
+            "{}{} {}: {}\n",
+            diag.level,
+            code,
+            diag.location(),
+            diag.message
+        ));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_diagnostics_sorts_errors_first() {
+        let stdout = [
+            r#"{"reason":"compiler-message","message":{"message":"unused variable: `x`","code":{"code":"unused_variables"},"level":"warning","spans":[{"file_name":"src/a.rs","line_start":2,"column_start":9,"is_primary":true}]}}"#,
+            r#"{"reason":"compiler-message","message":{"message":"cannot find value `y`","code":{"code":"E0425"},"level":"error","spans":[{"file_name":"src/b.rs","line_start":7,"column_start":5,"is_primary":true}]}}"#,
+            r#"{"reason":"compiler-message","message":{"message":"aborting due to 1 previous error","code":null,"level":"error","spans":[]}}"#,
+            r#"{"reason":"build-finished","success":false}"#,
+        ]
+        .join("\n");
+
+        let diagnostics = collect_diagnostics(&stdout, true);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0425"));
+        assert_eq!(diagnostics[1].level, "warning");
+
+        let errors_only = collect_diagnostics(&stdout, false);
+        assert_eq!(errors_only.len(), 1);
+    }
+}
@@ -0,0 +1,137 @@
+//! Shared helpers for tools that drive cargo with `--message-format=json`
+
+use crate::error::ToolError;
+use serde_json::Value;
+use std::process::Stdio;
+use tokio::process::Command;
+use tokio::time::{timeout, Duration};
+
+/// A compiler diagnostic parsed from a cargo `compiler-message` line
+#[derive(Debug, Clone, PartialEq)]
+pub struct CargoDiagnostic {
+    pub file: Option<String>,
+    pub line: Option<u64>,
+    pub column: Option<u64>,
+    pub level: String,
+    pub message: String,
+    pub code: Option<String>,
+    /// Source text of the span's lines, trimmed
+    pub snippet: Option<String>,
+}
+
+impl CargoDiagnostic {
+    /// Whether this diagnostic is an error (as opposed to a warning or note)
+    pub fn is_error(&self) -> bool {
+        self.level.starts_with("error")
+    }
+
+    /// "file:line:col" location, or "<unknown>" for diagnostics without a span
+    pub fn location(&self) -> String {
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(col)) => format!("{}:{}:{}", file, line, col),
+            (Some(file), Some(line), None) => format!("{}:{}", file, line),
+            (Some(file), None, _) => file.clone(),
+            _ => "<unknown>".to_string(),
+        }
+    }
+}
+
+/// Output of a finished cargo invocation
+pub struct CargoOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub success: bool,
+}
+
+/// Run cargo with the given arguments and wait for it to finish
+pub async fn run_cargo(args: &[String], working_dir: Option<&str>, timeout_secs: u64) -> Result<CargoOutput, ToolError> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(args);
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    // A timed-out cargo must not keep building in the background
+    cmd.kill_on_drop(true);
+
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let output = timeout(Duration::from_secs(timeout_secs), cmd.output())
+        .await
+        .map_err(|_| ToolError::CommandTimeout)?
+        .map_err(|e| ToolError::command_failed(format!("Failed to run cargo (is it installed?): {}", e)))?;
+
+    Ok(CargoOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        success: output.status.success(),
+    })
+}
+
+/// Parse one line of `--message-format=json` output into a diagnostic.
+/// Returns None for non-JSON lines and for non-diagnostic messages (artifacts, build-finished).
+pub fn parse_compiler_message(line: &str) -> Option<CargoDiagnostic> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+
+    let value: Value = serde_json::from_str(line).ok()?;
+    if value.get("reason")?.as_str()? != "compiler-message" {
+        return None;
+    }
+
+    let message = value.get("message")?;
+    let level = message.get("level")?.as_str()?.to_string();
+    let text = message.get("message")?.as_str()?.to_string();
+    let code = message
+        .get("code")
+        .and_then(|c| c.get("code"))
+        .and_then(|c| c.as_str())
+        .map(|c| c.to_string());
+
+    // Prefer the primary span, falling back to the first one
+    let spans = message.get("spans").and_then(|s| s.as_array());
+    let span = spans.and_then(|spans| {
+        spans
+            .iter()
+            .find(|s| s.get("is_primary").and_then(|p| p.as_bool()).unwrap_or(false))
+            .or_else(|| spans.first())
+    });
+
+    Some(CargoDiagnostic {
+        file: span.and_then(|s| s.get("file_name")).and_then(|f| f.as_str()).map(|f| f.to_string()),
+        line: span.and_then(|s| s.get("line_start")).and_then(|l| l.as_u64()),
+        column: span.and_then(|s| s.get("column_start")).and_then(|c| c.as_u64()),
+        level,
+        message: text,
+        code,
+        snippet: span.and_then(|s| s.get("text")).and_then(|t| t.as_array()).map(|lines| {
+            lines
+                .iter()
+                .filter_map(|l| l.get("text").and_then(|t| t.as_str()))
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("\n")
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compiler_message() {
+        let line = r#"{"reason":"compiler-message","package_id":"x","message":{"message":"mismatched types","code":{"code":"E0308","explanation":null},"level":"error","spans":[{"file_name":"src/other.rs","line_start":1,"column_start":1,"is_primary":false},{"file_name":"src/main.rs","line_start":3,"column_start":13,"is_primary":true}],"rendered":"error[E0308]"}}"#;
+        let diag = parse_compiler_message(line).unwrap();
+        assert_eq!(diag.file.as_deref(), Some("src/main.rs"));
+        assert_eq!(diag.line, Some(3));
+        assert_eq!(diag.code.as_deref(), Some("E0308"));
+        assert!(diag.is_error());
+        assert_eq!(diag.location(), "src/main.rs:3:13");
+
+        assert!(parse_compiler_message(r#"{"reason":"build-finished","success":true}"#).is_none());
+        assert!(parse_compiler_message("test foo ... ok").is_none());
+    }
+}
@@ -0,0 +1,280 @@
+//! Dependency management through `cargo add` and `cargo remove`
+//!
+//! cargo resolves versions against the registry, keeps the manifest's
+//! formatting and refuses unknown crates or features, which the model can't do
+//! reliably by editing Cargo.toml. The result lists cargo's own report and the
+//! diff of the manifest; running the same call twice changes nothing.
+
+use crate::diff::UnifiedDiff;
+use crate::error::ToolError;
+use crate::tools::{agent_ignore, cargo_common, context_dirs, write_root};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Timeout for cargo add/remove, which may need to update the registry index
+const TIMEOUT_SECS: u64 = 300;
+
+/// Which dependency table to change
+#[derive(Debug, Default, Deserialize)]
+pub struct DependencyTable {
+    /// [dev-dependencies]
+    pub dev: Option<bool>,
+    /// [build-dependencies]
+    pub build: Option<bool>,
+}
+
+impl DependencyTable {
+    fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.dev.unwrap_or(false) {
+            args.push("--dev".to_string());
+        }
+        if self.build.unwrap_or(false) {
+            args.push("--build".to_string());
+        }
+        args
+    }
+}
+
+/// Arguments for the AddDependency tool
+#[derive(Debug, Deserialize)]
+pub struct AddDependencyArgs {
+    /// Crate to add
+    pub crate_name: String,
+    /// Version requirement (default: latest compatible)
+    pub version: Option<String>,
+    /// Features to enable
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Disable the crate's default features
+    pub no_default_features: Option<bool>,
+    /// Mark the dependency optional
+    pub optional: Option<bool>,
+    #[serde(flatten)]
+    pub table: DependencyTable,
+    /// Package to change in a workspace (cargo -p)
+    pub package: Option<String>,
+    /// Optional working directory (the crate or workspace root)
+    pub working_dir: Option<String>,
+}
+
+/// Arguments for the RemoveDependency tool
+#[derive(Debug, Deserialize)]
+pub struct RemoveDependencyArgs {
+    /// Crate to remove
+    pub crate_name: String,
+    #[serde(flatten)]
+    pub table: DependencyTable,
+    /// Package to change in a workspace (cargo -p)
+    pub package: Option<String>,
+    /// Optional working directory (the crate or workspace root)
+    pub working_dir: Option<String>,
+}
+
+/// Tool to add a dependency with `cargo add`
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct AddDependency;
+
+/// Tool to remove a dependency with `cargo remove`
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RemoveDependency;
+
+impl Tool for AddDependency {
+    const NAME: &'static str = "add_dependency";
+    type Error = ToolError;
+    type Args = AddDependencyArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Add a dependency to Cargo.toml with `cargo add`, which picks a real version and checks feature names. Returns cargo's report and the manifest diff. Prefer this over editing Cargo.toml.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "crate_name": {"type": "string", "description": "Crate to add, e.g. 'serde'"},
+                    "version": {"type": "string", "description": "Version requirement, e.g. '1.0' (default: latest)"},
+                    "features": {"type": "array", "items": {"type": "string"}, "description": "Features to enable"},
+                    "no_default_features": {"type": "boolean", "description": "Disable the crate's default features (default: false)"},
+                    "optional": {"type": "boolean", "description": "Add as an optional dependency (default: false)"},
+                    "dev": {"type": "boolean", "description": "Add to [dev-dependencies] (default: false)"},
+                    "build": {"type": "boolean", "description": "Add to [build-dependencies] (default: false)"},
+                    "package": {"type": "string", "description": "Package to change in a workspace (cargo -p)"},
+                    "working_dir": {"type": "string", "description": "Optional working directory (defaults to project root)"}
+                },
+                "required": ["crate_name"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        check_argument("crate name", &args.crate_name)?;
+        let mut spec = args.crate_name.trim().to_string();
+        if let Some(version) = args.version.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            check_argument("version", version)?;
+            spec = format!("{}@{}", spec, version);
+        }
+
+        let mut cargo_args = vec!["add".to_string(), spec];
+        let features: Vec<&str> = args.features.iter().map(|f| f.trim()).filter(|f| !f.is_empty()).collect();
+        for feature in &features {
+            check_argument("feature", feature)?;
+        }
+        if !features.is_empty() {
+            cargo_args.push("--features".to_string());
+            cargo_args.push(features.join(","));
+        }
+        if args.no_default_features.unwrap_or(false) {
+            cargo_args.push("--no-default-features".to_string());
+        }
+        if args.optional.unwrap_or(false) {
+            cargo_args.push("--optional".to_string());
+        }
+        cargo_args.extend(args.table.cargo_args());
+
+        run(cargo_args, args.package.as_deref(), args.working_dir.as_deref()).await
+    }
+}
+
+impl Tool for RemoveDependency {
+    const NAME: &'static str = "remove_dependency";
+    type Error = ToolError;
+    type Args = RemoveDependencyArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Remove a dependency from Cargo.toml with `cargo remove`. Returns cargo's report and the manifest diff.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "crate_name": {"type": "string", "description": "Crate to remove"},
+                    "dev": {"type": "boolean", "description": "Remove from [dev-dependencies] (default: false)"},
+                    "build": {"type": "boolean", "description": "Remove from [build-dependencies] (default: false)"},
+                    "package": {"type": "string", "description": "Package to change in a workspace (cargo -p)"},
+                    "working_dir": {"type": "string", "description": "Optional working directory (defaults to project root)"}
+                },
+                "required": ["crate_name"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        check_argument("crate name", &args.crate_name)?;
+        let mut cargo_args = vec!["remove".to_string(), args.crate_name.trim().to_string()];
+        cargo_args.extend(args.table.cargo_args());
+
+        run(cargo_args, args.package.as_deref(), args.working_dir.as_deref()).await
+    }
+}
+
+/// Reject empty values and values cargo would read as options
+fn check_argument(what: &str, value: &str) -> Result<(), ToolError> {
+    let value = value.trim();
+    if value.is_empty() || value.starts_with('-') || value.contains(char::is_whitespace) {
+        return Err(ToolError::invalid_arguments(format!("Invalid {}: '{}'", what, value)));
+    }
+    Ok(())
+}
+
+/// Run cargo add/remove and report the outcome with the manifest diff
+async fn run(mut cargo_args: Vec<String>, package: Option<&str>, working_dir: Option<&str>) -> Result<String, ToolError> {
+    if let Some(package) = package {
+        check_argument("package", package)?;
+        cargo_args.push("-p".to_string());
+        cargo_args.push(package.to_string());
+    }
+
+    // cargo rewrites the manifest itself, so it gets the checks write_file applies
+    let manifest = manifest_path(package, working_dir).await.ok_or_else(|| {
+        ToolError::command_failed("Could not locate the Cargo.toml to change; pass the crate directory as working_dir")
+    })?;
+    let manifest_str = manifest.to_string_lossy();
+    agent_ignore::check(&manifest_str)?;
+    context_dirs::check_writable(&manifest_str)?;
+    write_root::check(&manifest_str)?;
+    let before = std::fs::read_to_string(&manifest).ok();
+
+    let output = cargo_common::run_cargo(&cargo_args, working_dir, TIMEOUT_SECS).await?;
+    let report = cargo_report(&output.stderr);
+    if !output.success {
+        return Err(ToolError::command_failed(format!("cargo {} failed:\n{}", cargo_args[0], report)));
+    }
+
+    let mut result = format!("cargo {} succeeded:\n{}\n", cargo_args[0], report);
+    if let Some(before) = before
+        && let Ok(after) = std::fs::read_to_string(&manifest)
+    {
+        let label = manifest.display().to_string();
+        let diff = UnifiedDiff::from_texts(label.clone(), &before, &after);
+        if diff.has_changes() {
+            result.push_str(&format!("\n{}", diff.to_unified_string(&label, &label, 2)));
+        } else {
+            result.push_str(&format!("\nNo changes to {} (already up to date).", label));
+        }
+    }
+    Ok(result)
+}
+
+/// The manifest cargo changes: the named workspace member's, or the nearest one
+async fn manifest_path(package: Option<&str>, working_dir: Option<&str>) -> Option<PathBuf> {
+    let Some(package) = package else {
+        let args = ["locate-project".to_string(), "--message-format=plain".to_string()];
+        let output = cargo_common::run_cargo(&args, working_dir, 30).await.ok()?;
+        return output.success.then(|| PathBuf::from(output.stdout.trim()));
+    };
+
+    let args = ["metadata".to_string(), "--no-deps".to_string(), "--format-version=1".to_string()];
+    let output = cargo_common::run_cargo(&args, working_dir, 60).await.ok()?;
+    let metadata: Value = serde_json::from_str(&output.stdout).ok()?;
+    metadata["packages"]
+        .as_array()?
+        .iter()
+        .find(|p| p["name"].as_str() == Some(package))
+        .and_then(|p| p["manifest_path"].as_str())
+        .map(|path| Path::new(path).to_path_buf())
+}
+
+/// cargo's status lines without the registry update noise
+fn cargo_report(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| {
+            let trimmed = line.trim_start();
+            !trimmed.is_empty() && !trimmed.starts_with("Updating ") && !trimmed.starts_with("Blocking ")
+        })
+        .collect();
+    if lines.is_empty() {
+        "(no output)".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_report() {
+        let stderr = "    Updating crates.io index\n      Adding serde v1.0.210 to dependencies\n             Features:\n             + derive\n             - alloc\n";
+        assert_eq!(
+            cargo_report(stderr),
+            "      Adding serde v1.0.210 to dependencies\n             Features:\n             + derive\n             - alloc"
+        );
+        assert_eq!(cargo_report("    Updating crates.io index\n"), "(no output)");
+    }
+
+    #[test]
+    fn test_check_argument() {
+        assert!(check_argument("crate name", "serde_json").is_ok());
+        assert!(check_argument("crate name", "--path=/tmp").is_err());
+        assert!(check_argument("feature", "a b").is_err());
+        assert!(check_argument("version", "").is_err());
+    }
+}
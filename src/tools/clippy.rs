@@ -0,0 +1,233 @@
+//! Linting Rust files after the agent writes them (--lint-on-write)
+//!
+//! clippy is run on the package the file belongs to, and only diagnostics
+//! in that file that haven't been reported before are passed back, so the
+//! agent isn't handed the same pre-existing warnings after every edit.
+//! Warnings the package already had before the agent's first edit to it
+//! are recorded as a baseline and never reported.
+
+use crate::error::ToolError;
+use crate::tools::cargo_common::{self, CargoDiagnostic, CargoOutput};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// How long clippy may take on one package
+const CLIPPY_TIMEOUT_SECS: u64 = 300;
+
+/// Most diagnostics listed in one note
+const MAX_REPORTED: usize = 20;
+
+/// Lines of cargo's error output kept when clippy couldn't run
+const MAX_ERROR_LINES: usize = 10;
+
+/// Manifest of the package `path` belongs to (the nearest Cargo.toml with a
+/// [package] section, skipping virtual workspace manifests)
+fn package_manifest(path: &Path) -> Option<PathBuf> {
+    path.ancestors().skip(1).map(|dir| dir.join("Cargo.toml")).find(|manifest| {
+        std::fs::read_to_string(manifest)
+            .map(|contents| contents.lines().any(|line| line.trim() == "[package]"))
+            .unwrap_or(false)
+    })
+}
+
+/// Clippy diagnostics already accounted for in a session
+#[derive(Debug, Default)]
+pub struct ReportedLints {
+    /// Keys of diagnostics reported to the model or present in a baseline
+    seen: HashSet<String>,
+    /// Manifests of packages whose pre-existing warnings were recorded
+    baselined: HashSet<PathBuf>,
+}
+
+/// Identifies a diagnostic across runs by its lint, message and source
+/// text; line numbers are left out because they move with every edit above
+/// them
+fn diagnostic_key(diag: &CargoDiagnostic) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        diag.file.as_deref().unwrap_or_default(),
+        diag.code.as_deref().unwrap_or_default(),
+        diag.message,
+        diag.snippet.as_deref().unwrap_or_default()
+    )
+}
+
+/// Diagnostics from clippy's JSON output with their keys. Identical
+/// diagnostics are numbered in order, so a second copy of a lint on the
+/// same source text elsewhere still gets a key of its own.
+fn keyed_diagnostics(stdout: &str) -> Vec<(String, CargoDiagnostic)> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    stdout
+        .lines()
+        .filter_map(cargo_common::parse_compiler_message)
+        .map(|diag| {
+            let key = diagnostic_key(&diag);
+            let count = occurrences.entry(key.clone()).or_default();
+            *count += 1;
+            (format!("{}#{}", key, count), diag)
+        })
+        .collect()
+}
+
+/// Diagnostics in `file` from clippy's JSON output that aren't in `seen`,
+/// adding them to it. Diagnostic paths are relative to the workspace root.
+fn new_diagnostics(stdout: &str, file: &Path, seen: &mut HashSet<String>) -> Vec<CargoDiagnostic> {
+    let mut found: Vec<CargoDiagnostic> = keyed_diagnostics(stdout)
+        .into_iter()
+        .filter(|(_, diag)| diag.file.as_deref().is_some_and(|name| file.ends_with(name)))
+        .filter(|(key, _)| seen.insert(key.clone()))
+        .map(|(_, diag)| diag)
+        .collect();
+    found.sort_by(|a, b| b.is_error().cmp(&a.is_error()).then_with(|| a.line.cmp(&b.line)));
+    found
+}
+
+/// Run clippy with JSON output on the package at `manifest`
+async fn run_clippy(manifest: &Path) -> Result<CargoOutput, ToolError> {
+    let args = vec![
+        "clippy".to_string(),
+        "--message-format=json".to_string(),
+        "--manifest-path".to_string(),
+        manifest.display().to_string(),
+    ];
+    let working_dir = manifest.parent().map(|dir| dir.display().to_string());
+    cargo_common::run_cargo(&args, working_dir.as_deref(), CLIPPY_TIMEOUT_SECS).await
+}
+
+/// Record the warnings the package of `path` has before the agent first
+/// edits it, so lint_file only reports what the agent introduced. Runs
+/// clippy once per package; later calls return immediately.
+pub async fn baseline(path: &Path, reported: &mut ReportedLints) {
+    let Some(manifest) = package_manifest(path) else {
+        return;
+    };
+    if !reported.baselined.insert(manifest.clone()) {
+        return;
+    }
+    if let Ok(output) = run_clippy(&manifest).await {
+        reported.seen.extend(keyed_diagnostics(&output.stdout).into_iter().map(|(key, _)| key));
+    }
+}
+
+/// Run clippy on the package of a Rust file the agent just changed. Returns
+/// a note for the tool result listing new warnings in that file.
+pub async fn lint_file(path: &Path, reported: &mut ReportedLints) -> String {
+    let Some(manifest) = package_manifest(path) else {
+        return "[lint-on-write: not part of a Cargo package; clippy was not run]".to_string();
+    };
+
+    let output = match run_clippy(&manifest).await {
+        Ok(output) => output,
+        Err(e) => return format!("[lint-on-write: clippy did not finish: {}]", e),
+    };
+
+    let diagnostics = new_diagnostics(&output.stdout, path, &mut reported.seen);
+    if diagnostics.is_empty() {
+        if !output.success && !output.stdout.lines().any(|line| cargo_common::parse_compiler_message(line).is_some()) {
+            // cargo itself failed (clippy not installed, bad manifest, ...)
+            let errors: Vec<&str> = output.stderr.lines().filter(|line| !line.trim().is_empty()).take(MAX_ERROR_LINES).collect();
+            return format!("[lint-on-write: clippy could not run:\n{}]", errors.join("\n"));
+        }
+        return "[lint-on-write: no new clippy warnings in this file]".to_string();
+    }
+
+    let mut note = format!("[lint-on-write: {} new clippy diagnostic(s) in this file; fix them before moving on:", diagnostics.len());
+    for diag in diagnostics.iter().take(MAX_REPORTED) {
+        let code = diag.code.as_ref().map(|c| format!("[{}]", c)).unwrap_or_default();
+        note.push_str(&format!("\n{}{} {}: {}", diag.level, code, diag.location(), diag.message));
+    }
+    if diagnostics.len() > MAX_REPORTED {
+        note.push_str(&format!("\n... and {} more", diagnostics.len() - MAX_REPORTED));
+    }
+    note.push(']');
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(file: &str, line: u64, code: &str, text: &str) -> String {
+        message_on(file, line, code, text, "return x;")
+    }
+
+    fn message_on(file: &str, line: u64, code: &str, text: &str, source: &str) -> String {
+        format!(
+            r#"{{"reason":"compiler-message","message":{{"message":"{}","code":{{"code":"{}"}},"level":"warning","spans":[{{"file_name":"{}","line_start":{},"column_start":5,"is_primary":true,"text":[{{"text":"    {}"}}]}}]}}}}"#,
+            text, code, file, line, source
+        )
+    }
+
+    #[test]
+    fn test_new_diagnostics() {
+        let stdout = [
+            message("src/lib.rs", 3, "clippy::needless_return", "unneeded `return` statement"),
+            message("src/other.rs", 7, "dead_code", "function `f` is never used"),
+            r#"{"reason":"build-finished","success":true}"#.to_string(),
+        ]
+        .join("\n");
+        let file = Path::new("/work/crate/src/lib.rs");
+        let mut seen = HashSet::new();
+
+        // Only the edited file counts
+        let found = new_diagnostics(&stdout, file, &mut seen);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].location(), "src/lib.rs:3:5");
+
+        // Already reported, even after moving to another line
+        let moved = message("src/lib.rs", 9, "clippy::needless_return", "unneeded `return` statement");
+        assert!(new_diagnostics(&moved, file, &mut seen).is_empty());
+
+        let other = message("src/lib.rs", 12, "clippy::len_zero", "length comparison to zero");
+        assert_eq!(new_diagnostics(&other, file, &mut seen).len(), 1);
+    }
+
+    #[test]
+    fn test_identical_lint_elsewhere_is_new() {
+        let file = Path::new("/work/crate/src/lib.rs");
+        let first = message_on("src/lib.rs", 3, "clippy::needless_return", "unneeded `return` statement", "return a;");
+        let mut seen = HashSet::new();
+        assert_eq!(new_diagnostics(&first, file, &mut seen).len(), 1);
+
+        // Same lint and message on different source text
+        let both = [
+            message_on("src/lib.rs", 5, "clippy::needless_return", "unneeded `return` statement", "return a;"),
+            message_on("src/lib.rs", 20, "clippy::needless_return", "unneeded `return` statement", "return b;"),
+        ]
+        .join("\n");
+        let found = new_diagnostics(&both, file, &mut seen);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].line, Some(20));
+
+        // Same source text too: the extra copy is counted
+        let copies = [
+            message_on("src/lib.rs", 5, "clippy::needless_return", "unneeded `return` statement", "return a;"),
+            message_on("src/lib.rs", 9, "clippy::needless_return", "unneeded `return` statement", "return a;"),
+            message_on("src/lib.rs", 20, "clippy::needless_return", "unneeded `return` statement", "return b;"),
+        ]
+        .join("\n");
+        assert_eq!(new_diagnostics(&copies, file, &mut seen).len(), 1);
+    }
+
+    #[test]
+    fn test_baseline_warnings_are_not_new() {
+        let before = [
+            message("src/lib.rs", 3, "clippy::needless_return", "unneeded `return` statement"),
+            message("src/other.rs", 7, "dead_code", "function `f` is never used"),
+        ]
+        .join("\n");
+        let mut reported = ReportedLints::default();
+        reported.seen.extend(keyed_diagnostics(&before).into_iter().map(|(key, _)| key));
+
+        // The first lint after an edit only reports what the edit added
+        let after = [
+            message("src/lib.rs", 4, "clippy::needless_return", "unneeded `return` statement"),
+            message("src/lib.rs", 10, "clippy::len_zero", "length comparison to zero"),
+        ]
+        .join("\n");
+        let found = new_diagnostics(&after, Path::new("/work/crate/src/lib.rs"), &mut reported.seen);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].code.as_deref(), Some("clippy::len_zero"));
+        assert!(new_diagnostics(&before, Path::new("/work/crate/src/other.rs"), &mut reported.seen).is_empty());
+    }
+}
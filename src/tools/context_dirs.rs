@@ -0,0 +1,75 @@
+//! Additional read-only context directories
+//!
+//! Directories passed with `--context-dir` are searched by grep and glob and
+//! indexed by the vecdb alongside the working directory, so the agent can read
+//! code that lives outside the project (a sibling crate, a dependency checkout).
+//! They are read-only: the file-writing tools refuse paths inside them.
+
+use crate::error::ToolError;
+use crate::tools::agent_ignore;
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref CONTEXT_DIRS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+}
+
+/// Register the context directories, replacing any previous ones.
+/// Returns the directories that were skipped because they don't exist.
+pub fn set(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let (valid, missing): (Vec<PathBuf>, Vec<PathBuf>) = dirs
+        .iter()
+        .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.clone()))
+        .partition(|dir| dir.is_dir());
+
+    if let Ok(mut guard) = CONTEXT_DIRS.write() {
+        *guard = valid;
+    }
+    missing
+}
+
+/// The registered context directories (absolute)
+pub fn dirs() -> Vec<PathBuf> {
+    CONTEXT_DIRS.read().map(|guard| guard.clone()).unwrap_or_default()
+}
+
+/// Whether a path (absolute, or relative to the process directory) lies inside a context directory
+fn is_read_only(path: &Path, context_dirs: &[PathBuf]) -> bool {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => return false,
+        }
+    };
+    let absolute = agent_ignore::normalize(&absolute);
+    context_dirs.iter().any(|dir| absolute.starts_with(dir))
+}
+
+/// Reject writes to a path inside a context directory
+pub fn check_writable(path: &str) -> Result<(), ToolError> {
+    if is_read_only(Path::new(path), &dirs()) {
+        return Err(ToolError::permission_denied(format!(
+            "{} is inside a read-only context directory; writes are limited to the working directory",
+            path
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_read_only() {
+        let dirs = vec![PathBuf::from("/deps/other-crate")];
+        assert!(is_read_only(Path::new("/deps/other-crate/src/lib.rs"), &dirs));
+        assert!(is_read_only(Path::new("/deps/other-crate"), &dirs));
+        assert!(!is_read_only(Path::new("/deps/other-crate-2/lib.rs"), &dirs));
+        assert!(!is_read_only(Path::new("/deps/other-crate/../mine.rs"), &dirs));
+        assert!(!is_read_only(Path::new("/project/src/main.rs"), &dirs));
+    }
+}
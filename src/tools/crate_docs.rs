@@ -0,0 +1,286 @@
+//! Rust crate documentation from docs.rs
+//!
+//! Without an item the tool returns the crate's front page (its docs plus the
+//! module and item lists). An item path is looked up in the crate's `all.html`
+//! index, so `Mutex`, `sync::Mutex` and `tokio::sync::Mutex` all find the same
+//! page, whatever kind of item it is. Pages go through web_fetch's readable
+//! extraction and the web cache.
+
+use crate::error::ToolError;
+use crate::tools::web_fetch::{html_to_text, FetchMode};
+use crate::tools::{network, web_cache};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Longest documentation returned, in characters
+const MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// Most candidates listed when an item path is ambiguous
+const MAX_CANDIDATES: usize = 20;
+
+/// Rustdoc item kinds, as they appear in page names (`struct.Mutex.html`)
+const ITEM_KINDS: &[&str] = &[
+    "struct", "enum", "trait", "fn", "macro", "type", "constant", "static", "union", "attr", "derive", "traitalias",
+];
+
+/// Arguments for the CrateDocs tool
+#[derive(Debug, Deserialize)]
+pub struct CrateDocsArgs {
+    /// Crate name as published on crates.io
+    pub crate_name: String,
+    /// Item to look up, e.g. `sync::Mutex` (default: the crate's front page)
+    pub item: Option<String>,
+    /// Crate version (default: latest)
+    pub version: Option<String>,
+}
+
+/// Tool to read a crate's API documentation from docs.rs
+#[derive(Debug, Default)]
+pub struct CrateDocs;
+
+/// An entry of a crate's all-items index
+#[derive(Debug, Clone, PartialEq)]
+struct DocItem {
+    /// Path inside the crate, e.g. `sync::Mutex`
+    path: String,
+    /// Rustdoc item kind, e.g. `struct`
+    kind: String,
+    /// Page relative to the crate root, e.g. `sync/struct.Mutex.html`
+    href: String,
+}
+
+impl Tool for CrateDocs {
+    const NAME: &'static str = "crate_docs";
+    type Error = ToolError;
+    type Args = CrateDocsArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Read a Rust crate's API documentation from docs.rs: signatures and docs of an item (struct, trait, function, macro, ...), or the crate overview when no item is given. Use this instead of guessing an unfamiliar crate's API.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "crate_name": {
+                        "type": "string",
+                        "description": "Crate name as on crates.io, e.g. 'tokio' or 'serde_json'"
+                    },
+                    "item": {
+                        "type": "string",
+                        "description": "Item path inside the crate, e.g. 'sync::Mutex' or just 'Mutex' (default: crate overview)"
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "Crate version, e.g. '1.40.0' (default: latest)"
+                    }
+                },
+                "required": ["crate_name"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        network::check()?;
+
+        let crate_name = args.crate_name.trim();
+        if crate_name.is_empty() || !crate_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(ToolError::invalid_arguments(format!("Invalid crate name: '{}'", args.crate_name)));
+        }
+        let version = args.version.as_deref().map(str::trim).filter(|v| !v.is_empty()).unwrap_or("latest");
+        if version.contains(['/', '?', '#']) {
+            return Err(ToolError::invalid_arguments(format!("Invalid version: '{}'", version)));
+        }
+
+        // The library target is the crate name with dashes as underscores
+        let lib = crate_name.replace('-', "_");
+        let base = format!("https://docs.rs/{}/{}/{}/", crate_name, version, lib);
+
+        let Some(item) = args.item.as_deref().map(str::trim).filter(|i| !i.is_empty()) else {
+            let page = fetch_page(&format!("{}index.html", base)).await?;
+            return Ok(format_docs(&format!("Crate {} ({})", crate_name, version), &base, &page));
+        };
+
+        let index = fetch_page(&format!("{}all.html", base)).await?;
+        let items = parse_all_items(&index);
+        let matches = find_items(&items, item, &lib);
+        match matches.as_slice() {
+            [] => {
+                let name = item.rsplit("::").next().unwrap_or(item).to_lowercase();
+                let similar: Vec<String> = items.iter()
+                    .filter(|i| i.path.to_lowercase().contains(&name))
+                    .take(MAX_CANDIDATES)
+                    .map(|i| format!("  {} ({})", i.path, i.kind))
+                    .collect();
+                if similar.is_empty() {
+                    Ok(format!("No item '{}' in {} {}.", item, crate_name, version))
+                } else {
+                    Ok(format!("No item '{}' in {} {}. Similar items:\n{}", item, crate_name, version, similar.join("\n")))
+                }
+            }
+            [found] => {
+                let url = format!("{}{}", base, found.href);
+                let page = fetch_page(&url).await?;
+                Ok(format_docs(&format!("{} {}::{} ({})", found.kind, lib, found.path, version), &url, &page))
+            }
+            several => {
+                let list: Vec<String> = several.iter()
+                    .take(MAX_CANDIDATES)
+                    .map(|i| format!("  {} ({})", i.path, i.kind))
+                    .collect();
+                Ok(format!(
+                    "{} items in {} match '{}'; call again with one of these paths:\n{}",
+                    several.len(), crate_name, item, list.join("\n")
+                ))
+            }
+        }
+    }
+}
+
+/// Fetch a docs.rs page, through the web cache
+async fn fetch_page(url: &str) -> Result<String, ToolError> {
+    if let Some((cached, _)) = web_cache::get("crate_docs", url) {
+        return Ok(cached);
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent("agent-t/1.0 (Terminal AI Agent)")
+        .timeout(Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::limited(5))
+        .build()
+        .map_err(|e| ToolError::network_error(format!("Failed to create HTTP client: {}", e)))?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| ToolError::network_error(format!("Failed to fetch {}: {}", url, e)))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(ToolError::http_error(format!(
+            "Not found on docs.rs: {} (check the crate name and version; crates whose library name differs from the package name are not supported)",
+            url
+        )));
+    }
+    if !status.is_success() {
+        return Err(ToolError::http_error(format!("HTTP {} fetching {}", status.as_u16(), url)));
+    }
+
+    let html = response
+        .text()
+        .await
+        .map_err(|e| ToolError::network_error(format!("Failed to read response: {}", e)))?;
+    web_cache::put("crate_docs", url, &html);
+    Ok(html)
+}
+
+/// Readable text of a rustdoc page under a title line, cut to `MAX_OUTPUT_CHARS`
+fn format_docs(title: &str, url: &str, html: &str) -> String {
+    let text = html_to_text(html, FetchMode::Readable);
+    let text = text.trim();
+    let truncated: String = text.chars().take(MAX_OUTPUT_CHARS).collect();
+    let note = if truncated.len() < text.len() {
+        format!("\n\n[truncated at {} characters; use web_fetch with an offset on {} to read on]", MAX_OUTPUT_CHARS, url)
+    } else {
+        String::new()
+    };
+    format!("{}\n{}\n\n{}{}", title, url, truncated, note)
+}
+
+/// Items listed on a rustdoc `all.html` page: links to `kind.Name.html` pages
+fn parse_all_items(html: &str) -> Vec<DocItem> {
+    let mut items: Vec<DocItem> = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = html[pos..].find("<a href=\"") {
+        let href_start = pos + found + 9;
+        let Some(href_len) = html[href_start..].find('"') else {
+            break;
+        };
+        let href = &html[href_start..href_start + href_len];
+        pos = href_start + href_len;
+
+        let Some(text_start) = html[pos..].find('>').map(|i| pos + i + 1) else {
+            break;
+        };
+        let Some(text_len) = html[text_start..].find("</a>") else {
+            break;
+        };
+        let path = &html[text_start..text_start + text_len];
+
+        let page = href.rsplit('/').next().unwrap_or(href);
+        let Some((kind, _)) = page.split_once('.') else {
+            continue;
+        };
+        if href.contains("://") || !page.ends_with(".html") || !ITEM_KINDS.contains(&kind) || path.contains('<') {
+            continue;
+        }
+        if !items.iter().any(|i| i.href == href) {
+            items.push(DocItem { path: path.to_string(), kind: kind.to_string(), href: href.to_string() });
+        }
+    }
+    items
+}
+
+/// Items whose path is `query`, or ends with it, ignoring case and a leading
+/// `crate::` or `<lib>::`
+fn find_items<'a>(items: &'a [DocItem], query: &str, lib: &str) -> Vec<&'a DocItem> {
+    let query = query.to_lowercase();
+    let lib_prefix = format!("{}::", lib.to_lowercase());
+    let query = query
+        .strip_prefix(&lib_prefix)
+        .or_else(|| query.strip_prefix("crate::"))
+        .unwrap_or(&query);
+
+    let exact: Vec<&DocItem> = items.iter().filter(|i| i.path.to_lowercase() == query).collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    let suffix = format!("::{}", query);
+    items.iter().filter(|i| i.path.to_lowercase().ends_with(&suffix)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_HTML: &str = r#"<nav class="sidebar"><a href="../tokio/index.html">tokio</a><a href="https://crates.io/crates/tokio">crates.io</a></nav>
+<h3 id="structs">Structs</h3><ul class="all-items">
+<li><a href="sync/struct.Mutex.html">sync::Mutex</a></li>
+<li><a href="sync/struct.MutexGuard.html">sync::MutexGuard</a></li>
+<li><a href="sync/mpsc/struct.Sender.html">sync::mpsc::Sender</a></li>
+<li><a href="sync/broadcast/struct.Sender.html">sync::broadcast::Sender</a></li>
+</ul><h3 id="functions">Functions</h3><ul class="all-items">
+<li><a href="fn.spawn.html">spawn</a></li>
+<li><a href="task/fn.spawn.html">task::spawn</a></li>
+</ul><h3 id="macros">Macros</h3><ul class="all-items"><li><a href="macro.select.html">select</a></li></ul>"#;
+
+    #[test]
+    fn test_parse_all_items() {
+        let items = parse_all_items(ALL_HTML);
+        assert_eq!(items.len(), 7);
+        assert_eq!(items[0], DocItem {
+            path: "sync::Mutex".to_string(),
+            kind: "struct".to_string(),
+            href: "sync/struct.Mutex.html".to_string(),
+        });
+        assert_eq!(items[6].kind, "macro");
+    }
+
+    #[test]
+    fn test_find_items() {
+        let items = parse_all_items(ALL_HTML);
+        let paths = |query: &str| find_items(&items, query, "tokio").iter().map(|i| i.path.clone()).collect::<Vec<_>>();
+
+        assert_eq!(paths("Mutex"), ["sync::Mutex"]);
+        assert_eq!(paths("tokio::sync::mutex"), ["sync::Mutex"]);
+        assert_eq!(paths("crate::select"), ["select"]);
+        // An exact top-level match wins over deeper items with the same name
+        assert_eq!(paths("spawn"), ["spawn"]);
+        assert_eq!(paths("Sender"), ["sync::mpsc::Sender", "sync::broadcast::Sender"]);
+        assert!(paths("RwLock").is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::diff::UnifiedDiff;
+use crate::error::ToolError;
+use crate::tools::agent_ignore;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::fs;
+
+/// Arguments for the DiffFiles tool
+#[derive(Debug, Deserialize)]
+pub struct DiffFilesArgs {
+    /// Path to the original file
+    pub old_path: String,
+    /// Path to the changed file (one of new_path / new_content is required)
+    pub new_path: Option<String>,
+    /// Inline text to compare against old_path instead of a second file
+    pub new_content: Option<String>,
+    /// Lines of context around each change (default: 3)
+    pub context_lines: Option<usize>,
+}
+
+/// Tool to produce a unified diff between two files, or a file and a string
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct DiffFiles;
+
+impl Tool for DiffFiles {
+    const NAME: &'static str = "diff_files";
+    type Error = ToolError;
+    type Args = DiffFilesArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Compare two files, or a file against inline text, and return a unified diff.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "old_path": {
+                        "type": "string",
+                        "description": "Path to the original file"
+                    },
+                    "new_path": {
+                        "type": "string",
+                        "description": "Path to the changed file"
+                    },
+                    "new_content": {
+                        "type": "string",
+                        "description": "Text to compare against old_path (instead of new_path)"
+                    },
+                    "context_lines": {
+                        "type": "integer",
+                        "description": "Lines of context around each change (default: 3)"
+                    }
+                },
+                "required": ["old_path"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let old_text = read_text(&args.old_path).await?;
+
+        let (new_label, new_text) = match (args.new_path, args.new_content) {
+            (Some(path), None) => {
+                let text = read_text(&path).await?;
+                (path, text)
+            }
+            (None, Some(content)) => ("<new_content>".to_string(), content),
+            _ => {
+                return Err(ToolError::invalid_arguments(
+                    "Provide exactly one of new_path or new_content",
+                ));
+            }
+        };
+
+        let diff = UnifiedDiff::from_texts(args.old_path.clone(), &old_text, &new_text);
+        if !diff.has_changes() {
+            return Ok(format!("No differences between {} and {}", args.old_path, new_label));
+        }
+
+        let context = args.context_lines.unwrap_or(3);
+        Ok(format!(
+            "{}\n({})",
+            diff.to_unified_string(&args.old_path, &new_label, context),
+            diff.summary()
+        ))
+    }
+}
+
+/// Read a file for diffing, applying the same checks as read_file
+async fn read_text(path: &str) -> Result<String, ToolError> {
+    agent_ignore::check(path)?;
+
+    fs::read_to_string(path).await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ToolError::file_not_found(path),
+        std::io::ErrorKind::PermissionDenied => ToolError::permission_denied(path),
+        _ => ToolError::Io(e),
+    })
+}
@@ -0,0 +1,286 @@
+use crate::codeblock;
+use crate::error::ToolError;
+use crate::tools::{agent_ignore, context_dirs, write_root};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use tokio::fs;
+
+/// Arguments for the EditMarkdown tool
+#[derive(Debug, Deserialize)]
+pub struct EditMarkdownArgs {
+    /// Path to the Markdown file to edit
+    pub file_path: String,
+    /// Headings from the outermost to the target, separated by '>' (e.g. "## Installation > ### Linux")
+    pub heading_path: String,
+    /// New body for the section (everything below its heading, up to the next heading of the same or higher level)
+    pub new_body: String,
+}
+
+/// An ATX heading in a Markdown document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// Number of leading '#' characters (1-6)
+    pub level: usize,
+    pub title: String,
+    /// Zero-based line index of the heading
+    pub line: usize,
+}
+
+/// Tool to replace the body of a Markdown section addressed by its heading path
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EditMarkdown;
+
+impl Tool for EditMarkdown {
+    const NAME: &'static str = "edit_markdown_section";
+    type Error = ToolError;
+    type Args = EditMarkdownArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Replace the body of a section in a Markdown file, addressed by its heading path (e.g. \"## Installation > ### Linux\"). The body is everything below the heading up to the next heading of the same or higher level, including subsections. More robust than edit_file for reflowed prose.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the Markdown file to edit"
+                    },
+                    "heading_path": {
+                        "type": "string",
+                        "description": "Headings from outermost to target separated by '>'. The '#' markers are optional; titles match case-insensitively."
+                    },
+                    "new_body": {
+                        "type": "string",
+                        "description": "New content for the section, without its heading line"
+                    }
+                },
+                "required": ["file_path", "heading_path", "new_body"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        agent_ignore::check(&args.file_path)?;
+        context_dirs::check_writable(&args.file_path)?;
+        write_root::check(&args.file_path)?;
+
+        let path = Path::new(&args.file_path);
+        if !path.exists() {
+            return Err(ToolError::file_not_found(&args.file_path));
+        }
+
+        let contents = fs::read_to_string(path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                ToolError::permission_denied(&args.file_path)
+            } else {
+                ToolError::Io(e)
+            }
+        })?;
+
+        let updated = replace_section(&contents, &args.heading_path, &args.new_body)
+            .map_err(ToolError::invalid_arguments)?;
+
+        fs::write(path, &updated).await.map_err(ToolError::Io)?;
+
+        Ok(format!(
+            "Replaced section '{}' in {} ({} lines)",
+            args.heading_path.trim(),
+            args.file_path,
+            args.new_body.lines().count()
+        ))
+    }
+}
+
+/// Find ATX headings, skipping fenced code blocks
+pub fn parse_headings(doc: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut open_fence: Option<String> = None;
+
+    for (line_idx, line) in doc.lines().enumerate() {
+        let trimmed = line.trim_start();
+
+        if let Some(ref fence) = open_fence {
+            if trimmed.trim_end().starts_with(fence.as_str()) {
+                open_fence = None;
+            }
+            continue;
+        }
+        if let Some(fence) = codeblock::fence_marker(trimmed) {
+            open_fence = Some(fence.to_string());
+            continue;
+        }
+
+        // Up to three spaces of indentation are allowed before the '#'s
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        if let Some((level, title)) = parse_heading_line(trimmed) {
+            headings.push(Heading { level, title, line: line_idx });
+        }
+    }
+
+    headings
+}
+
+/// Parse "## Title ##" into (2, "Title")
+fn parse_heading_line(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, title.to_string()))
+}
+
+/// One segment of a heading path: an optional level and a title
+fn parse_segment(segment: &str) -> (Option<usize>, String) {
+    let segment = segment.trim();
+    match parse_heading_line(segment) {
+        Some((level, title)) => (Some(level), title.to_lowercase()),
+        None => (None, segment.to_lowercase()),
+    }
+}
+
+/// Index (into `headings`) of the heading ending the section that starts at `idx`
+fn section_end(headings: &[Heading], idx: usize) -> usize {
+    headings[idx + 1..]
+        .iter()
+        .position(|h| h.level <= headings[idx].level)
+        .map(|offset| idx + 1 + offset)
+        .unwrap_or(headings.len())
+}
+
+/// Resolve a heading path to the index of the target heading
+fn find_section(headings: &[Heading], heading_path: &str) -> Result<usize, String> {
+    let segments: Vec<(Option<usize>, String)> = heading_path
+        .split('>')
+        .map(parse_segment)
+        .filter(|(_, title)| !title.is_empty())
+        .collect();
+    if segments.is_empty() {
+        return Err("heading_path is empty".to_string());
+    }
+
+    let matches_segment = |h: &Heading, (level, title): &(Option<usize>, String)| {
+        h.title.to_lowercase() == *title && level.is_none_or(|l| l == h.level)
+    };
+
+    // Candidate scopes: (first heading index, end index) to search within
+    let mut scopes: Vec<(usize, usize)> = vec![(0, headings.len())];
+    let mut found: Vec<usize> = Vec::new();
+
+    for segment in &segments {
+        found = scopes
+            .iter()
+            .flat_map(|&(start, end)| (start..end).filter(|&i| matches_segment(&headings[i], segment)))
+            .collect();
+        found.dedup();
+        scopes = found.iter().map(|&i| (i + 1, section_end(headings, i))).collect();
+    }
+
+    match found.as_slice() {
+        [idx] => Ok(*idx),
+        [] => {
+            let available: Vec<String> = headings
+                .iter()
+                .map(|h| format!("{} {}", "#".repeat(h.level), h.title))
+                .collect();
+            Err(format!(
+                "No section matches '{}'. Headings in the file:\n{}",
+                heading_path.trim(),
+                available.join("\n")
+            ))
+        }
+        multiple => {
+            let lines: Vec<String> = multiple.iter().map(|&i| (headings[i].line + 1).to_string()).collect();
+            Err(format!(
+                "'{}' matches {} sections (lines {}); add parent headings to disambiguate",
+                heading_path.trim(),
+                multiple.len(),
+                lines.join(", ")
+            ))
+        }
+    }
+}
+
+/// Replace the body of the section addressed by `heading_path`
+pub fn replace_section(doc: &str, heading_path: &str, new_body: &str) -> Result<String, String> {
+    let headings = parse_headings(doc);
+    let idx = find_section(&headings, heading_path)?;
+
+    let lines: Vec<&str> = doc.lines().collect();
+    let body_start = headings[idx].line + 1;
+    let body_end = headings
+        .get(section_end(&headings, idx))
+        .map(|h| h.line)
+        .unwrap_or(lines.len());
+
+    let mut result: Vec<&str> = lines[..body_start].to_vec();
+    let body = new_body.trim_matches('\n');
+
+    // Keep a blank line around the body so headings stay separated
+    result.push("");
+    if !body.is_empty() {
+        result.extend(body.lines());
+        result.push("");
+    }
+    result.extend(&lines[body_end..]);
+
+    // Don't leave a trailing blank line at the end of the document
+    while result.len() > 1 && result.last() == Some(&"") && body_end == lines.len() {
+        result.pop();
+    }
+
+    let mut output = result.join("\n");
+    if doc.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Project\n\nIntro.\n\n## Installation\n\nGeneric steps.\n\n### Linux\n\nOld linux\nsteps.\n\n### macOS\n\nbrew install\n\n## Usage\n\n```sh\n# not a heading\n```\n";
+
+    #[test]
+    fn test_parse_headings_skips_code() {
+        let headings = parse_headings(DOC);
+        let titles: Vec<&str> = headings.iter().map(|h| h.title.as_str()).collect();
+        assert_eq!(titles, vec!["Project", "Installation", "Linux", "macOS", "Usage"]);
+        assert_eq!(headings[2].level, 3);
+    }
+
+    #[test]
+    fn test_replace_section_by_path() {
+        let updated = replace_section(DOC, "## Installation > ### Linux", "apt install tool").unwrap();
+        assert!(updated.contains("### Linux\n\napt install tool\n\n### macOS"));
+        assert!(!updated.contains("Old linux"));
+
+        // Markers are optional and titles are case-insensitive
+        let same = replace_section(DOC, "installation > linux", "apt install tool").unwrap();
+        assert_eq!(same, updated);
+
+        // Last section runs to the end of the document
+        let usage = replace_section(DOC, "Usage", "Run it.").unwrap();
+        assert!(usage.ends_with("## Usage\n\nRun it.\n"));
+    }
+
+    #[test]
+    fn test_replace_section_errors() {
+        assert!(replace_section(DOC, "Windows", "x").unwrap_err().contains("Headings in the file"));
+        let doc = "## A\n### Notes\nx\n## B\n### Notes\ny\n";
+        assert!(replace_section(doc, "Notes", "z").unwrap_err().contains("matches 2 sections"));
+        assert!(replace_section(doc, "B > Notes", "z").unwrap().contains("### Notes\n\nz\n"));
+    }
+}
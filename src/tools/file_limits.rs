@@ -0,0 +1,120 @@
+//! Size and content guards for the file tools
+//!
+//! `read_file` refuses whole-file reads above the limit and reports files that
+//! look binary (detected type plus a short hexdump) instead of dumping them;
+//! `write_file` content above the limit needs an extra confirmation.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default byte limit for read_file / write_file (1 MiB)
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// How much of a file is scanned when looking for binary content
+pub(crate) const BINARY_SCAN_BYTES: usize = 8192;
+
+/// Share of invalid UTF-8 above which content is treated as binary
+const MAX_INVALID_UTF8_RATIO: f64 = 0.3;
+
+/// Number of leading bytes shown in the hexdump of a binary file
+pub const HEXDUMP_BYTES: usize = 64;
+
+/// Current limit in bytes (0 = unlimited)
+static MAX_FILE_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_FILE_SIZE);
+
+/// Set the byte limit (0 disables the check)
+pub fn set_max_file_size(bytes: u64) {
+    MAX_FILE_SIZE.store(bytes, Ordering::Relaxed);
+}
+
+/// Current byte limit (0 = unlimited)
+pub fn max_file_size() -> u64 {
+    MAX_FILE_SIZE.load(Ordering::Relaxed)
+}
+
+/// Whether a size in bytes exceeds the limit
+pub fn exceeds_limit(bytes: u64) -> bool {
+    let limit = max_file_size();
+    limit != 0 && bytes > limit
+}
+
+/// Magic numbers of common binary formats
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (b"\xff\xd8\xff", "JPEG image"),
+    (b"GIF87a", "GIF image"),
+    (b"GIF89a", "GIF image"),
+    (b"%PDF-", "PDF document"),
+    (b"PK\x03\x04", "ZIP archive"),
+    (b"\x1f\x8b", "gzip archive"),
+    (b"\x7fELF", "ELF executable"),
+    (b"\xcf\xfa\xed\xfe", "Mach-O executable"),
+    (b"\xca\xfe\xba\xbe", "Mach-O universal binary / Java class"),
+    (b"\0asm", "WebAssembly module"),
+    (b"SQLite format 3\0", "SQLite database"),
+];
+
+/// Classify content as binary, returning a description of the detected type.
+/// Checks known magic numbers, then NUL bytes and the share of invalid UTF-8
+/// in the first few KB.
+pub fn detect_binary(bytes: &[u8]) -> Option<&'static str> {
+    if let Some((_, kind)) = SIGNATURES.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return Some(kind);
+    }
+
+    let sample = &bytes[..bytes.len().min(BINARY_SCAN_BYTES)];
+    if sample.contains(&0) {
+        return Some("unknown binary");
+    }
+
+    let invalid: usize = sample.utf8_chunks().map(|chunk| chunk.invalid().len()).sum();
+    if !sample.is_empty() && invalid as f64 / sample.len() as f64 > MAX_INVALID_UTF8_RATIO {
+        return Some("non-UTF-8 data");
+    }
+
+    None
+}
+
+/// Classic hexdump (offset, hex bytes, printable ASCII) of the first bytes
+pub fn hexdump(bytes: &[u8], max_bytes: usize) -> String {
+    bytes[..bytes.len().min(max_bytes)]
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", row * 16, hex.join(" "), ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_binary() {
+        assert!(detect_binary(b"fn main() {}\n").is_none());
+        assert!(detect_binary("héllo".as_bytes()).is_none());
+        assert!(detect_binary(b"").is_none());
+        assert_eq!(detect_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("PNG image"));
+        assert_eq!(detect_binary(b"text\0more"), Some("unknown binary"));
+        assert_eq!(detect_binary(b"\x7fELF\x02\x01\x01"), Some("ELF executable"));
+        assert_eq!(detect_binary(b"%PDF-1.7\n"), Some("PDF document"));
+        assert_eq!(detect_binary(&[0xfe, 0xed, 0xc3, 0x28, 0xa0, 0xa1, b'a']), Some("non-UTF-8 data"));
+        assert_eq!(detect_binary("plain text ✓".as_bytes()), None);
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let dump = hexdump(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\x01", 64);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "00000000  89 50 4e 47 0d 0a 1a 0a 00 00 00 0d 49 48 44 52  |.PNG........IHDR|");
+        assert!(lines[1].starts_with("00000010  00 00 01 "));
+        assert!(lines[1].ends_with("|...|"));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::error::ToolError;
+use crate::git;
+use crate::tools::agent_ignore;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+/// Arguments for the GitDiff tool
+#[derive(Debug, Deserialize)]
+pub struct GitDiffArgs {
+    /// Only staged (true) or only unstaged (false) changes; both when omitted
+    pub staged: Option<bool>,
+    /// Limit the diff to this file or directory
+    pub path: Option<String>,
+}
+
+/// Tool to show staged and unstaged changes as unified diffs
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct GitDiff;
+
+impl Tool for GitDiff {
+    const NAME: &'static str = "git_diff";
+    type Error = ToolError;
+    type Args = GitDiffArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Show uncommitted changes as unified diffs: staged changes (index vs HEAD), unstaged changes (working tree vs index), or both. Untracked files are not included; see git_status.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "staged": {
+                        "type": "boolean",
+                        "description": "true for staged changes only, false for unstaged only (default: both)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Limit the diff to this file or directory"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        if let Some(ref path) = args.path {
+            agent_ignore::check(path)?;
+        }
+
+        let dir = Path::new(".");
+        let root = git::toplevel(dir)
+            .map_err(|_| ToolError::invalid_path("The working directory is not inside a git repository"))?;
+
+        let sections = match args.staged {
+            Some(true) => vec![("Staged", true)],
+            Some(false) => vec![("Unstaged", false)],
+            None => vec![("Staged", true), ("Unstaged", false)],
+        };
+
+        let mut output = Vec::new();
+        for (title, staged) in sections {
+            let diff = git::diff(dir, staged, args.path.as_deref())
+                .map_err(|e| ToolError::command_failed(e.to_string()))?;
+            let diff = without_ignored_files(&diff, &root);
+            let body = if diff.is_empty() { "(no changes)".to_string() } else { diff };
+            output.push(format!("=== {} changes ===\n{}", title, body));
+        }
+
+        Ok(output.join("\n\n"))
+    }
+}
+
+/// Drop the per-file sections of a diff for files excluded by `.agentignore`
+fn without_ignored_files(diff: &str, root: &Path) -> String {
+    let mut kept = Vec::new();
+    let mut keep = true;
+
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            keep = diff_path(header).is_none_or(|path| !agent_ignore::is_ignored(&root.join(path)));
+        }
+        if keep {
+            kept.push(line);
+        }
+    }
+
+    kept.join("\n")
+}
+
+/// Path of the new side from a "a/<old> b/<new>" diff header
+fn diff_path(header: &str) -> Option<&str> {
+    header.rsplit_once(" b/").map(|(_, path)| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_path() {
+        assert_eq!(diff_path("a/src/main.rs b/src/main.rs"), Some("src/main.rs"));
+        assert_eq!(diff_path("a/old name.rs b/new name.rs"), Some("new name.rs"));
+        assert_eq!(diff_path("garbage"), None);
+    }
+}
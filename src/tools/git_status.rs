@@ -0,0 +1,91 @@
+use crate::error::ToolError;
+use crate::git;
+use crate::tools::agent_ignore;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+/// Number of recent commits listed after the status
+const RECENT_COMMITS: usize = 5;
+
+/// Arguments for the GitStatus tool
+#[derive(Debug, Deserialize)]
+pub struct GitStatusArgs {
+    /// Directory inside the repository (default: working directory)
+    pub path: Option<String>,
+}
+
+/// Tool to report branch, staged/unstaged/untracked files and recent commits
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct GitStatus;
+
+impl Tool for GitStatus {
+    const NAME: &'static str = "git_status";
+    type Error = ToolError;
+    type Args = GitStatusArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Show the git branch, staged, unstaged and untracked files, and the most recent commits. Use instead of running git status through bash.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory inside the repository (default: working directory)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let dir = Path::new(args.path.as_deref().unwrap_or("."));
+        let root = git::toplevel(dir)
+            .map_err(|_| ToolError::invalid_path(format!("{} is not inside a git repository", dir.display())))?;
+        let status = git::GitStatus::read(dir).map_err(|e| ToolError::command_failed(e.to_string()))?;
+        let visible = |path: &String| !agent_ignore::is_ignored(&root.join(path));
+
+        let info = git::GitInfo::detect(&dir.to_string_lossy());
+        let mut output = format!(
+            "Repository: {}\nBranch: {}\n",
+            root.display(),
+            info.branch.as_deref().unwrap_or("unknown")
+        );
+
+        if status.is_clean() {
+            output.push_str("\nWorking tree clean\n");
+        }
+        for (title, entries) in [("Staged", &status.staged), ("Unstaged", &status.unstaged)] {
+            let entries: Vec<_> = entries.iter().filter(|(_, path)| visible(path)).collect();
+            if !entries.is_empty() {
+                output.push_str(&format!("\n{} ({}):\n", title, entries.len()));
+                for (code, path) in entries {
+                    output.push_str(&format!("  {} {}\n", code, path));
+                }
+            }
+        }
+        let untracked: Vec<_> = status.untracked.iter().filter(|path| visible(path)).collect();
+        if !untracked.is_empty() {
+            output.push_str(&format!("\nUntracked ({}):\n", untracked.len()));
+            for path in untracked {
+                output.push_str(&format!("  {}\n", path));
+            }
+        }
+
+        if let Ok(commits) = git::recent_commits(dir, RECENT_COMMITS)
+            && !commits.is_empty()
+        {
+            output.push_str("\nRecent commits:\n");
+            for commit in commits {
+                output.push_str(&format!("  {}\n", commit));
+            }
+        }
+
+        Ok(output.trim_end().to_string())
+    }
+}
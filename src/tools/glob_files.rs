@@ -1,4 +1,5 @@
 use crate::error::ToolError;
+use crate::tools::agent_ignore;
 use glob::glob;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
@@ -12,6 +13,9 @@ pub struct GlobArgs {
     pub pattern: String,
     /// Base directory for the search (defaults to current directory)
     pub base_dir: Option<String>,
+    /// Extra roots searched alongside `base_dir` (filled in by the agent loop, never by the model)
+    #[serde(skip)]
+    pub extra_roots: Vec<std::path::PathBuf>,
 }
 
 /// Tool to find files matching a glob pattern
@@ -55,18 +59,29 @@ impl Tool for GlobFiles {
             None => args.pattern.clone(),
         };
 
-        // Execute glob (this is blocking, but typically fast)
-        let entries = glob(&full_pattern).map_err(|e| ToolError::pattern_error(e.to_string()))?;
+        let mut patterns = vec![full_pattern.clone()];
+        patterns.extend(
+            args.extra_roots
+                .iter()
+                .map(|root| format!("{}/{}", root.display(), args.pattern)),
+        );
 
         let mut files: Vec<String> = Vec::new();
-        for entry in entries {
-            match entry {
-                Ok(path) => {
-                    files.push(path.display().to_string());
-                }
-                Err(e) => {
-                    // Log but continue on individual errors
-                    eprintln!("Glob entry error: {}", e);
+        for pattern in &patterns {
+            // Execute glob (this is blocking, but typically fast)
+            let entries = glob(pattern).map_err(|e| ToolError::pattern_error(e.to_string()))?;
+
+            for entry in entries {
+                match entry {
+                    Ok(path) => {
+                        if !agent_ignore::is_ignored(&path) {
+                            files.push(path.display().to_string());
+                        }
+                    }
+                    Err(e) => {
+                        // Log but continue on individual errors
+                        tracing::debug!("Glob entry error: {}", e);
+                    }
                 }
             }
         }
@@ -1,4 +1,5 @@
 use crate::error::ToolError;
+use crate::tools::agent_ignore;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,9 @@ pub struct GrepArgs {
     pub ignore_case: Option<bool>,
     /// Maximum number of results to return
     pub max_results: Option<usize>,
+    /// Extra roots searched alongside `path` (filled in by the agent loop, never by the model)
+    #[serde(skip)]
+    pub extra_roots: Vec<std::path::PathBuf>,
 }
 
 /// Tool to search for patterns in files
@@ -59,6 +63,10 @@ impl Tool for GrepSearch {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        if let Some(ref path) = args.path {
+            agent_ignore::check(path)?;
+        }
+
         // Try ripgrep first, fall back to grep
         let (cmd_name, use_rg) = if Command::new("rg")
             .arg("--version")
@@ -92,6 +100,7 @@ impl Tool for GrepSearch {
             } else {
                 cmd.arg(".");
             }
+            cmd.args(&args.extra_roots);
         } else {
             // Fallback to grep
             cmd.arg("-rn");
@@ -107,6 +116,7 @@ impl Tool for GrepSearch {
             } else {
                 cmd.arg(".");
             }
+            cmd.args(&args.extra_roots);
         }
 
         cmd.stdout(Stdio::piped());
@@ -121,13 +131,22 @@ impl Tool for GrepSearch {
             return Err(ToolError::command_failed(stderr.to_string()));
         }
 
-        if stdout.is_empty() {
+        // Drop matches in files excluded by .agentignore ("path:line:text")
+        let matches: Vec<&str> = stdout
+            .lines()
+            .filter(|line| match line.split_once(':') {
+                Some((file, _)) => !agent_ignore::is_ignored(std::path::Path::new(file)),
+                None => true,
+            })
+            .collect();
+
+        if matches.is_empty() {
             Ok("No matches found.".to_string())
         } else {
             // Limit results if needed
             let max = args.max_results.unwrap_or(50);
-            let lines: Vec<&str> = stdout.lines().take(max).collect();
-            let total_matches = stdout.lines().count();
+            let lines: Vec<&str> = matches.iter().take(max).copied().collect();
+            let total_matches = matches.len();
 
             let mut result = lines.join("\n");
             if total_matches > max {
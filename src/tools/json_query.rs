@@ -0,0 +1,374 @@
+use crate::error::ToolError;
+use crate::tools::agent_ignore;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use tokio::fs;
+
+/// Arguments for the JsonQuery tool
+#[derive(Debug, Deserialize)]
+pub struct JsonQueryArgs {
+    /// Path expression: a jq subset (`.items[0].name`) or JSONPath (`$.items[*].name`)
+    pub query: String,
+    /// JSON file to query
+    pub file_path: Option<String>,
+    /// Inline JSON to query (instead of file_path)
+    pub json: Option<String>,
+}
+
+/// One step of a parsed query
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// `.name`, `."name"`, `["name"]`
+    Key(String),
+    /// `[2]`, `[-1]`
+    Index(i64),
+    /// `[1:3]`, `[:2]`, `[-2:]`
+    Slice(Option<i64>, Option<i64>),
+    /// `[]`, `[*]`
+    Iterate,
+    /// `..`
+    Recurse,
+    /// `keys`
+    Keys,
+    /// `length`
+    Length,
+}
+
+/// A step, and whether errors from it are ignored (`?`, or a key right after `..`)
+type Filter = Vec<(Step, bool)>;
+
+/// Tool to extract values from JSON with a path expression
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct JsonQuery;
+
+impl Tool for JsonQuery {
+    const NAME: &'static str = "json_query";
+    type Error = ToolError;
+    type Args = JsonQueryArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Extract values from a JSON file or inline JSON with a path expression, instead of reading the whole document. Supports a jq subset (.a.b, .[0], .[-1], .[1:3], .[], .., '|', keys, length, ? to skip errors) and JSONPath ($.a[*].b, $..b, ['key']). Each match is printed as JSON.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Path expression, e.g. '.dependencies | keys', '.items[].name' or '$..id'"
+                    },
+                    "file_path": {
+                        "type": "string",
+                        "description": "JSON file to query"
+                    },
+                    "json": {
+                        "type": "string",
+                        "description": "Inline JSON to query (instead of file_path)"
+                    }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let (source, text) = match (args.file_path, args.json) {
+            (Some(file_path), None) => {
+                agent_ignore::check(&file_path)?;
+                let path = Path::new(&file_path);
+                if !path.exists() {
+                    return Err(ToolError::file_not_found(&file_path));
+                }
+                let text = fs::read_to_string(path).await.map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::PermissionDenied {
+                        ToolError::permission_denied(&file_path)
+                    } else {
+                        ToolError::Io(e)
+                    }
+                })?;
+                (file_path, text)
+            }
+            (None, Some(json)) => ("inline JSON".to_string(), json),
+            _ => return Err(ToolError::invalid_arguments("Provide exactly one of file_path or json")),
+        };
+
+        let document: Value = serde_json::from_str(&text)
+            .map_err(|e| ToolError::invalid_arguments(format!("Invalid JSON in {}: {}", source, e)))?;
+        let filter = parse_query(&args.query).map_err(ToolError::invalid_arguments)?;
+        let results = evaluate(&filter, &document).map_err(ToolError::invalid_arguments)?;
+
+        if results.is_empty() {
+            return Ok(format!("No matches for '{}' in {}", args.query.trim(), source));
+        }
+        Ok(results
+            .iter()
+            .map(|value| serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+/// Parse a jq-style or JSONPath expression into steps
+fn parse_query(query: &str) -> Result<Filter, String> {
+    let chars: Vec<char> = query.trim().chars().collect();
+    let mut steps: Filter = Vec::new();
+    let mut i = 0;
+
+    // JSONPath root
+    if chars.first() == Some(&'$') {
+        i = 1;
+    }
+
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let read_ident = |i: &mut usize| -> String {
+        let start = *i;
+        while *i < chars.len() && is_ident(chars[*i]) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect()
+    };
+
+    while i < chars.len() {
+        match chars[i] {
+            c if c.is_whitespace() || c == '|' => i += 1,
+            '?' => {
+                match steps.last_mut() {
+                    Some(last) => last.1 = true,
+                    None => return Err("'?' must follow a step".to_string()),
+                }
+                i += 1;
+            }
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                steps.push((Step::Recurse, false));
+                i += 2;
+                // JSONPath `$..name`: take the key from every object that has it
+                if i < chars.len() && is_ident(chars[i]) {
+                    steps.push((Step::Key(read_ident(&mut i)), true));
+                }
+            }
+            '.' => {
+                i += 1;
+                if i < chars.len() && is_ident(chars[i]) {
+                    steps.push((Step::Key(read_ident(&mut i)), false));
+                } else if chars.get(i) == Some(&'"') {
+                    steps.push((Step::Key(read_quoted(&chars, &mut i)?), false));
+                }
+                // A bare '.' is the identity
+            }
+            '[' => {
+                i += 1;
+                steps.push((parse_bracket(&chars, &mut i)?, false));
+            }
+            c if is_ident(c) => {
+                let name = read_ident(&mut i);
+                let step = match name.as_str() {
+                    "keys" => Step::Keys,
+                    "length" => Step::Length,
+                    _ => return Err(format!("Unsupported function '{}' (supported: keys, length)", name)),
+                };
+                steps.push((step, false));
+            }
+            c => return Err(format!("Unexpected '{}' at position {} in query", c, i + 1)),
+        }
+    }
+
+    // A key following `..` skips values that don't have it
+    for idx in 1..steps.len() {
+        if steps[idx - 1].0 == Step::Recurse && matches!(steps[idx].0, Step::Key(_)) {
+            steps[idx].1 = true;
+        }
+    }
+
+    Ok(steps)
+}
+
+/// Parse a quoted key starting at `chars[*i]` (either quote style)
+fn read_quoted(chars: &[char], i: &mut usize) -> Result<String, String> {
+    let quote = chars[*i];
+    *i += 1;
+    let mut key = String::new();
+    while *i < chars.len() && chars[*i] != quote {
+        if chars[*i] == '\\' && *i + 1 < chars.len() {
+            *i += 1;
+        }
+        key.push(chars[*i]);
+        *i += 1;
+    }
+    if *i >= chars.len() {
+        return Err("Unterminated string in query".to_string());
+    }
+    *i += 1;
+    Ok(key)
+}
+
+/// Parse the inside of `[...]`, with `*i` just past the opening bracket
+fn parse_bracket(chars: &[char], i: &mut usize) -> Result<Step, String> {
+    let step = if matches!(chars.get(*i), Some('"') | Some('\'')) {
+        Step::Key(read_quoted(chars, i)?)
+    } else {
+        let start = *i;
+        while *i < chars.len() && chars[*i] != ']' {
+            *i += 1;
+        }
+        let inner: String = chars[start..*i].iter().collect::<String>().trim().to_string();
+        let parse_index = |s: &str| -> Result<Option<i64>, String> {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            s.parse().map(Some).map_err(|_| format!("Invalid index '{}' in query", s))
+        };
+        match inner.as_str() {
+            "" | "*" => Step::Iterate,
+            _ => match inner.split_once(':') {
+                Some((from, to)) => Step::Slice(parse_index(from)?, parse_index(to)?),
+                None => Step::Index(parse_index(&inner)?.unwrap_or(0)),
+            },
+        }
+    };
+
+    if chars.get(*i) != Some(&']') {
+        return Err("Missing ']' in query".to_string());
+    }
+    *i += 1;
+    Ok(step)
+}
+
+/// Run a parsed query, returning every matched value
+fn evaluate(filter: &Filter, document: &Value) -> Result<Vec<Value>, String> {
+    let mut current = vec![document.clone()];
+    for (step, optional) in filter {
+        let mut next = Vec::new();
+        for value in &current {
+            if let Err(e) = apply(step, value, *optional, &mut next)
+                && !optional
+            {
+                return Err(e);
+            }
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+/// Apply one step to a value, pushing its results
+fn apply(step: &Step, value: &Value, optional: bool, out: &mut Vec<Value>) -> Result<(), String> {
+    match (step, value) {
+        (Step::Key(key), Value::Object(map)) => match map.get(key) {
+            Some(found) => out.push(found.clone()),
+            None if !optional => out.push(Value::Null),
+            None => {}
+        },
+        (Step::Key(_), Value::Null) if !optional => out.push(Value::Null),
+        (Step::Index(index), Value::Array(items)) => {
+            let resolved = if *index < 0 { items.len() as i64 + index } else { *index };
+            out.push(usize::try_from(resolved).ok().and_then(|idx| items.get(idx)).cloned().unwrap_or(Value::Null));
+        }
+        (Step::Slice(from, to), Value::Array(items)) => {
+            let len = items.len() as i64;
+            let clamp = |bound: i64| (if bound < 0 { len + bound } else { bound }).clamp(0, len) as usize;
+            let start = clamp(from.unwrap_or(0));
+            let end = clamp(to.unwrap_or(len));
+            out.push(Value::Array(items[start..end.max(start)].to_vec()));
+        }
+        (Step::Index(_) | Step::Slice(..), Value::Null) => out.push(Value::Null),
+        (Step::Iterate, Value::Array(items)) => out.extend(items.iter().cloned()),
+        (Step::Iterate, Value::Object(map)) => out.extend(map.values().cloned()),
+        (Step::Recurse, _) => collect_recursive(value, out),
+        (Step::Keys, Value::Object(map)) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push(json!(keys));
+        }
+        (Step::Keys, Value::Array(items)) => out.push(json!((0..items.len()).collect::<Vec<_>>())),
+        (Step::Length, Value::Array(items)) => out.push(json!(items.len())),
+        (Step::Length, Value::Object(map)) => out.push(json!(map.len())),
+        (Step::Length, Value::String(s)) => out.push(json!(s.chars().count())),
+        (Step::Length, Value::Null) => out.push(json!(0)),
+        (Step::Length, Value::Number(n)) => out.push(json!(n.as_f64().map(f64::abs))),
+        (step, value) => {
+            return Err(format!("Cannot apply {} to {}", describe_step(step), type_name(value)));
+        }
+    }
+    Ok(())
+}
+
+/// The value and everything nested in it, outermost first
+fn collect_recursive(value: &Value, out: &mut Vec<Value>) {
+    out.push(value.clone());
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_recursive(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_recursive(item, out)),
+        _ => {}
+    }
+}
+
+/// Short description of a step for error messages
+fn describe_step(step: &Step) -> String {
+    match step {
+        Step::Key(key) => format!("key \"{}\"", key),
+        Step::Index(index) => format!("index [{}]", index),
+        Step::Slice(..) => "a slice".to_string(),
+        Step::Iterate => "[]".to_string(),
+        Step::Recurse => "..".to_string(),
+        Step::Keys => "keys".to_string(),
+        Step::Length => "length".to_string(),
+    }
+}
+
+/// JSON type name of a value
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(expr: &str, doc: &Value) -> Result<Vec<Value>, String> {
+        evaluate(&parse_query(expr)?, doc)
+    }
+
+    #[test]
+    fn test_jq_paths() {
+        let doc = json!({
+            "name": "agent-t",
+            "items": [{ "id": 1, "tags": ["a"] }, { "id": 2 }, { "id": 3 }],
+            "dev-dependencies": { "tokio": "1", "anyhow": "1" }
+        });
+
+        assert_eq!(query(".", &doc).unwrap(), vec![doc.clone()]);
+        assert_eq!(query(".name", &doc).unwrap(), vec![json!("agent-t")]);
+        assert_eq!(query(".items[0].id", &doc).unwrap(), vec![json!(1)]);
+        assert_eq!(query(".items[-1].id", &doc).unwrap(), vec![json!(3)]);
+        assert_eq!(query(".items[].id", &doc).unwrap(), vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(query(".items[1:] | length", &doc).unwrap(), vec![json!(2)]);
+        assert_eq!(query(".[\"dev-dependencies\"] | keys", &doc).unwrap(), vec![json!(["anyhow", "tokio"])]);
+        assert_eq!(query(".missing", &doc).unwrap(), vec![Value::Null]);
+        assert_eq!(query(".items[].tags[0]?", &doc).unwrap(), vec![json!("a"), Value::Null, Value::Null]);
+        assert!(query(".name.first", &doc).unwrap_err().contains("Cannot apply key \"first\" to a string"));
+        assert!(query(".name | first", &doc).unwrap_err().contains("Unsupported function"));
+    }
+
+    #[test]
+    fn test_jsonpath_and_recursion() {
+        let doc = json!({ "a": { "id": 1, "b": [{ "id": 2 }, { "x": 0 }] } });
+
+        assert_eq!(query("$.a.b[*].id", &doc).unwrap(), vec![json!(2), Value::Null]);
+        assert_eq!(query("$['a']['id']", &doc).unwrap(), vec![json!(1)]);
+        assert_eq!(query("$..id", &doc).unwrap(), vec![json!(1), json!(2)]);
+        assert_eq!(query(".. | .id", &doc).unwrap(), vec![json!(1), json!(2)]);
+        assert!(parse_query(".a[0").unwrap_err().contains("Missing ']'"));
+    }
+}
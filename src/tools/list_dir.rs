@@ -1,4 +1,5 @@
 use crate::error::ToolError;
+use crate::tools::agent_ignore;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -6,11 +7,16 @@ use serde_json::json;
 use std::path::Path;
 use tokio::fs;
 
+/// Entries listed when no limit is given
+const DEFAULT_LIMIT: usize = 500;
+
 /// Arguments for the ListDir tool
 #[derive(Debug, Deserialize)]
 pub struct ListDirArgs {
     /// Path to the directory to list
     pub path: String,
+    /// Maximum number of entries to return (default: 500)
+    pub limit: Option<usize>,
 }
 
 /// Tool to list directory contents
@@ -33,6 +39,10 @@ impl Tool for ListDir {
                     "path": {
                         "type": "string",
                         "description": "The path to the directory to list"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of entries to return (default: 500)"
                     }
                 },
                 "required": ["path"]
@@ -41,6 +51,8 @@ impl Tool for ListDir {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        agent_ignore::check(&args.path)?;
+
         let path = Path::new(&args.path);
 
         if !path.exists() {
@@ -67,6 +79,10 @@ impl Tool for ListDir {
             let file_name = entry.file_name().to_string_lossy().to_string();
             let file_type = entry.file_type().await?;
 
+            if agent_ignore::is_ignored(&entry.path()) {
+                continue;
+            }
+
             let type_indicator = if file_type.is_dir() {
                 "/"
             } else if file_type.is_symlink() {
@@ -80,10 +96,40 @@ impl Tool for ListDir {
 
         entries.sort();
 
-        if entries.is_empty() {
-            Ok("(empty directory)".to_string())
-        } else {
-            Ok(entries.join("\n"))
-        }
+        Ok(format_listing(&entries, args.limit.unwrap_or(DEFAULT_LIMIT)))
+    }
+}
+
+/// The first `limit` entries, with a note saying how many were left out
+fn format_listing(entries: &[String], limit: usize) -> String {
+    if entries.is_empty() {
+        return "(empty directory)".to_string();
+    }
+
+    let limit = limit.max(1);
+    let mut result = entries.iter().take(limit).cloned().collect::<Vec<_>>().join("\n");
+    if entries.len() > limit {
+        result.push_str(&format!(
+            "\n[{} more entries omitted; {} total. Pass a higher limit, or use glob to narrow down]",
+            entries.len() - limit,
+            entries.len()
+        ));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_listing() {
+        let entries: Vec<String> = (0..5).map(|i| format!("file{}.txt", i)).collect();
+        assert_eq!(format_listing(&entries, 10), entries.join("\n"));
+        assert_eq!(
+            format_listing(&entries, 2),
+            "file0.txt\nfile1.txt\n[3 more entries omitted; 5 total. Pass a higher limit, or use glob to narrow down]"
+        );
+        assert_eq!(format_listing(&[], 2), "(empty directory)");
     }
 }
@@ -3,20 +3,45 @@
 //! This module contains implementations of various tools that the agent
 //! can use to interact with the filesystem, execute commands, and more.
 
+pub mod agent_ignore;
+pub mod clippy;
+pub mod context_dirs;
+pub mod file_limits;
+pub mod network;
+pub mod paths;
+pub mod rustfmt;
 mod read_file;
+mod summarize_file;
 mod write_file;
 mod list_dir;
 mod bash;
+pub mod shell;
 mod edit_file;
+pub mod edit_markdown;
 mod grep;
 mod glob_files;
+mod diff_files;
+mod git_status;
+mod git_diff;
 mod bash_status;
 mod bash_output;
 mod bash_kill;
 mod bash_list;
 mod web_fetch;
-mod web_search;
+mod crate_docs;
+pub mod web_search;
+pub mod web_cache;
+pub mod write_root;
 mod math_calc;
+mod json_query;
+mod scratchpad;
+mod vecdb_search;
+
+// Cargo tools
+pub mod cargo_common;
+mod run_tests;
+mod cargo_check;
+mod cargo_deps;
 
 // Memory tools
 mod store_key_memory;
@@ -36,19 +61,33 @@ mod ra_rename;
 mod ra_format;
 
 pub use read_file::ReadFile;
+pub use summarize_file::SummarizeFile;
 pub use write_file::WriteFile;
 pub use list_dir::ListDir;
-pub use bash::BashCommand;
+pub use bash::{mask_secret_env, BashArgs, BashCommand, LineSink, ShellSession};
 pub use edit_file::EditFile;
-pub use grep::GrepSearch;
-pub use glob_files::GlobFiles;
+pub use edit_markdown::EditMarkdown;
+pub use grep::{GrepArgs, GrepSearch};
+pub use glob_files::{GlobArgs, GlobFiles};
+pub use diff_files::DiffFiles;
+pub use git_status::GitStatus;
+pub use git_diff::GitDiff;
 pub use bash_status::BashStatus;
 pub use bash_output::BashOutput;
 pub use bash_kill::BashKill;
 pub use bash_list::BashList;
 pub use web_fetch::WebFetch;
 pub use web_search::WebSearch;
+pub use crate_docs::CrateDocs;
 pub use math_calc::MathCalc;
+pub use json_query::JsonQuery;
+pub use scratchpad::{ScratchRead, ScratchWrite, Scratchpad};
+pub use vecdb_search::VecdbSearch;
+
+// Cargo tools
+pub use run_tests::RunTests;
+pub use cargo_check::CargoCheck;
+pub use cargo_deps::{AddDependency, RemoveDependency};
 
 // Memory tools
 pub use store_key_memory::StoreKeyMemory;
@@ -56,11 +95,11 @@ pub use search_routine_memory::SearchRoutineMemory;
 pub use search_key_memory::SearchKeyMemory;
 
 // Rust Analyzer tools
-pub use ra_diagnostics::RaDiagnostics;
+pub use ra_diagnostics::{diagnostics_report, panel_report, RaDiagnostics};
 pub use ra_goto_definition::RaGotoDefinition;
 pub use ra_find_references::RaFindReferences;
 pub use ra_hover::RaHover;
-pub use ra_symbols::RaSymbols;
+pub use ra_symbols::{document_outline, RaSymbols};
 pub use ra_completion::RaCompletion;
 pub use ra_code_actions::RaCodeActions;
 pub use ra_rename::RaRename;
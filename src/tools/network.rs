@@ -0,0 +1,35 @@
+//! Network access gate (--allow-network)
+//!
+//! With network access off, `web_fetch`, `web_search` and `crate_docs` refuse to run and are
+//! left out of the tool definitions, so nothing is fetched from or sent to the
+//! outside by the agent's own tools. It defaults to on; an agent can turn it off
+//! with `"allow_network": false` in its agent.json.
+
+use crate::error::ToolError;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Tools that talk to the network
+pub const NETWORK_TOOLS: &[&str] = &["web_fetch", "web_search", "crate_docs"];
+
+static ALLOWED: AtomicBool = AtomicBool::new(true);
+
+/// Allow or block the network tools
+pub fn set_allowed(allowed: bool) {
+    ALLOWED.store(allowed, Ordering::Relaxed);
+}
+
+/// Whether the network tools may run
+pub fn allowed() -> bool {
+    ALLOWED.load(Ordering::Relaxed)
+}
+
+/// Fail unless network access is allowed
+pub fn check() -> Result<(), ToolError> {
+    if allowed() {
+        Ok(())
+    } else {
+        Err(ToolError::PermissionDenied(
+            "network access disabled (start with --allow-network to use web_fetch, web_search and crate_docs)".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,200 @@
+//! Resolving the paths file tools are given
+//!
+//! The model's paths are relative to the agent's working directory, which
+//! `/cd` can move away from the process directory the tools would otherwise
+//! resolve them against. Before a file tool runs, its path arguments are made
+//! absolute against the working directory and resolved the way the OS will
+//! when opening them: `..` and every symlink along the path are followed, so
+//! the checks in the tools (.agentignore, --context-dir, --write-root) see
+//! where the call really lands rather than the path it was spelled with.
+
+use crate::error::ToolError;
+use crate::tools::agent_ignore;
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Symlinks followed while resolving one path before giving up (as ELOOP)
+const MAX_SYMLINKS: usize = 40;
+
+/// Arguments naming a file or directory, by tool
+const PATH_ARGUMENTS: &[(&str, &[&str])] = &[
+    ("read_file", &["file_path"]),
+    ("summarize_file", &["file_path"]),
+    ("write_file", &["file_path"]),
+    ("edit_file", &["file_path"]),
+    ("edit_markdown_section", &["file_path"]),
+    ("list_dir", &["path"]),
+    ("grep", &["path"]),
+    ("glob", &["base_dir"]),
+    ("git_status", &["path"]),
+    ("git_diff", &["path"]),
+    ("cargo_check", &["working_dir"]),
+    ("run_tests", &["working_dir"]),
+    ("add_dependency", &["working_dir"]),
+    ("remove_dependency", &["working_dir"]),
+    ("json_query", &["file_path"]),
+    ("diff_files", &["old_path", "new_path"]),
+    ("ra_diagnostics", &["file_path"]),
+    ("ra_goto_definition", &["file_path"]),
+    ("ra_find_references", &["file_path"]),
+    ("ra_hover", &["file_path"]),
+    ("ra_symbols", &["file_path"]),
+    ("ra_completion", &["file_path"]),
+    ("ra_code_actions", &["file_path"]),
+    ("ra_rename", &["file_path"]),
+    ("ra_format", &["file_path"]),
+];
+
+/// `path` made absolute against `base`, without touching the filesystem
+pub fn absolute(base: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+/// Where `path` (absolute, or relative to the process directory) really
+/// points, with `..` and symlinks resolved, including a dangling symlink at the
+/// end. Components past the first one that doesn't exist are kept as they are,
+/// so the target of a write need not exist yet. None on a symlink loop.
+pub fn resolve(path: &Path) -> Option<PathBuf> {
+    let absolute = absolute(&std::env::current_dir().ok()?, path);
+    let mut links = 0;
+    resolve_from(PathBuf::from("/"), &absolute, &mut links)
+}
+
+fn resolve_from(mut resolved: PathBuf, path: &Path, links: &mut usize) -> Option<PathBuf> {
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => resolved = PathBuf::from(component.as_os_str()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                let candidate = resolved.join(name);
+                let is_symlink = std::fs::symlink_metadata(&candidate).is_ok_and(|m| m.file_type().is_symlink());
+                if !is_symlink {
+                    resolved = candidate;
+                    continue;
+                }
+                *links += 1;
+                if *links > MAX_SYMLINKS {
+                    return None;
+                }
+                // A relative link target is relative to the directory holding the link
+                let target = std::fs::read_link(&candidate).ok()?;
+                resolved = resolve_from(resolved, &target, links)?;
+            }
+        }
+    }
+    Some(resolved)
+}
+
+/// Resolve one path argument against `working_dir`. It is refused when
+/// `is_ignored` matches the path as written or where it leads, so a symlink
+/// can't make a file excluded by .agentignore readable under another name.
+fn resolve_argument(working_dir: &Path, path: &str, is_ignored: &dyn Fn(&Path) -> bool) -> Result<PathBuf, ToolError> {
+    let lexical = absolute(working_dir, Path::new(path));
+    let resolved = resolve(&lexical)
+        .ok_or_else(|| ToolError::invalid_path(format!("{}: too many levels of symbolic links", path)))?;
+    if is_ignored(&lexical) || is_ignored(&resolved) {
+        return Err(ToolError::permission_denied(format!(
+            "{} is excluded by {} and cannot be accessed",
+            path,
+            agent_ignore::IGNORE_FILE
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Replace the path arguments of a file tool call with their resolved form,
+/// relative paths being taken from `working_dir`
+pub fn resolve_tool_paths(tool_name: &str, working_dir: &Path, args: &mut Value) -> Result<(), ToolError> {
+    let Some((_, keys)) = PATH_ARGUMENTS.iter().find(|(tool, _)| *tool == tool_name) else {
+        return Ok(());
+    };
+    let Some(obj) = args.as_object_mut() else {
+        return Ok(());
+    };
+    for key in keys.iter() {
+        let Some(Value::String(path)) = obj.get(*key) else {
+            continue;
+        };
+        let resolved = resolve_argument(working_dir, path, &agent_ignore::is_ignored)?;
+        obj.insert(key.to_string(), Value::String(resolved.to_string_lossy().to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use serde_json::json;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve() {
+        let dir = TempDir::new("paths");
+        std::fs::create_dir_all(dir.path().join("root/src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("outside")).unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink("../outside/new.txt", root.join("dangling")).unwrap();
+        std::os::unix::fs::symlink("src", root.join("inner")).unwrap();
+
+        assert_eq!(resolve(&root.join("src/new/file.rs")), Some(root.join("src/new/file.rs")));
+        assert_eq!(resolve(&root.join("src/../../outside/x")), Some(outside.join("x")));
+        assert_eq!(resolve(&root.join("escape/x")), Some(outside.join("x")));
+        assert_eq!(resolve(&root.join("dangling")), Some(outside.join("new.txt")));
+        assert_eq!(resolve(&root.join("inner/lib.rs")), Some(root.join("src/lib.rs")));
+
+        std::os::unix::fs::symlink("loop", root.join("loop")).unwrap();
+        assert_eq!(resolve(&root.join("loop/x")), None);
+
+        let new_path = outside.join("b.rs").to_string_lossy().to_string();
+        let mut args = json!({"old_path": "inner/a.rs", "new_path": new_path, "context_lines": 3});
+        resolve_tool_paths("diff_files", &root, &mut args).unwrap();
+        assert_eq!(args["old_path"], root.join("src/a.rs").to_string_lossy().as_ref());
+        assert_eq!(args["new_path"], new_path);
+
+        let mut args = json!({"pattern": "*.rs", "base_dir": "inner"});
+        resolve_tool_paths("glob", &root, &mut args).unwrap();
+        assert_eq!(args["base_dir"], root.join("src").to_string_lossy().as_ref());
+
+        let mut args = json!({"command": "ls inner"});
+        resolve_tool_paths("bash", &root, &mut args).unwrap();
+        assert_eq!(args["command"], "ls inner");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_does_not_unhide_ignored_file() {
+        let dir = TempDir::new("paths-ignore");
+        std::fs::create_dir_all(dir.path().join("root")).unwrap();
+        std::fs::create_dir_all(dir.path().join("outside")).unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        let root = base.join("root");
+        std::fs::write(base.join("outside/secrets"), "KEY=1").unwrap();
+        std::fs::write(root.join("config.txt"), "KEY=1").unwrap();
+        std::os::unix::fs::symlink("../outside/secrets", root.join(".env")).unwrap();
+        std::os::unix::fs::symlink("config.txt", root.join(".env.local")).unwrap();
+
+        let ignore = agent_ignore::AgentIgnore::parse(&root, ".env\n.env.local\n");
+        let is_ignored = |path: &Path| ignore.is_ignored(path, false);
+
+        // Ignored as written, though the target is outside the project or not ignored
+        assert!(resolve_argument(&root, ".env", &is_ignored).is_err());
+        assert!(resolve_argument(&root, ".env.local", &is_ignored).is_err());
+        assert!(resolve_argument(&root, "./sub/../.env", &is_ignored).is_err());
+        // Not ignored as written, but the target is
+        std::os::unix::fs::symlink(root.join(".env.local"), root.join("local-link")).unwrap();
+        let ignore_target = agent_ignore::AgentIgnore::parse(&root, "config.txt\n");
+        assert!(resolve_argument(&root, "local-link", &|path: &Path| ignore_target.is_ignored(path, false)).is_err());
+        // Neither is ignored
+        assert_eq!(resolve_argument(&root, "config.txt", &is_ignored).unwrap(), root.join("config.txt"));
+    }
+}
@@ -2,13 +2,23 @@
 
 use crate::rust_analyzer::RustAnalyzerClient;
 use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Give up on automatic restarts after this many crashes in one session
+const MAX_RESTARTS: usize = 3;
+
+/// Number of automatic restarts so far
+static RESTARTS: AtomicUsize = AtomicUsize::new(0);
+
 lazy_static! {
     /// Global rust-analyzer client instance
     /// Wrapped in Arc for cheap cloning across tools
     pub static ref RUST_ANALYZER: Arc<RwLock<Option<Arc<RustAnalyzerClient>>>> = Arc::new(RwLock::new(None));
+
+    /// Set when the client was restarted; taken by the agent loop to show a warning
+    static ref RESTART_NOTICE: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
 }
 
 /// Set the global rust-analyzer client
@@ -16,16 +26,61 @@ pub async fn set_client(client: RustAnalyzerClient) {
     *RUST_ANALYZER.write().await = Some(Arc::new(client));
 }
 
-/// Get a cloned reference to the global rust-analyzer client
+/// Get a cloned reference to the global rust-analyzer client,
+/// restarting the server first if it has exited
 pub async fn get_client() -> Result<Arc<RustAnalyzerClient>, crate::error::ToolError> {
-    let guard = RUST_ANALYZER.read().await;
-    guard.as_ref()
-        .map(Arc::clone)
-        .ok_or_else(|| {
-            crate::error::ToolError::Other(
-                "rust-analyzer is not available (not a Rust project or rust-analyzer not installed)".to_string()
-            )
-        })
+    let client = {
+        let guard = RUST_ANALYZER.read().await;
+        guard.as_ref()
+            .map(Arc::clone)
+            .ok_or_else(|| {
+                crate::error::ToolError::Other(
+                    "rust-analyzer is not available (not a Rust project or rust-analyzer not installed)".to_string()
+                )
+            })?
+    };
+
+    if client.is_alive() {
+        return Ok(client);
+    }
+
+    restart(client).await
+}
+
+/// Replace a dead client with a freshly spawned and initialized one
+async fn restart(dead: Arc<RustAnalyzerClient>) -> Result<Arc<RustAnalyzerClient>, crate::error::ToolError> {
+    // Holding the write lock makes concurrent tool calls wait for the new client
+    let mut guard = RUST_ANALYZER.write().await;
+
+    // Another call may have restarted it while we waited for the lock
+    if let Some(current) = guard.as_ref()
+        && !Arc::ptr_eq(current, &dead) {
+            return Ok(Arc::clone(current));
+        }
+
+    if RESTARTS.fetch_add(1, Ordering::SeqCst) >= MAX_RESTARTS {
+        return Err(crate::error::ToolError::Other(format!(
+            "rust-analyzer exited and has already been restarted {} times; not restarting again",
+            MAX_RESTARTS
+        )));
+    }
+
+    let client = RustAnalyzerClient::new(dead.workspace_root().clone())
+        .await
+        .map_err(|e| crate::error::ToolError::Other(format!("rust-analyzer exited and could not be restarted: {}", e)))?;
+    let client = Arc::new(client);
+    *guard = Some(Arc::clone(&client));
+
+    if let Ok(mut notice) = RESTART_NOTICE.lock() {
+        *notice = Some("rust-analyzer exited unexpectedly and was restarted".to_string());
+    }
+
+    Ok(client)
+}
+
+/// Take the pending restart notice, if the client was restarted since the last call
+pub fn take_restart_notice() -> Option<String> {
+    RESTART_NOTICE.lock().ok().and_then(|mut notice| notice.take())
 }
 
 /// Check if rust-analyzer is available
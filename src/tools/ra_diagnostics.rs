@@ -2,10 +2,13 @@
 
 use crate::error::ToolError;
 use crate::tools::ra_common;
+use lsp_types::{Diagnostic, DiagnosticSeverity, Url};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
 
 /// Arguments for the RaDiagnostics tool
 #[derive(Debug, Deserialize)]
@@ -118,3 +121,101 @@ impl Tool for RaDiagnostics {
         }
     }
 }
+
+/// Errors, then warnings, of the whole workspace grouped by file, for /check
+pub async fn diagnostics_report() -> Result<String, ToolError> {
+    let client = ra_common::get_client().await?;
+    let diagnostics = client.get_diagnostics().await;
+    Ok(format_report(&diagnostics, client.workspace_root()))
+}
+
+/// The same report for the TUI's diagnostics panel, None while rust-analyzer
+/// is not running. Polling this never restarts a crashed server; the next
+/// ra_* call does.
+pub async fn panel_report() -> Option<String> {
+    let client = ra_common::RUST_ANALYZER.read().await.clone()?;
+    if !client.is_alive() {
+        return None;
+    }
+    let diagnostics = client.get_diagnostics().await;
+    Some(format_report(&diagnostics, client.workspace_root()))
+}
+
+fn format_report(diagnostics: &HashMap<Url, Vec<Diagnostic>>, root: &Path) -> String {
+    let mut files: Vec<(String, &Vec<Diagnostic>)> = diagnostics
+        .iter()
+        .map(|(uri, diags)| {
+            let path = uri.to_file_path().unwrap_or_else(|_| uri.path().into());
+            let path = path.strip_prefix(root).unwrap_or(&path).display().to_string();
+            (path, diags)
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut report = String::new();
+    let mut counts = Vec::new();
+    for (severity, heading) in [(DiagnosticSeverity::ERROR, "Errors"), (DiagnosticSeverity::WARNING, "Warnings")] {
+        let mut section = String::new();
+        let mut count = 0;
+        for (path, diags) in &files {
+            let mut matching: Vec<&Diagnostic> = diags.iter().filter(|d| d.severity == Some(severity)).collect();
+            if matching.is_empty() {
+                continue;
+            }
+            matching.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+            count += matching.len();
+            section.push_str(&format!("  {}\n", path));
+            for diag in matching {
+                let message = diag.message.lines().next().unwrap_or("");
+                section.push_str(&format!("    {}:{}  {}\n", diag.range.start.line + 1, diag.range.start.character + 1, message));
+            }
+        }
+        counts.push(count);
+        if count > 0 {
+            report.push_str(&format!("\n{}:\n{}", heading, section));
+        }
+    }
+
+    if report.is_empty() {
+        return "No errors or warnings reported by rust-analyzer (right after startup it may still be checking the workspace)".to_string();
+    }
+    format!("rust-analyzer: {} error(s), {} warning(s)\n{}", counts[0], counts[1], report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{Position, Range};
+
+    fn diagnostic(line: u32, severity: DiagnosticSeverity, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 4), Position::new(line, 8)),
+            severity: Some(severity),
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_report() {
+        let mut diagnostics = HashMap::new();
+        diagnostics.insert(
+            Url::parse("file:///work/src/main.rs").unwrap(),
+            vec![
+                diagnostic(20, DiagnosticSeverity::WARNING, "unused variable: `x`"),
+                diagnostic(9, DiagnosticSeverity::ERROR, "mismatched types\nexpected `u32`"),
+                diagnostic(3, DiagnosticSeverity::HINT, "consider borrowing"),
+            ],
+        );
+        diagnostics.insert(Url::parse("file:///work/src/lib.rs").unwrap(), Vec::new());
+
+        let report = format_report(&diagnostics, Path::new("/work"));
+        assert_eq!(
+            report,
+            "rust-analyzer: 1 error(s), 1 warning(s)\n\
+             \nErrors:\n  src/main.rs\n    10:5  mismatched types\n\
+             \nWarnings:\n  src/main.rs\n    21:5  unused variable: `x`\n"
+        );
+        assert!(format_report(&HashMap::new(), Path::new("/work")).starts_with("No errors"));
+    }
+}
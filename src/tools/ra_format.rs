@@ -1,6 +1,7 @@
 //! Rust Analyzer format document tool
 
 use crate::error::ToolError;
+use crate::terminal;
 use crate::tools::ra_common;
 use lsp_types::Url;
 use rig::completion::ToolDefinition;
@@ -87,11 +88,7 @@ impl Tool for RaFormat {
                     ));
 
                     // Show a preview of the change (truncated)
-                    let preview = if edit.new_text.len() > 100 {
-                        format!("{}...", &edit.new_text[..100])
-                    } else {
-                        edit.new_text.clone()
-                    };
+                    let preview = terminal::truncate_chars(&edit.new_text, 100);
                     output.push_str(&format!("   New text: {}\n", preview));
                 }
                 Ok(output)
@@ -2,7 +2,8 @@
 
 use crate::error::ToolError;
 use crate::tools::ra_common;
-use lsp_types::Url;
+use crate::tools::agent_ignore;
+use lsp_types::{DocumentSymbol, SymbolKind, Url};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -50,46 +51,11 @@ impl Tool for RaSymbols {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        let client = ra_common::get_client().await?;
-
         if let Some(file_path) = args.file_path {
-            // Document symbols
-            let path = PathBuf::from(&file_path);
-            let absolute_path = if path.is_absolute() {
-                path
-            } else {
-                std::env::current_dir()
-                    .map_err(|e| ToolError::Other(format!("Failed to get current directory: {}", e)))?
-                    .join(path)
-            };
-
-            let uri = Url::from_file_path(&absolute_path)
-                .map_err(|_| ToolError::invalid_arguments("Invalid file path"))?;
-
-            // Read file content and open it with rust-analyzer
-            let content = tokio::fs::read_to_string(&absolute_path).await
-                .map_err(ToolError::from)?;
-
-            client.did_open(uri.clone(), "rust".to_string(), 1, content).await
-                .map_err(|e| ToolError::Other(format!("Failed to open document: {}", e)))?;
-
-            // Get document symbols
-            let result = client.document_symbols(uri.clone()).await
-                .map_err(|e| ToolError::Other(format!("Failed to get document symbols: {}", e)))?;
-
-            // Close the document
-            let _ = client.did_close(uri).await;
-
-            match result {
-                Some(symbols) if !symbols.is_empty() => {
-                    let mut output = String::from("Document symbols:\n");
-                    format_symbols(&symbols, &mut output, 0);
-                    Ok(output)
-                }
-                _ => Ok("No symbols found in document.".to_string()),
-            }
+            document_outline(&file_path).await
         } else {
             // Workspace symbols
+            let client = ra_common::get_client().await?;
             let query = args.query.unwrap_or_default();
             let result = client.workspace_symbols(query.clone()).await
                 .map_err(|e| ToolError::Other(format!("Failed to search workspace symbols: {}", e)))?;
@@ -115,15 +81,63 @@ impl Tool for RaSymbols {
     }
 }
 
-fn format_symbols(symbols: &[lsp_types::DocumentSymbol], output: &mut String, indent: usize) {
+/// Hierarchical outline of a Rust file's items, indented by nesting
+pub async fn document_outline(file_path: &str) -> Result<String, ToolError> {
+    agent_ignore::check(file_path)?;
+    let client = ra_common::get_client().await?;
+
+    let path = PathBuf::from(file_path);
+    let absolute_path = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir()
+            .map_err(|e| ToolError::Other(format!("Failed to get current directory: {}", e)))?
+            .join(path)
+    };
+
+    let uri = Url::from_file_path(&absolute_path)
+        .map_err(|_| ToolError::invalid_arguments("Invalid file path"))?;
+
+    // Read file content and open it with rust-analyzer
+    let content = tokio::fs::read_to_string(&absolute_path).await
+        .map_err(ToolError::from)?;
+
+    client.did_open(uri.clone(), "rust".to_string(), 1, content).await
+        .map_err(|e| ToolError::Other(format!("Failed to open document: {}", e)))?;
+
+    // Get document symbols
+    let result = client.document_symbols(uri.clone()).await
+        .map_err(|e| ToolError::Other(format!("Failed to get document symbols: {}", e)))?;
+
+    // Close the document
+    let _ = client.did_close(uri).await;
+
+    match result {
+        Some(symbols) if !symbols.is_empty() => {
+            let mut output = format!("Outline of {}:\n", file_path);
+            format_symbols(&symbols, &mut output, 0);
+            Ok(output)
+        }
+        _ => Ok("No symbols found in document.".to_string()),
+    }
+}
+
+fn format_symbols(symbols: &[DocumentSymbol], output: &mut String, indent: usize) {
     for symbol in symbols {
         let indent_str = "  ".repeat(indent);
+        let start = symbol.range.start.line + 1;
+        let end = symbol.range.end.line + 1;
+        let lines = if start == end {
+            format!("line {}", start)
+        } else {
+            format!("lines {}-{}", start, end)
+        };
         output.push_str(&format!(
-            "{}{} ({:?}) - Line {}\n",
+            "{}{} {}  ({})\n",
             indent_str,
+            kind_label(symbol.kind),
             symbol.name,
-            symbol.kind,
-            symbol.range.start.line + 1
+            lines
         ));
 
         if let Some(ref children) = symbol.children {
@@ -131,3 +145,21 @@ fn format_symbols(symbols: &[lsp_types::DocumentSymbol], output: &mut String, in
         }
     }
 }
+
+/// Rust keyword for a symbol kind, as rust-analyzer reports them
+fn kind_label(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::MODULE => "mod",
+        SymbolKind::STRUCT => "struct",
+        SymbolKind::ENUM => "enum",
+        SymbolKind::ENUM_MEMBER => "variant",
+        SymbolKind::INTERFACE => "trait",
+        SymbolKind::OBJECT => "impl",
+        SymbolKind::FUNCTION | SymbolKind::METHOD => "fn",
+        SymbolKind::FIELD => "field",
+        SymbolKind::CONSTANT => "const",
+        SymbolKind::VARIABLE => "static",
+        SymbolKind::TYPE_PARAMETER => "type",
+        _ => "item",
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::ToolError;
+use crate::tools::{agent_ignore, file_limits};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -53,6 +54,8 @@ impl Tool for ReadFile {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        agent_ignore::check(&args.file_path)?;
+
         let path = Path::new(&args.file_path);
 
         // Check if file exists
@@ -68,8 +71,19 @@ impl Tool for ReadFile {
             )));
         }
 
+        // Refuse to pull a huge file into the context in one go
+        let size = fs::metadata(path).await.map_err(ToolError::Io)?.len();
+        if file_limits::exceeds_limit(size) && args.offset.is_none() && args.limit.is_none() {
+            return Err(ToolError::invalid_arguments(format!(
+                "{} is {} bytes, over the {} byte limit. Read it in parts with offset and limit.",
+                args.file_path,
+                size,
+                file_limits::max_file_size()
+            )));
+        }
+
         // Read file contents
-        let contents = fs::read_to_string(path).await.map_err(|e| {
+        let bytes = fs::read(path).await.map_err(|e| {
             if e.kind() == std::io::ErrorKind::PermissionDenied {
                 ToolError::permission_denied(&args.file_path)
             } else {
@@ -77,6 +91,19 @@ impl Tool for ReadFile {
             }
         })?;
 
+        // Binary content would only fill the context with mojibake
+        if let Some(kind) = file_limits::detect_binary(&bytes) {
+            return Ok(format!(
+                "{} appears to be a binary file ({} bytes, detected type {}); contents not shown.\n\nFirst {} bytes:\n{}",
+                args.file_path,
+                bytes.len(),
+                kind,
+                bytes.len().min(file_limits::HEXDUMP_BYTES),
+                file_limits::hexdump(&bytes, file_limits::HEXDUMP_BYTES)
+            ));
+        }
+        let contents = String::from_utf8_lossy(&bytes);
+
         // Apply offset and limit
         let lines: Vec<&str> = contents.lines().collect();
         let total_lines = lines.len();
@@ -0,0 +1,317 @@
+use crate::error::ToolError;
+use crate::tools::cargo_common;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Arguments for the RunTests tool
+#[derive(Debug, Deserialize)]
+pub struct RunTestsArgs {
+    /// Only run tests whose name contains this string
+    pub filter: Option<String>,
+    /// Package to test in a workspace (cargo -p)
+    pub package: Option<String>,
+    /// Optional working directory (the crate or workspace root)
+    pub working_dir: Option<String>,
+    /// Optional timeout in seconds (default: 600)
+    pub timeout_secs: Option<u64>,
+}
+
+/// A single failing test
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFailure {
+    pub name: String,
+    pub file: Option<String>,
+    pub line: Option<u64>,
+    pub message: String,
+}
+
+/// Aggregated results of a cargo test run
+#[derive(Debug, Default, PartialEq)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub failures: Vec<TestFailure>,
+}
+
+/// Tool to run cargo tests and report structured results
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RunTests;
+
+impl Tool for RunTests {
+    const NAME: &'static str = "run_tests";
+    type Error = ToolError;
+    type Args = RunTestsArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Run `cargo test` and return a structured summary: passed/failed/ignored counts and, for each failure, the test name, file, line and assertion message. Compilation errors are reported as diagnostics. Prefer this over running cargo test through bash.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "filter": {
+                        "type": "string",
+                        "description": "Only run tests whose name contains this string"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Package to test in a workspace (cargo -p)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Optional working directory (defaults to project root)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Optional timeout in seconds (default: 600)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut cargo_args = vec![
+            "test".to_string(),
+            "--no-fail-fast".to_string(),
+            "--message-format=json".to_string(),
+        ];
+        if let Some(ref package) = args.package {
+            cargo_args.push("-p".to_string());
+            cargo_args.push(package.clone());
+        }
+        if let Some(ref filter) = args.filter {
+            cargo_args.push(filter.clone());
+        }
+
+        let output = cargo_common::run_cargo(
+            &cargo_args,
+            args.working_dir.as_deref(),
+            args.timeout_secs.unwrap_or(600),
+        )
+        .await?;
+
+        // Compilation errors mean no tests ran
+        let build_errors: Vec<_> = output.stdout
+            .lines()
+            .filter_map(cargo_common::parse_compiler_message)
+            .filter(|d| d.is_error())
+            .collect();
+
+        if !build_errors.is_empty() {
+            let mut result = format!("Build failed with {} error(s); no tests were run:\n\n", build_errors.len());
+            for diag in &build_errors {
+                let code = diag.code.as_ref().map(|c| format!("[{}] ", c)).unwrap_or_default();
+                result.push_str(&format!("- {} {}{}\n", diag.location(), code, diag.message));
+            }
+            return Ok(result);
+        }
+
+        let summary = parse_test_output(&output.stdout);
+
+        if summary.passed + summary.failed + summary.ignored == 0 && !output.success {
+            // Something other than a test failure went wrong (bad package name, etc.)
+            return Err(ToolError::command_failed(output.stderr.trim().to_string()));
+        }
+
+        Ok(format_summary(&summary))
+    }
+}
+
+/// Parse libtest's human-readable output (interleaved with cargo JSON lines)
+pub fn parse_test_output(stdout: &str) -> TestSummary {
+    let mut summary = TestSummary::default();
+    let mut failed_names: Vec<String> = Vec::new();
+    let lines: Vec<&str> = stdout.lines().collect();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(rest) = line.strip_prefix("test result: ") {
+            summary.passed += count_before(rest, "passed");
+            summary.failed += count_before(rest, "failed");
+            summary.ignored += count_before(rest, "ignored");
+        } else if let Some(name) = line.strip_prefix("test ").and_then(|l| l.strip_suffix(" ... FAILED")) {
+            failed_names.push(name.to_string());
+        } else if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            // Collect the captured output of a failing test
+            let mut section = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].starts_with("---- ") && lines[i] != "failures:" {
+                section.push(lines[i]);
+                i += 1;
+            }
+            summary.failures.push(parse_failure_section(name, &section));
+            continue;
+        }
+
+        i += 1;
+    }
+
+    // Failures that produced no captured output (e.g. should_panic that didn't panic)
+    for name in failed_names {
+        if !summary.failures.iter().any(|f| f.name == name) {
+            summary.failures.push(TestFailure {
+                name,
+                file: None,
+                line: None,
+                message: String::new(),
+            });
+        }
+    }
+
+    summary
+}
+
+/// Extract the panic location and message from a failing test's output
+fn parse_failure_section(name: &str, section: &[&str]) -> TestFailure {
+    let mut failure = TestFailure {
+        name: name.to_string(),
+        file: None,
+        line: None,
+        message: String::new(),
+    };
+
+    let Some(panic_idx) = section.iter().position(|l| l.contains("panicked at ")) else {
+        failure.message = section.iter().map(|l| l.trim_end()).collect::<Vec<_>>().join("\n").trim().to_string();
+        return failure;
+    };
+
+    let panic_line = section[panic_idx];
+    let after = &panic_line[panic_line.find("panicked at ").unwrap() + "panicked at ".len()..];
+
+    if let Some(quoted) = after.strip_prefix('\'') {
+        // Old format: panicked at 'message', src/lib.rs:10:5
+        if let Some((message, location)) = quoted.rsplit_once("', ") {
+            failure.message = message.to_string();
+            (failure.file, failure.line) = parse_location(location);
+        }
+    } else {
+        // New format: panicked at src/lib.rs:10:5:\nmessage
+        (failure.file, failure.line) = parse_location(after.trim_end_matches(':'));
+        failure.message = section[panic_idx + 1..]
+            .iter()
+            .take_while(|l| !l.starts_with("note: "))
+            .map(|l| l.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+    }
+
+    failure
+}
+
+/// Parse "file:line:col" into a file and line
+fn parse_location(location: &str) -> (Option<String>, Option<u64>) {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let _col = parts.next();
+    let line = parts.next().and_then(|l| l.parse().ok());
+    let file = parts.next().map(|f| f.to_string());
+    match (file, line) {
+        (Some(file), Some(line)) => (Some(file), Some(line)),
+        _ => (None, None),
+    }
+}
+
+/// Read the number preceding a label in "1 passed; 2 failed; ..."
+fn count_before(text: &str, label: &str) -> usize {
+    text.split(';')
+        .find_map(|part| {
+            let mut words = part.split_whitespace().rev();
+            if words.next()? == label {
+                words.next()?.parse().ok()
+            } else {
+                None
+            }
+        })
+        .unwrap_or(0)
+}
+
+/// Render a test summary for the model
+fn format_summary(summary: &TestSummary) -> String {
+    let mut result = format!(
+        "Tests: {} passed, {} failed, {} ignored\n",
+        summary.passed, summary.failed, summary.ignored
+    );
+
+    if summary.failures.is_empty() {
+        return result;
+    }
+
+    result.push_str("\nFailures:\n");
+    for (idx, failure) in summary.failures.iter().enumerate() {
+        let location = match (&failure.file, failure.line) {
+            (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+            _ => String::new(),
+        };
+        result.push_str(&format!("{}. {}{}\n", idx + 1, failure.name, location));
+        for line in failure.message.lines() {
+            result.push_str(&format!("   {}\n", line));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+{\"reason\":\"build-finished\",\"success\":true}
+running 3 tests
+test tests::a ... ok
+test tests::b ... FAILED
+test tests::c ... ignored
+
+failures:
+
+---- tests::b stdout ----
+
+thread 'tests::b' panicked at src/lib.rs:12:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::b
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+
+    #[test]
+    fn test_parse_test_output() {
+        let summary = parse_test_output(OUTPUT);
+        assert_eq!(summary.passed, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.ignored, 1);
+        assert_eq!(summary.failures.len(), 1);
+
+        let failure = &summary.failures[0];
+        assert_eq!(failure.name, "tests::b");
+        assert_eq!(failure.file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(failure.line, Some(12));
+        assert!(failure.message.starts_with("assertion `left == right` failed"));
+        assert!(failure.message.contains("right: 2"));
+    }
+
+    #[test]
+    fn test_parse_old_panic_format() {
+        let section = ["thread 'x' panicked at 'boom', src/main.rs:4:5"];
+        let failure = parse_failure_section("x", &section);
+        assert_eq!(failure.message, "boom");
+        assert_eq!(failure.file.as_deref(), Some("src/main.rs"));
+        assert_eq!(failure.line, Some(4));
+    }
+}
@@ -0,0 +1,174 @@
+//! Formatting Rust files after the agent writes them (--format-on-write)
+//!
+//! The file's contents are piped through rustfmt with the edition of the
+//! package it belongs to, inherited from the workspace when the package says
+//! so (rustfmt on its own assumes 2015), from the file's
+//! directory so it also picks up the project's rustfmt.toml. Going through
+//! stdin keeps rustfmt from following `mod` declarations and reformatting
+//! files the agent didn't write.
+
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::{timeout, Duration};
+
+/// How long rustfmt may take on one file
+const RUSTFMT_TIMEOUT_SECS: u64 = 30;
+
+/// Edition used when no Cargo.toml names one
+const DEFAULT_EDITION: &str = "2021";
+
+/// Lines of rustfmt's error output kept in the note
+const MAX_ERROR_LINES: usize = 10;
+
+/// An `edition` entry of a Cargo.toml
+#[derive(Debug, PartialEq)]
+enum Edition {
+    Year(String),
+    /// `edition.workspace = true`: the workspace's `[workspace.package]` edition
+    Workspace,
+}
+
+/// The `edition` in one section (such as `[package]`) of a Cargo.toml
+fn parse_edition(manifest: &str, section: &str) -> Option<Edition> {
+    let mut in_section = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == section;
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key == "edition.workspace" || (key == "edition" && value.starts_with('{') && value.contains("workspace")) {
+            return Some(Edition::Workspace);
+        }
+        if key == "edition" {
+            let value = value.trim_matches('"');
+            let is_year = !value.is_empty() && value.chars().all(|c| c.is_ascii_digit());
+            return is_year.then(|| Edition::Year(value.to_string()));
+        }
+    }
+    None
+}
+
+/// Edition of the package `path` belongs to. Manifests without an edition of
+/// their own are walked past; a virtual manifest, or the workspace root of a
+/// package that inherits its edition, supplies `[workspace.package]`'s.
+fn edition_for(path: &Path) -> String {
+    let mut inherited = false;
+    for dir in path.ancestors().skip(1) {
+        let Ok(manifest) = std::fs::read_to_string(dir.join("Cargo.toml")) else {
+            continue;
+        };
+        if !inherited {
+            match parse_edition(&manifest, "[package]") {
+                Some(Edition::Year(edition)) => return edition,
+                Some(Edition::Workspace) => inherited = true,
+                None => {}
+            }
+        }
+        if let Some(Edition::Year(edition)) = parse_edition(&manifest, "[workspace.package]") {
+            return edition;
+        }
+    }
+    DEFAULT_EDITION.to_string()
+}
+
+/// Format a Rust file in place. Returns a note for the tool result: whether
+/// it was reformatted, or why rustfmt couldn't format it.
+pub async fn format_file(path: &Path) -> String {
+    let before = match tokio::fs::read(path).await {
+        Ok(before) => before,
+        Err(e) => return format!("[format-on-write: could not read the file back: {}]", e),
+    };
+
+    let mut cmd = Command::new("rustfmt");
+    cmd.arg("--edition").arg(edition_for(path)).args(["--emit", "stdout"]);
+    cmd.stdin(Stdio::piped());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    cmd.kill_on_drop(true);
+    if let Some(dir) = path.parent() {
+        cmd.current_dir(dir);
+    }
+
+    let run = async {
+        let mut child = cmd.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&before).await?;
+        }
+        child.wait_with_output().await
+    };
+
+    let output = match timeout(Duration::from_secs(RUSTFMT_TIMEOUT_SECS), run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return format!("[format-on-write: could not run rustfmt (is it installed?): {}]", e),
+        Err(_) => return format!("[format-on-write: rustfmt timed out after {}s; the file was left as written]", RUSTFMT_TIMEOUT_SECS),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let errors: Vec<&str> = stderr.lines().take(MAX_ERROR_LINES).collect();
+        return format!(
+            "[format-on-write: rustfmt failed, so the file was left as written. Fix these first:\n{}]",
+            errors.join("\n")
+        );
+    }
+
+    if output.stdout.is_empty() || output.stdout == before {
+        return "[format-on-write: already formatted]".to_string();
+    }
+    if let Err(e) = tokio::fs::write(path, &output.stdout).await {
+        return format!("[format-on-write: could not write the formatted file: {}]", e);
+    }
+    "[format-on-write: reformatted with rustfmt; read the file again before editing it]".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_parse_edition() {
+        let manifest = "[package]\nname = \"agent-t\"\nedition = \"2024\"\n\n[dependencies]\nedition = \"1\"\n";
+        assert_eq!(parse_edition(manifest, "[package]"), Some(Edition::Year("2024".to_string())));
+
+        // Only the asked-for section counts
+        assert_eq!(parse_edition("[dependencies]\nedition = \"2018\"\n", "[package]"), None);
+        assert_eq!(parse_edition("[package]\nedition.workspace = true\n", "[package]"), Some(Edition::Workspace));
+        assert_eq!(parse_edition("[package]\nedition = { workspace = true }\n", "[package]"), Some(Edition::Workspace));
+        assert_eq!(
+            parse_edition("[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nedition = \"2021\"\n", "[workspace.package]"),
+            Some(Edition::Year("2021".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_edition_for_workspace() {
+        let dir = TempDir::new("rustfmt-edition");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("inherits/src")).unwrap();
+        std::fs::create_dir_all(root.join("own/src")).unwrap();
+        std::fs::create_dir_all(root.join("unset/src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"inherits\", \"own\", \"unset\"]\n\n[workspace.package]\nedition = \"2024\"\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("inherits/Cargo.toml"), "[package]\nname = \"inherits\"\nedition.workspace = true\n").unwrap();
+        std::fs::write(root.join("own/Cargo.toml"), "[package]\nname = \"own\"\nedition = \"2018\"\n").unwrap();
+        std::fs::write(root.join("unset/Cargo.toml"), "[package]\nname = \"unset\"\n").unwrap();
+
+        assert_eq!(edition_for(&root.join("inherits/src/lib.rs")), "2024");
+        assert_eq!(edition_for(&root.join("own/src/lib.rs")), "2018");
+        assert_eq!(edition_for(&root.join("unset/src/lib.rs")), "2024");
+        assert_eq!(edition_for(&root.join("build.rs")), "2024");
+    }
+}
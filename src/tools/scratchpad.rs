@@ -0,0 +1,199 @@
+use crate::error::ToolError;
+use crate::terminal;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Key-value notes shared by an agent and every sub-agent it spawns
+pub type Scratchpad = Arc<Mutex<BTreeMap<String, String>>>;
+
+/// Largest value kept under one key, so one agent can't fill the parent's context
+const MAX_VALUE_BYTES: usize = 64 * 1024;
+
+/// Characters of each value shown when listing the scratchpad
+const PREVIEW_CHARS: usize = 80;
+
+/// Arguments for the ScratchWrite tool
+#[derive(Debug, Deserialize)]
+pub struct ScratchWriteArgs {
+    /// Key to store the value under
+    pub key: String,
+    /// Text to store
+    pub value: String,
+    /// Append to the existing value instead of replacing it
+    pub append: Option<bool>,
+}
+
+/// Arguments for the ScratchRead tool
+#[derive(Debug, Deserialize)]
+pub struct ScratchReadArgs {
+    /// Key to read (omit to list every key)
+    pub key: Option<String>,
+}
+
+/// Store a value, or append it on a new line to what the key holds
+fn write_entry(entries: &mut BTreeMap<String, String>, key: &str, value: &str, append: bool) -> Result<String, ToolError> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(ToolError::invalid_arguments("key must not be empty"));
+    }
+
+    let new_value = match entries.get(key) {
+        Some(existing) if append && !existing.is_empty() => format!("{}\n{}", existing, value),
+        _ => value.to_string(),
+    };
+    if new_value.len() > MAX_VALUE_BYTES {
+        return Err(ToolError::invalid_arguments(format!(
+            "'{}' would hold {} bytes; the limit is {} KB per key. Split it across several keys",
+            key,
+            new_value.len(),
+            MAX_VALUE_BYTES / 1024
+        )));
+    }
+
+    let verb = if append && entries.contains_key(key) { "Appended to" } else { "Stored" };
+    let size = new_value.len();
+    entries.insert(key.to_string(), new_value);
+    Ok(format!("{} '{}' ({} bytes)", verb, key, size))
+}
+
+/// The value under `key`, or a listing of every key with a preview
+fn read_entries(entries: &BTreeMap<String, String>, key: Option<&str>) -> Result<String, ToolError> {
+    match key.map(str::trim) {
+        Some(key) => entries.get(key).cloned().ok_or_else(|| {
+            let keys: Vec<&str> = entries.keys().map(String::as_str).collect();
+            ToolError::Other(format!(
+                "No scratchpad entry '{}'. Keys: {}",
+                key,
+                if keys.is_empty() { "(none)".to_string() } else { keys.join(", ") }
+            ))
+        }),
+        None if entries.is_empty() => Ok("The scratchpad is empty".to_string()),
+        None => Ok(entries
+            .iter()
+            .map(|(key, value)| format!(
+                "{} ({} bytes): {}",
+                key,
+                value.len(),
+                terminal::truncate_chars(&value.replace('\n', " "), PREVIEW_CHARS)
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
+
+/// Tool to store a value in the shared scratchpad
+#[derive(Debug, Clone)]
+pub struct ScratchWrite {
+    pub scratchpad: Scratchpad,
+}
+
+impl Tool for ScratchWrite {
+    const NAME: &'static str = "scratch_write";
+    type Error = ToolError;
+    type Args = ScratchWriteArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Store text under a key in the scratchpad shared by the main agent and all its sub-agents. Use it to hand results between agents, e.g. each sub-agent writes its part under its own key and the parent reads them all.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Key to store the value under (e.g. 'api_summary', 'review/src_main')"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "Text to store"
+                    },
+                    "append": {
+                        "type": "boolean",
+                        "description": "Append to the key's current value on a new line instead of replacing it (default: false)"
+                    }
+                },
+                "required": ["key", "value"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut entries = self.scratchpad.lock().await;
+        write_entry(&mut entries, &args.key, &args.value, args.append.unwrap_or(false))
+    }
+}
+
+/// Tool to read from the shared scratchpad
+#[derive(Debug, Clone)]
+pub struct ScratchRead {
+    pub scratchpad: Scratchpad,
+}
+
+impl Tool for ScratchRead {
+    const NAME: &'static str = "scratch_read";
+    type Error = ToolError;
+    type Args = ScratchReadArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Read a value from the scratchpad shared by the main agent and all its sub-agents, or list every key with a preview when no key is given.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Key to read. Omit to list all keys"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let entries = self.scratchpad.lock().await;
+        read_entries(&entries, args.key.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let mut entries = BTreeMap::new();
+        assert_eq!(read_entries(&entries, None).unwrap(), "The scratchpad is empty");
+
+        assert_eq!(write_entry(&mut entries, "parts", "first", true).unwrap(), "Stored 'parts' (5 bytes)");
+        assert_eq!(write_entry(&mut entries, "parts", "second", true).unwrap(), "Appended to 'parts' (12 bytes)");
+        write_entry(&mut entries, "summary", "done", false).unwrap();
+        write_entry(&mut entries, "summary", "redone", false).unwrap();
+
+        assert_eq!(read_entries(&entries, Some("parts")).unwrap(), "first\nsecond");
+        assert_eq!(read_entries(&entries, Some("summary")).unwrap(), "redone");
+        assert_eq!(
+            read_entries(&entries, None).unwrap(),
+            "parts (12 bytes): first second\nsummary (6 bytes): redone"
+        );
+        assert!(read_entries(&entries, Some("missing")).unwrap_err().to_string().contains("Keys: parts, summary"));
+    }
+
+    #[test]
+    fn test_write_limits() {
+        let mut entries = BTreeMap::new();
+        assert!(write_entry(&mut entries, "  ", "value", false).is_err());
+
+        let half = "x".repeat(MAX_VALUE_BYTES / 2 + 1);
+        write_entry(&mut entries, "big", &half, false).unwrap();
+        assert!(write_entry(&mut entries, "big", &half, true).is_err());
+        assert_eq!(entries["big"].len(), half.len());
+    }
+}
@@ -18,12 +18,16 @@ pub struct SearchKeyMemoryArgs {
     pub categories: Option<Vec<String>>,
     /// Minimum importance level (optional)
     pub min_importance: Option<String>,
+    /// Search other projects' memories too (default: false)
+    pub all_projects: Option<bool>,
 }
 
 /// Tool to search curated key memories
 #[derive(Debug, Clone)]
 pub struct SearchKeyMemory {
     pub memory_manager: Option<Arc<Mutex<MemoryManager>>>,
+    /// Project searches are limited to (see `memory::project_scope`)
+    pub project: Option<String>,
 }
 
 impl Tool for SearchKeyMemory {
@@ -61,6 +65,10 @@ impl Tool for SearchKeyMemory {
                         "type": "string",
                         "enum": ["low", "medium", "high", "critical"],
                         "description": "Optional: Only return memories at or above this importance level"
+                    },
+                    "all_projects": {
+                        "type": "boolean",
+                        "description": "Also search memories from other projects (default: false, only this project and facts about the user)"
                     }
                 },
                 "required": ["query"]
@@ -108,7 +116,8 @@ impl Tool for SearchKeyMemory {
         };
 
         let mut manager = memory_manager.lock().await;
-        let results = manager.search_key(&args.query, top_k, categories, min_importance)
+        let project = if args.all_projects.unwrap_or(false) { None } else { self.project.as_deref() };
+        let results = manager.search_key(&args.query, top_k, categories, min_importance, project)
             .map_err(|e| ToolError::Other(format!("Memory search failed: {}", e)))?;
 
         if results.is_empty() {
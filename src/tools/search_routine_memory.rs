@@ -1,5 +1,6 @@
 use crate::error::ToolError;
 use crate::memory::MemoryManager;
+use crate::terminal;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::Deserialize;
@@ -14,12 +15,16 @@ pub struct SearchRoutineMemoryArgs {
     pub query: String,
     /// Number of results to return (default: 5)
     pub top_k: Option<usize>,
+    /// Search conversations from other projects too (default: false)
+    pub all_projects: Option<bool>,
 }
 
 /// Tool to search routine conversation memory
 #[derive(Debug, Clone)]
 pub struct SearchRoutineMemory {
     pub memory_manager: Option<Arc<Mutex<MemoryManager>>>,
+    /// Project searches are limited to (see `memory::project_scope`)
+    pub project: Option<String>,
 }
 
 impl Tool for SearchRoutineMemory {
@@ -44,6 +49,10 @@ impl Tool for SearchRoutineMemory {
                         "description": "Number of results to return (default: 5, max: 20)",
                         "minimum": 1,
                         "maximum": 20
+                    },
+                    "all_projects": {
+                        "type": "boolean",
+                        "description": "Also search conversations held in other projects (default: false)"
                     }
                 },
                 "required": ["query"]
@@ -58,7 +67,8 @@ impl Tool for SearchRoutineMemory {
         let top_k = args.top_k.unwrap_or(5).min(20);
 
         let mut manager = memory_manager.lock().await;
-        let results = manager.search_routine(&args.query, top_k)
+        let project = if args.all_projects.unwrap_or(false) { None } else { self.project.as_deref() };
+        let results = manager.search_routine(&args.query, top_k, project)
             .map_err(|e| ToolError::Other(format!("Memory search failed: {}", e)))?;
 
         if results.is_empty() {
@@ -69,11 +79,7 @@ impl Tool for SearchRoutineMemory {
 
         for (idx, (chunk, score)) in results.iter().enumerate() {
             let timestamp = chunk.timestamp.format("%Y-%m-%d %H:%M");
-            let content_preview = if chunk.content.len() > 200 {
-                format!("{}...", &chunk.content[..200])
-            } else {
-                chunk.content.clone()
-            };
+            let content_preview = terminal::truncate_chars(&chunk.content, 200);
 
             output.push_str(&format!(
                 "{}. [{}] ({:.2} relevance) {}: {}\n   Session: {}, Role: {}\n   Tags: {}\n\n",
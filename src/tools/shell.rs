@@ -0,0 +1,94 @@
+//! Shell used to run commands
+//!
+//! The `bash` tool, background processes and the `!` passthrough all spawn
+//! commands through this module. The shell comes from `--shell`, or is
+//! detected: PowerShell (falling back to `cmd`) on Windows, `bash` (falling
+//! back to `sh`) elsewhere.
+
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+use tokio::process::Command;
+
+lazy_static! {
+    static ref SHELL: RwLock<Option<String>> = RwLock::new(None);
+    static ref DEFAULT_SHELL: String = detect();
+}
+
+/// Use this shell instead of the detected one
+pub fn set(shell: &str) {
+    if let Ok(mut guard) = SHELL.write() {
+        *guard = Some(shell.to_string());
+    }
+}
+
+/// The shell program commands are run with
+pub fn program() -> String {
+    SHELL
+        .read()
+        .ok()
+        .and_then(|guard| guard.clone())
+        .unwrap_or_else(|| DEFAULT_SHELL.clone())
+}
+
+/// A command that runs `script` with the configured shell
+pub fn command(script: &str) -> Command {
+    let program = program();
+    let mut cmd = Command::new(&program);
+    cmd.args(script_args(&program)).arg(script);
+    cmd
+}
+
+/// Whether a shell understands POSIX sh syntax (sh, bash, zsh, ...)
+pub fn is_posix(program: &str) -> bool {
+    script_args(program) == ["-c"]
+}
+
+/// Arguments that make a shell run the following script argument
+fn script_args(program: &str) -> &'static [&'static str] {
+    // Split on both separators so Windows paths are recognized everywhere
+    let name = program.rsplit(['/', '\\']).next().unwrap_or(program).to_lowercase();
+    match name.strip_suffix(".exe").unwrap_or(&name) {
+        "cmd" => &["/C"],
+        "powershell" | "pwsh" => &["-NoProfile", "-NonInteractive", "-Command"],
+        _ => &["-c"],
+    }
+}
+
+/// Pick the default shell for this platform from what is on PATH
+fn detect() -> String {
+    let candidates: &[&str] = if cfg!(windows) {
+        &["pwsh", "powershell", "cmd"]
+    } else {
+        &["bash", "sh"]
+    };
+    candidates
+        .iter()
+        .find(|name| on_path(name))
+        .unwrap_or(&candidates[candidates.len() - 1])
+        .to_string()
+}
+
+/// Whether an executable with this name is on PATH
+fn on_path(name: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        dir.join(name).is_file() || (cfg!(windows) && dir.join(format!("{}.exe", name)).is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_args() {
+        assert_eq!(script_args("bash"), &["-c"]);
+        assert_eq!(script_args("/usr/bin/zsh"), &["-c"]);
+        assert_eq!(script_args("cmd"), &["/C"]);
+        assert_eq!(script_args(r"C:\Windows\System32\cmd.exe"), &["/C"]);
+        assert_eq!(script_args("pwsh"), &["-NoProfile", "-NonInteractive", "-Command"]);
+        assert_eq!(script_args("PowerShell.exe"), &["-NoProfile", "-NonInteractive", "-Command"]);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::ToolError;
 use crate::memory::{ImportanceLevel, KeyMemoryChunk, MemoryCategory, MemoryManager};
+use crate::terminal;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::Deserialize;
@@ -27,6 +28,8 @@ pub struct StoreKeyMemoryArgs {
 pub struct StoreKeyMemory {
     pub memory_manager: Option<Arc<Mutex<MemoryManager>>>,
     pub session_id: Option<String>,
+    /// Project the memory is filed under (see `memory::project_scope`)
+    pub project: Option<String>,
 }
 
 impl Tool for StoreKeyMemory {
@@ -109,6 +112,7 @@ impl Tool for StoreKeyMemory {
             args.tags.unwrap_or_default(),
             args.related_files.unwrap_or_default(),
             self.session_id.clone(),
+            self.project.clone(),
         );
 
         // Store the memory
@@ -120,11 +124,7 @@ impl Tool for StoreKeyMemory {
             "✓ Stored {} memory ({}): {}",
             category,
             importance,
-            if args.content.len() > 60 {
-                format!("{}...", &args.content[..60])
-            } else {
-                args.content
-            }
+            terminal::truncate_chars(&args.content, 60)
         ))
     }
 }
@@ -0,0 +1,309 @@
+use crate::agent_loop::{TokenBudget, TokenUsage};
+use crate::error::ToolError;
+use crate::inspector::TrafficHandle;
+use crate::tools::{agent_ignore, file_limits};
+use rig::completion::{AssistantContent, CompletionModel, ToolDefinition};
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::fs;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::sync::Semaphore;
+
+/// Instructions for the summarizing request
+const SUMMARY_PREAMBLE: &str = "You summarize source files for a coding agent that cannot read them whole. \
+Be factual and compact; never invent items that are not in the file. Answer in this structure:\n\
+Purpose: one or two sentences on what the file is for.\n\
+Top-level items: each type, trait, function, constant or section with its line number and a short note.\n\
+Key functions: the most important functions or entry points and what they do.\n\
+Notes: dependencies, invariants, TODOs or anything surprising.";
+
+/// Most tokens the summary itself may take
+const MAX_SUMMARY_TOKENS: u64 = 2048;
+
+/// Appended to a first line too long to send whole (minified code, JSON)
+const TRUNCATED_LINE: &str = " [... line truncated]";
+
+/// Arguments for the SummarizeFile tool
+#[derive(Debug, Deserialize)]
+pub struct SummarizeFileArgs {
+    /// Path to the file to summarize
+    pub file_path: String,
+    /// Optional aspect to pay particular attention to
+    pub focus: Option<String>,
+}
+
+/// Tool to summarize a large file with a separate model request, instead of
+/// putting its contents into the conversation
+#[derive(Clone)]
+pub struct SummarizeFile<M: CompletionModel> {
+    pub model: M,
+    /// Provider options for the request (num_ctx, sampling)
+    pub model_options: Value,
+    /// Most characters of the file sent to the model; the rest is left out
+    pub max_input_chars: usize,
+    /// Session token budget the request is charged to (--max-total-tokens)
+    pub token_budget: Option<Arc<TokenBudget>>,
+    /// Limit on concurrent model requests shared with the agent loop (--max-concurrent-requests)
+    pub request_limiter: Option<Arc<Semaphore>>,
+    /// Traffic inspector the request and response are logged to
+    pub traffic: TrafficHandle,
+}
+
+/// The file with line numbers, cut at a line boundary to fit `max_chars`;
+/// a first line that is too long on its own is cut short with a marker.
+/// Lines past the cut are only counted, never held in memory. Returns the
+/// text, the number of lines it holds and the file's total line count.
+async fn numbered_excerpt<R: AsyncBufRead + Unpin>(mut reader: R, max_chars: usize) -> std::io::Result<(String, usize, usize)> {
+    let mut excerpt = String::new();
+    let mut included = 0;
+    let mut line = Vec::new();
+    // A line longer than the whole excerpt can't fit, so no more of it is read
+    let max_line_bytes = max_chars as u64 + 1;
+    let lines_before_cut = loop {
+        line.clear();
+        if (&mut reader).take(max_line_bytes).read_until(b'\n', &mut line).await? == 0 {
+            return Ok((excerpt, included, included));
+        }
+        let text = String::from_utf8_lossy(&line);
+        let text = text.strip_suffix('\n').map(|t| t.strip_suffix('\r').unwrap_or(t)).unwrap_or(&text);
+        let numbered = format!("{:>6}\t{}\n", included + 1, text);
+        if excerpt.len() + numbered.len() <= max_chars {
+            excerpt.push_str(&numbered);
+            included += 1;
+            continue;
+        }
+        if included > 0 {
+            break included;
+        }
+
+        // Keep what fits of the first line
+        let prefix = format!("{:>6}\t", 1);
+        let mut end = max_chars.saturating_sub(prefix.len() + TRUNCATED_LINE.len() + 1).min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        excerpt = format!("{}{}{}\n", prefix, &text[..end], TRUNCATED_LINE);
+        included = 1;
+        break 0;
+    };
+
+    // The line that was cut, plus every line after it
+    let mut total_lines = lines_before_cut + line.iter().filter(|&&b| b == b'\n').count();
+    let mut last_byte = line.last().copied();
+    loop {
+        let buf = reader.fill_buf().await?;
+        let Some(&last) = buf.last() else {
+            break;
+        };
+        total_lines += buf.iter().filter(|&&b| b == b'\n').count();
+        last_byte = Some(last);
+        let len = buf.len();
+        reader.consume(len);
+    }
+    if last_byte.is_some_and(|b| b != b'\n') {
+        total_lines += 1;
+    }
+    Ok((excerpt, included, total_lines))
+}
+
+/// The request asking for a summary of the excerpt
+fn summary_prompt(file_path: &str, excerpt: &str, total_lines: usize, included: usize, focus: Option<&str>) -> String {
+    let mut prompt = format!("Summarize {} ({} lines).", file_path, total_lines);
+    if included < total_lines {
+        prompt.push_str(&format!(" Only lines 1-{} are shown.", included));
+    }
+    if let Some(focus) = focus.filter(|f| !f.trim().is_empty()) {
+        prompt.push_str(&format!(" Pay particular attention to: {}.", focus.trim()));
+    }
+    prompt.push_str(&format!("\n\n```\n{}```", excerpt));
+    prompt
+}
+
+impl<M: CompletionModel> Tool for SummarizeFile<M> {
+    const NAME: &'static str = "summarize_file";
+    type Error = ToolError;
+    type Args = SummarizeFileArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Summarize a large file instead of reading it: purpose, top-level items with line numbers, and key functions. Use read_file with offset and limit afterwards for the parts you need.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the file to summarize"
+                    },
+                    "focus": {
+                        "type": "string",
+                        "description": "Optional aspect to pay particular attention to (e.g. 'error handling', 'the public API')"
+                    }
+                },
+                "required": ["file_path"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        agent_ignore::check(&args.file_path)?;
+
+        let path = Path::new(&args.file_path);
+        if !path.exists() {
+            return Err(ToolError::file_not_found(&args.file_path));
+        }
+        if !path.is_file() {
+            return Err(ToolError::invalid_path(format!("{} is not a file", args.file_path)));
+        }
+        if let Some(ref budget) = self.token_budget
+            && budget.is_exhausted() {
+                return Err(ToolError::Other(format!(
+                    "Token budget exhausted: {} of {} tokens used this session (--max-total-tokens)",
+                    budget.used(),
+                    budget.limit()
+                )));
+            }
+
+        let map_read_error = |e: std::io::Error| {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                ToolError::permission_denied(&args.file_path)
+            } else {
+                ToolError::Io(e)
+            }
+        };
+        let mut file = fs::File::open(path).await.map_err(map_read_error)?;
+        let size = file.metadata().await.map_err(map_read_error)?.len();
+
+        // Only the start is needed to spot a binary file
+        let mut head = Vec::new();
+        (&mut file).take(file_limits::BINARY_SCAN_BYTES as u64).read_to_end(&mut head).await.map_err(map_read_error)?;
+        if let Some(kind) = file_limits::detect_binary(&head) {
+            return Err(ToolError::invalid_arguments(format!(
+                "{} appears to be a binary file (detected type {}); there is nothing to summarize",
+                args.file_path, kind
+            )));
+        }
+
+        let reader = BufReader::new(std::io::Cursor::new(head).chain(file));
+        let (excerpt, included, total_lines) = numbered_excerpt(reader, self.max_input_chars).await.map_err(map_read_error)?;
+        if total_lines == 0 {
+            return Ok(format!("{} is empty", args.file_path));
+        }
+
+        let prompt = summary_prompt(&args.file_path, &excerpt, total_lines, included, args.focus.as_deref());
+
+        self.traffic
+            .log_request(
+                format!("Summary request for {}", args.file_path),
+                json!({
+                    "file_path": &args.file_path,
+                    "lines": included,
+                    "total_lines": total_lines,
+                    "prompt": &prompt,
+                }),
+            )
+            .await;
+
+        // Wait for a request slot like the agent loop does; held until the response is read
+        let _request_permit = match self.request_limiter {
+            Some(ref limiter) => Some(
+                limiter
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| ToolError::Other(format!("Request limiter closed: {}", e)))?,
+            ),
+            None => None,
+        };
+        let request_start = Instant::now();
+
+        let response = self
+            .model
+            .completion_request(prompt.as_str())
+            .preamble(SUMMARY_PREAMBLE.to_string())
+            .max_tokens(MAX_SUMMARY_TOKENS)
+            .additional_params(self.model_options.clone())
+            .send()
+            .await
+            .map_err(|e| ToolError::Other(format!("Summary request failed: {}", e)))?;
+
+        let summary: String = response
+            .choice
+            .iter()
+            .filter_map(|content| match content {
+                AssistantContent::Text(text) => Some(text.text.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.traffic
+            .log_response(
+                format!("Summary of {}", args.file_path),
+                json!({ "file_path": &args.file_path, "summary": &summary }),
+                Some(request_start.elapsed().as_millis() as u64),
+            )
+            .await;
+        if let Some(ref budget) = self.token_budget {
+            budget.charge(TokenUsage::estimate_tokens(SUMMARY_PREAMBLE) + TokenUsage::estimate_tokens(&prompt) + TokenUsage::estimate_tokens(&summary));
+        }
+        if summary.trim().is_empty() {
+            return Err(ToolError::Other("The model returned an empty summary".to_string()));
+        }
+
+        let mut output = format!("Summary of {} ({} lines, {} bytes):\n\n{}", args.file_path, total_lines, size, summary.trim());
+        if included < total_lines {
+            output.push_str(&format!(
+                "\n\n[Only lines 1-{} fit in one request and were summarized; read_file with offset {} for the rest]",
+                included,
+                included + 1
+            ));
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbered_excerpt() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let excerpt = |contents: &str, max_chars| runtime.block_on(numbered_excerpt(contents.as_bytes(), max_chars)).unwrap();
+        let contents = "fn a() {}\r\nfn b() {}\nfn c() {}\n";
+        let (text, included, total) = excerpt(contents, 1000);
+        assert_eq!((included, total), (3, 3));
+        assert!(text.starts_with("     1\tfn a() {}\n"));
+
+        // Cut at a line boundary, but always keep the first line; the rest is still counted
+        let (text, included, total) = excerpt(contents, 20);
+        assert_eq!((text.as_str(), included, total), ("     1\tfn a() {}\n", 1, 3));
+        assert_eq!(excerpt(contents, 1).1, 1);
+        assert_eq!(excerpt("a\nb\nc\nno newline", 10).2, 4);
+        assert_eq!(excerpt("", 10), (String::new(), 0, 0));
+
+        // A single long line is cut short instead of sent whole
+        let long = "é".repeat(5000);
+        let (text, included, total) = excerpt(&long, 100);
+        assert!(text.len() <= 100);
+        assert!(text.starts_with("     1\té"));
+        assert!(text.ends_with(" [... line truncated]\n"));
+        assert_eq!((included, total), (1, 1));
+        assert_eq!(excerpt(&format!("{}\nb\nc", "x".repeat(500)), 100).2, 3);
+    }
+
+    #[test]
+    fn test_summary_prompt() {
+        let prompt = summary_prompt("src/lib.rs", "     1\tfn a() {}\n", 3000, 1, Some("error handling"));
+        assert!(prompt.starts_with("Summarize src/lib.rs (3000 lines). Only lines 1-1 are shown."));
+        assert!(prompt.contains("Pay particular attention to: error handling."));
+        assert!(prompt.ends_with("```\n     1\tfn a() {}\n```"));
+
+        assert!(!summary_prompt("a.rs", "", 10, 10, Some("  ")).contains("attention"));
+    }
+}
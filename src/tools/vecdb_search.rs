@@ -0,0 +1,86 @@
+use crate::error::ToolError;
+use crate::vecdb::VectorDB;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Arguments for the VecdbSearch tool
+#[derive(Debug, Deserialize)]
+pub struct VecdbSearchArgs {
+    /// What to look for, in natural language or code
+    pub query: String,
+    /// Number of results to return (default: 5)
+    pub top_k: Option<usize>,
+}
+
+/// Tool to run a semantic search over the --vecdb code index
+#[derive(Clone)]
+pub struct VecdbSearch {
+    pub vecdb: Option<Arc<Mutex<VectorDB>>>,
+}
+
+impl Tool for VecdbSearch {
+    const NAME: &'static str = "vecdb_search";
+    type Error = ToolError;
+    type Args = VecdbSearchArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Semantic search over the indexed code base. Finds code by meaning rather than exact text (e.g. 'where are retries handled'); use grep for exact names.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What to look for, described in words or as a code snippet"
+                    },
+                    "top_k": {
+                        "type": "integer",
+                        "description": "Number of results to return (default: 5, max: 20)",
+                        "minimum": 1,
+                        "maximum": 20
+                    }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let vecdb = self.vecdb.as_ref()
+            .ok_or_else(|| ToolError::Other("Vector database not enabled (start with --vecdb)".to_string()))?;
+
+        let top_k = args.top_k.unwrap_or(5).clamp(1, 20);
+
+        let db = vecdb.lock().await;
+        let results = db.search(&args.query, top_k)
+            .await
+            .map_err(|e| ToolError::Other(format!("Vector search failed: {}", e)))?;
+
+        if results.is_empty() {
+            return Ok(format!("No indexed code found for query: '{}'", args.query));
+        }
+
+        let mut output = format!("Found {} code chunks:\n", results.len());
+        // Search returns cosine distances; show similarity
+        for (idx, (chunk, distance)) in results.iter().enumerate() {
+            output.push_str(&format!(
+                "\n{}. {}:{}-{} ({}) - Similarity: {:.2}\n```\n{}\n```\n",
+                idx + 1,
+                chunk.file_path,
+                chunk.start_line,
+                chunk.end_line,
+                chunk.language,
+                1.0 - distance,
+                chunk.content
+            ));
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,152 @@
+//! On-disk cache for web_fetch and web_search results
+//!
+//! Entries live under `~/.agent-t/cache/web/<namespace>/` as one JSON file per
+//! key. Entries older than the TTL are ignored, and once a namespace grows past
+//! `MAX_ENTRIES` the least recently used entries are evicted (a hit refreshes
+//! the file's modification time).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default time-to-live for cached entries (1 hour)
+pub const DEFAULT_TTL_SECS: u64 = 3600;
+
+/// Maximum entries kept per namespace before LRU eviction
+const MAX_ENTRIES: usize = 500;
+
+/// Current TTL in seconds (0 disables the cache)
+static TTL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_TTL_SECS);
+
+/// A cached tool result
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    created_at: DateTime<Utc>,
+    value: String,
+}
+
+/// Set the cache TTL in seconds (0 disables caching)
+pub fn set_ttl(secs: u64) {
+    TTL_SECS.store(secs, Ordering::Relaxed);
+}
+
+/// Look up a cached value; returns the value and the time it was stored
+pub fn get(namespace: &str, key: &str) -> Option<(String, DateTime<Utc>)> {
+    let ttl = TTL_SECS.load(Ordering::Relaxed);
+    if ttl == 0 {
+        return None;
+    }
+
+    let path = entry_path(namespace, key)?;
+    let json = std::fs::read_to_string(&path).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&json).ok()?;
+
+    // Guard against hash collisions
+    if entry.key != key {
+        return None;
+    }
+
+    let age = Utc::now().signed_duration_since(entry.created_at);
+    if age.num_seconds() < 0 || age.num_seconds() as u64 >= ttl {
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
+
+    // Mark as recently used
+    if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+        let _ = file.set_modified(std::time::SystemTime::now());
+    }
+
+    Some((entry.value, entry.created_at))
+}
+
+/// Store a value in the cache (errors are ignored; caching is best effort)
+pub fn put(namespace: &str, key: &str, value: &str) {
+    if TTL_SECS.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    let Some(path) = entry_path(namespace, key) else {
+        return;
+    };
+    if let Some(dir) = path.parent()
+        && std::fs::create_dir_all(dir).is_err()
+    {
+        return;
+    }
+
+    let entry = CacheEntry {
+        key: key.to_string(),
+        created_at: Utc::now(),
+        value: value.to_string(),
+    };
+    if let Ok(json) = serde_json::to_string(&entry) {
+        let _ = std::fs::write(&path, json);
+    }
+
+    if let Some(dir) = path.parent() {
+        evict_lru(dir);
+    }
+}
+
+/// Remove the least recently used entries beyond MAX_ENTRIES
+fn evict_lru(dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+            Some((modified, e.path()))
+        })
+        .collect();
+
+    if files.len() <= MAX_ENTRIES {
+        return;
+    }
+
+    files.sort_by_key(|(modified, _)| *modified);
+    let excess = files.len() - MAX_ENTRIES;
+    for (_, path) in files.into_iter().take(excess) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// File path for a cache key
+fn entry_path(namespace: &str, key: &str) -> Option<PathBuf> {
+    Some(
+        dirs::home_dir()?
+            .join(".agent-t")
+            .join("cache")
+            .join("web")
+            .join(namespace)
+            .join(format!("{:016x}.json", fnv1a(key))),
+    )
+}
+
+/// Stable 64-bit FNV-1a hash (std's DefaultHasher is not stable across releases)
+fn fnv1a(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        // Cache file names must not change between builds
+        assert_eq!(fnv1a(""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a("a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(fnv1a("https://a.example"), fnv1a("https://b.example"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::ToolError;
+use crate::tools::{network, web_cache};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -10,10 +11,96 @@ use std::time::Duration;
 pub struct WebFetchArgs {
     /// URL to fetch
     pub url: String,
-    /// Optional size limit in KB (default: 100KB)
+    /// Size of the returned window of text in KB (default: 100KB)
     pub size_limit_kb: Option<usize>,
+    /// Character offset of the window, to continue a page cut off earlier
+    pub offset: Option<usize>,
+    /// Bypass the result cache and fetch a fresh copy
+    pub no_cache: Option<bool>,
+    /// How HTML pages are converted (default: readable)
+    #[serde(default)]
+    pub mode: FetchMode,
 }
 
+/// How an HTML page is turned into text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchMode {
+    /// Main content only, without navigation, sidebars and footers
+    #[default]
+    Readable,
+    /// The whole page as plain text
+    Raw,
+    /// The whole page, keeping headings, emphasis and links
+    Markdown,
+}
+
+impl FetchMode {
+    fn name(self) -> &'static str {
+        match self {
+            FetchMode::Readable => "readable",
+            FetchMode::Raw => "raw",
+            FetchMode::Markdown => "markdown",
+        }
+    }
+}
+
+/// Largest response body downloaded at all; pages up to this size are
+/// returned in windows of `size_limit_kb`
+const MAX_DOWNLOAD_BYTES: usize = 10 * 1024 * 1024;
+
+/// A fetched and converted page, as cached
+#[derive(Debug, Serialize, Deserialize)]
+struct FetchedPage {
+    status: u16,
+    content_type: String,
+    final_url: String,
+    size: usize,
+    content: String,
+}
+
+impl FetchedPage {
+    /// The page's metadata and `window` characters of its content from `offset`
+    fn window(&self, offset: usize, window: usize) -> String {
+        let total = self.content.chars().count();
+        let start = offset.min(total);
+        let text: String = self.content.chars().skip(start).take(window).collect();
+        let end = start + text.chars().count();
+        let range = if end < total {
+            format!("characters {}-{} of {}; more remains, call again with offset={}", start, end, total, end)
+        } else if start > 0 {
+            format!("characters {}-{} of {} (end of page)", start, end, total)
+        } else {
+            format!("{} characters (complete)", total)
+        };
+        format!(
+            "Status: {}\nContent-Type: {}\nFinal URL: {}\nSize: {} bytes\nContent: {}\n\n{}\n",
+            self.status, self.content_type, self.final_url, self.size, range, text
+        )
+    }
+}
+
+/// Wrap width for converted HTML
+const TEXT_WIDTH: usize = 80;
+
+/// Readable text shorter than this is taken as a failed extraction, and the
+/// whole page is returned instead
+const MIN_READABLE_CHARS: usize = 200;
+
+/// Elements dropped from readable output along with their contents
+const BOILERPLATE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "aside", "form", "button", "footer",
+];
+
+/// Words in a class or id that mark an element as page furniture
+const BOILERPLATE_NAMES: &[&str] = &[
+    "sidebar", "navbar", "nav", "menu", "breadcrumb", "breadcrumbs", "cookie", "cookies", "banner", "share", "social",
+    "advert", "ads", "related", "comments", "toc",
+];
+
+/// ARIA roles that mark an element as page furniture
+const BOILERPLATE_ROLES: &[&str] = &["navigation", "banner", "contentinfo", "complementary", "search"];
+
 /// Tool to fetch content from a URL
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct WebFetch;
@@ -27,7 +114,7 @@ impl Tool for WebFetch {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "Fetch content from a URL. Automatically converts HTML to readable text. Returns content with metadata (status, content type, final URL).".to_string(),
+            description: "Fetch content from a URL. HTML is converted to text: by default only the main content (mode 'readable'), or the whole page as plain text ('raw') or with headings and links ('markdown'). Returns content with metadata (status, content type, final URL).".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -37,7 +124,20 @@ impl Tool for WebFetch {
                     },
                     "size_limit_kb": {
                         "type": "integer",
-                        "description": "Optional size limit in KB (default: 100KB). Maximum allowed is 500KB."
+                        "description": "Size of the returned text in KB (default: 100KB, max: 500KB). Longer pages are cut off at this size; use offset to read on."
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Character offset to start from, as given by an earlier call on the same page (default: 0)"
+                    },
+                    "no_cache": {
+                        "type": "boolean",
+                        "description": "Bypass the result cache and fetch a fresh copy (default: false)"
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["readable", "raw", "markdown"],
+                        "description": "How HTML is converted: 'readable' keeps the main content (default), 'raw' the whole page as text, 'markdown' the whole page with headings and links"
                     }
                 },
                 "required": ["url"]
@@ -46,6 +146,8 @@ impl Tool for WebFetch {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        network::check()?;
+
         // Validate and parse URL
         let parsed_url = url::Url::parse(&args.url)
             .map_err(|e| ToolError::invalid_url(format!("Invalid URL: {}", e)))?;
@@ -85,8 +187,24 @@ impl Tool for WebFetch {
                 ));
             }
 
-        // Set size limit (default 100KB, max 500KB)
-        let size_limit = args.size_limit_kb.unwrap_or(100).min(500) * 1024;
+        // Window of text returned (default 100KB, max 500KB)
+        let window = args.size_limit_kb.unwrap_or(100).clamp(1, 500) * 1024;
+        let offset = args.offset.unwrap_or(0);
+
+        // Serve from the cache unless a fresh copy was requested; the mode
+        // changes the output, so it is part of the key. Later windows of a
+        // page are read from the cached copy, so they line up with the first.
+        let cache_key = format!("{}\n{}", args.mode.name(), parsed_url);
+        if !args.no_cache.unwrap_or(false)
+            && let Some((cached, stored_at)) = web_cache::get("fetch", &cache_key)
+            && let Ok(page) = serde_json::from_str::<FetchedPage>(&cached)
+        {
+            return Ok(format!(
+                "[cached result from {}; pass no_cache to refresh]\n{}",
+                stored_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                page.window(offset, window)
+            ));
+        }
 
         // Build HTTP client
         let client = reqwest::Client::builder()
@@ -128,11 +246,11 @@ impl Tool for WebFetch {
             .await
             .map_err(|e| ToolError::network_error(format!("Failed to read response: {}", e)))?;
 
-        if bytes.len() > size_limit {
+        if bytes.len() > MAX_DOWNLOAD_BYTES {
             return Err(ToolError::http_error(format!(
                 "Response size ({} bytes) exceeds limit ({} bytes)",
                 bytes.len(),
-                size_limit
+                MAX_DOWNLOAD_BYTES
             )));
         }
 
@@ -141,22 +259,231 @@ impl Tool for WebFetch {
 
         // Process content based on type
         let processed_content = if content_type.contains("html") {
-            // Convert HTML to readable text
-            html2text::from_read(content.as_bytes(), 80)
+            html_to_text(&content, args.mode)
         } else {
             content
         };
 
-        // Format output with metadata
-        let output = format!(
-            "Status: {}\nContent-Type: {}\nFinal URL: {}\nSize: {} bytes\n\n{}\n",
-            status.as_u16(),
+        let page = FetchedPage {
+            status: status.as_u16(),
             content_type,
             final_url,
-            bytes.len(),
-            processed_content.trim()
-        );
+            size: bytes.len(),
+            content: processed_content.trim().to_string(),
+        };
+        if let Ok(json) = serde_json::to_string(&page) {
+            web_cache::put("fetch", &cache_key, &json);
+        }
 
+        let output = page.window(offset, window);
         Ok(output)
     }
 }
+
+/// Convert an HTML page to text in the given mode
+pub(crate) fn html_to_text(html: &str, mode: FetchMode) -> String {
+    match mode {
+        FetchMode::Raw => html2text::from_read_with_decorator(
+            html.as_bytes(),
+            TEXT_WIDTH,
+            html2text::render::text_renderer::TrivialDecorator::new(),
+        ),
+        FetchMode::Markdown => html2text::from_read(html.as_bytes(), TEXT_WIDTH),
+        FetchMode::Readable => {
+            let text = html2text::from_read(readable_html(html).as_bytes(), TEXT_WIDTH);
+            if text.trim().chars().count() >= MIN_READABLE_CHARS {
+                text
+            } else {
+                html2text::from_read(html.as_bytes(), TEXT_WIDTH)
+            }
+        }
+    }
+}
+
+/// The main content of a page, Readability-style: the `<main>` element or the
+/// longest `<article>` (falling back to `<body>`), without scripts, navigation,
+/// sidebars, footers and elements whose class, id or role marks them as such
+fn readable_html(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let content = elements(&lower, "main").into_iter().next()
+        .or_else(|| elements(&lower, "article").into_iter().max_by_key(|(start, end)| end - start));
+
+    // A page-level <header> is the site banner; one inside the content is
+    // usually the article title, so only the former goes
+    match content {
+        Some((start, end)) => strip_boilerplate(&html[start..end], false),
+        None => {
+            let body = elements(&lower, "body").into_iter().next().map_or(html, |(start, end)| &html[start..end]);
+            strip_boilerplate(body, true)
+        }
+    }
+}
+
+/// Ranges of the outermost `tag` elements, from the open tag to after the close tag
+fn elements(lower: &str, tag: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = find_open_tag(lower, tag, pos) {
+        let end = element_end(lower, tag, start);
+        ranges.push((start, end));
+        pos = end;
+    }
+    ranges
+}
+
+/// Position of the next `<tag` open tag at or after `from`
+fn find_open_tag(lower: &str, tag: &str, from: usize) -> Option<usize> {
+    let needle = format!("<{}", tag);
+    let mut pos = from;
+    while let Some(found) = lower[pos..].find(&needle) {
+        let start = pos + found;
+        let after = lower[start + needle.len()..].chars().next();
+        if matches!(after, Some(c) if c.is_whitespace() || c == '>' || c == '/') {
+            return Some(start);
+        }
+        pos = start + needle.len();
+    }
+    None
+}
+
+/// End of the element whose open tag starts at `start`, counting nested
+/// elements of the same name; an unclosed element runs to the end of the input
+fn element_end(lower: &str, tag: &str, start: usize) -> usize {
+    let Some(open_end) = lower[start..].find('>').map(|i| start + i + 1) else {
+        return lower.len();
+    };
+    if lower[..open_end].ends_with("/>") {
+        return open_end;
+    }
+
+    let close = format!("</{}", tag);
+    let mut depth = 1;
+    let mut pos = open_end;
+    loop {
+        let next_close = lower[pos..].find(&close).map(|i| pos + i);
+        let next_open = find_open_tag(lower, tag, pos);
+        match (next_open, next_close) {
+            (Some(open), Some(close_at)) if open < close_at => {
+                depth += 1;
+                pos = open + 1;
+            }
+            (_, Some(close_at)) => {
+                depth -= 1;
+                let close_end = lower[close_at..].find('>').map_or(lower.len(), |i| close_at + i + 1);
+                if depth == 0 {
+                    return close_end;
+                }
+                pos = close_end;
+            }
+            (_, None) => return lower.len(),
+        }
+    }
+}
+
+/// Remove boilerplate elements (see `BOILERPLATE_TAGS` and friends)
+fn strip_boilerplate(html: &str, drop_header: bool) -> String {
+    let lower = html.to_ascii_lowercase();
+    let mut output = String::with_capacity(html.len());
+    let mut pos = 0;
+    while let Some(found) = lower[pos..].find('<') {
+        let start = pos + found;
+        let name: String = lower[start + 1..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        let open_end = lower[start..].find('>').map_or(lower.len(), |i| start + i + 1);
+        if !name.is_empty() && is_boilerplate(&name, &lower[start..open_end], drop_header) {
+            output.push_str(&html[pos..start]);
+            pos = element_end(&lower, &name, start);
+        } else {
+            output.push_str(&html[pos..open_end]);
+            pos = open_end;
+        }
+    }
+    output.push_str(&html[pos..]);
+    output
+}
+
+/// Whether an element, given its name and open tag, is page furniture
+fn is_boilerplate(name: &str, open_tag: &str, drop_header: bool) -> bool {
+    if BOILERPLATE_TAGS.contains(&name) || (drop_header && name == "header") {
+        return true;
+    }
+    if let Some(role) = attribute(open_tag, "role")
+        && BOILERPLATE_ROLES.contains(&role)
+    {
+        return true;
+    }
+    ["class", "id"].iter().filter_map(|attr| attribute(open_tag, attr)).any(|value| {
+        value.split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+            .any(|word| BOILERPLATE_NAMES.contains(&word))
+    })
+}
+
+/// Value of a quoted attribute in a (lowercased) open tag
+fn attribute<'a>(open_tag: &'a str, name: &str) -> Option<&'a str> {
+    for quote in ['"', '\''] {
+        let needle = format!(" {}={}", name, quote);
+        if let Some(start) = open_tag.find(&needle).map(|i| i + needle.len()) {
+            return open_tag[start..].find(quote).map(|end| &open_tag[start..start + end]);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head><title>Guide</title><style>p { color: red }</style></head>
+<body>
+  <header><a href="/">Home</a> | <a href="/docs">Docs</a></header>
+  <div class="site-sidebar"><ul><li>Menu item</li></ul></div>
+  <ARTICLE>
+    <header><h1>Getting started</h1></header>
+    <p>Install the <a href="/cli">CLI</a> first.</p>
+    <div><div>Nested <b>content</b></div></div>
+    <div class="share-buttons">Share this</div>
+  </ARTICLE>
+  <script>var nav = "<nav>";</script>
+  <footer>Copyright</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_page_window() {
+        let page = FetchedPage {
+            status: 200,
+            content_type: "text/plain".to_string(),
+            final_url: "https://example.com/".to_string(),
+            size: 10,
+            content: "abcdéfghij".to_string(),
+        };
+
+        let first = page.window(0, 4);
+        assert!(first.contains("Content: characters 0-4 of 10; more remains, call again with offset=4\n\nabcd\n"));
+        assert!(page.window(4, 4).ends_with("\n\néfgh\n"));
+        assert!(page.window(8, 4).contains("characters 8-10 of 10 (end of page)\n\nij\n"));
+        assert!(page.window(0, 100).contains("Content: 10 characters (complete)"));
+        assert!(page.window(50, 4).contains("characters 10-10 of 10"));
+    }
+
+    #[test]
+    fn test_readable_html() {
+        let readable = readable_html(PAGE);
+        assert!(readable.contains("<h1>Getting started</h1>"));
+        assert!(readable.contains("Nested <b>content</b></div></div>"));
+        for boilerplate in ["Home", "Menu item", "Share this", "Copyright", "color: red", "var nav"] {
+            assert!(!readable.contains(boilerplate), "kept {}", boilerplate);
+        }
+    }
+
+    #[test]
+    fn test_html_to_text_modes() {
+        let markdown = html_to_text(PAGE, FetchMode::Markdown);
+        assert!(markdown.contains("# Getting started"));
+        assert!(markdown.contains("Copyright"));
+
+        let raw = html_to_text(PAGE, FetchMode::Raw);
+        assert!(raw.contains("Getting started") && !raw.contains("# Getting started"));
+
+        // Too little main content to trust, so the whole page is returned
+        assert!(html_to_text(PAGE, FetchMode::Readable).contains("Copyright"));
+    }
+}
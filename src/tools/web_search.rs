@@ -1,10 +1,75 @@
 use crate::error::ToolError;
+use crate::tools::{network, web_cache};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
+use std::sync::RwLock;
 use std::time::Duration;
 
+/// Search engine used by the web_search tool (--search-backend)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SearchBackend {
+    /// Scrape DuckDuckGo's HTML results page (no key needed)
+    #[default]
+    DuckDuckGo,
+    /// A SearXNG instance with the JSON output format enabled
+    Searxng { url: String },
+    /// Brave Search API
+    Brave { api_key: String },
+    /// Google Programmable Search (Custom Search JSON API)
+    Google { api_key: String, cx: String },
+}
+
+/// Backend names accepted by --search-backend
+pub const BACKEND_NAMES: [&str; 4] = ["duckduckgo", "searxng", "brave", "google"];
+
+impl SearchBackend {
+    /// Build the backend named on the command line. API keys come from
+    /// `BRAVE_API_KEY`, or `GOOGLE_API_KEY` and `GOOGLE_CSE_ID`.
+    pub fn from_config(name: &str, searxng_url: Option<&str>) -> Result<Self, String> {
+        let env = |var: &str| {
+            std::env::var(var)
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .ok_or_else(|| format!("--search-backend {} needs {} to be set", name, var))
+        };
+        match name {
+            "duckduckgo" => Ok(Self::DuckDuckGo),
+            "searxng" => searxng_url
+                .map(|url| Self::Searxng { url: url.trim_end_matches('/').to_string() })
+                .ok_or_else(|| "--search-backend searxng needs --searxng-url".to_string()),
+            "brave" => Ok(Self::Brave { api_key: env("BRAVE_API_KEY")? }),
+            "google" => Ok(Self::Google { api_key: env("GOOGLE_API_KEY")?, cx: env("GOOGLE_CSE_ID")? }),
+            other => Err(format!("Unknown search backend '{}' (expected one of: {})", other, BACKEND_NAMES.join(", "))),
+        }
+    }
+
+    /// Short name, also used to keep cached results apart
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DuckDuckGo => "duckduckgo",
+            Self::Searxng { .. } => "searxng",
+            Self::Brave { .. } => "brave",
+            Self::Google { .. } => "google",
+        }
+    }
+}
+
+static BACKEND: RwLock<SearchBackend> = RwLock::new(SearchBackend::DuckDuckGo);
+
+/// Use this backend for all later searches
+pub fn set_backend(backend: SearchBackend) {
+    if let Ok(mut guard) = BACKEND.write() {
+        *guard = backend;
+    }
+}
+
+/// The backend searches currently go to
+fn backend() -> SearchBackend {
+    BACKEND.read().map(|guard| guard.clone()).unwrap_or_default()
+}
+
 /// Arguments for the WebSearch tool
 #[derive(Debug, Deserialize)]
 pub struct WebSearchArgs {
@@ -12,6 +77,8 @@ pub struct WebSearchArgs {
     pub query: String,
     /// Number of results to return (default: 5, max: 10)
     pub num_results: Option<usize>,
+    /// Bypass the result cache and run a fresh search
+    pub no_cache: Option<bool>,
 }
 
 /// Tool to search the web
@@ -27,7 +94,7 @@ impl Tool for WebSearch {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "Search the web using DuckDuckGo. Returns a list of search results with title, URL, and snippet for each result.".to_string(),
+            description: "Search the web. Returns a list of search results with title, URL, and snippet for each result.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -38,6 +105,10 @@ impl Tool for WebSearch {
                     "num_results": {
                         "type": "integer",
                         "description": "Number of results to return (default: 5, max: 10)"
+                    },
+                    "no_cache": {
+                        "type": "boolean",
+                        "description": "Bypass the result cache and run a fresh search (default: false)"
                     }
                 },
                 "required": ["query"]
@@ -46,12 +117,27 @@ impl Tool for WebSearch {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        network::check()?;
+
         if args.query.trim().is_empty() {
             return Err(ToolError::invalid_arguments("Search query cannot be empty"));
         }
 
         let num_results = args.num_results.unwrap_or(5).min(10);
 
+        // The backend and result count are part of the key since they change the output
+        let backend = backend();
+        let cache_key = format!("{}\n{}\n{}", backend.name(), num_results, args.query.trim());
+        if !args.no_cache.unwrap_or(false)
+            && let Some((cached, stored_at)) = web_cache::get("search", &cache_key)
+        {
+            return Ok(format!(
+                "[cached result from {}; pass no_cache to refresh]\n{}",
+                stored_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                cached
+            ));
+        }
+
         // Build HTTP client
         let client = reqwest::Client::builder()
             .user_agent("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
@@ -60,31 +146,43 @@ impl Tool for WebSearch {
             .build()
             .map_err(|e| ToolError::network_error(format!("Failed to create HTTP client: {}", e)))?;
 
-        // Use DuckDuckGo HTML interface
-        let search_url = format!("https://html.duckduckgo.com/html/?q={}",
-            urlencoding::encode(&args.query));
-
-        // Fetch search results
-        let response = client
-            .get(&search_url)
-            .send()
-            .await
-            .map_err(|e| ToolError::network_error(format!("Failed to fetch search results: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(ToolError::http_error(format!(
-                "Search request failed with status: {}",
-                response.status()
-            )));
-        }
-
-        let html = response
-            .text()
-            .await
-            .map_err(|e| ToolError::network_error(format!("Failed to read response: {}", e)))?;
-
-        // Parse search results
-        let results = parse_duckduckgo_results(&html, num_results);
+        let results = match &backend {
+            SearchBackend::DuckDuckGo => {
+                // Use DuckDuckGo HTML interface
+                let search_url = format!("https://html.duckduckgo.com/html/?q={}",
+                    urlencoding::encode(&args.query));
+                let html = fetch(client.get(&search_url)).await?
+                    .text()
+                    .await
+                    .map_err(|e| ToolError::network_error(format!("Failed to read response: {}", e)))?;
+                parse_duckduckgo_results(&html, num_results)
+            }
+            SearchBackend::Searxng { url } => {
+                let request = client
+                    .get(format!("{}/search", url))
+                    .query(&[("q", args.query.as_str()), ("format", "json")]);
+                parse_json_results(&backend, &fetch_json(request).await?, num_results)
+            }
+            SearchBackend::Brave { api_key } => {
+                let request = client
+                    .get("https://api.search.brave.com/res/v1/web/search")
+                    .header("X-Subscription-Token", api_key)
+                    .header("Accept", "application/json")
+                    .query(&[("q", args.query.as_str()), ("count", &num_results.to_string())]);
+                parse_json_results(&backend, &fetch_json(request).await?, num_results)
+            }
+            SearchBackend::Google { api_key, cx } => {
+                let request = client
+                    .get("https://www.googleapis.com/customsearch/v1")
+                    .query(&[
+                        ("key", api_key.as_str()),
+                        ("cx", cx.as_str()),
+                        ("q", args.query.as_str()),
+                        ("num", &num_results.to_string()),
+                    ]);
+                parse_json_results(&backend, &fetch_json(request).await?, num_results)
+            }
+        };
 
         if results.is_empty() {
             return Ok("No search results found.".to_string());
@@ -103,10 +201,36 @@ impl Tool for WebSearch {
             ));
         }
 
+        web_cache::put("search", &cache_key, &output);
+
         Ok(output)
     }
 }
 
+/// Send a search request, failing on a non-success status
+async fn fetch(request: reqwest::RequestBuilder) -> Result<reqwest::Response, ToolError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| ToolError::network_error(format!("Failed to fetch search results: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(ToolError::http_error(format!(
+            "Search request failed with status: {}",
+            response.status()
+        )));
+    }
+    Ok(response)
+}
+
+/// Send a search API request and parse its JSON body
+async fn fetch_json(request: reqwest::RequestBuilder) -> Result<Value, ToolError> {
+    fetch(request).await?
+        .json()
+        .await
+        .map_err(|e| ToolError::network_error(format!("Failed to parse search response: {}", e)))
+}
+
 /// Represents a search result
 #[derive(Debug)]
 struct SearchResult {
@@ -153,6 +277,32 @@ fn parse_duckduckgo_results(html: &str, limit: usize) -> Vec<SearchResult> {
     results
 }
 
+/// Pull results out of a search API response. Each backend has its own
+/// result list and field names; missing fields become empty strings.
+fn parse_json_results(backend: &SearchBackend, body: &Value, limit: usize) -> Vec<SearchResult> {
+    let (list, url_field, snippet_field) = match backend {
+        SearchBackend::DuckDuckGo => return Vec::new(),
+        SearchBackend::Searxng { .. } => (&body["results"], "url", "content"),
+        SearchBackend::Brave { .. } => (&body["web"]["results"], "url", "description"),
+        SearchBackend::Google { .. } => (&body["items"], "link", "snippet"),
+    };
+    let field = |item: &Value, name: &str| decode_html(item[name].as_str().unwrap_or_default());
+
+    list.as_array()
+        .map(|items| {
+            items.iter()
+                .map(|item| SearchResult {
+                    title: field(item, "title"),
+                    url: field(item, url_field),
+                    snippet: field(item, snippet_field),
+                })
+                .filter(|result| !result.url.is_empty())
+                .take(limit)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Extract title and URL from a result section
 fn extract_title_and_url(html: &str) -> Option<(String, String)> {
     // Find the title link (class="result__a")
@@ -212,6 +362,8 @@ fn decode_html(text: &str) -> String {
         .replace("</b>", "")
         .replace("<em>", "")
         .replace("</em>", "")
+        .replace("<strong>", "")
+        .replace("</strong>", "")
         .trim()
         .to_string()
 }
@@ -255,3 +407,40 @@ mod urlencoding {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_results() {
+        let brave = SearchBackend::Brave { api_key: "key".to_string() };
+        let body = json!({ "web": { "results": [
+            { "title": "Tokio", "url": "https://tokio.rs", "description": "An <strong>async</strong> runtime" },
+            { "title": "No URL" },
+            { "title": "Docs", "url": "https://docs.rs/tokio", "description": "API docs" },
+        ]}});
+        let results = parse_json_results(&brave, &body, 5);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].snippet, "An async runtime");
+        assert_eq!(parse_json_results(&brave, &body, 1).len(), 1);
+
+        let google = SearchBackend::Google { api_key: "key".to_string(), cx: "cx".to_string() };
+        let body = json!({ "items": [{ "title": "Rust", "link": "https://rust-lang.org", "snippet": "A language" }] });
+        assert_eq!(parse_json_results(&google, &body, 5)[0].url, "https://rust-lang.org");
+
+        let searxng = SearchBackend::Searxng { url: "http://localhost:8888".to_string() };
+        assert!(parse_json_results(&searxng, &json!({ "error": "format disabled" }), 5).is_empty());
+    }
+
+    #[test]
+    fn test_backend_from_config() {
+        assert_eq!(SearchBackend::from_config("duckduckgo", None), Ok(SearchBackend::DuckDuckGo));
+        assert_eq!(
+            SearchBackend::from_config("searxng", Some("http://localhost:8888/")),
+            Ok(SearchBackend::Searxng { url: "http://localhost:8888".to_string() })
+        );
+        assert!(SearchBackend::from_config("searxng", None).is_err());
+        assert!(SearchBackend::from_config("bing", None).is_err());
+    }
+}
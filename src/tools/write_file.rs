@@ -1,4 +1,5 @@
 use crate::error::ToolError;
+use crate::tools::{agent_ignore, context_dirs, write_root};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -47,6 +48,10 @@ impl Tool for WriteFile {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        agent_ignore::check(&args.file_path)?;
+        context_dirs::check_writable(&args.file_path)?;
+        write_root::check(&args.file_path)?;
+
         let path = Path::new(&args.file_path);
 
         // Create parent directories if they don't exist
@@ -0,0 +1,54 @@
+//! Confining file writes to one directory (--write-root)
+//!
+//! With a write root set, write_file, edit_file and edit_markdown_section
+//! refuse any target that does not resolve to a path inside it. Targets are
+//! resolved by `paths::resolve`, following `..` and every symlink, so neither
+//! traversal nor a link pointing out of the tree can escape the root.
+
+use crate::error::ToolError;
+use crate::tools::paths;
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref WRITE_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Confine writes to `root`, which must be an existing directory
+pub fn set(root: &Path) -> std::io::Result<PathBuf> {
+    let root = root.canonicalize()?;
+    if !root.is_dir() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotADirectory, format!("{} is not a directory", root.display())));
+    }
+    if let Ok(mut guard) = WRITE_ROOT.write() {
+        *guard = Some(root.clone());
+    }
+    Ok(root)
+}
+
+/// The write root, if writes are confined (canonical)
+pub fn root() -> Option<PathBuf> {
+    WRITE_ROOT.read().ok().and_then(|guard| guard.clone())
+}
+
+/// Reject a write whose target resolves outside the write root
+pub fn check(path: &str) -> Result<(), ToolError> {
+    let Some(root) = root() else {
+        return Ok(());
+    };
+    match paths::resolve(Path::new(path)) {
+        Some(target) if target.starts_with(&root) => Ok(()),
+        Some(target) => Err(ToolError::permission_denied(format!(
+            "{} resolves to {}, which is outside the write root {} (--write-root)",
+            path,
+            target.display(),
+            root.display()
+        ))),
+        None => Err(ToolError::permission_denied(format!(
+            "{} could not be resolved (too many symlinks); writes are limited to {} (--write-root)",
+            path,
+            root.display()
+        ))),
+    }
+}
@@ -1,12 +1,35 @@
 use anyhow::{anyhow, Result};
+use std::ops::Range;
 use std::path::Path;
 use streaming_iterator::StreamingIterator;
-use tree_sitter::{Language, Node, Parser, Query, QueryCursor, Tree};
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor};
 
 use crate::vecdb::CodeChunk;
 
-/// Maximum chunk size in characters for fallback chunking
-const MAX_CHUNK_SIZE: usize = 1500;
+/// Default maximum chunk size in bytes (--vecdb-chunk-size)
+pub const DEFAULT_CHUNK_SIZE: usize = 1500;
+
+/// Default number of preceding lines carried into each chunk (--vecdb-chunk-overlap)
+pub const DEFAULT_CHUNK_OVERLAP: usize = 3;
+
+/// How files are cut into chunks
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkOptions {
+    /// Chunks larger than this are split at syntax (or line) boundaries
+    pub max_size: usize,
+    /// Lines before a chunk that are repeated at its start, so a chunk keeps
+    /// the doc comment or code leading into it
+    pub overlap_lines: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_CHUNK_SIZE,
+            overlap_lines: DEFAULT_CHUNK_OVERLAP,
+        }
+    }
+}
 
 /// Get the appropriate tree-sitter language for a file extension
 fn get_language(ext: &str) -> Option<Language> {
@@ -104,11 +127,17 @@ fn get_query_patterns(ext: &str) -> Option<&'static str> {
     }
 }
 
-/// Extract code chunks using tree-sitter parsing
+/// Extract code chunks using tree-sitter parsing.
+///
+/// Each captured item (function, impl, class, ...) becomes one chunk. Items
+/// over `max_size` are replaced by the items inside them, or split between
+/// their child nodes, so a chunk never starts or ends in the middle of a line
+/// or statement. Uncaptured regions are filled in afterwards.
 pub fn chunk_code_with_tree_sitter(
     file_path: &Path,
     content: &str,
     language: &str,
+    options: ChunkOptions,
 ) -> Result<Vec<CodeChunk>> {
     let ext = file_path
         .extension()
@@ -118,11 +147,11 @@ pub fn chunk_code_with_tree_sitter(
     // Get language and query pattern
     let Some(ts_language) = get_language(ext) else {
         // Fallback to simple chunking for unsupported languages
-        return Ok(fallback_chunk(file_path, content, language));
+        return Ok(fallback_chunk(file_path, content, language, options));
     };
 
     let Some(query_pattern) = get_query_patterns(ext) else {
-        return Ok(fallback_chunk(file_path, content, language));
+        return Ok(fallback_chunk(file_path, content, language, options));
     };
 
     // Parse the file
@@ -139,209 +168,259 @@ pub fn chunk_code_with_tree_sitter(
     let query = Query::new(&ts_language, query_pattern)
         .map_err(|e| anyhow!("Failed to create query: {}", e))?;
 
-    // Execute query and collect chunks
-    let mut chunks = Vec::new();
+    // Collect the byte ranges of all captured items
+    let mut items: Vec<Range<usize>> = Vec::new();
+    let mut nodes: Vec<Node> = Vec::new();
     let mut cursor = QueryCursor::new();
     let mut matches = cursor.matches(&query, tree.root_node(), content.as_bytes());
-
-    // Iterate through matches
     while let Some(match_) = matches.next() {
         for capture in match_.captures {
-            let node = capture.node;
-
-            // Skip nodes that are too large
-            if node.byte_range().len() > MAX_CHUNK_SIZE {
-                // For large nodes, try to extract smaller sub-nodes
-                chunks.extend(extract_subnodes(node, content, file_path, language)?);
-            } else if let Some(chunk) = node_to_chunk(node, content, file_path, language)? {
-                chunks.push(chunk);
+            if !items.contains(&capture.node.byte_range()) {
+                items.push(capture.node.byte_range());
+                nodes.push(capture.node);
             }
         }
     }
 
+    let contains = |outer: &Range<usize>, inner: &Range<usize>| {
+        outer != inner && outer.start <= inner.start && inner.end <= outer.end
+    };
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (node, range) in nodes.iter().zip(&items) {
+        // Already part of an enclosing item that is kept whole
+        if items.iter().any(|outer| contains(outer, range) && outer.len() <= options.max_size) {
+            continue;
+        }
+        if range.len() <= options.max_size {
+            ranges.push(range.clone());
+        } else if !items.iter().any(|inner| contains(range, inner)) {
+            ranges.extend(split_node(*node, options.max_size));
+        }
+        // Otherwise the items inside it are chunks of their own
+    }
+
+    let mut chunks: Vec<CodeChunk> = ranges
+        .into_iter()
+        .filter_map(|range| line_chunk(content, range, file_path, language))
+        .collect();
+
+    // Pieces sharing a line (e.g. a signature and the start of its split body)
+    // can end up inside a neighbour once widened to whole lines
+    chunks.sort_by_key(|chunk| (chunk.start_line, std::cmp::Reverse(chunk.end_line)));
+    let mut last_end = 0;
+    chunks.retain(|chunk| {
+        let keep = chunk.end_line > last_end;
+        last_end = last_end.max(chunk.end_line);
+        keep
+    });
+
     // If we didn't find any chunks, fall back to simple chunking
     if chunks.is_empty() {
-        return Ok(fallback_chunk(file_path, content, language));
+        return Ok(fallback_chunk(file_path, content, language, options));
     }
 
     // Add coverage for uncaptured regions if there are significant gaps
-    chunks.extend(fill_gaps(&tree, content, file_path, language, &chunks)?);
+    let gaps = fill_gaps(content, file_path, language, &chunks);
+    chunks.extend(gaps);
+    chunks.sort_by_key(|chunk| chunk.start_line);
 
+    add_overlap(&mut chunks, content, options.overlap_lines);
     Ok(chunks)
 }
 
-/// Extract smaller sub-nodes from a large node
-fn extract_subnodes(
-    node: Node,
-    content: &str,
-    file_path: &Path,
-    language: &str,
-) -> Result<Vec<CodeChunk>> {
-    let mut chunks = Vec::new();
+/// Split an oversized node between its children, grouping neighbours up to
+/// `max_size`. Children that are too big themselves are split the same way;
+/// a leaf is never cut.
+fn split_node(node: Node, max_size: usize) -> Vec<Range<usize>> {
+    let mut pieces: Vec<Range<usize>> = Vec::new();
+    let mut current: Option<Range<usize>> = None;
 
-    // Try to extract child nodes
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
-        if child.byte_range().len() <= MAX_CHUNK_SIZE
-            && let Some(chunk) = node_to_chunk(child, content, file_path, language)? {
-                chunks.push(chunk);
+        let range = child.byte_range();
+        if range.len() > max_size && child.child_count() > 0 {
+            pieces.extend(current.take());
+            pieces.extend(split_node(child, max_size));
+            continue;
+        }
+        current = match current {
+            Some(group) if range.end - group.start <= max_size => Some(group.start..range.end),
+            Some(group) => {
+                pieces.push(group);
+                Some(range)
             }
+            None => Some(range),
+        };
     }
+    pieces.extend(current);
 
-    // If no suitable children, fall back to the original node (truncated)
-    if chunks.is_empty()
-        && let Some(chunk) = node_to_chunk(node, content, file_path, language)? {
-            chunks.push(chunk);
-        }
-
-    Ok(chunks)
-}
-
-/// Convert a tree-sitter node to a CodeChunk
-fn node_to_chunk(
-    node: Node,
-    content: &str,
-    file_path: &Path,
-    language: &str,
-) -> Result<Option<CodeChunk>> {
-    let start_byte = node.start_byte();
-    let end_byte = node.end_byte();
-
-    // Skip empty nodes
-    if start_byte >= end_byte {
-        return Ok(None);
+    if pieces.is_empty() {
+        pieces.push(node.byte_range());
     }
+    pieces
+}
 
-    let chunk_content = &content[start_byte..end_byte];
-
-    // Skip whitespace-only chunks
-    if chunk_content.trim().is_empty() {
-        return Ok(None);
+/// A chunk of the whole lines covering a byte range; None for blank ranges
+fn line_chunk(content: &str, range: Range<usize>, file_path: &Path, language: &str) -> Option<CodeChunk> {
+    let start = content[..range.start].rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+    let end = content[range.end..]
+        .find('\n')
+        .map(|pos| range.end + pos)
+        .unwrap_or(content.len());
+    let text = content[start..end].trim_end();
+    if text.trim().is_empty() {
+        return None;
     }
 
-    let start_line = node.start_position().row + 1;
-    let end_line = node.end_position().row + 1;
-
-    Ok(Some(CodeChunk {
+    let start_line = content[..start].matches('\n').count() + 1;
+    Some(CodeChunk {
         file_path: file_path.to_string_lossy().to_string(),
         start_line,
-        end_line,
-        content: chunk_content.to_string(),
+        end_line: start_line + text.matches('\n').count(),
+        content: text.to_string(),
         language: language.to_string(),
-    }))
+    })
 }
 
 /// Fill gaps between extracted chunks with additional content
 fn fill_gaps(
-    _tree: &Tree,
     content: &str,
     file_path: &Path,
     language: &str,
     existing_chunks: &[CodeChunk],
-) -> Result<Vec<CodeChunk>> {
-    let mut gap_chunks = Vec::new();
-
-    // Get byte ranges of existing chunks
-    let mut covered_ranges: Vec<(usize, usize)> = existing_chunks
+) -> Vec<CodeChunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut covered: Vec<(usize, usize)> = existing_chunks
         .iter()
-        .map(|chunk| {
-            let start = content[..].lines()
-                .take(chunk.start_line - 1)
-                .map(|l| l.len() + 1)
-                .sum::<usize>();
-            let end = content[..].lines()
-                .take(chunk.end_line)
-                .map(|l| l.len() + 1)
-                .sum::<usize>();
-            (start, end)
-        })
+        .map(|chunk| (chunk.start_line, chunk.end_line))
         .collect();
+    covered.sort();
+    covered.push((lines.len() + 1, lines.len() + 1));
 
-    covered_ranges.sort_by_key(|r| r.0);
-
-    // Find gaps
-    let mut current_pos = 0;
-    for (start, end) in covered_ranges {
-        if start > current_pos {
-            // There's a gap
-            let gap_content = &content[current_pos..start];
-            if gap_content.trim().len() > 50 {  // Only include significant gaps
-                let start_line = content[..current_pos].matches('\n').count() + 1;
-                let end_line = content[..start].matches('\n').count() + 1;
-
+    // Find gaps (1-based, inclusive line numbers)
+    let mut gap_chunks = Vec::new();
+    let mut next_line = 1;
+    for (start, end) in covered {
+        if start > next_line {
+            let gap = lines[next_line - 1..start - 1].join("\n");
+            if gap.trim().len() > 50 {  // Only include significant gaps
                 gap_chunks.push(CodeChunk {
                     file_path: file_path.to_string_lossy().to_string(),
-                    start_line,
-                    end_line,
-                    content: gap_content.to_string(),
+                    start_line: next_line,
+                    end_line: start - 1,
+                    content: gap.trim_end().to_string(),
                     language: language.to_string(),
                 });
             }
         }
-        current_pos = current_pos.max(end);
+        next_line = next_line.max(end + 1);
     }
 
-    Ok(gap_chunks)
+    gap_chunks
 }
 
-/// Fallback chunking for unsupported languages or parsing errors
-fn fallback_chunk(file_path: &Path, content: &str, language: &str) -> Vec<CodeChunk> {
-    const CHUNK_SIZE: usize = 1000;
-    const CHUNK_OVERLAP: usize = 200;
+/// Start each chunk with the `overlap_lines` lines before it
+fn add_overlap(chunks: &mut [CodeChunk], content: &str, overlap_lines: usize) {
+    if overlap_lines == 0 {
+        return;
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    for chunk in chunks {
+        let first = chunk.start_line.saturating_sub(overlap_lines).max(1);
+        if first == chunk.start_line {
+            continue;
+        }
+        let lead = lines[first - 1..chunk.start_line - 1].join("\n");
+        chunk.content = format!("{}\n{}", lead, chunk.content);
+        chunk.start_line = first;
+    }
+}
 
+/// Fallback chunking for unsupported languages or parsing errors: runs of
+/// whole lines up to `max_size`, each overlapping the previous one
+fn fallback_chunk(file_path: &Path, content: &str, language: &str, options: ChunkOptions) -> Vec<CodeChunk> {
+    let lines: Vec<&str> = content.lines().collect();
     let mut chunks = Vec::new();
-    let mut current_pos = 0;
-
-    while current_pos < content.len() {
-        let remaining = &content[current_pos..];
-
-        // Find a safe chunk end that respects UTF-8 boundaries
-        let chunk_end = if remaining.len() > CHUNK_SIZE {
-            let safe_end = remaining
-                .char_indices()
-                .take_while(|(idx, _)| *idx <= CHUNK_SIZE)
-                .last()
-                .map(|(idx, ch)| idx + ch.len_utf8())
-                .unwrap_or(0);
-
-            // Try to find a newline before the safe boundary
-            if let Some(pos) = remaining[..safe_end].rfind('\n') {
-                current_pos + pos + 1
-            } else {
-                current_pos + safe_end
-            }
-        } else {
-            content.len()
-        };
-
-        let chunk_content = content[current_pos..chunk_end].trim().to_string();
-
-        if !chunk_content.is_empty() {
-            let chunk_lines = content[..current_pos].matches('\n').count();
-            let end_lines = content[..chunk_end].matches('\n').count();
+    let mut start = 0;
+
+    while start < lines.len() {
+        // Take lines until the size limit, but always at least one
+        let mut end = start + 1;
+        let mut size = lines[start].len();
+        while end < lines.len() && options.max_size > size + lines[end].len() {
+            size += lines[end].len() + 1;
+            end += 1;
+        }
 
+        let chunk_content = lines[start..end].join("\n");
+        if !chunk_content.trim().is_empty() {
             chunks.push(CodeChunk {
                 file_path: file_path.to_string_lossy().to_string(),
-                start_line: chunk_lines + 1,
-                end_line: end_lines + 1,
-                content: chunk_content,
+                start_line: start + 1,
+                end_line: end,
+                content: chunk_content.trim_end().to_string(),
                 language: language.to_string(),
             });
         }
 
-        // Move forward with overlap
-        current_pos = if chunk_end < content.len() {
-            let target_pos = chunk_end.saturating_sub(CHUNK_OVERLAP);
-            content[..chunk_end]
-                .char_indices()
-                .find(|(idx, _)| *idx >= target_pos)
-                .map(|(idx, _)| idx)
-                .unwrap_or(chunk_end)
-                .max(current_pos + 1)
-        } else {
-            chunk_end
-        };
+        if end == lines.len() {
+            break;
+        }
+        // Move forward with overlap, always making progress
+        start = end.saturating_sub(options.overlap_lines).max(start + 1);
     }
 
     chunks
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_chunks(source: &str, max_size: usize, overlap_lines: usize) -> Vec<CodeChunk> {
+        let options = ChunkOptions { max_size, overlap_lines };
+        chunk_code_with_tree_sitter(Path::new("lib.rs"), source, "Rust", options).unwrap()
+    }
+
+    #[test]
+    fn test_items_are_kept_whole() {
+        let source = "/// Adds\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn x(&self) -> i32 {\n        self.x\n    }\n}\n";
+        let chunks = rust_chunks(source, 1500, 0);
+        let starts: Vec<usize> = chunks.iter().map(|c| c.start_line).collect();
+        // The method is inside the impl chunk, not a chunk of its own
+        assert_eq!(starts, vec![2, 6, 10]);
+        assert_eq!(chunks[2].content, "impl Point {\n    fn x(&self) -> i32 {\n        self.x\n    }\n}");
+
+        // Overlap brings in the doc comment before the function
+        let chunks = rust_chunks(source, 1500, 1);
+        assert!(chunks[0].content.starts_with("/// Adds\nfn add"));
+        assert_eq!(chunks[0].start_line, 1);
+    }
+
+    #[test]
+    fn test_large_items_split_between_statements() {
+        let body: String = (0..40).map(|i| format!("    let v{} = {} * 2;\n", i, i)).collect();
+        let source = format!("fn big() {{\n{}}}\n", body);
+        let chunks = rust_chunks(&source, 300, 0);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.content.lines().all(|line| line.trim().is_empty() || line.ends_with(';') || line.ends_with('{') || line.ends_with('}')));
+            assert_eq!(chunk.content.lines().count(), chunk.end_line - chunk.start_line + 1);
+        }
+        // Every line of the function is covered
+        let covered: usize = chunks.iter().map(|c| c.end_line - c.start_line + 1).sum();
+        assert!(covered >= 42);
+    }
+
+    #[test]
+    fn test_fallback_chunk_overlap() {
+        let content: String = (1..=30).map(|i| format!("line {:02}\n", i)).collect();
+        let options = ChunkOptions { max_size: 80, overlap_lines: 2 };
+        let chunks = fallback_chunk(Path::new("notes.txt"), &content, "Text", options);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 10));
+        assert_eq!(chunks[1].start_line, 9);
+        assert!(chunks[0].content.ends_with("line 10"));
+        assert_eq!(chunks.last().unwrap().end_line, 30);
+    }
+}
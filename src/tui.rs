@@ -7,7 +7,7 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
     Frame, Terminal,
@@ -19,7 +19,9 @@ use std::{
 };
 use tokio::sync::{mpsc::{Receiver, Sender}, oneshot};
 use tui_textarea::{Input, TextArea};
+use crate::agent_loop;
 use crate::colors;
+use crate::markdown;
 use crate::commands::CommandRegistry;
 
 /// Permission decision made by the user
@@ -27,9 +29,28 @@ use crate::commands::CommandRegistry;
 pub enum PermissionDecision {
     ApproveOnce,
     ApproveAll,
+    /// Approve this and later writes/edits to the same file
+    ApproveFile,
     Reject,
 }
 
+/// Answer to the overview of a response's tool calls
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchDecision {
+    /// Run every call in the batch without asking again
+    ApproveAll,
+    /// Ask for each call as usual
+    Review,
+    /// Run none of them
+    RejectAll,
+}
+
+/// Most lines the diagnostics panel takes, borders included
+const DIAGNOSTICS_PANEL_MAX_HEIGHT: u16 = 10;
+
+/// Lines of a tool's output shown while it is collapsed
+const TOOL_PREVIEW_LINES: usize = 4;
+
 /// Events that can be sent from the agent to the TUI
 #[derive(Debug)]
 pub enum TuiEvent {
@@ -37,8 +58,9 @@ pub enum TuiEvent {
     UserMessage { agent_id: String, text: String },
     AssistantMessage { agent_id: String, text: String },
     AssistantChunk { agent_id: String, chunk: String },  // For streaming
+    ReasoningChunk { agent_id: String, chunk: String },  // Model reasoning (--show-reasoning)
     ToolStart { agent_id: String, name: String, args: HashMap<String, String> },
-    ToolSuccess { agent_id: String, name: String, result: String },
+    ToolSuccess { agent_id: String, name: String, result: String, output: String },  // Summary line and the tool's output
     ToolError { agent_id: String, name: String, error: String },
     Info { agent_id: String, text: String },
     Warning { agent_id: String, text: String },
@@ -46,8 +68,13 @@ pub enum TuiEvent {
 
     // Status updates
     TokenUsage { agent_id: String, prompt: usize, completion: usize },
+    Thinking { agent_id: String, active: bool },  // Waiting for the model to start responding
+    IterationUpdate { agent_id: String, iteration: usize, max_iterations: usize, elapsed_ms: u64 },  // Each agent loop pass
     SessionUpdate { id: String, model: String },
     SessionListUpdate(Vec<String>),  // List of session IDs for autocomplete
+    Diagnostics(Option<String>),  // rust-analyzer report for the diagnostics panel (None = not running)
+    Tabs(crate::commands::TabAction),  // /tabs and /tab
+    Idle(String),  // What was done on going idle (--idle-timeout), e.g. "session saved"
 
     // Tab lifecycle events
     TabCreate { agent_id: String, name: String },
@@ -62,9 +89,27 @@ pub enum TuiEvent {
         diff: Option<crate::diff::UnifiedDiff>,
         response_tx: oneshot::Sender<PermissionDecision>,
     },
+    // Overview of several tool calls from one response, before their own prompts
+    BatchPermissionRequest {
+        calls: Vec<(String, HashMap<String, String>)>,
+        response_tx: oneshot::Sender<BatchDecision>,
+    },
+    // A turn is about to change more files than --max-files-per-turn; true = let it
+    FileLimitRequest {
+        limit: usize,
+        changed: Vec<String>,
+        pending: Vec<String>,
+        response_tx: oneshot::Sender<bool>,
+    },
+    // Plan to approve before the agent acts on it (--plan); true = approved
+    PlanApproval {
+        plan: String,
+        response_tx: oneshot::Sender<bool>,
+    },
 
     // System events
     Clear,
+    Rewind { agent_id: String, user_turns: usize },  // Keep only the first N user turns
     Quit,
     Interrupt,  // Escape key pressed - cancel all agent activity
 }
@@ -75,8 +120,9 @@ pub enum ChatMessage {
     User(String),
     Assistant(String),
     AssistantStreaming(String),  // Being actively streamed
+    Reasoning { text: String, expanded: bool },  // Model reasoning, collapsed by default
     ToolHeader { name: String, args: HashMap<String, String> },
-    ToolResult { name: String, success: bool, message: String },
+    ToolResult { name: String, success: bool, message: String, output: String, expanded: bool },  // Long output collapsed by default
     Info(String),
     Warning(String),
     Error(String),
@@ -86,7 +132,7 @@ impl ChatMessage {
     /// Wrap long text with backslash continuation (like shell commands)
     /// Returns lines that fit within max_width, with '\' at the end of continued lines
     fn wrap_with_continuation(text: &str, max_width: usize, indent: usize) -> Vec<String> {
-        if text.len() + indent <= max_width {
+        if text.chars().count() + indent <= max_width {
             return vec![text.to_string()];
         }
 
@@ -102,7 +148,7 @@ impl ChatMessage {
                 max_width.saturating_sub(indent + 2)  // Subsequent lines: indent + " \"
             };
 
-            if remaining.len() <= available {
+            if remaining.chars().count() <= available {
                 // Last chunk fits
                 if lines.is_empty() {
                     lines.push(remaining.to_string());
@@ -112,10 +158,13 @@ impl ChatMessage {
                 break;
             }
 
+            // Byte offset of the first char that doesn't fit
+            let limit = remaining.char_indices().nth(available).map_or(remaining.len(), |(i, _)| i);
+
             // Find a good break point (prefer breaking at spaces)
-            let mut break_at = available;
-            if let Some(last_space) = remaining[..available].rfind(char::is_whitespace)
-                && last_space > available / 2 {  // Only use space if it's not too early
+            let mut break_at = limit;
+            if let Some(last_space) = remaining[..limit].rfind(char::is_whitespace)
+                && last_space > limit / 2 {  // Only use space if it's not too early
                     break_at = last_space;
                 }
 
@@ -131,6 +180,63 @@ impl ChatMessage {
         lines
     }
 
+    /// Plain text content of the message, as copied to the clipboard
+    fn copy_text(&self) -> String {
+        match self {
+            ChatMessage::User(text)
+            | ChatMessage::Assistant(text)
+            | ChatMessage::AssistantStreaming(text)
+            | ChatMessage::Info(text)
+            | ChatMessage::Warning(text)
+            | ChatMessage::Error(text)
+            | ChatMessage::Reasoning { text, .. } => text.clone(),
+            ChatMessage::ToolHeader { name, args } => {
+                let mut args: Vec<_> = args.iter().collect();
+                args.sort();
+                let args_str = args.iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("{} {}", name, args_str).trim_end().to_string()
+            }
+            ChatMessage::ToolResult { message, output, .. } => {
+                if output.is_empty() { message.clone() } else { output.clone() }
+            }
+        }
+    }
+
+    /// Whether the message has a long tool output that can be collapsed
+    fn is_collapsible(&self) -> bool {
+        matches!(self, ChatMessage::ToolResult { output, .. } if output.lines().count() > TOOL_PREVIEW_LINES + 1)
+    }
+
+    /// Dimmed lines of a tool's output, just the first few unless expanded
+    fn tool_output_items(output: &str, expanded: bool, max_width: usize) -> Vec<ListItem<'static>> {
+        let style = Style::default().fg(colors::tui(colors::theme().overlay0));
+        let prefix = "    │ ";
+        let total = output.lines().count();
+        let collapsed = !expanded && total > TOOL_PREVIEW_LINES + 1;
+        let shown = if collapsed { TOOL_PREVIEW_LINES } else { total };
+
+        let mut items = Vec::new();
+        for line in output.lines().take(shown) {
+            let line = line.replace('\t', "    ");
+            for wrapped in Self::wrap_with_continuation(&line, max_width - prefix.len(), 0) {
+                items.push(ListItem::new(Line::from(Span::styled(format!("{}{}", prefix, wrapped), style))));
+            }
+        }
+
+        let hint = if collapsed {
+            format!("    ▸ {} more lines (select with Alt+Up, Enter to expand)", total - shown)
+        } else if total > TOOL_PREVIEW_LINES + 1 {
+            "    ▾ Enter to collapse".to_string()
+        } else {
+            return items;
+        };
+        items.push(ListItem::new(Line::from(Span::styled(hint, style.add_modifier(Modifier::ITALIC)))));
+        items
+    }
+
     /// Convert message to styled list items
     fn to_list_items(&self, agent_name: &str) -> Vec<ListItem<'static>> {
         match self {
@@ -147,15 +253,15 @@ impl ChatMessage {
                     if i == 0 {
                         items.push(ListItem::new(Line::from(vec![
                             Span::styled(prefix, Style::default()
-                                .fg(Color::Rgb(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2))
+                                .fg(colors::tui(colors::theme().green))
                                 .add_modifier(Modifier::BOLD)),
                             Span::styled(line.clone(), Style::default()
-                                .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
+                                .fg(colors::tui(colors::theme().text))),
                         ])));
                     } else {
                         items.push(ListItem::new(Line::from(Span::styled(
                             format!("{}{}", " ".repeat(prefix_len), line),
-                            Style::default().fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)),
+                            Style::default().fg(colors::tui(colors::theme().text)),
                         ))));
                     }
                 }
@@ -168,18 +274,37 @@ impl ChatMessage {
                     ListItem::new(Line::from(Span::styled(
                         format!("{}:", agent_name),
                         Style::default()
-                            .fg(Color::Rgb(colors::BLUE.0, colors::BLUE.1, colors::BLUE.2))
+                            .fg(colors::tui(colors::theme().blue))
                             .add_modifier(Modifier::BOLD),
                     )))
                 ];
 
-                // Split text into lines and wrap each line if needed
+                items.extend(markdown::render(text, MAX_WIDTH).into_iter().map(ListItem::new));
+                items
+            }
+            ChatMessage::Reasoning { text, expanded } => {
+                const MAX_WIDTH: usize = 120;
+                let style = Style::default()
+                    .fg(colors::tui(colors::theme().overlay0))
+                    .add_modifier(Modifier::ITALIC);
+                let text = text.trim();
+
+                if !expanded {
+                    return vec![ListItem::new(Line::from(Span::styled(
+                        format!("▸ Thinking ({} lines, Ctrl+R to expand)", text.lines().count()),
+                        style,
+                    )))];
+                }
+
+                let mut items = vec![
+                    ListItem::new(Line::from(Span::styled("▾ Thinking (Ctrl+R to collapse)", style)))
+                ];
                 for line in text.lines() {
                     let wrapped = Self::wrap_with_continuation(line, MAX_WIDTH - 2, 2);
                     for wrapped_line in wrapped {
                         items.push(ListItem::new(Line::from(Span::styled(
                             format!("  {}", wrapped_line),
-                            Style::default().fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)),
+                            style,
                         ))));
                     }
                 }
@@ -190,9 +315,9 @@ impl ChatMessage {
                 let mut items = vec![
                     ListItem::new(Line::from(vec![
                         Span::styled("⚡ ", Style::default()
-                            .fg(Color::Rgb(colors::PEACH.0, colors::PEACH.1, colors::PEACH.2))),
+                            .fg(colors::tui(colors::theme().peach))),
                         Span::styled(name.clone(), Style::default()
-                            .fg(Color::Rgb(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2))
+                            .fg(colors::tui(colors::theme().mauve))
                             .add_modifier(Modifier::BOLD)),
                     ]))
                 ];
@@ -217,18 +342,18 @@ impl ChatMessage {
 
                         items.push(ListItem::new(Line::from(Span::styled(
                             display_text,
-                            Style::default().fg(Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2)),
+                            Style::default().fg(colors::tui(colors::theme().overlay0)),
                         ))));
                     }
                 }
 
                 items
             }
-            ChatMessage::ToolResult { name: _, success, message } => {
+            ChatMessage::ToolResult { name: _, success, message, output, expanded } => {
                 let (icon, color) = if *success {
-                    ("✓", Color::Rgb(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2))
+                    ("✓", colors::tui(colors::theme().green))
                 } else {
-                    ("✗", Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2))
+                    ("✗", colors::tui(colors::theme().red))
                 };
 
                 const MAX_WIDTH: usize = 120;
@@ -253,6 +378,7 @@ impl ChatMessage {
                     }
                 }
 
+                items.extend(Self::tool_output_items(output, *expanded, MAX_WIDTH));
                 items
             }
             ChatMessage::Info(text) => {
@@ -261,7 +387,7 @@ impl ChatMessage {
                     ListItem::new(Line::from(Span::styled(
                         "ℹ Info:",
                         Style::default()
-                            .fg(Color::Rgb(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2))
+                            .fg(colors::tui(colors::theme().sapphire))
                             .add_modifier(Modifier::BOLD),
                     )))
                 ];
@@ -272,7 +398,7 @@ impl ChatMessage {
                     for wrapped_line in wrapped {
                         items.push(ListItem::new(Line::from(Span::styled(
                             format!("  {}", wrapped_line),
-                            Style::default().fg(Color::Rgb(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2)),
+                            Style::default().fg(colors::tui(colors::theme().sapphire)),
                         ))));
                     }
                 }
@@ -285,7 +411,7 @@ impl ChatMessage {
                     ListItem::new(Line::from(Span::styled(
                         "⚠ Warning:",
                         Style::default()
-                            .fg(Color::Rgb(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2))
+                            .fg(colors::tui(colors::theme().yellow))
                             .add_modifier(Modifier::BOLD),
                     )))
                 ];
@@ -296,7 +422,7 @@ impl ChatMessage {
                     for wrapped_line in wrapped {
                         items.push(ListItem::new(Line::from(Span::styled(
                             format!("  {}", wrapped_line),
-                            Style::default().fg(Color::Rgb(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2)),
+                            Style::default().fg(colors::tui(colors::theme().yellow)),
                         ))));
                     }
                 }
@@ -309,7 +435,7 @@ impl ChatMessage {
                     ListItem::new(Line::from(Span::styled(
                         "✗ Error:",
                         Style::default()
-                            .fg(Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2))
+                            .fg(colors::tui(colors::theme().red))
                             .add_modifier(Modifier::BOLD),
                     )))
                 ];
@@ -320,7 +446,7 @@ impl ChatMessage {
                     for wrapped_line in wrapped {
                         items.push(ListItem::new(Line::from(Span::styled(
                             format!("  {}", wrapped_line),
-                            Style::default().fg(Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2)),
+                            Style::default().fg(colors::tui(colors::theme().red)),
                         ))));
                     }
                 }
@@ -340,6 +466,52 @@ pub enum TabStatus {
     Killed,
 }
 
+impl TabStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            TabStatus::Running => "running",
+            TabStatus::Completed => "completed",
+            TabStatus::Failed => "failed",
+            TabStatus::Killed => "stopped",
+        }
+    }
+}
+
+/// Loop progress of the agent's current (or last) turn
+#[derive(Debug, Clone)]
+pub struct TurnProgress {
+    pub iteration: usize,
+    pub max_iterations: usize,
+    pub started: std::time::Instant,
+    /// Set once the turn has ended
+    pub finished: Option<Duration>,
+}
+
+impl TurnProgress {
+    /// "iteration 14/100, 3m12s running"
+    fn status(&self) -> String {
+        let (elapsed, state) = match self.finished {
+            Some(duration) => (duration, "last turn"),
+            None => (self.started.elapsed(), "running"),
+        };
+        format!("Iteration {}/{}, {} {}", self.iteration, self.max_iterations, format_elapsed(elapsed), state)
+    }
+
+    fn finish(&mut self) {
+        self.finished.get_or_insert_with(|| self.started.elapsed());
+    }
+}
+
+/// Compact duration: "45s", "3m12s", "1h05m"
+fn format_elapsed(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
 /// A tab representing an agent (main or sub-agent)
 pub struct AgentTab {
     pub id: String,
@@ -351,6 +523,14 @@ pub struct AgentTab {
     pub completion_tokens: usize,
     pub auto_scroll: bool,
     pub start_time: std::time::Instant,
+    /// When the agent completed, failed or was stopped
+    pub end_time: Option<std::time::Instant>,
+    /// Message selected for copying (None = most recent assistant message)
+    pub selected_message: Option<usize>,
+    /// When the agent started waiting on the model (None = not waiting)
+    pub thinking_since: Option<std::time::Instant>,
+    /// Iteration and running time of the current or last turn
+    pub turn: Option<TurnProgress>,
 }
 
 impl AgentTab {
@@ -368,6 +548,10 @@ impl AgentTab {
             completion_tokens: 0,
             auto_scroll: true,
             start_time: std::time::Instant::now(),
+            end_time: None,
+            selected_message: None,
+            thinking_since: None,
+            turn: None,
         }
     }
 
@@ -375,9 +559,31 @@ impl AgentTab {
         matches!(self.status, TabStatus::Running)
     }
 
-    #[allow(dead_code)]
+    /// Index of the message to copy: the selection, or the latest assistant reply
+    fn copy_target(&self) -> Option<usize> {
+        self.selected_message
+            .filter(|&i| i < self.messages.len())
+            .or_else(|| self.messages.iter().rposition(|m| {
+                matches!(m, ChatMessage::Assistant(_) | ChatMessage::AssistantStreaming(_))
+            }))
+    }
+
+    /// Time the agent has been running, or ran for once it stopped
     pub fn duration(&self) -> std::time::Duration {
-        self.start_time.elapsed()
+        match self.end_time {
+            Some(end) => end.duration_since(self.start_time),
+            None => self.start_time.elapsed(),
+        }
+    }
+
+    /// Mark the agent as no longer running
+    fn stop(&mut self, status: TabStatus) {
+        self.status = status;
+        self.end_time = Some(std::time::Instant::now());
+        self.thinking_since = None;
+        if let Some(turn) = self.turn.as_mut() {
+            turn.finish();
+        }
     }
 }
 
@@ -411,6 +617,9 @@ pub struct App {
 
     /// Permission modal state
     permission_modal: Option<PermissionModal>,
+    batch_modal: Option<BatchModal>,
+    plan_modal: Option<PlanModal>,
+    file_limit_modal: Option<FileLimitModal>,
 
     /// Autocomplete suggestions for current input
     autocomplete_suggestions: Vec<String>,
@@ -426,6 +635,27 @@ pub struct App {
 
     /// Whether mouse capture is enabled (for scrolling vs text selection)
     mouse_capture_enabled: bool,
+
+    /// Transient notification shown in the status bar, with the time it was raised
+    toast: Option<(String, std::time::Instant)>,
+
+    /// Price of the model for the status bar cost estimate (None = local)
+    price: Option<crate::pricing::ModelPrice>,
+
+    /// Latest rust-analyzer report (None = not running)
+    diagnostics: Option<String>,
+
+    /// Whether the diagnostics panel is shown (Ctrl+G)
+    show_diagnostics: bool,
+
+    /// Go idle after this long without input or agent activity (--idle-timeout)
+    idle_timeout: Option<Duration>,
+
+    /// Last input or agent event
+    last_activity: std::time::Instant,
+
+    /// When the session went idle, and what was done then (None = active)
+    idle: Option<(std::time::Instant, String)>,
 }
 
 /// State for the permission modal
@@ -437,8 +667,87 @@ struct PermissionModal {
     scroll_offset: usize,
 }
 
+impl PermissionModal {
+    /// Whether "[F] Approve all edits to this file" applies to this request:
+    /// the file-writing tools the agent loop accepts per-file approvals for
+    fn offers_file_approval(&self) -> bool {
+        agent_loop::FILE_WRITE_TOOLS.contains(&self.tool_name.as_str()) && self.args.contains_key("file_path")
+    }
+}
+
+/// State for the tool call batch modal
+struct BatchModal {
+    calls: Vec<(String, HashMap<String, String>)>,
+    response_tx: oneshot::Sender<BatchDecision>,
+}
+
+/// State for the --max-files-per-turn modal
+struct FileLimitModal {
+    limit: usize,
+    /// Files the turn has changed so far
+    changed: Vec<String>,
+    /// New files the rest of the response would change
+    pending: Vec<String>,
+    response_tx: oneshot::Sender<bool>,
+}
+
+/// State for the plan approval modal
+struct PlanModal {
+    plan: String,
+    response_tx: oneshot::Sender<bool>,
+    scroll_offset: usize,
+}
+
+/// Calls listed in the batch modal before the rest are counted
+const BATCH_MODAL_MAX_CALLS: usize = 15;
+
+/// One line of the batch modal: the tool and its arguments, sorted by name
+fn batch_call_line(tool_name: &str, args: &HashMap<String, String>, max_chars: usize) -> String {
+    let mut keys: Vec<&String> = args.keys().collect();
+    keys.sort();
+    let args = keys
+        .iter()
+        .map(|key| format!("{}: {}", key, args[*key].replace('\n', " ")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    crate::terminal::truncate_chars(&format!("{}  {}", tool_name, args), max_chars)
+}
+
+/// Key hints shown at the bottom of the permission modal
+fn permission_actions(offers_file_approval: bool) -> Line<'static> {
+    let key = |label: &'static str, color: colors::Rgb| {
+        Span::styled(label, Style::default()
+            .fg(colors::tui(color))
+            .add_modifier(Modifier::BOLD))
+    };
+    let text = |label: &'static str| {
+        Span::styled(label, Style::default()
+            .fg(colors::tui(colors::theme().text)))
+    };
+
+    let mut spans = vec![
+        key("[Enter/Y]", colors::theme().green),
+        text(" Approve Once  "),
+        key("[A]", colors::theme().blue),
+        text(" Approve All  "),
+    ];
+    if offers_file_approval {
+        spans.push(key("[F]", colors::theme().blue));
+        spans.push(text(" Approve all edits to this file  "));
+    }
+    spans.push(key("[Esc/N]", colors::theme().red));
+    spans.push(text(" Reject"));
+    Line::from(spans)
+}
+
 impl App {
-    pub fn new(session_id: String, model_name: String, agent_name: String, cwd: String) -> Self {
+    pub fn new(
+        session_id: String,
+        model_name: String,
+        agent_name: String,
+        cwd: String,
+        price: Option<crate::pricing::ModelPrice>,
+    ) -> Self {
         let mut textarea = TextArea::default();
         textarea.set_block(
             Block::default()
@@ -467,14 +776,50 @@ impl App {
             history_index: None,
             current_draft: String::new(),
             permission_modal: None,
+            batch_modal: None,
+            plan_modal: None,
+            file_limit_modal: None,
             autocomplete_suggestions: Vec::new(),
             autocomplete_index: 0,
             session_ids: Vec::new(),
             cwd,
             mouse_capture_enabled: true,
+            toast: None,
+            price,
+            diagnostics: None,
+            show_diagnostics: false,
+            idle_timeout: None,
+            last_activity: std::time::Instant::now(),
+            idle: None,
         }
     }
 
+    /// Note input or agent output, leaving the idle state
+    fn mark_active(&mut self) {
+        self.last_activity = std::time::Instant::now();
+        self.idle = None;
+    }
+
+    /// Whether an agent is waiting on the model or in the middle of a turn
+    fn agents_busy(&self) -> bool {
+        self.tabs.iter().any(|tab| {
+            tab.thinking_since.is_some() || tab.turn.as_ref().is_some_and(|turn| turn.finished.is_none())
+        })
+    }
+
+    /// Go idle once nothing has happened for --idle-timeout, asking the agent
+    /// task to save the session (and unload the model with --idle-unload)
+    fn check_idle(&mut self, input_tx: &Sender<String>) {
+        let Some(timeout) = self.idle_timeout else {
+            return;
+        };
+        if self.idle.is_some() || self.last_activity.elapsed() < timeout || self.agents_busy() {
+            return;
+        }
+        self.idle = Some((std::time::Instant::now(), String::new()));
+        let _ = input_tx.try_send("\x1b[IDLE]".to_string());
+    }
+
     // Tab management helper methods
 
     fn get_active_tab(&self) -> &AgentTab {
@@ -538,6 +883,9 @@ impl App {
             }
             TuiEvent::AssistantMessage { agent_id, text } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
+                    if let Some(turn) = self.tabs[index].turn.as_mut() {
+                        turn.finish();
+                    }
                     // Replace streaming message if exists, or add new
                     if let Some(ChatMessage::AssistantStreaming(_)) = self.tabs[index].messages.last() {
                         self.tabs[index].messages.pop();
@@ -561,18 +909,31 @@ impl App {
                     self.switch_to_tab(index);
                 }
             }
+            TuiEvent::ReasoningChunk { agent_id, chunk } => {
+                if let Some(index) = self.find_tab_by_id(&agent_id) {
+                    // Append to the thinking section being streamed or start a new one
+                    if let Some(ChatMessage::Reasoning { text, .. }) = self.tabs[index].messages.last_mut() {
+                        text.push_str(&chunk);
+                    } else {
+                        self.tabs[index].messages.push(ChatMessage::Reasoning { text: chunk, expanded: false });
+                    }
+                    self.scroll_tab_to_bottom(index);
+                }
+            }
             TuiEvent::ToolStart { agent_id, name, args } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
                     self.tabs[index].messages.push(ChatMessage::ToolHeader { name, args });
                     self.scroll_tab_to_bottom(index);
                 }
             }
-            TuiEvent::ToolSuccess { agent_id, name, result } => {
+            TuiEvent::ToolSuccess { agent_id, name, result, output } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
                     self.tabs[index].messages.push(ChatMessage::ToolResult {
                         name,
                         success: true,
                         message: result,
+                        output,
+                        expanded: false,
                     });
                     self.scroll_tab_to_bottom(index);
                 }
@@ -583,6 +944,8 @@ impl App {
                         name,
                         success: false,
                         message: error,
+                        output: String::new(),
+                        expanded: false,
                     });
                     self.scroll_tab_to_bottom(index);
                 }
@@ -601,20 +964,41 @@ impl App {
             }
             TuiEvent::Error { agent_id, text } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
+                    self.tabs[index].thinking_since = None;
+                    if let Some(turn) = self.tabs[index].turn.as_mut() {
+                        turn.finish();
+                    }
                     self.tabs[index].messages.push(ChatMessage::Error(text));
                     self.scroll_tab_to_bottom(index);
                 }
             }
             TuiEvent::TokenUsage { agent_id, prompt, completion } => {
-                //eprintln!("DEBUG: Received TokenUsage event - agent_id: {}, prompt: {}, completion: {}", agent_id, prompt, completion);
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
-                    //eprintln!("DEBUG: Found tab at index {}, updating tokens", index);
                     self.tabs[index].prompt_tokens = prompt;
                     self.tabs[index].completion_tokens = completion;
-                    //eprintln!("DEBUG: Tab tokens updated - prompt: {}, completion: {}",
-                             //self.tabs[index].prompt_tokens, self.tabs[index].completion_tokens);
                 } else {
-                    //eprintln!("DEBUG: No tab found for agent_id: {}", agent_id);
+                    tracing::debug!("Token usage for unknown tab {}", agent_id);
+                }
+            }
+            TuiEvent::IterationUpdate { agent_id, iteration, max_iterations, elapsed_ms } => {
+                if let Some(index) = self.find_tab_by_id(&agent_id) {
+                    let elapsed = Duration::from_millis(elapsed_ms);
+                    self.tabs[index].turn = Some(TurnProgress {
+                        iteration,
+                        max_iterations,
+                        started: std::time::Instant::now().checked_sub(elapsed).unwrap_or_else(std::time::Instant::now),
+                        finished: None,
+                    });
+                }
+            }
+            TuiEvent::Thinking { agent_id, active } => {
+                if let Some(index) = self.find_tab_by_id(&agent_id) {
+                    let tab = &mut self.tabs[index];
+                    tab.thinking_since = if active {
+                        tab.thinking_since.or_else(|| Some(std::time::Instant::now()))
+                    } else {
+                        None
+                    };
                 }
             }
             TuiEvent::TabCreate { agent_id, name } => {
@@ -622,21 +1006,24 @@ impl App {
             }
             TuiEvent::TabComplete { agent_id } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
-                    self.tabs[index].status = TabStatus::Completed;
+                    self.tabs[index].stop(TabStatus::Completed);
                     // Switch back to main tab
                     self.switch_to_tab(0);
                 }
             }
             TuiEvent::TabFailed { agent_id, error } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
-                    self.tabs[index].status = TabStatus::Failed;
+                    self.tabs[index].stop(TabStatus::Failed);
                     self.tabs[index].messages.push(ChatMessage::Error(error));
                     self.scroll_tab_to_bottom(index);
                 }
             }
             TuiEvent::TabKill { agent_id } => {
                 if let Some(index) = self.find_tab_by_id(&agent_id) {
-                    self.tabs[index].status = TabStatus::Killed;
+                    let tab = &mut self.tabs[index];
+                    tab.stop(TabStatus::Killed);
+                    tab.messages.push(ChatMessage::Warning("Sub-agent stopped".to_string()));
+                    self.scroll_tab_to_bottom(index);
                 }
             }
             TuiEvent::SessionUpdate { id, model } => {
@@ -646,6 +1033,15 @@ impl App {
             TuiEvent::SessionListUpdate(session_ids) => {
                 self.session_ids = session_ids;
             }
+            TuiEvent::BatchPermissionRequest { calls, response_tx } => {
+                self.batch_modal = Some(BatchModal { calls, response_tx });
+            }
+            TuiEvent::FileLimitRequest { limit, changed, pending, response_tx } => {
+                self.file_limit_modal = Some(FileLimitModal { limit, changed, pending, response_tx });
+            }
+            TuiEvent::PlanApproval { plan, response_tx } => {
+                self.plan_modal = Some(PlanModal { plan, response_tx, scroll_offset: 0 });
+            }
             TuiEvent::PermissionRequest { tool_name, args, diff, response_tx } => {
                 self.permission_modal = Some(PermissionModal {
                     tool_name,
@@ -655,14 +1051,59 @@ impl App {
                     scroll_offset: 0,
                 });
             }
+            TuiEvent::Diagnostics(report) => {
+                self.diagnostics = report;
+            }
+            TuiEvent::Tabs(action) => {
+                self.run_tab_action(action);
+            }
+            TuiEvent::Idle(done) => {
+                // Ignored when input came in meanwhile
+                if let Some((_, note)) = self.idle.as_mut() {
+                    *note = done;
+                }
+            }
             TuiEvent::Clear => {
                 // Only clear active tab
-                self.get_active_tab_mut().messages.clear();
+                let tab = self.get_active_tab_mut();
+                tab.messages.clear();
+                tab.selected_message = None;
+            }
+            TuiEvent::Rewind { agent_id, user_turns } => {
+                if let Some(index) = self.find_tab_by_id(&agent_id) {
+                    let tab = &mut self.tabs[index];
+                    let cut = tab.messages
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, m)| matches!(m, ChatMessage::User(_)))
+                        .nth(user_turns)
+                        .map(|(idx, _)| idx)
+                        .unwrap_or(tab.messages.len());
+                    tab.messages.truncate(cut);
+                    tab.selected_message = None;
+                    self.scroll_tab_to_bottom(index);
+                }
             }
             TuiEvent::Quit => {
                 self.should_quit = true;
             }
             TuiEvent::Interrupt => {
+                for (index, tab) in self.tabs.iter_mut().enumerate() {
+                    // Sub-agents are stopped along with the main agent's turn
+                    if index > 0 && tab.is_active() {
+                        tab.stop(TabStatus::Killed);
+                    } else {
+                        tab.thinking_since = None;
+                        if let Some(turn) = tab.turn.as_mut() {
+                            turn.finish();
+                        }
+                    }
+                }
+                // The turn waiting for an answer is gone
+                self.permission_modal = None;
+                self.batch_modal = None;
+                self.plan_modal = None;
+                self.file_limit_modal = None;
                 // Show interrupt notification
                 self.get_active_tab_mut().messages.push(ChatMessage::Warning(
                     "⚠ Interrupt requested - cancelling agent activity...".to_string()
@@ -674,6 +1115,70 @@ impl App {
 
     /// Handle keyboard input
     pub fn handle_input(&mut self, event: Event, input_tx: &Sender<String>) -> Result<()> {
+        // The batch overview comes before any per-call prompt
+        if let Some(modal) = self.batch_modal.take() {
+            let decision = match event {
+                Event::Key(key) => match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => Some(BatchDecision::ApproveAll),
+                    KeyCode::Char('r') | KeyCode::Char('R') => Some(BatchDecision::Review),
+                    KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => Some(BatchDecision::RejectAll),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match decision {
+                Some(decision) => {
+                    let _ = modal.response_tx.send(decision);
+                }
+                // Restore modal and ignore other input
+                None => self.batch_modal = Some(modal),
+            }
+            return Ok(());
+        }
+
+        if let Some(modal) = self.file_limit_modal.take() {
+            let decision = match event {
+                Event::Key(key) => match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => Some(true),
+                    KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => Some(false),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match decision {
+                Some(decision) => {
+                    let _ = modal.response_tx.send(decision);
+                }
+                // Restore modal and ignore other input
+                None => self.file_limit_modal = Some(modal),
+            }
+            return Ok(());
+        }
+
+        if let Some(mut modal) = self.plan_modal.take() {
+            let max_offset = modal.plan.lines().count().saturating_sub(1);
+            if let Event::Key(key) = event {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        let _ = modal.response_tx.send(true);
+                        return Ok(());
+                    }
+                    KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+                        let _ = modal.response_tx.send(false);
+                        return Ok(());
+                    }
+                    KeyCode::Up => modal.scroll_offset = modal.scroll_offset.saturating_sub(1),
+                    KeyCode::Down => modal.scroll_offset = (modal.scroll_offset + 1).min(max_offset),
+                    KeyCode::PageUp => modal.scroll_offset = modal.scroll_offset.saturating_sub(10),
+                    KeyCode::PageDown => modal.scroll_offset = (modal.scroll_offset + 10).min(max_offset),
+                    _ => {}
+                }
+            }
+            // Restore modal; other input is ignored
+            self.plan_modal = Some(modal);
+            return Ok(());
+        }
+
         // If permission modal is active, handle modal-specific input
         if let Some(mut modal) = self.permission_modal.take() {
             match event {
@@ -689,6 +1194,11 @@ impl App {
                             let _ = modal.response_tx.send(PermissionDecision::ApproveAll);
                             return Ok(());
                         }
+                        KeyCode::Char('f') | KeyCode::Char('F') if modal.offers_file_approval() => {
+                            // Approve all edits to this file
+                            let _ = modal.response_tx.send(PermissionDecision::ApproveFile);
+                            return Ok(());
+                        }
                         KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
                             // Reject
                             let _ = modal.response_tx.send(PermissionDecision::Reject);
@@ -791,7 +1301,44 @@ impl App {
                     }
                     // Ctrl+L - Clear history
                     (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
-                        self.get_active_tab_mut().messages.clear();
+                        let tab = self.get_active_tab_mut();
+                        tab.messages.clear();
+                        tab.selected_message = None;
+                        return Ok(());
+                    }
+                    // Ctrl+Y - Copy selected (or latest assistant) message to clipboard
+                    (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
+                        self.copy_selected_message();
+                        return Ok(());
+                    }
+                    // Ctrl+O - Write code blocks with a filename hint from the selected message to disk
+                    (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                        self.apply_selected_code_blocks(input_tx);
+                        return Ok(());
+                    }
+                    // Ctrl+R - Expand or collapse a thinking section
+                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                        self.toggle_reasoning();
+                        return Ok(());
+                    }
+                    // Alt+Up - Select previous message for copying
+                    (KeyCode::Up, KeyModifiers::ALT) => {
+                        self.select_message_prev();
+                        return Ok(());
+                    }
+                    // Alt+Down - Select next message (past the last clears the selection)
+                    (KeyCode::Down, KeyModifiers::ALT) => {
+                        self.select_message_next();
+                        return Ok(());
+                    }
+                    // Ctrl+K - Stop the sub-agent of the active tab
+                    (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+                        self.kill_active_subagent();
+                        return Ok(());
+                    }
+                    // Ctrl+G - Show or hide the diagnostics panel
+                    (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+                        self.show_diagnostics = !self.show_diagnostics;
                         return Ok(());
                     }
                     // Ctrl+M - Toggle mouse capture (for text selection)
@@ -834,6 +1381,12 @@ impl App {
                         self.prev_autocomplete();
                         return Ok(());
                     }
+                    // Enter or Space on a selected tool result, with nothing typed - Expand or collapse its output
+                    (KeyCode::Enter | KeyCode::Char(' '), KeyModifiers::NONE)
+                        if self.textarea.is_empty() && self.selected_output_collapsible() => {
+                        self.toggle_selected_output();
+                        return Ok(());
+                    }
                     // Enter without Alt - Submit
                     (KeyCode::Enter, mods) if !mods.contains(KeyModifiers::ALT) => {
                         let input = self.textarea.lines().join("\n");
@@ -844,8 +1397,18 @@ impl App {
                             self.history_index = None;
                             self.current_draft.clear();
 
-                            // Send to agent
-                            let _ = input_tx.try_send(input.clone());
+                            // Tabs live in the TUI: handle /tabs and /tab here, so they
+                            // work while the agent is busy. Everything else goes to the agent.
+                            match crate::commands::parse_tab_command(&input) {
+                                Some(Ok(action)) => self.run_tab_action(action),
+                                Some(Err(usage)) => {
+                                    self.get_active_tab_mut().messages.push(ChatMessage::Error(usage));
+                                    self.scroll_to_bottom();
+                                }
+                                None => {
+                                    let _ = input_tx.try_send(input.clone());
+                                }
+                            }
                             // Clear input
                             self.textarea = TextArea::default();
                             self.textarea.set_block(
@@ -961,6 +1524,183 @@ impl App {
         }
     }
 
+    /// Move the message selection one message up
+    fn select_message_prev(&mut self) {
+        let tab = self.get_active_tab_mut();
+        if tab.messages.is_empty() {
+            return;
+        }
+        let index = match tab.selected_message {
+            Some(i) => i.saturating_sub(1),
+            None => tab.copy_target().unwrap_or(tab.messages.len() - 1),
+        };
+        tab.selected_message = Some(index);
+        self.scroll_to_message(index);
+    }
+
+    /// Move the message selection one message down
+    fn select_message_next(&mut self) {
+        let tab = self.get_active_tab_mut();
+        match tab.selected_message {
+            Some(i) if i + 1 < tab.messages.len() => {
+                tab.selected_message = Some(i + 1);
+                self.scroll_to_message(i + 1);
+            }
+            _ => tab.selected_message = None,
+        }
+    }
+
+    /// Expand or collapse the selected thinking section, or the latest one
+    fn toggle_reasoning(&mut self) {
+        let tab = self.get_active_tab_mut();
+        let is_reasoning = |m: &ChatMessage| matches!(m, ChatMessage::Reasoning { .. });
+        let target = tab.selected_message
+            .filter(|&i| tab.messages.get(i).is_some_and(is_reasoning))
+            .or_else(|| tab.messages.iter().rposition(is_reasoning));
+
+        if let Some(index) = target
+            && let ChatMessage::Reasoning { expanded, .. } = &mut tab.messages[index] {
+                *expanded = !*expanded;
+            }
+    }
+
+    /// List, switch to or close tabs (`n` numbers tabs from 1)
+    fn run_tab_action(&mut self, action: crate::commands::TabAction) {
+        use crate::commands::TabAction;
+
+        match action {
+            TabAction::List => {
+                let mut text = String::from("Agent tabs:\n\n");
+                for (i, tab) in self.tabs.iter().enumerate() {
+                    let marker = if i == self.active_tab_index { "▸" } else { " " };
+                    text.push_str(&format!(
+                        "{} {:>2}  {:<24} {:<10} {}\n",
+                        marker,
+                        i + 1,
+                        crate::terminal::truncate_chars(&tab.name, 21),
+                        tab.status.label(),
+                        format_elapsed(tab.duration()),
+                    ));
+                }
+                text.push_str("\n/tab <n> switches to a tab; /tab close <n> closes a finished one.");
+                self.get_active_tab_mut().messages.push(ChatMessage::Info(text));
+                self.scroll_to_bottom();
+            }
+            TabAction::Switch(n) => {
+                if n == 0 || n > self.tabs.len() {
+                    self.show_toast(&format!("No tab {} (there are {})", n, self.tabs.len()));
+                } else {
+                    self.switch_to_tab(n - 1);
+                }
+            }
+            TabAction::Close(n) => {
+                if n == 0 || n > self.tabs.len() {
+                    self.show_toast(&format!("No tab {} (there are {})", n, self.tabs.len()));
+                } else if n == 1 {
+                    self.show_toast("The main agent's tab can't be closed");
+                } else if self.tabs[n - 1].is_active() {
+                    self.show_toast("This sub-agent is still running; stop it with Ctrl+K first");
+                } else {
+                    let index = n - 1;
+                    self.tabs.remove(index);
+                    if self.active_tab_index >= index {
+                        self.active_tab_index = self.active_tab_index.saturating_sub(1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Stop the sub-agent shown in the active tab; the main agent is left to Escape
+    fn kill_active_subagent(&mut self) {
+        if self.active_tab_index == 0 {
+            self.show_toast("Ctrl+K stops a sub-agent from its tab; press Esc to interrupt the main agent");
+            return;
+        }
+        let tab = self.get_active_tab();
+        if !tab.is_active() {
+            self.show_toast("This sub-agent is no longer running");
+        } else if crate::agent_loop::kill_subagent(&tab.id) {
+            self.show_toast("Stopping sub-agent...");
+        } else {
+            self.show_toast("This sub-agent could not be found; it may have just finished");
+        }
+    }
+
+    /// Whether the selected message is a tool result with collapsible output
+    fn selected_output_collapsible(&self) -> bool {
+        let tab = self.get_active_tab();
+        tab.selected_message
+            .and_then(|i| tab.messages.get(i))
+            .is_some_and(ChatMessage::is_collapsible)
+    }
+
+    /// Expand or collapse the output of the selected tool result
+    fn toggle_selected_output(&mut self) {
+        let tab = self.get_active_tab_mut();
+        if let Some(ChatMessage::ToolResult { expanded, .. }) = tab.selected_message.and_then(|i| tab.messages.get_mut(i)) {
+            *expanded = !*expanded;
+        }
+    }
+
+    /// Scroll the active tab so the given message is visible
+    fn scroll_to_message(&mut self, message_index: usize) {
+        let agent_name = self.agent_name.clone();
+        let tab = self.get_active_tab_mut();
+        let first_item = tab.messages.iter()
+            .take(message_index)
+            .map(|m| m.to_list_items(&agent_name).len())
+            .sum::<usize>();
+        tab.auto_scroll = false;
+        tab.list_state.select(Some(first_item));
+    }
+
+    /// Copy the selected message (or the latest assistant reply) to the clipboard
+    fn copy_selected_message(&mut self) {
+        let tab = self.get_active_tab();
+        let Some(index) = tab.copy_target() else {
+            self.show_toast("Nothing to copy");
+            return;
+        };
+        let text = tab.messages[index].copy_text();
+
+        match crate::clipboard::copy(&text) {
+            Ok(method) => {
+                let lines = text.lines().count();
+                self.show_toast(&format!("Copied {} line(s) to clipboard ({})", lines, method));
+            }
+            Err(e) => self.show_toast(&format!("Copy failed: {}", e)),
+        }
+    }
+
+    /// Offer to write the fenced code blocks of the selected (or latest
+    /// assistant) message to disk; each file goes through the permission modal
+    fn apply_selected_code_blocks(&mut self, input_tx: &Sender<String>) {
+        let tab = self.get_active_tab();
+        let Some(index) = tab.copy_target() else {
+            self.show_toast("No message to apply code from");
+            return;
+        };
+        let text = tab.messages[index].copy_text();
+
+        let count = crate::codeblock::extract(&text)
+            .iter()
+            .filter(|block| block.path.is_some())
+            .count();
+        if count == 0 {
+            self.show_toast("No code blocks with a filename hint in this message");
+            return;
+        }
+
+        let _ = input_tx.try_send(format!("\x1b[APPLY]{}", text));
+        self.show_toast(&format!("Applying {} code block(s)...", count));
+    }
+
+    /// Show a transient notification in the status bar
+    fn show_toast(&mut self, text: &str) {
+        self.toast = Some((text.to_string(), std::time::Instant::now()));
+    }
+
     /// Helper to set textarea content
     fn set_textarea_content(&mut self, content: &str) {
         self.textarea = TextArea::from(content.lines().map(|s| s.to_string()));
@@ -1133,12 +1873,15 @@ impl App {
         // Calculate dynamic input height based on content
         let input_height = self.calculate_input_height();
 
-        // Create layout with three sections: history, status bar, input
+        let diagnostics_height = if self.show_diagnostics { self.diagnostics_panel_height() } else { 0 };
+
+        // Create layout with five sections: tab bar, history, diagnostics (when shown), status bar, input
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),         // Tab bar
                 Constraint::Min(10),           // Chat history (takes remaining space)
+                Constraint::Length(diagnostics_height), // Diagnostics panel (Ctrl+G)
                 Constraint::Length(1),         // Status bar
                 Constraint::Length(input_height), // Input area (dynamic)
             ])
@@ -1150,20 +1893,33 @@ impl App {
         // Render chat history
         self.render_history(frame, chunks[1]);
 
+        if self.show_diagnostics {
+            self.render_diagnostics(frame, chunks[2]);
+        }
+
         // Render status bar
-        self.render_status_bar(frame, chunks[2]);
+        self.render_status_bar(frame, chunks[3]);
 
         // Render input area
-        frame.render_widget(&self.textarea, chunks[3]);
+        frame.render_widget(&self.textarea, chunks[4]);
 
         // Render permission modal on top if active
         if self.permission_modal.is_some() {
             self.render_permission_modal(frame, terminal_area);
         }
+        if self.batch_modal.is_some() {
+            self.render_batch_modal(frame, terminal_area);
+        }
+        if self.plan_modal.is_some() {
+            self.render_plan_modal(frame, terminal_area);
+        }
+        if self.file_limit_modal.is_some() {
+            self.render_file_limit_modal(frame, terminal_area);
+        }
 
         // Render autocomplete suggestions if available
         if !self.autocomplete_suggestions.is_empty() {
-            self.render_autocomplete(frame, chunks[3]);
+            self.render_autocomplete(frame, chunks[4]);
         }
     }
 
@@ -1181,26 +1937,26 @@ impl App {
             };
 
             let status_color = match tab.status {
-                TabStatus::Running => Color::Rgb(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2),
-                TabStatus::Completed => Color::Rgb(colors::BLUE.0, colors::BLUE.1, colors::BLUE.2),
-                TabStatus::Failed => Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2),
-                TabStatus::Killed => Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2),
+                TabStatus::Running => colors::tui(colors::theme().green),
+                TabStatus::Completed => colors::tui(colors::theme().blue),
+                TabStatus::Failed => colors::tui(colors::theme().red),
+                TabStatus::Killed => colors::tui(colors::theme().overlay0),
             };
 
-            let tab_name = if tab.name.len() > 15 {
-                format!("{}...", &tab.name[..12])
+            let tab_name = if tab.name.chars().count() > 15 {
+                crate::terminal::truncate_chars(&tab.name, 12)
             } else {
                 tab.name.clone()
             };
 
             let style = if i == self.active_tab_index {
                 Style::default()
-                    .bg(Color::Rgb(colors::SURFACE0.0, colors::SURFACE0.1, colors::SURFACE0.2))
-                    .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))
+                    .bg(colors::tui(colors::theme().surface0))
+                    .fg(colors::tui(colors::theme().text))
                     .add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
-                    .fg(Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2))
+                    .fg(colors::tui(colors::theme().overlay0))
             };
 
             Line::from(vec![
@@ -1213,11 +1969,11 @@ impl App {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(" Agents (Ctrl+T: next, Ctrl+1-9: direct) ")
+                    .title(" Agents (Ctrl+T: next, Ctrl+1-9: direct, /tabs: list) ")
             )
             .highlight_style(
                 Style::default()
-                    .bg(Color::Rgb(colors::SURFACE0.0, colors::SURFACE0.1, colors::SURFACE0.2))
+                    .bg(colors::tui(colors::theme().surface0))
                     .add_modifier(Modifier::BOLD)
             )
             .select(self.active_tab_index);
@@ -1229,9 +1985,20 @@ impl App {
     fn render_history(&mut self, frame: &mut Frame, area: Rect) {
         let agent_name = self.agent_name.clone();
         let tab = self.get_active_tab_mut();
-        // Convert messages to list items
+        let selected = tab.selected_message;
+        let selected_style = Style::default()
+            .bg(colors::tui(colors::theme().surface0));
+        // Convert messages to list items, highlighting the message selected for copying
         let items: Vec<ListItem> = tab.messages.iter()
-            .flat_map(|msg| msg.to_list_items(&agent_name))
+            .enumerate()
+            .flat_map(|(i, msg)| {
+                let items = msg.to_list_items(&agent_name);
+                if selected == Some(i) {
+                    items.into_iter().map(|item| item.style(selected_style)).collect()
+                } else {
+                    items
+                }
+            })
             .collect();
 
         let list = List::new(items)
@@ -1240,6 +2007,44 @@ impl App {
         frame.render_stateful_widget(list, area, &mut tab.list_state);
     }
 
+    /// Height of the diagnostics panel: the report plus borders, capped
+    fn diagnostics_panel_height(&self) -> u16 {
+        let lines = self.diagnostics.as_deref().map_or(1, |report| report.lines().count());
+        (lines as u16 + 2).min(DIAGNOSTICS_PANEL_MAX_HEIGHT)
+    }
+
+    /// Render the rust-analyzer diagnostics panel
+    fn render_diagnostics(&self, frame: &mut Frame, area: Rect) {
+        let theme = colors::theme();
+        let text = Style::default().fg(colors::tui(theme.text));
+        let lines: Vec<Line> = match self.diagnostics.as_deref() {
+            None => vec![Line::from(Span::styled(
+                "rust-analyzer is not running (diagnostics are available in Rust projects)",
+                Style::default().fg(colors::tui(theme.overlay0)),
+            ))],
+            Some(report) => report
+                .lines()
+                .map(|line| {
+                    let style = match line {
+                        "Errors:" => text.fg(colors::tui(theme.red)).add_modifier(Modifier::BOLD),
+                        "Warnings:" => text.fg(colors::tui(theme.yellow)).add_modifier(Modifier::BOLD),
+                        _ if line.starts_with("    ") => text,
+                        _ => text.fg(colors::tui(theme.blue)),
+                    };
+                    Line::from(Span::styled(line.to_string(), style))
+                })
+                .collect(),
+        };
+
+        let panel = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(colors::tui(theme.overlay0)))
+                .title(" Diagnostics (Ctrl+G to hide) "),
+        );
+        frame.render_widget(panel, area);
+    }
+
     /// Render status bar
     fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
         let tab = self.get_active_tab();
@@ -1257,21 +2062,47 @@ impl App {
             "Select (Shift+drag)"
         };
 
-        let status_text = format!(
-            " Session: {} | Model: {} | Tab: {} | Tokens: {}/{}/{} | Mode: {} (Ctrl+M to toggle) ",
+        let cost = crate::pricing::format_cost(self.price, tab.prompt_tokens, tab.completion_tokens);
+
+        let turn = tab.turn.as_ref().map(|turn| format!("{} | ", turn.status())).unwrap_or_default();
+
+        let mut status_text = format!(
+            " Session: {} | Model: {} | Tab: {} | {}Tokens: {}/{}/{} | Cost: {} | Mode: {} (Ctrl+M to toggle) ",
             session_short,
             self.model_name,
             tab.name,
+            turn,
             tab.prompt_tokens,
             tab.completion_tokens,
             total_tokens,
+            cost,
             mode_indicator
         );
 
+        if let Some((since, note)) = &self.idle {
+            let note = if note.is_empty() { String::new() } else { format!(" ({})", note) };
+            status_text = format!(" Idle {}{} |{}", format_elapsed(since.elapsed() + self.idle_timeout.unwrap_or_default()), note, status_text);
+        }
+
+        // Spinner while the model hasn't started answering (redrawn every 100ms)
+        if let Some(since) = tab.thinking_since {
+            const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+            let elapsed = since.elapsed();
+            let frame_char = SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()];
+            status_text = format!(" {} Thinking {}s |{}", frame_char, elapsed.as_secs(), status_text);
+        }
+
+        // Toasts replace the status line for a few seconds
+        const TOAST_DURATION: Duration = Duration::from_secs(3);
+        if let Some((toast, raised_at)) = &self.toast
+            && raised_at.elapsed() < TOAST_DURATION {
+                status_text = format!(" {} ", toast);
+            }
+
         let status = Paragraph::new(status_text)
             .style(Style::default()
-                .bg(Color::Rgb(colors::SURFACE0.0, colors::SURFACE0.1, colors::SURFACE0.2))
-                .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)));
+                .bg(colors::tui(colors::theme().surface0))
+                .fg(colors::tui(colors::theme().text)));
 
         frame.render_widget(status, area);
     }
@@ -1282,10 +2113,10 @@ impl App {
             // Clear the entire background first
             frame.render_widget(Clear, area);
 
-            // Then render a solid black overlay to block background text
+            // Then render a solid overlay to block background text
             let overlay = Block::default()
                 .style(Style::default()
-                    .bg(Color::Rgb(0, 0, 0))); // Solid black background
+                    .bg(colors::tui(colors::theme().base)));
             frame.render_widget(overlay, area);
 
             // Create larger modal area for diff display
@@ -1333,15 +2164,15 @@ impl App {
                 let header_lines = vec![
                     Line::from(vec![
                         Span::styled("Tool: ", Style::default()
-                            .fg(Color::Rgb(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2))
+                            .fg(colors::tui(colors::theme().yellow))
                             .add_modifier(Modifier::BOLD)),
                         Span::styled(&modal.tool_name, Style::default()
-                            .fg(Color::Rgb(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2))
+                            .fg(colors::tui(colors::theme().mauve))
                             .add_modifier(Modifier::BOLD)),
                         Span::styled(" - ", Style::default()
-                            .fg(Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2))),
+                            .fg(colors::tui(colors::theme().overlay0))),
                         Span::styled(diff.summary(), Style::default()
-                            .fg(Color::Rgb(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2))),
+                            .fg(colors::tui(colors::theme().sapphire))),
                     ]),
                 ];
 
@@ -1350,9 +2181,9 @@ impl App {
                         .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
                         .title(" Permission Required ")
                         .style(Style::default()
-                            .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2))))
+                            .bg(colors::tui(colors::theme().base))))
                     .style(Style::default()
-                        .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2)));
+                        .bg(colors::tui(colors::theme().base)));
 
                 frame.render_widget(header, chunks[0]);
 
@@ -1373,14 +2204,14 @@ impl App {
 
                         // Choose color and prefix based on change type
                         let (prefix, color) = match diff_line.change_type {
-                            crate::diff::DiffChangeType::Addition => ("+", Color::Rgb(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2)),
-                            crate::diff::DiffChangeType::Deletion => ("-", Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2)),
-                            crate::diff::DiffChangeType::Context => (" ", Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)),
+                            crate::diff::DiffChangeType::Addition => ("+", colors::tui(colors::theme().green)),
+                            crate::diff::DiffChangeType::Deletion => ("-", colors::tui(colors::theme().red)),
+                            crate::diff::DiffChangeType::Context => (" ", colors::tui(colors::theme().text)),
                         };
 
                         Line::from(vec![
                             Span::styled(line_num_str, Style::default()
-                                .fg(Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2))),
+                                .fg(colors::tui(colors::theme().overlay0))),
                             Span::styled(format!("{} ", prefix), Style::default().fg(color)),
                             Span::styled(&diff_line.content, Style::default().fg(color)),
                         ])
@@ -1398,40 +2229,22 @@ impl App {
                         .borders(Borders::LEFT | Borders::RIGHT)
                         .title(scroll_indicator)
                         .style(Style::default()
-                            .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2))))
+                            .bg(colors::tui(colors::theme().base))))
                     .style(Style::default()
-                        .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2)));
+                        .bg(colors::tui(colors::theme().base)));
 
                 frame.render_widget(diff_view, chunks[1]);
 
                 // Render footer with instructions
-                let footer_lines = vec![
-                    Line::from(vec![
-                        Span::styled("[Enter/Y]", Style::default()
-                            .fg(Color::Rgb(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2))
-                            .add_modifier(Modifier::BOLD)),
-                        Span::styled(" Approve Once  ", Style::default()
-                            .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
-                        Span::styled("[A]", Style::default()
-                            .fg(Color::Rgb(colors::BLUE.0, colors::BLUE.1, colors::BLUE.2))
-                            .add_modifier(Modifier::BOLD)),
-                        Span::styled(" Approve All  ", Style::default()
-                            .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
-                        Span::styled("[Esc/N]", Style::default()
-                            .fg(Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2))
-                            .add_modifier(Modifier::BOLD)),
-                        Span::styled(" Reject", Style::default()
-                            .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
-                    ]),
-                ];
+                let footer_lines = vec![permission_actions(modal.offers_file_approval())];
 
                 let footer = Paragraph::new(footer_lines)
                     .block(Block::default()
                         .borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
                         .style(Style::default()
-                            .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2))))
+                            .bg(colors::tui(colors::theme().base))))
                     .style(Style::default()
-                        .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2)));
+                        .bg(colors::tui(colors::theme().base)));
 
                 frame.render_widget(footer, chunks[2]);
             } else {
@@ -1439,10 +2252,10 @@ impl App {
                 let mut lines = vec![
                     Line::from(vec![
                         Span::styled("Tool: ", Style::default()
-                            .fg(Color::Rgb(colors::YELLOW.0, colors::YELLOW.1, colors::YELLOW.2))
+                            .fg(colors::tui(colors::theme().yellow))
                             .add_modifier(Modifier::BOLD)),
                         Span::styled(&modal.tool_name, Style::default()
-                            .fg(Color::Rgb(colors::MAUVE.0, colors::MAUVE.1, colors::MAUVE.2))
+                            .fg(colors::tui(colors::theme().mauve))
                             .add_modifier(Modifier::BOLD)),
                     ]),
                     Line::from(""),
@@ -1452,19 +2265,15 @@ impl App {
                 if !modal.args.is_empty() {
                     lines.push(Line::from(Span::styled("Arguments:", Style::default().add_modifier(Modifier::BOLD))));
                     for (key, value) in &modal.args {
-                        let display_value = if value.len() > 60 {
-                            format!("{}...", &value[..60])
-                        } else {
-                            value.clone()
-                        };
+                        let display_value = crate::terminal::truncate_chars(value, 60);
                         lines.push(Line::from(vec![
                             Span::styled("  ", Style::default()),
                             Span::styled(key, Style::default()
-                                .fg(Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2))),
+                                .fg(colors::tui(colors::theme().overlay0))),
                             Span::styled(": ", Style::default()
-                                .fg(Color::Rgb(colors::OVERLAY0.0, colors::OVERLAY0.1, colors::OVERLAY0.2))),
+                                .fg(colors::tui(colors::theme().overlay0))),
                             Span::styled(display_value, Style::default()
-                                .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
+                                .fg(colors::tui(colors::theme().text))),
                         ]));
                     }
                     lines.push(Line::from(""));
@@ -1472,23 +2281,7 @@ impl App {
 
                 // Add instructions
                 lines.push(Line::from(""));
-                lines.push(Line::from(vec![
-                    Span::styled("[Enter/Y]", Style::default()
-                        .fg(Color::Rgb(colors::GREEN.0, colors::GREEN.1, colors::GREEN.2))
-                        .add_modifier(Modifier::BOLD)),
-                    Span::styled(" Approve Once  ", Style::default()
-                        .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
-                    Span::styled("[A]", Style::default()
-                        .fg(Color::Rgb(colors::BLUE.0, colors::BLUE.1, colors::BLUE.2))
-                        .add_modifier(Modifier::BOLD)),
-                    Span::styled(" Approve All  ", Style::default()
-                        .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
-                    Span::styled("[Esc/N]", Style::default()
-                        .fg(Color::Rgb(colors::RED.0, colors::RED.1, colors::RED.2))
-                        .add_modifier(Modifier::BOLD)),
-                    Span::styled(" Reject", Style::default()
-                        .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))),
-                ]));
+                lines.push(permission_actions(modal.offers_file_approval()));
 
                 let paragraph = Paragraph::new(lines)
                     .block(
@@ -1496,17 +2289,229 @@ impl App {
                             .borders(Borders::ALL)
                             .title(" Permission Required ")
                             .style(Style::default()
-                                .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2))
-                                .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2)))
+                                .bg(colors::tui(colors::theme().base))
+                                .fg(colors::tui(colors::theme().text)))
                     )
                     .style(Style::default()
-                        .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2)));
+                        .bg(colors::tui(colors::theme().base)));
 
                 frame.render_widget(paragraph, modal_area);
             }
         }
     }
 
+    /// Render the overview of a batch of tool calls
+    fn render_batch_modal(&self, frame: &mut Frame, area: Rect) {
+        let Some(modal) = &self.batch_modal else {
+            return;
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(Block::default().style(Style::default().bg(colors::tui(colors::theme().base))), area);
+
+        let shown = modal.calls.len().min(BATCH_MODAL_MAX_CALLS);
+        let hidden = modal.calls.len() - shown;
+        let modal_width = area.width.saturating_sub(4).min(100);
+        let modal_height = (7 + shown as u16 + u16::from(hidden > 0)).min(area.height.saturating_sub(4));
+        let modal_area = Rect {
+            x: area.x + (area.width.saturating_sub(modal_width)) / 2,
+            y: area.y + (area.height.saturating_sub(modal_height)) / 2,
+            width: modal_width,
+            height: modal_height,
+        };
+
+        let key = |label: &'static str, color: colors::Rgb| {
+            Span::styled(label, Style::default()
+                .fg(colors::tui(color))
+                .add_modifier(Modifier::BOLD))
+        };
+        let text_style = Style::default().fg(colors::tui(colors::theme().text));
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("The model wants to run {} tools:", modal.calls.len()),
+                Style::default()
+                    .fg(colors::tui(colors::theme().yellow))
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+        let max_chars = modal_width.saturating_sub(10) as usize;
+        for (idx, (tool_name, args)) in modal.calls.iter().take(shown).enumerate() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:>3}. ", idx + 1), Style::default()
+                    .fg(colors::tui(colors::theme().overlay0))),
+                Span::styled(batch_call_line(tool_name, args, max_chars), text_style),
+            ]));
+        }
+        if hidden > 0 {
+            lines.push(Line::from(Span::styled(
+                format!("     ... and {} more", hidden),
+                Style::default().fg(colors::tui(colors::theme().overlay0)),
+            )));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            key("[Enter/Y]", colors::theme().green),
+            Span::styled(" Approve batch  ", text_style),
+            key("[R]", colors::theme().blue),
+            Span::styled(" Review individually  ", text_style),
+            key("[Esc/N]", colors::theme().red),
+            Span::styled(" Reject all", text_style),
+        ]));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Tool Calls ")
+                    .style(Style::default()
+                        .bg(colors::tui(colors::theme().base))
+                        .fg(colors::tui(colors::theme().text)))
+            )
+            .style(Style::default()
+                .bg(colors::tui(colors::theme().base)));
+
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    /// Render the question whether a turn may change more files than the limit
+    fn render_file_limit_modal(&self, frame: &mut Frame, area: Rect) {
+        let Some(modal) = &self.file_limit_modal else {
+            return;
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(Block::default().style(Style::default().bg(colors::tui(colors::theme().base))), area);
+
+        let shown_changed = modal.changed.len().min(BATCH_MODAL_MAX_CALLS);
+        let shown_pending = modal.pending.len().min(BATCH_MODAL_MAX_CALLS);
+        let listed = (shown_changed + shown_pending) as u16
+            + u16::from(modal.changed.len() > shown_changed)
+            + u16::from(modal.pending.len() > shown_pending);
+        let modal_width = area.width.saturating_sub(4).min(100);
+        let modal_height = (10 + listed).min(area.height.saturating_sub(4));
+        let modal_area = Rect {
+            x: area.x + (area.width.saturating_sub(modal_width)) / 2,
+            y: area.y + (area.height.saturating_sub(modal_height)) / 2,
+            width: modal_width,
+            height: modal_height,
+        };
+
+        let key = |label: &'static str, color: colors::Rgb| {
+            Span::styled(label, Style::default()
+                .fg(colors::tui(color))
+                .add_modifier(Modifier::BOLD))
+        };
+        let text_style = Style::default().fg(colors::tui(colors::theme().text));
+        let heading_style = Style::default().fg(colors::tui(colors::theme().overlay0));
+        let max_chars = modal_width.saturating_sub(8) as usize;
+        let file_lines = |files: &[String], shown: usize| {
+            let mut lines: Vec<Line> = files
+                .iter()
+                .take(shown)
+                .map(|file| Line::from(Span::styled(
+                    format!("  {}", crate::terminal::truncate_chars(file, max_chars)),
+                    text_style,
+                )))
+                .collect();
+            if files.len() > shown {
+                lines.push(Line::from(Span::styled(format!("  ... and {} more", files.len() - shown), heading_style)));
+            }
+            lines
+        };
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("This turn is about to change more than {} files (--max-files-per-turn)", modal.limit),
+                Style::default()
+                    .fg(colors::tui(colors::theme().yellow))
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(format!("Changed so far ({}):", modal.changed.len()), heading_style)),
+        ];
+        lines.extend(file_lines(&modal.changed, shown_changed));
+        lines.push(Line::from(Span::styled(format!("Next in this response ({}):", modal.pending.len()), heading_style)));
+        lines.extend(file_lines(&modal.pending, shown_pending));
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            key("[Enter/Y]", colors::theme().green),
+            Span::styled(" Allow for the rest of this turn  ", text_style),
+            key("[Esc/N]", colors::theme().red),
+            Span::styled(" Stop the turn", text_style),
+        ]));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" File Limit ")
+                    .style(Style::default()
+                        .bg(colors::tui(colors::theme().base))
+                        .fg(colors::tui(colors::theme().text)))
+            )
+            .style(Style::default()
+                .bg(colors::tui(colors::theme().base)));
+
+        frame.render_widget(paragraph, modal_area);
+    }
+
+    /// Render the plan waiting for approval
+    fn render_plan_modal(&self, frame: &mut Frame, area: Rect) {
+        let Some(modal) = &self.plan_modal else {
+            return;
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(Block::default().style(Style::default().bg(colors::tui(colors::theme().base))), area);
+
+        let modal_width = area.width.saturating_sub(4).min(100);
+        let modal_height = area.height.saturating_sub(4);
+        let modal_area = Rect {
+            x: area.x + (area.width.saturating_sub(modal_width)) / 2,
+            y: area.y + (area.height.saturating_sub(modal_height)) / 2,
+            width: modal_width,
+            height: modal_height,
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)])
+            .split(modal_area);
+
+        let steps = crate::plan::step_count(&modal.plan);
+        let title = if steps > 0 {
+            format!(" Plan ({} steps) ", steps)
+        } else {
+            " Plan ".to_string()
+        };
+        let plan = Paragraph::new(markdown::render(&modal.plan, modal_width.saturating_sub(2) as usize))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .style(Style::default()
+                        .bg(colors::tui(colors::theme().base))
+                        .fg(colors::tui(colors::theme().text)))
+            )
+            .scroll((modal.scroll_offset as u16, 0));
+        frame.render_widget(plan, chunks[0]);
+
+        let key = |label: &'static str, color: colors::Rgb| {
+            Span::styled(label, Style::default()
+                .fg(colors::tui(color))
+                .add_modifier(Modifier::BOLD))
+        };
+        let text_style = Style::default().fg(colors::tui(colors::theme().text));
+        let actions = Paragraph::new(Line::from(vec![
+            key("[Enter/Y]", colors::theme().green),
+            Span::styled(" Approve and execute  ", text_style),
+            key("[Esc/N]", colors::theme().red),
+            Span::styled(" Reject (then describe changes)  ", text_style),
+            Span::styled("↑/↓ scroll", Style::default().fg(colors::tui(colors::theme().overlay0))),
+        ]))
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().bg(colors::tui(colors::theme().base)));
+        frame.render_widget(actions, chunks[1]);
+    }
+
     /// Render autocomplete suggestions popup
     fn render_autocomplete(&self, frame: &mut Frame, input_area: Rect) {
         if self.autocomplete_suggestions.is_empty() {
@@ -1540,12 +2545,12 @@ impl App {
                 let style = if i == self.autocomplete_index {
                     // Highlight selected item
                     Style::default()
-                        .bg(Color::Rgb(colors::SURFACE0.0, colors::SURFACE0.1, colors::SURFACE0.2))
-                        .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))
+                        .bg(colors::tui(colors::theme().surface0))
+                        .fg(colors::tui(colors::theme().text))
                         .add_modifier(Modifier::BOLD)
                 } else {
                     Style::default()
-                        .fg(Color::Rgb(colors::TEXT.0, colors::TEXT.1, colors::TEXT.2))
+                        .fg(colors::tui(colors::theme().text))
                 };
 
                 ListItem::new(Line::from(Span::styled(
@@ -1561,8 +2566,8 @@ impl App {
                     .borders(Borders::ALL)
                     .title(" Suggestions (Tab/Shift+Tab to cycle) ")
                     .style(Style::default()
-                        .bg(Color::Rgb(colors::BASE.0, colors::BASE.1, colors::BASE.2))
-                        .fg(Color::Rgb(colors::SAPPHIRE.0, colors::SAPPHIRE.1, colors::SAPPHIRE.2)))
+                        .bg(colors::tui(colors::theme().base))
+                        .fg(colors::tui(colors::theme().sapphire)))
             );
 
         frame.render_widget(list, popup_area);
@@ -1575,6 +2580,8 @@ pub async fn run(
     model_name: String,
     agent_name: String,
     cwd: String,
+    price: Option<crate::pricing::ModelPrice>,
+    idle_timeout: Option<Duration>,
     mut event_rx: Receiver<TuiEvent>,
     input_tx: Sender<String>,
 ) -> Result<()> {
@@ -1586,7 +2593,8 @@ pub async fn run(
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new(session_id, model_name, agent_name, cwd);
+    let mut app = App::new(session_id, model_name, agent_name, cwd, price);
+    app.idle_timeout = idle_timeout;
 
     // Track previous mouse capture state to detect changes
     let mut prev_mouse_capture = app.mouse_capture_enabled;
@@ -1617,15 +2625,21 @@ pub async fn run(
             poll_result = tokio::task::spawn_blocking(move || event::poll(timeout)) => {
                 if let Ok(Ok(true)) = poll_result
                     && let Ok(event) = event::read() {
+                        app.mark_active();
                         app.handle_input(event, &input_tx)?;
                     }
             }
 
             // Handle events from agent
             Some(tui_event) = event_rx.recv() => {
+                // Background refreshes don't count as activity
+                if !matches!(tui_event, TuiEvent::Diagnostics(_) | TuiEvent::SessionListUpdate(_) | TuiEvent::Idle(_)) {
+                    app.mark_active();
+                }
                 app.handle_tui_event(tui_event);
             }
         }
+        app.check_idle(&input_tx);
 
         // Check if should quit
         if app.should_quit {
@@ -9,6 +9,9 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use crate::hash_embedding;
+use crate::tree_sitter_chunker::ChunkOptions;
+
 /// A code chunk with its metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeChunk {
@@ -21,18 +24,43 @@ pub struct CodeChunk {
 
 type OllamaEmbedder = ollama::EmbeddingModel<reqwest::Client>;
 
+/// Where embeddings come from
+enum Embedder {
+    Ollama(OllamaEmbedder),
+    /// Offline feature hashing, used when the Ollama model is unavailable
+    Hashing,
+}
+
+/// Chunks added to a user message by default (--vecdb-top-k)
+pub const DEFAULT_TOP_K: usize = 3;
+
+/// Cosine similarity below which a chunk counts as unrelated (--vecdb-min-similarity)
+pub const DEFAULT_MIN_SIMILARITY: f32 = 0.5;
+
 /// Vector database for code context
 pub struct VectorDB {
     /// Mapping from vector index to code chunk
     chunks: Vec<CodeChunk>,
     /// Embedding model
-    embedding_model: OllamaEmbedder,
+    embedder: Embedder,
+    /// Name of the Ollama embedding model
+    model_name: String,
+    /// Fall back to hashing embeddings when the model is unavailable
+    fallback: bool,
     /// Database directory
     db_dir: PathBuf,
     /// Embedding dimension
     dimension: usize,
     /// ruvector-core database instance
     ruvector_db: Option<RuVectorDB>,
+    /// Number of chunks `search_relevant` asks for
+    top_k: usize,
+    /// Chunks scoring below this are left out by `search_relevant`
+    min_similarity: f32,
+    /// Chunk size and overlap used when indexing
+    chunk_options: ChunkOptions,
+    /// Lowercase language names or extensions to index (empty = all)
+    languages: Vec<String>,
 }
 
 impl VectorDB {
@@ -57,13 +85,93 @@ impl VectorDB {
 
         Ok(Self {
             chunks: Vec::new(),
-            embedding_model,
+            embedder: Embedder::Ollama(embedding_model),
+            model_name: embedding_model_name.to_string(),
+            fallback: false,
             db_dir,
             dimension: 768, // Default for nomic-embed-text
             ruvector_db: None,
+            top_k: DEFAULT_TOP_K,
+            min_similarity: DEFAULT_MIN_SIMILARITY,
+            chunk_options: ChunkOptions::default(),
+            languages: Vec::new(),
         })
     }
 
+    /// Use offline hashing embeddings if the embedding model can't be reached
+    pub fn set_embedding_fallback(&mut self, enabled: bool) {
+        self.fallback = enabled;
+    }
+
+    /// Make sure the embedding model answers, switching to hashing
+    /// embeddings (when allowed) or failing with a hint on what to do
+    pub async fn check_embedder(&mut self) -> Result<()> {
+        let Embedder::Ollama(ref model) = self.embedder else {
+            return Ok(());
+        };
+        let Err(e) = model.embed_texts(vec!["ping".to_string()]).await else {
+            return Ok(());
+        };
+
+        let hint = ollama_error_hint(&self.model_name, &e.to_string());
+        if !self.fallback {
+            return Err(anyhow!("{} (or pass --embedding-fallback)", hint));
+        }
+        tracing::warn!("Embedding model unavailable: {}", e);
+        crate::terminal::print_warning(&format!("{}; using offline hashing embeddings, which match on shared words only", hint));
+        self.embedder = Embedder::Hashing;
+        Ok(())
+    }
+
+    /// Name recorded with the index, to catch loading it with other embeddings
+    fn embedder_name(&self) -> &str {
+        match self.embedder {
+            Embedder::Ollama(_) => &self.model_name,
+            Embedder::Hashing => hash_embedding::NAME,
+        }
+    }
+
+    /// Embed a batch of texts
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        match self.embedder {
+            Embedder::Ollama(ref model) => {
+                let embeddings = model
+                    .embed_texts(texts)
+                    .await
+                    .map_err(|e| anyhow!("Failed to generate embeddings: {}", ollama_error_hint(&self.model_name, &e.to_string())))?;
+                // Convert f64 to f32
+                Ok(embeddings.iter().map(|e| e.vec.iter().map(|&x| x as f32).collect()).collect())
+            }
+            Embedder::Hashing => Ok(texts.iter().map(|text| hash_embedding::embed(text, self.dimension)).collect()),
+        }
+    }
+
+    /// Only index files in these languages (names like "rust" or extensions like "rs")
+    pub fn set_languages(&mut self, languages: &[String]) {
+        self.languages = languages.iter().map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty()).collect();
+    }
+
+    /// Whether a file is in one of the selected languages
+    fn is_selected_language(&self, path: &Path) -> bool {
+        if self.languages.is_empty() {
+            return true;
+        }
+        let language = self.detect_language(path).to_lowercase();
+        let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        self.languages.iter().any(|selected| *selected == language || *selected == ext)
+    }
+
+    /// Set how files are chunked the next time they are indexed
+    pub fn set_chunk_options(&mut self, chunk_options: ChunkOptions) {
+        self.chunk_options = chunk_options;
+    }
+
+    /// Set how many chunks are injected per message and the similarity they need
+    pub fn set_search_limits(&mut self, top_k: usize, min_similarity: f32) {
+        self.top_k = top_k;
+        self.min_similarity = min_similarity;
+    }
+
     /// Check if index exists
     pub fn index_exists(&self) -> bool {
         self.db_dir.join("ruvector.db").exists()
@@ -83,6 +191,16 @@ impl VectorDB {
             return Err(anyhow!("Vector database does not exist"));
         }
 
+        // An index from one embedding model can't be searched with another
+        if let Ok(built_with) = std::fs::read_to_string(self.db_dir.join("embedder"))
+            && built_with.trim() != self.embedder_name() {
+                return Err(anyhow!(
+                    "Index was built with '{}' embeddings but '{}' is in use; run with --reindex",
+                    built_with.trim(),
+                    self.embedder_name()
+                ));
+            }
+
         // Load chunks metadata
         let chunks_json = std::fs::read_to_string(&chunks_path)?;
         self.chunks = serde_json::from_str(&chunks_json)?;
@@ -105,11 +223,12 @@ impl VectorDB {
         let chunks_path = self.db_dir.join("chunks.json");
         let chunks_json = serde_json::to_string(&self.chunks)?;
         std::fs::write(&chunks_path, chunks_json)?;
+        std::fs::write(self.db_dir.join("embedder"), self.embedder_name())?;
         Ok(())
     }
 
-    /// Index a directory containing code files
-    pub async fn index_directory(&mut self, dir_path: &str) -> Result<usize> {
+    /// Index one or more directories containing code files into a single index
+    pub async fn index_directories(&mut self, dir_paths: &[String]) -> Result<usize> {
         use crate::terminal;
 
         // Supported file extensions
@@ -122,32 +241,39 @@ impl VectorDB {
 
         // First pass: collect all files to process
         let mut files_to_process = Vec::new();
-        for entry in WalkDir::new(dir_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Skip hidden directories and common build/dependency directories
-                let name = e.file_name().to_string_lossy();
-                !name.starts_with('.')
-                    && name != "target"
-                    && name != "node_modules"
-                    && name != "dist"
-                    && name != "build"
-            })
-        {
-            let entry = entry?;
-            if !entry.file_type().is_file() {
-                continue;
-            }
-
-            let path = entry.path();
-            if let Some(ext) = path.extension()
-                && supported_extensions.contains(&ext.to_string_lossy().as_ref()) {
-                    files_to_process.push(path.to_path_buf());
+        for dir_path in dir_paths {
+            for entry in WalkDir::new(dir_path)
+                .follow_links(false)
+                .into_iter()
+                .filter_entry(|e| {
+                    // Skip hidden directories and common build/dependency directories
+                    let name = e.file_name().to_string_lossy();
+                    !name.starts_with('.')
+                        && name != "target"
+                        && name != "node_modules"
+                        && name != "dist"
+                        && name != "build"
+                        && !crate::tools::agent_ignore::is_ignored(e.path())
+                })
+            {
+                let entry = entry?;
+                if !entry.file_type().is_file() {
+                    continue;
                 }
+
+                let path = entry.path();
+                if let Some(ext) = path.extension()
+                    && supported_extensions.contains(&ext.to_string_lossy().as_ref())
+                    && self.is_selected_language(path) {
+                        files_to_process.push(path.to_path_buf());
+                    }
+            }
         }
 
         if files_to_process.is_empty() {
+            if !self.languages.is_empty() {
+                return Err(anyhow!("No code files found to index in: {}", self.languages.join(", ")));
+            }
             return Err(anyhow!("No code files found to index"));
         }
 
@@ -249,7 +375,7 @@ impl VectorDB {
         }
 
         // Use tree-sitter to intelligently chunk the code
-        crate::tree_sitter_chunker::chunk_code_with_tree_sitter(file_path, &content, &language)
+        crate::tree_sitter_chunker::chunk_code_with_tree_sitter(file_path, &content, &language, self.chunk_options)
     }
 
     /// Detect programming language from file extension
@@ -294,15 +420,10 @@ impl VectorDB {
         for batch in texts.chunks(BATCH_SIZE) {
             pb.set_message(format!("Embedding batch {}/{}", processed / BATCH_SIZE + 1, texts.len().div_ceil(BATCH_SIZE)));
 
-            let batch_embeddings = self.embedding_model
-                .embed_texts(batch.to_vec())
-                .await
-                .map_err(|e| anyhow!("Failed to generate embeddings: {}", e))?;
+            let batch_embeddings = self.embed(batch.to_vec()).await?;
 
             for embedding in batch_embeddings {
-                // Convert f64 to f32
-                let vec_f32: Vec<f32> = embedding.vec.iter().map(|&x| x as f32).collect();
-                all_embeddings.push(vec_f32);
+                all_embeddings.push(embedding);
                 processed += 1;
                 pb.set_position(processed as u64);
             }
@@ -311,23 +432,16 @@ impl VectorDB {
         Ok(all_embeddings)
     }
 
-    /// Search for relevant code chunks
+    /// Search for relevant code chunks, scored by cosine distance (lower is better)
     pub async fn search(&self, query: &str, top_k: usize) -> Result<Vec<(CodeChunk, f32)>> {
         let ruvector_db = self.ruvector_db.as_ref()
             .ok_or_else(|| anyhow!("Vector database not initialized"))?;
 
         // Generate embedding for query
-        let query_embedding = self.embedding_model
-            .embed_texts(vec![query.to_string()])
-            .await
-            .map_err(|e| anyhow!("Failed to generate query embedding: {}", e))?;
-
-        if query_embedding.is_empty() {
-            return Err(anyhow!("No embedding generated for query"));
-        }
-
-        // Convert f64 to f32
-        let query_vec: Vec<f32> = query_embedding[0].vec.iter().map(|&x| x as f32).collect();
+        let query_vec = self.embed(vec![query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding generated for query"))?;
 
         // Create search query
         let search_query = SearchQuery {
@@ -347,7 +461,7 @@ impl VectorDB {
             if let Ok(idx) = result.id.parse::<usize>()
                 && idx < self.chunks.len() {
                     let chunk = self.chunks[idx].clone();
-                    // ruvector-core returns similarity scores (higher is better)
+                    // ruvector-core returns cosine distances: 0 is identical
                     results.push((chunk, result.score));
                 }
         }
@@ -355,6 +469,21 @@ impl VectorDB {
         Ok(results)
     }
 
+    /// Up to `top_k` chunks that are similar enough to the query to be worth
+    /// adding to the context, with their cosine similarity (higher is better);
+    /// empty when nothing in the index is related
+    pub async fn search_relevant(&self, query: &str) -> Result<Vec<(CodeChunk, f32)>> {
+        if self.top_k == 0 {
+            return Ok(Vec::new());
+        }
+        let results = self.search(query, self.top_k).await?;
+        Ok(results
+            .into_iter()
+            .map(|(chunk, distance)| (chunk, 1.0 - distance))
+            .filter(|(_, similarity)| *similarity >= self.min_similarity)
+            .collect())
+    }
+
     /// Get database statistics
     pub fn stats(&self) -> HashMap<String, String> {
         let mut stats = HashMap::new();
@@ -364,3 +493,29 @@ impl VectorDB {
         stats
     }
 }
+
+/// What to do about an Ollama embedding error
+fn ollama_error_hint(model: &str, error: &str) -> String {
+    let lower = error.to_lowercase();
+    if lower.contains("not found") || lower.contains("try pulling") {
+        format!("Embedding model '{}' is not available; run `ollama pull {}`", model, model)
+    } else if lower.contains("connect") || lower.contains("error sending request") {
+        format!("Could not reach Ollama for embeddings ({}); start it with `ollama serve` or set --ollama-url", error)
+    } else {
+        format!("Embedding model '{}' failed: {}", model, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_error_hint() {
+        let hint = ollama_error_hint("nomic-embed-text", "model \"nomic-embed-text\" not found, try pulling it first");
+        assert!(hint.contains("`ollama pull nomic-embed-text`"));
+
+        let hint = ollama_error_hint("nomic-embed-text", "error sending request for url (http://localhost:11434/api/embed)");
+        assert!(hint.contains("ollama serve"));
+    }
+}